@@ -241,7 +241,8 @@ function Setup-Gitignore {
         "# sessync"
         ".claude/sessync/service-account-key.json"
         ".claude/sessync/config.json"
-        ".claude/sessync/upload-state.json"
+        ".claude/sessync/destinations/"
+        ".claude/sessync/sessync.lock"
         ".claude/sessync/sessync"
         ".claude/sessync/sessync.exe"
     )
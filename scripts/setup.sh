@@ -334,7 +334,8 @@ setup_gitignore() {
     "# sessync"
     ".claude/sessync/service-account-key.json"
     ".claude/sessync/config.json"
-    ".claude/sessync/upload-state.json"
+    ".claude/sessync/destinations/"
+    ".claude/sessync/sessync.lock"
     ".claude/sessync/sessync"
     ".claude/sessync/sessync.exe"
   )
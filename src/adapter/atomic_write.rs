@@ -0,0 +1,57 @@
+//! Atomic File Writes
+//!
+//! 一時ファイルに書き込んでから置き換えることで、途中で強制終了されても書きかけのファイルを残さない
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// 一時ファイルに書き込んでから置き換える
+///
+/// 一時ファイルは同じディレクトリに作るため、置き換えは同じファイルシステム内の名前変更になる。
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_atomic_replaces_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        // 一時ファイルは残らない
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_missing_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("missing/data.json");
+
+        assert!(write_atomic(&path, b"data").is_err());
+        assert!(!path.exists());
+    }
+}
@@ -17,10 +17,13 @@ use super::retry::{
 use crate::adapter::config::Config;
 
 /// Prepare rows for BigQuery insertion
+///
+/// Re-uploaded versions get their own insert id so that BigQuery's best-effort
+/// deduplication does not drop them as retries of the first upload.
 pub fn prepare_rows(logs: &[SessionLogOutput]) -> Vec<Row<SessionLogOutput>> {
     logs.iter()
         .map(|log| Row {
-            insert_id: Some(log.uuid.clone()),
+            insert_id: Some(log.insert_id()),
             json: log.clone(),
         })
         .collect()
@@ -455,6 +458,11 @@ mod tests {
             version: None,
             message: json!({}),
             tool_use_result: None,
+            extra: None,
+            root_session_id: None,
+            depth: None,
+            spawning_tool_use_id: None,
+            record_version: 0,
             developer_id: "dev-001".to_string(),
             hostname: "test-host".to_string(),
             user_email: "test@example.com".to_string(),
@@ -472,9 +480,16 @@ mod tests {
             table: "test-table".to_string(),
             location: "US".to_string(),
             service_account_key_path: "/path/to/key.json".to_string(),
+            filter: Default::default(),
+            state_backend: Default::default(),
+            state_retention_days: None,
+            destination: Default::default(),
+            destinations: Vec::new(),
             upload_batch_size: 100,
+            parse_workers: 1,
             enable_auto_upload: false,
             enable_deduplication: true,
+            change_policy: Default::default(),
             developer_id: "dev-001".to_string(),
             user_email: "test@example.com".to_string(),
             project_name: "test-project".to_string(),
@@ -506,6 +521,16 @@ mod tests {
         assert_eq!(rows[2].insert_id, Some("uuid-3".to_string()));
     }
 
+    #[test]
+    fn test_prepare_rows_new_version() {
+        let mut log = create_test_log("uuid-1");
+        log.record_version = 2;
+        let rows = prepare_rows(&[log]);
+
+        assert_eq!(rows[0].insert_id, Some("uuid-1:v2".to_string()));
+        assert_eq!(rows[0].json.uuid, "uuid-1");
+    }
+
     #[test]
     fn test_prepare_rows_empty() {
         let logs: Vec<SessionLogOutput> = vec![];
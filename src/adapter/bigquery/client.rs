@@ -5,7 +5,9 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use google_cloud_bigquery::client::Client;
+use google_cloud_bigquery::http::job::query::QueryRequest;
 use google_cloud_bigquery::http::tabledata::insert_all::{InsertAllRequest, InsertAllResponse};
+use google_cloud_bigquery::query::row::Row;
 
 #[cfg(test)]
 use mockall::automock;
@@ -27,6 +29,20 @@ pub trait BigQueryInserter: Send + Sync {
     ) -> Result<InsertAllResponse>;
 }
 
+/// Trait for BigQuery query operations
+/// Columns are returned as strings so that tests can mock rows without the client's row type
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait BigQueryQuerier: Send + Sync {
+    /// Run a query and return the first `columns` columns of every row
+    async fn query(
+        &self,
+        project_id: &str,
+        request: &QueryRequest,
+        columns: usize,
+    ) -> Result<Vec<Vec<Option<String>>>>;
+}
+
 /// Real BigQuery client wrapper implementing BigQueryInserter
 pub struct RealBigQueryClient<'a> {
     client: &'a Client,
@@ -85,10 +101,43 @@ impl BigQueryInserter for OwnedBigQueryClient {
     }
 }
 
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[async_trait]
+impl BigQueryQuerier for OwnedBigQueryClient {
+    async fn query(
+        &self,
+        project_id: &str,
+        request: &QueryRequest,
+        columns: usize,
+    ) -> Result<Vec<Vec<Option<String>>>> {
+        let mut iter = self
+            .client
+            .query::<Row>(project_id, request.clone())
+            .await
+            .context("BigQuery query failed")?;
+
+        let mut rows = Vec::new();
+        while let Some(row) = iter.next().await.context("Failed to read query results")? {
+            rows.push(
+                (0..columns)
+                    .map(|i| row.column::<Option<String>>(i))
+                    .collect::<Result<Vec<_>, _>>()
+                    .context("Failed to decode query results")?,
+            );
+        }
+        Ok(rows)
+    }
+}
+
 /// Factory for creating BigQuery clients
 #[async_trait]
 pub trait BigQueryClientFactory: Send + Sync {
     async fn create_client(&self) -> Result<Box<dyn BigQueryInserter>>;
+
+    /// Create a client for running queries (factories that only insert don't support it)
+    async fn create_querier(&self) -> Result<Box<dyn BigQueryQuerier>> {
+        anyhow::bail!("This BigQuery client factory does not support queries")
+    }
 }
 
 /// Production implementation of BigQueryClientFactory
@@ -109,4 +158,9 @@ impl BigQueryClientFactory for RealClientFactory {
         let client = crate::adapter::auth::create_bigquery_client(&self.key_path).await?;
         Ok(Box::new(OwnedBigQueryClient::new(client)))
     }
+
+    async fn create_querier(&self) -> Result<Box<dyn BigQueryQuerier>> {
+        let client = crate::adapter::auth::create_bigquery_client(&self.key_path).await?;
+        Ok(Box::new(OwnedBigQueryClient::new(client)))
+    }
 }
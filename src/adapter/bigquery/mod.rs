@@ -5,4 +5,5 @@
 pub mod batch_uploader;
 pub mod client;
 pub mod models;
+pub mod record_query;
 pub mod retry;
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize, Serializer};
 
+use crate::domain::entities::session_log::SessionLog;
+
 // Custom serializer: serialize serde_json::Value as JSON string
 // This is required for BigQuery Streaming Insert API with JSON type columns.
 // The insertAll API expects JSON column values as pre-serialized JSON strings,
@@ -76,6 +78,17 @@ pub struct SessionLogOutput {
     pub message: serde_json::Value,
     #[serde(serialize_with = "serialize_option_json_value_as_string")]
     pub tool_use_result: Option<serde_json::Value>,
+    // Unrecognized top-level transcript keys, kept so new fields are never lost
+    #[serde(serialize_with = "serialize_option_json_value_as_string")]
+    pub extra: Option<serde_json::Value>,
+
+    // Session tree (sub-agent / sidechain attribution)
+    pub root_session_id: Option<String>,
+    pub depth: Option<u32>,
+    pub spawning_tool_use_id: Option<String>,
+
+    /// Incremented each time a record is re-uploaded with changed content (0 for the first upload)
+    pub record_version: u32,
 
     // Team collaboration metadata
     pub developer_id: String,
@@ -89,6 +102,61 @@ pub struct SessionLogOutput {
     pub uploaded_at: DateTime<Utc>,
 }
 
+/// Row id used for deduplication by every destination
+///
+/// Re-uploaded versions get their own id so they are not dropped as retries of the first upload.
+pub fn insert_id(uuid: &str, record_version: u32) -> String {
+    if record_version == 0 {
+        uuid.to_string()
+    } else {
+        format!("{}:v{}", uuid, record_version)
+    }
+}
+
+impl SessionLogOutput {
+    /// Row id used for deduplication (see [`insert_id`])
+    pub fn insert_id(&self) -> String {
+        insert_id(&self.uuid, self.record_version)
+    }
+}
+
+impl From<&SessionLog> for SessionLogOutput {
+    fn from(log: &SessionLog) -> Self {
+        Self {
+            uuid: log.uuid.clone(),
+            timestamp: log.timestamp,
+            session_id: log.session_id.clone(),
+            agent_id: log.agent_id.clone(),
+            is_sidechain: log.is_sidechain,
+            parent_uuid: log.parent_uuid.clone(),
+            user_type: log.user_type.clone(),
+            message_type: log.message_type.clone(),
+            slug: log.slug.clone(),
+            request_id: log.request_id.clone(),
+            cwd: log.cwd.clone(),
+            git_branch: log.git_branch.clone(),
+            version: log.version.clone(),
+            message: log.message.clone(),
+            tool_use_result: log.tool_use_result.clone(),
+            extra: log.extra.clone(),
+            root_session_id: log.lineage.as_ref().map(|l| l.root_session_id.clone()),
+            depth: log.lineage.as_ref().map(|l| l.depth),
+            spawning_tool_use_id: log
+                .lineage
+                .as_ref()
+                .and_then(|l| l.spawning_tool_use_id.clone()),
+            record_version: log.record_version,
+            developer_id: log.metadata.developer_id.clone(),
+            hostname: log.metadata.hostname.clone(),
+            user_email: log.metadata.user_email.clone(),
+            project_name: log.metadata.project_name.clone(),
+            upload_batch_id: log.metadata.upload_batch_id.clone(),
+            source_file: log.metadata.source_file.clone(),
+            uploaded_at: log.metadata.uploaded_at,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -112,6 +180,11 @@ mod tests {
             version: Some("1.0.0".to_string()),
             message: json!({"role": "user", "content": "Hello"}),
             tool_use_result: Some(json!({"output": "success"})),
+            extra: None,
+            root_session_id: None,
+            depth: None,
+            spawning_tool_use_id: None,
+            record_version: 0,
             developer_id: "dev-001".to_string(),
             hostname: "test-host".to_string(),
             user_email: "test@example.com".to_string(),
@@ -171,6 +244,19 @@ mod tests {
         assert!(parsed["tool_use_result"].is_null());
     }
 
+    #[test]
+    fn test_extra_serialization() {
+        let mut output = create_test_output();
+        let parsed: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&output).unwrap()).unwrap();
+        assert!(parsed["extra"].is_null());
+
+        output.extra = Some(json!({"isMeta": true}));
+        let parsed: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&output).unwrap()).unwrap();
+        assert_eq!(parsed["extra"], r#"{"isMeta":true}"#);
+    }
+
     #[test]
     fn test_session_log_input_deserialization() {
         let json_str = r#"{
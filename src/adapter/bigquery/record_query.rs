@@ -0,0 +1,274 @@
+//! BigQuery Record Query Logic
+//!
+//! 送信先テーブルに既に存在するレコードの照会ロジック
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use google_cloud_bigquery::http::job::query::QueryRequest;
+use google_cloud_bigquery::http::types::{QueryParameter, QueryParameterType, QueryParameterValue};
+
+use super::client::{BigQueryClientFactory, BigQueryQuerier};
+use crate::adapter::config::Config;
+use crate::domain::repositories::remote_record_repository::RemoteRecord;
+
+/// Number of columns selected by `build_query`
+const RECORD_COLUMNS: usize = 5;
+
+/// Timestamp format returned by the query (parsed back with RFC 3339)
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%E6SZ";
+
+/// Build a query for records whose timestamp falls within `[since, until]`
+pub fn build_query(config: &Config, since: DateTime<Utc>, until: DateTime<Utc>) -> QueryRequest {
+    let query = format!(
+        "SELECT uuid, session_id, source_file, \
+         FORMAT_TIMESTAMP('{format}', timestamp), FORMAT_TIMESTAMP('{format}', uploaded_at) \
+         FROM `{}.{}.{}` \
+         WHERE timestamp BETWEEN @since AND @until",
+        config.project_id,
+        config.dataset,
+        config.table,
+        format = TIMESTAMP_FORMAT,
+    );
+
+    QueryRequest {
+        query,
+        use_legacy_sql: false,
+        parameter_mode: Some("NAMED".to_string()),
+        query_parameters: vec![
+            timestamp_parameter("since", since),
+            timestamp_parameter("until", until),
+        ],
+        location: config.location.clone(),
+        ..Default::default()
+    }
+}
+
+fn timestamp_parameter(name: &str, value: DateTime<Utc>) -> QueryParameter {
+    QueryParameter {
+        name: Some(name.to_string()),
+        parameter_type: QueryParameterType {
+            parameter_type: "TIMESTAMP".to_string(),
+            ..Default::default()
+        },
+        parameter_value: QueryParameterValue {
+            value: Some(value.to_rfc3339_opts(SecondsFormat::Micros, true)),
+            ..Default::default()
+        },
+    }
+}
+
+/// Convert a result row into a RemoteRecord
+fn parse_row(row: Vec<Option<String>>) -> Result<RemoteRecord> {
+    let mut columns = row.into_iter();
+    let mut next = |name: &str| {
+        columns
+            .next()
+            .flatten()
+            .with_context(|| format!("Query result is missing {}", name))
+    };
+
+    let uuid = next("uuid")?;
+    let session_id = next("session_id")?;
+    let source_file = next("source_file")?;
+    let timestamp = parse_timestamp(&next("timestamp")?)?;
+    let uploaded_at = parse_timestamp(&next("uploaded_at")?)?;
+
+    Ok(RemoteRecord {
+        uuid,
+        session_id,
+        source_file,
+        timestamp,
+        uploaded_at,
+    })
+}
+
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(value)
+        .with_context(|| format!("Invalid timestamp in query result: {}", value))?
+        .with_timezone(&Utc))
+}
+
+/// Query records already present in the destination table
+pub async fn query_uploaded_records<T: BigQueryQuerier + ?Sized>(
+    querier: &T,
+    config: &Config,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<RemoteRecord>> {
+    let request = build_query(config, since, until);
+    let rows = querier
+        .query(&config.project_id, &request, RECORD_COLUMNS)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to query {}.{}.{}",
+                config.project_id, config.dataset, config.table
+            )
+        })?;
+
+    rows.into_iter().map(parse_row).collect()
+}
+
+/// Query records already present in the destination table using a factory
+pub async fn query_uploaded_records_with_factory<F: BigQueryClientFactory + ?Sized>(
+    factory: &F,
+    config: &Config,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<RemoteRecord>> {
+    let querier = factory.create_querier().await?;
+    query_uploaded_records(querier.as_ref(), config, since, until).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::bigquery::client::{BigQueryInserter, MockBigQueryQuerier};
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+
+    fn create_test_config() -> Config {
+        Config {
+            project_id: "test-project".to_string(),
+            dataset: "test-dataset".to_string(),
+            table: "test-table".to_string(),
+            location: "US".to_string(),
+            service_account_key_path: "/path/to/key.json".to_string(),
+            filter: Default::default(),
+            state_backend: Default::default(),
+            state_retention_days: None,
+            destination: Default::default(),
+            destinations: Vec::new(),
+            upload_batch_size: 100,
+            parse_workers: 1,
+            enable_auto_upload: false,
+            enable_deduplication: true,
+            change_policy: Default::default(),
+            developer_id: "dev-001".to_string(),
+            user_email: "test@example.com".to_string(),
+            project_name: "test-project".to_string(),
+        }
+    }
+
+    fn since() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    fn until() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap()
+    }
+
+    fn row(uuid: &str) -> Vec<Option<String>> {
+        vec![
+            Some(uuid.to_string()),
+            Some("session-1".to_string()),
+            Some("/logs/session-1.jsonl".to_string()),
+            Some("2024-01-01T10:00:00.123456Z".to_string()),
+            Some("2024-01-01T11:00:00.000000Z".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_build_query() {
+        let request = build_query(&create_test_config(), since(), until());
+
+        assert!(request
+            .query
+            .contains("FROM `test-project.test-dataset.test-table`"));
+        assert!(request.query.contains("BETWEEN @since AND @until"));
+        assert!(!request.use_legacy_sql);
+        assert_eq!(request.location, "US");
+        assert_eq!(request.query_parameters.len(), 2);
+        assert_eq!(request.query_parameters[0].name.as_deref(), Some("since"));
+        assert_eq!(
+            request.query_parameters[0].parameter_type.parameter_type,
+            "TIMESTAMP"
+        );
+        assert_eq!(
+            request.query_parameters[1].parameter_value.value.as_deref(),
+            Some("2024-01-02T00:00:00.000000Z")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_uploaded_records() {
+        let mut mock = MockBigQueryQuerier::new();
+        mock.expect_query()
+            .withf(|project_id, _, columns| project_id == "test-project" && *columns == 5)
+            .times(1)
+            .returning(|_, _, _| Ok(vec![row("uuid-1"), row("uuid-2")]));
+
+        let records = query_uploaded_records(&mock, &create_test_config(), since(), until())
+            .await
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].uuid, "uuid-1");
+        assert_eq!(records[0].session_id, "session-1");
+        assert_eq!(records[0].source_file, "/logs/session-1.jsonl");
+        assert_eq!(
+            records[0].timestamp,
+            Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap()
+                + chrono::Duration::microseconds(123456)
+        );
+        assert_eq!(
+            records[1].uploaded_at,
+            Utc.with_ymd_and_hms(2024, 1, 1, 11, 0, 0).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_uploaded_records_missing_column() {
+        let mut mock = MockBigQueryQuerier::new();
+        mock.expect_query().returning(|_, _, _| {
+            let mut row = row("uuid-1");
+            row[1] = None;
+            Ok(vec![row])
+        });
+
+        let result = query_uploaded_records(&mock, &create_test_config(), since(), until()).await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("missing session_id"));
+    }
+
+    #[tokio::test]
+    async fn test_query_uploaded_records_query_error() {
+        let mut mock = MockBigQueryQuerier::new();
+        mock.expect_query()
+            .returning(|_, _, _| Err(anyhow::anyhow!("Access Denied")));
+
+        let result = query_uploaded_records(&mock, &create_test_config(), since(), until()).await;
+
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("Failed to query"));
+        assert!(format!("{:#}", err).contains("Access Denied"));
+    }
+
+    struct InsertOnlyFactory;
+
+    #[async_trait]
+    impl BigQueryClientFactory for InsertOnlyFactory {
+        async fn create_client(&self) -> Result<Box<dyn BigQueryInserter>> {
+            anyhow::bail!("not used")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_with_factory_without_query_support() {
+        let result = query_uploaded_records_with_factory(
+            &InsertOnlyFactory,
+            &create_test_config(),
+            since(),
+            until(),
+        )
+        .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("does not support queries"));
+    }
+}
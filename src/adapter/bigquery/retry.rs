@@ -36,6 +36,7 @@ pub fn is_connection_error(error_msg: &str) -> bool {
         || error_msg.contains("Connection refused")
         || error_msg.contains("connection refused")
         || error_msg.contains("connection error")
+        || error_msg.contains("connection closed")
         || error_msg.contains("EOF")
         || error_msg.contains("unexpected end of file")
 }
@@ -109,6 +110,9 @@ mod tests {
         // Test connection refused
         assert!(is_connection_error("Connection refused"));
 
+        // Test closed connection (PostgreSQL)
+        assert!(is_connection_error("connection closed"));
+
         // Test EOF
         assert!(is_connection_error("EOF"));
         assert!(is_connection_error("unexpected end of file"));
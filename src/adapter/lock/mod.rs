@@ -0,0 +1,7 @@
+//! Lock Module
+//!
+//! 複数プロセスの同時実行を防ぐロック関連の機能
+
+pub mod run_lock;
+
+pub use run_lock::RunLock;
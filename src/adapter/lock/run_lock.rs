@@ -0,0 +1,184 @@
+//! Run Lock
+//!
+//! 実行全体を囲むアドバイザリロック（ロックファイルの排他ロック）
+
+use anyhow::{Context, Result};
+use log::{debug, info};
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// ロックが解放されたかを確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 実行ロック
+///
+/// SessionEnd フックと手動実行など、同じプロジェクトで sessync が同時に動くと
+/// 状態ファイルの読み込みから保存までが競合するため、実行全体をこのロックで直列化する。
+/// OS のアドバイザリロックを使うため、プロセスが異常終了してもロックは自動的に解放される。
+/// ドロップ時にロックを解放する。
+#[derive(Debug)]
+pub struct RunLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl RunLock {
+    /// ロックの取得を試みます（他のプロセスが保持している場合は `None`）。
+    ///
+    /// # エラー
+    ///
+    /// ロックファイルを作成できない場合にエラーを返します。
+    pub fn try_acquire(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        let file = Self::open(path)?;
+
+        match file.try_lock() {
+            Ok(()) => {
+                debug!("Acquired run lock: {}", path.display());
+                Ok(Some(Self {
+                    file,
+                    path: path.to_path_buf(),
+                }))
+            }
+            Err(fs::TryLockError::WouldBlock) => Ok(None),
+            Err(fs::TryLockError::Error(e)) => {
+                Err(e).context(format!("Failed to lock {}", path.display()))
+            }
+        }
+    }
+
+    /// ロックを取得します（他のプロセスが保持している場合は解放されるまで待機）。
+    ///
+    /// # エラー
+    ///
+    /// `timeout` までにロックを取得できない場合、またはロックファイルを
+    /// 作成できない場合にエラーを返します。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::adapter::lock::RunLock;
+    /// use std::time::Duration;
+    ///
+    /// let dir = tempfile::TempDir::new().unwrap();
+    /// let path = dir.path().join("sessync.lock");
+    ///
+    /// let lock = RunLock::acquire(&path, Duration::from_secs(1)).unwrap();
+    /// assert!(RunLock::try_acquire(&path).unwrap().is_none());
+    ///
+    /// drop(lock);
+    /// assert!(RunLock::try_acquire(&path).unwrap().is_some());
+    /// ```
+    pub fn acquire(path: impl AsRef<Path>, timeout: Duration) -> Result<Self> {
+        let path = path.as_ref();
+        let started = Instant::now();
+        let mut waiting = false;
+
+        loop {
+            if let Some(lock) = Self::try_acquire(path)? {
+                return Ok(lock);
+            }
+            if started.elapsed() >= timeout {
+                anyhow::bail!(
+                    "Another sessync run is still holding {} (waited {}s)",
+                    path.display(),
+                    timeout.as_secs()
+                );
+            }
+            if !waiting {
+                info!(
+                    "Waiting for another sessync run to finish: {}",
+                    path.display()
+                );
+                waiting = true;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// ロックファイルのパス
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// ロックファイルを開く（存在しなければ作成）
+    fn open(path: &Path) -> Result<File> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create lock directory")?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .context(format!("Failed to open lock file: {}", path.display()))
+    }
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+        debug!("Released run lock: {}", self.path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_try_acquire_is_exclusive() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("sessync.lock");
+
+        let lock = RunLock::try_acquire(&path).unwrap().unwrap();
+        assert_eq!(lock.path(), path);
+        assert!(RunLock::try_acquire(&path).unwrap().is_none());
+
+        drop(lock);
+        assert!(RunLock::try_acquire(&path).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_acquire_times_out() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("sessync.lock");
+
+        let _lock = RunLock::try_acquire(&path).unwrap().unwrap();
+        let result = RunLock::acquire(&path, Duration::from_millis(300));
+
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Another sessync run is still holding"));
+    }
+
+    #[test]
+    fn test_acquire_waits_for_release() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("sessync.lock");
+
+        let lock = RunLock::try_acquire(&path).unwrap().unwrap();
+        let releaser = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            drop(lock);
+        });
+
+        let acquired = RunLock::acquire(&path, Duration::from_secs(10));
+        releaser.join().unwrap();
+
+        assert!(acquired.is_ok());
+    }
+
+    #[test]
+    fn test_creates_parent_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("sessync.lock");
+
+        assert!(RunLock::try_acquire(&path).unwrap().is_some());
+        assert!(path.exists());
+    }
+}
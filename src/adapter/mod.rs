@@ -2,7 +2,9 @@
 //!
 //! 外部システム（BigQuery, ファイルシステム）との統合
 
+pub mod atomic_write;
 pub mod auth;
 pub mod bigquery;
 pub mod config;
+pub mod lock;
 pub mod repositories;
@@ -0,0 +1,716 @@
+//! Archive Log Repository Implementation
+//!
+//! LogRepositoryのアーカイブ（tar / tar.gz / zip）実装
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::info;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tempfile::NamedTempFile;
+use zip::ZipArchive;
+
+use super::file_log_repository::FileLogRepository;
+use crate::domain::entities::file_checkpoint::FileCheckpoint;
+use crate::domain::repositories::log_repository::{LogRepository, LogStream};
+
+/// アーカイブのパスとアーカイブ内のパスの区切り（`bundle.tar.gz!/inner/path.jsonl`）
+pub const ENTRY_SEPARATOR: &str = "!/";
+
+/// アーカイブの形式（拡張子で判定）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    /// `.tar`
+    Tar,
+    /// `.tar.gz` / `.tgz`
+    TarGz,
+    /// `.zip`
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// アーカイブであれば形式を返す（アーカイブでなければ `None`）
+    fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// 索引に記録したアーカイブ内のファイル
+struct IndexedEntry {
+    /// アーカイブ内のパス（先頭の `./` と `/` は除く）
+    name: String,
+    /// 更新時刻
+    modified_at: Option<DateTime<Utc>>,
+    /// 内容の位置（tar はデータの先頭のバイトオフセット、zip はエントリの番号）
+    position: u64,
+    /// 内容のサイズ（バイト）
+    size: u64,
+}
+
+/// エントリの内容を読み出す元
+enum EntrySource {
+    /// tar（tar.gz は展開した内容を書き出した一時ファイル）
+    Tar {
+        /// 読み出すファイルのパス
+        path: PathBuf,
+        /// tar.gz を展開した一時ファイル（索引が破棄されると削除される）
+        _spool: Option<NamedTempFile>,
+    },
+    /// zip（中央ディレクトリは読み込み済みで、読み出しごとに複製して使う）
+    Zip(ZipArchive<ReopeningFile>),
+}
+
+/// 一度だけ走査したアーカイブの索引
+struct ArchiveIndex {
+    /// アーカイブ内のファイル（アーカイブ内の順）
+    entries: Vec<IndexedEntry>,
+    /// アーカイブ内のパス → `entries` の位置
+    by_name: HashMap<String, usize>,
+    /// 内容を読み出す元
+    source: EntrySource,
+}
+
+impl ArchiveIndex {
+    /// アーカイブを走査して索引を作成する（同期処理）
+    ///
+    /// tar.gz は先頭から展開するしかないため、展開した内容を一時ファイルに書き出してから
+    /// tar として索引を作る。
+    fn build(archive_path: &Path) -> Result<Self> {
+        let format = ArchiveFormat::detect(archive_path)
+            .with_context(|| format!("Unsupported archive format: {}", archive_path.display()))?;
+        let mut file = fs::File::open(archive_path).context(format!(
+            "Failed to open archive: {}",
+            archive_path.display()
+        ))?;
+
+        match format {
+            ArchiveFormat::Tar => Self::build_tar(archive_path, archive_path.to_path_buf(), None),
+            ArchiveFormat::TarGz => {
+                let mut spool = NamedTempFile::new()
+                    .context("Failed to create a temporary file for the archive")?;
+                io::copy(
+                    &mut flate2::read::MultiGzDecoder::new(&mut file),
+                    spool.as_file_mut(),
+                )
+                .context(format!(
+                    "Failed to decompress archive: {}",
+                    archive_path.display()
+                ))?;
+                Self::build_tar(archive_path, spool.path().to_path_buf(), Some(spool))
+            }
+            ArchiveFormat::Zip => Self::build_zip(archive_path),
+        }
+    }
+
+    /// tar の索引を作成する（`path` は展開済みの tar）
+    fn build_tar(archive_path: &Path, path: PathBuf, spool: Option<NamedTempFile>) -> Result<Self> {
+        let file = fs::File::open(&path).context(format!(
+            "Failed to open archive: {}",
+            archive_path.display()
+        ))?;
+        let mut archive = tar::Archive::new(file);
+        let tar_entries = archive.entries().context(format!(
+            "Failed to read tar archive: {}",
+            archive_path.display()
+        ))?;
+
+        let mut entries = Vec::new();
+        for entry in tar_entries {
+            let entry = entry.context(format!(
+                "Failed to read tar archive: {}",
+                archive_path.display()
+            ))?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            entries.push(IndexedEntry {
+                name: ArchiveLogRepository::normalize_entry_name(&entry.path()?.to_string_lossy()),
+                modified_at: entry
+                    .header()
+                    .mtime()
+                    .ok()
+                    .and_then(|secs| DateTime::from_timestamp(secs as i64, 0)),
+                position: entry.raw_file_position(),
+                size: entry.size(),
+            });
+        }
+
+        Ok(Self::new(
+            entries,
+            EntrySource::Tar {
+                path,
+                _spool: spool,
+            },
+        ))
+    }
+
+    /// zip の索引を作成する
+    fn build_zip(archive_path: &Path) -> Result<Self> {
+        let mut archive = ZipArchive::new(ReopeningFile::new(archive_path)).context(format!(
+            "Failed to read zip archive: {}",
+            archive_path.display()
+        ))?;
+
+        let mut entries = Vec::new();
+        for index in 0..archive.len() {
+            let entry = archive.by_index_raw(index).context(format!(
+                "Failed to read zip archive: {}",
+                archive_path.display()
+            ))?;
+            if !entry.is_file() {
+                continue;
+            }
+            entries.push(IndexedEntry {
+                name: ArchiveLogRepository::normalize_entry_name(entry.name()),
+                // zip の更新時刻はタイムゾーンを持たないため UTC として扱う
+                modified_at: entry
+                    .last_modified()
+                    .and_then(|t| NaiveDateTime::try_from(t).ok())
+                    .map(|t| t.and_utc()),
+                position: index as u64,
+                size: entry.size(),
+            });
+        }
+
+        Ok(Self::new(entries, EntrySource::Zip(archive)))
+    }
+
+    fn new(entries: Vec<IndexedEntry>, source: EntrySource) -> Self {
+        let by_name = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (entry.name.clone(), i))
+            .collect();
+        Self {
+            entries,
+            by_name,
+            source,
+        }
+    }
+
+    /// エントリを探す（見つからなければエラー）
+    fn entry(&self, file_path: &Path, entry_name: &str) -> Result<&IndexedEntry> {
+        self.by_name
+            .get(entry_name)
+            .map(|&i| &self.entries[i])
+            .with_context(|| format!("Log file not found in archive: {}", file_path.display()))
+    }
+
+    /// エントリの内容を `read` で読み込む（同期処理）
+    fn read_entry<T>(
+        &self,
+        entry: &IndexedEntry,
+        read: impl FnOnce(&mut dyn BufRead) -> Result<T>,
+    ) -> Result<T> {
+        match &self.source {
+            EntrySource::Tar { path, .. } => {
+                let mut file = fs::File::open(path)?;
+                file.seek(SeekFrom::Start(entry.position))?;
+                read(&mut BufReader::new(file.take(entry.size)))
+            }
+            EntrySource::Zip(archive) => {
+                let mut archive = archive.clone();
+                let mut reader = BufReader::new(archive.by_index(entry.position as usize)?);
+                read(&mut reader)
+            }
+        }
+    }
+}
+
+/// 複製するたびに開き直すファイル
+///
+/// zip の中央ディレクトリを読み直さずに、複数のエントリを並行して読むために使う
+/// （`ZipArchive` は読み込み元ごと複製される）。ファイルは最初の読み込み時に開く。
+struct ReopeningFile {
+    path: Arc<PathBuf>,
+    file: Option<fs::File>,
+}
+
+impl ReopeningFile {
+    fn new(path: &Path) -> Self {
+        Self {
+            path: Arc::new(path.to_path_buf()),
+            file: None,
+        }
+    }
+
+    fn file(&mut self) -> io::Result<&mut fs::File> {
+        if self.file.is_none() {
+            self.file = Some(fs::File::open(self.path.as_ref())?);
+        }
+        Ok(self.file.as_mut().expect("file was just opened"))
+    }
+}
+
+impl Clone for ReopeningFile {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            file: None,
+        }
+    }
+}
+
+impl Read for ReopeningFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file()?.read(buf)
+    }
+}
+
+impl Seek for ReopeningFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file()?.seek(pos)
+    }
+}
+
+/// アーカイブベースのログリポジトリ
+///
+/// `~/.claude/projects` をまとめた tar / tar.gz / zip から、展開せずに
+/// `.jsonl` のエントリを直接読み込む。ログファイルのパスは
+/// `<アーカイブのパス>!/<アーカイブ内のパス>` で表す。
+///
+/// アーカイブは最初に一度だけ走査して索引を作り、各エントリは索引から直接読み込む
+/// （tar.gz は展開した内容を一時ファイルに置く）。
+/// チェックポイントには対応しない（取り込み済みのレコードは重複排除で除外される）。
+pub struct ArchiveLogRepository {
+    /// 走査済みのアーカイブの索引（アーカイブのパス → 索引）
+    indexes: Arc<Mutex<HashMap<PathBuf, Arc<ArchiveIndex>>>>,
+}
+
+impl ArchiveLogRepository {
+    /// 新しいリポジトリを作成
+    pub fn new() -> Self {
+        Self {
+            indexes: Arc::default(),
+        }
+    }
+
+    /// アーカイブとして読み込めるファイルかを判定します。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::adapter::repositories::archive_log_repository::ArchiveLogRepository;
+    /// use std::path::Path;
+    ///
+    /// assert!(ArchiveLogRepository::is_archive(Path::new("bundle.tar.gz")));
+    /// assert!(ArchiveLogRepository::is_archive(Path::new("bundle.zip")));
+    /// assert!(!ArchiveLogRepository::is_archive(Path::new("session.jsonl")));
+    /// ```
+    pub fn is_archive(path: &Path) -> bool {
+        ArchiveFormat::detect(path).is_some()
+    }
+
+    /// アーカイブ内のエントリを指すパスを作成します。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::adapter::repositories::archive_log_repository::ArchiveLogRepository;
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// let path = ArchiveLogRepository::entry_path(Path::new("/tmp/bundle.zip"), "app/s1.jsonl");
+    /// assert_eq!(path, PathBuf::from("/tmp/bundle.zip!/app/s1.jsonl"));
+    /// ```
+    pub fn entry_path(archive_path: &Path, entry_name: &str) -> PathBuf {
+        PathBuf::from(format!(
+            "{}{}{}",
+            archive_path.display(),
+            ENTRY_SEPARATOR,
+            entry_name
+        ))
+    }
+
+    /// エントリを指すパスをアーカイブのパスとアーカイブ内のパスに分割
+    ///
+    /// 区切りより前がアーカイブの拡張子で終わる最初の位置で分割する。
+    pub(crate) fn split_entry_path(path: &Path) -> Result<(PathBuf, String)> {
+        let path_str = path.to_string_lossy();
+        path_str
+            .match_indices(ENTRY_SEPARATOR)
+            .map(|(i, _)| {
+                (
+                    PathBuf::from(&path_str[..i]),
+                    path_str[i + ENTRY_SEPARATOR.len()..].to_string(),
+                )
+            })
+            .find(|(archive_path, _)| ArchiveFormat::detect(archive_path).is_some())
+            .with_context(|| format!("Not an archive entry path: {}", path.display()))
+    }
+
+    /// アーカイブ内のパスを正規化（先頭の `./` と `/` を除く）
+    fn normalize_entry_name(name: &str) -> String {
+        let mut name = name;
+        loop {
+            if let Some(rest) = name.strip_prefix("./") {
+                name = rest;
+            } else if let Some(rest) = name.strip_prefix('/') {
+                name = rest;
+            } else {
+                return name.to_string();
+            }
+        }
+    }
+
+    /// アーカイブの索引を取得する（初回のみ走査する、同期処理）
+    fn index(
+        indexes: &Mutex<HashMap<PathBuf, Arc<ArchiveIndex>>>,
+        archive_path: &Path,
+    ) -> Result<Arc<ArchiveIndex>> {
+        // 同じアーカイブを並行して走査しないよう、索引の作成中もロックを保持する
+        let mut indexes = indexes
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Archive index lock was poisoned"))?;
+        if let Some(index) = indexes.get(archive_path) {
+            return Ok(index.clone());
+        }
+        let index = Arc::new(ArchiveIndex::build(archive_path)?);
+        indexes.insert(archive_path.to_path_buf(), index.clone());
+        Ok(index)
+    }
+
+    /// ログファイルを発見する（内部実装）
+    fn discover_log_files_internal(
+        indexes: &Mutex<HashMap<PathBuf, Arc<ArchiveIndex>>>,
+        archive_path: &str,
+    ) -> Result<Vec<PathBuf>> {
+        let expanded_path = shellexpand::tilde(archive_path);
+        let archive_path = PathBuf::from(expanded_path.as_ref());
+
+        let log_files: Vec<PathBuf> = Self::index(indexes, &archive_path)?
+            .entries
+            .iter()
+            .filter(|entry| entry.name.ends_with(".jsonl"))
+            .map(|entry| Self::entry_path(&archive_path, &entry.name))
+            .collect();
+
+        info!(
+            "Found {} log files in {}",
+            log_files.len(),
+            archive_path.display()
+        );
+
+        Ok(log_files)
+    }
+}
+
+#[async_trait]
+impl LogRepository for ArchiveLogRepository {
+    async fn discover_log_files(&self, log_dir: &str) -> Result<Vec<PathBuf>> {
+        // log_dir にはアーカイブのパスを指定する
+        let archive_path = log_dir.to_string();
+        let indexes = self.indexes.clone();
+        tokio::task::spawn_blocking(move || {
+            Self::discover_log_files_internal(&indexes, &archive_path)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn modified_at(&self, file_path: &Path) -> Result<Option<DateTime<Utc>>> {
+        let file_path = file_path.to_path_buf();
+        let indexes = self.indexes.clone();
+        tokio::task::spawn_blocking(move || {
+            let (archive_path, entry_name) = Self::split_entry_path(&file_path)?;
+            let index = Self::index(&indexes, &archive_path)?;
+            Ok(index.entry(&file_path, &entry_name)?.modified_at)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    fn stream_log_file(
+        &self,
+        file_path: PathBuf,
+        _checkpoint: Option<FileCheckpoint>,
+    ) -> LogStream<'_> {
+        let indexes = self.indexes.clone();
+        FileLogRepository::spawn_stream(move |on_item| {
+            let (archive_path, entry_name) = Self::split_entry_path(&file_path)?;
+            let index = Self::index(&indexes, &archive_path)?;
+            let entry = index.entry(&file_path, &entry_name)?;
+            index
+                .read_entry(entry, |reader| {
+                    FileLogRepository::read_lines(reader, &file_path, 0, 0, on_item)
+                })
+                .context(format!("Failed to read log file: {}", file_path.display()))?;
+            Ok(None)
+        })
+    }
+}
+
+impl Default for ArchiveLogRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    use crate::domain::repositories::log_repository::LogStreamItem;
+
+    const LINE_1: &str = r#"{"uuid":"550e8400-e29b-41d4-a716-446655440000","timestamp":"2024-01-01T00:00:00Z","sessionId":"session1","agentId":"agent1","isSidechain":false,"parentUuid":null,"userType":"human","type":"text","slug":"test","requestId":null,"cwd":"/test","gitBranch":"main","version":"1.0.0","message":{},"toolUseResult":null}"#;
+    const LINE_2: &str = r#"{"uuid":"550e8400-e29b-41d4-a716-446655440001","timestamp":"2024-01-01T00:00:01Z","sessionId":"session2","agentId":"agent1","isSidechain":false,"parentUuid":null,"userType":"human","type":"text","slug":"test","requestId":null,"cwd":"/test","gitBranch":"main","version":"1.0.0","message":{},"toolUseResult":null}"#;
+
+    /// テスト用のアーカイブの中身（パス, 内容）
+    fn bundle() -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "projects/-Users-me-app/session1.jsonl",
+                format!("{}\n", LINE_1),
+            ),
+            (
+                "projects/-Users-me-app/session2.jsonl",
+                format!("{}\ninvalid json line\n", LINE_2),
+            ),
+            ("projects/README.md", "not a log".to_string()),
+        ]
+    }
+
+    /// テスト用の tar アーカイブを作成するヘルパー関数
+    fn write_tar<W: Write>(writer: W) -> W {
+        let mut builder = tar::Builder::new(writer);
+        for (name, content) in bundle() {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(1_735_000_000);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, format!("./{}", name), content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn create_tar(dir: &Path) -> PathBuf {
+        let path = dir.join("bundle.tar");
+        write_tar(fs::File::create(&path).unwrap());
+        path
+    }
+
+    fn create_tar_gz(dir: &Path) -> PathBuf {
+        let path = dir.join("bundle.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            fs::File::create(&path).unwrap(),
+            flate2::Compression::default(),
+        );
+        write_tar(encoder).finish().unwrap();
+        path
+    }
+
+    fn create_zip(dir: &Path) -> PathBuf {
+        let path = dir.join("bundle.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, content) in bundle() {
+            writer.start_file(name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+        path
+    }
+
+    async fn assert_reads_bundle(archive_path: &Path) {
+        let repo = ArchiveLogRepository::new();
+        let files = repo
+            .discover_log_files(archive_path.to_str().unwrap())
+            .await
+            .unwrap();
+
+        let display = archive_path.display();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from(format!(
+                    "{}!/projects/-Users-me-app/session1.jsonl",
+                    display
+                )),
+                PathBuf::from(format!(
+                    "{}!/projects/-Users-me-app/session2.jsonl",
+                    display
+                )),
+            ]
+        );
+
+        let records = repo.parse_log_file(&files[0]).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].identity(),
+            "550e8400-e29b-41d4-a716-446655440000"
+        );
+
+        let parsed = repo
+            .parse_log_file_incremental(&files[1], None)
+            .await
+            .unwrap();
+        assert_eq!(parsed.records.len(), 1);
+        assert_eq!(
+            parsed.records[0].identity(),
+            "550e8400-e29b-41d4-a716-446655440001"
+        );
+        assert_eq!(parsed.rejected.len(), 1);
+        assert_eq!(parsed.rejected[0].line_number, 2);
+        assert_eq!(
+            parsed.rejected[0].source_file,
+            files[1].to_string_lossy().to_string()
+        );
+        assert!(parsed.checkpoint.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reads_tar_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_reads_bundle(&create_tar(temp_dir.path())).await;
+    }
+
+    #[tokio::test]
+    async fn test_reads_tar_gz_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_reads_bundle(&create_tar_gz(temp_dir.path())).await;
+    }
+
+    #[tokio::test]
+    async fn test_reads_zip_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_reads_bundle(&create_zip(temp_dir.path())).await;
+    }
+
+    #[tokio::test]
+    async fn test_stream_log_file_reads_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = create_tar_gz(temp_dir.path());
+        let file_path = ArchiveLogRepository::entry_path(
+            &archive_path,
+            "projects/-Users-me-app/session2.jsonl",
+        );
+
+        let repo = ArchiveLogRepository::new();
+        let items: Vec<_> = repo
+            .stream_log_file(file_path, None)
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0], LogStreamItem::Record(_)));
+        assert!(matches!(items[1], LogStreamItem::Rejected(_)));
+    }
+
+    #[tokio::test]
+    async fn test_indexes_archive_once_and_reads_entries_in_any_order() {
+        let temp_dir = TempDir::new().unwrap();
+        for archive_path in [
+            create_tar(temp_dir.path()),
+            create_tar_gz(temp_dir.path()),
+            create_zip(temp_dir.path()),
+        ] {
+            let repo = ArchiveLogRepository::new();
+            let mut files = repo
+                .discover_log_files(archive_path.to_str().unwrap())
+                .await
+                .unwrap();
+            files.reverse();
+
+            let mut uuids = Vec::new();
+            for file in &files {
+                for record in repo.parse_log_file(file).await.unwrap() {
+                    uuids.push(record.identity().to_string());
+                }
+            }
+
+            assert_eq!(
+                uuids,
+                vec![
+                    "550e8400-e29b-41d4-a716-446655440001",
+                    "550e8400-e29b-41d4-a716-446655440000",
+                ]
+            );
+            assert_eq!(repo.indexes.lock().unwrap().len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_modified_at_from_tar_header() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = create_tar(temp_dir.path());
+        let file_path = ArchiveLogRepository::entry_path(
+            &archive_path,
+            "projects/-Users-me-app/session1.jsonl",
+        );
+
+        let repo = ArchiveLogRepository::new();
+        let modified_at = repo.modified_at(&file_path).await.unwrap();
+
+        assert_eq!(modified_at, DateTime::from_timestamp(1_735_000_000, 0));
+    }
+
+    #[tokio::test]
+    async fn test_parse_missing_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = create_zip(temp_dir.path());
+        let file_path = ArchiveLogRepository::entry_path(&archive_path, "missing.jsonl");
+
+        let repo = ArchiveLogRepository::new();
+        let result = repo.parse_log_file(&file_path).await;
+
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Log file not found in archive"));
+    }
+
+    #[tokio::test]
+    async fn test_discover_nonexistent_archive() {
+        let repo = ArchiveLogRepository::new();
+        let result = repo.discover_log_files("/nonexistent/bundle.tar.gz").await;
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_split_entry_path_with_separator_in_archive_dir() {
+        let path = Path::new("/tmp/odd!/dir/bundle.zip!/app/s1.jsonl");
+        let (archive_path, entry_name) = ArchiveLogRepository::split_entry_path(path).unwrap();
+
+        assert_eq!(archive_path, PathBuf::from("/tmp/odd!/dir/bundle.zip"));
+        assert_eq!(entry_name, "app/s1.jsonl");
+    }
+
+    #[test]
+    fn test_split_entry_path_not_an_archive() {
+        let result = ArchiveLogRepository::split_entry_path(Path::new("/logs/s1.jsonl"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_normalize_entry_name() {
+        assert_eq!(
+            ArchiveLogRepository::normalize_entry_name("./projects/a.jsonl"),
+            "projects/a.jsonl"
+        );
+        assert_eq!(
+            ArchiveLogRepository::normalize_entry_name("/projects/a.jsonl"),
+            "projects/a.jsonl"
+        );
+    }
+}
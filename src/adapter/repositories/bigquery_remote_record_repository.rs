@@ -0,0 +1,38 @@
+//! BigQuery Remote Record Repository Implementation
+//!
+//! RemoteRecordRepositoryのBigQuery実装
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+use crate::adapter::bigquery::client::BigQueryClientFactory;
+use crate::adapter::bigquery::record_query::query_uploaded_records_with_factory;
+use crate::adapter::config::Config;
+use crate::domain::repositories::remote_record_repository::{RemoteRecord, RemoteRecordRepository};
+
+/// BigQueryリモートレコードリポジトリ
+pub struct BigQueryRemoteRecordRepository {
+    factory: Arc<dyn BigQueryClientFactory>,
+    config: Config,
+}
+
+impl BigQueryRemoteRecordRepository {
+    /// 新しいリポジトリを作成
+    pub fn new(factory: Arc<dyn BigQueryClientFactory>, config: Config) -> Self {
+        Self { factory, config }
+    }
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[async_trait]
+impl RemoteRecordRepository for BigQueryRemoteRecordRepository {
+    async fn find_records(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<RemoteRecord>> {
+        query_uploaded_records_with_factory(self.factory.as_ref(), &self.config, since, until).await
+    }
+}
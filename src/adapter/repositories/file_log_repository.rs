@@ -4,15 +4,92 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use log::{info, warn};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use log::{debug, info, warn};
 use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tokio::sync::mpsc;
 use walkdir::WalkDir;
 
-use crate::domain::entities::session_log::SessionLogInput;
-use crate::domain::repositories::log_repository::LogRepository;
+use crate::adapter::repositories::archive_log_repository::ArchiveLogRepository;
+use crate::domain::entities::file_checkpoint::FileCheckpoint;
+use crate::domain::entities::rejected_line::RejectedLine;
+use crate::domain::entities::transcript_record::TranscriptRecord;
+use crate::domain::repositories::log_repository::{LogRepository, LogStream, LogStreamItem};
+
+/// ストリーム読み込み時のチャネル容量（先読みする最大行数）
+const STREAM_CHANNEL_CAPACITY: usize = 256;
+
+/// ログファイルの圧縮形式（拡張子で判定）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogCompression {
+    /// `.jsonl`
+    Plain,
+    /// `.jsonl.gz`
+    Gzip,
+    /// `.jsonl.zst`
+    Zstd,
+}
+
+impl LogCompression {
+    /// ログファイルであれば圧縮形式を返す（ログファイルでなければ `None`）
+    fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        if name.ends_with(".jsonl") {
+            Some(Self::Plain)
+        } else if name.ends_with(".jsonl.gz") {
+            Some(Self::Gzip)
+        } else if name.ends_with(".jsonl.zst") {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// 展開後の `offset` バイト目から読むリーダーを作成
+    ///
+    /// 圧縮ファイルはシークできないため、先頭から展開して読み飛ばす。
+    /// 連結された gzip メンバー・zstd フレームも続けて展開する。
+    fn open(self, file_path: &Path, offset: u64) -> Result<Box<dyn BufRead>> {
+        let mut file = fs::File::open(file_path)
+            .context(format!("Failed to read log file: {}", file_path.display()))?;
+
+        let mut reader: Box<dyn BufRead> = match self {
+            Self::Plain => {
+                file.seek(SeekFrom::Start(offset))
+                    .context(format!("Failed to seek log file: {}", file_path.display()))?;
+                return Ok(Box::new(BufReader::new(file)));
+            }
+            Self::Gzip => Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file))),
+            Self::Zstd => Box::new(BufReader::new(
+                zstd::stream::read::Decoder::new(file).context(format!(
+                    "Failed to open zstd log file: {}",
+                    file_path.display()
+                ))?,
+            )),
+        };
+
+        let skipped = io::copy(&mut reader.by_ref().take(offset), &mut io::sink()).context(
+            format!("Failed to decompress log file: {}", file_path.display()),
+        )?;
+        if skipped < offset {
+            anyhow::bail!(
+                "Decompressed log file is shorter than its checkpoint: {}",
+                file_path.display()
+            );
+        }
+
+        Ok(reader)
+    }
+}
 
 /// ファイルシステムベースのログリポジトリ
+///
+/// `.jsonl` に加え、gzip（`.jsonl.gz`）・zstd（`.jsonl.zst`）で圧縮されたログも
+/// 透過的に展開して読み込む。
 pub struct FileLogRepository;
 
 impl FileLogRepository {
@@ -39,7 +116,7 @@ impl FileLogRepository {
             .filter_map(|e| e.ok())
         {
             let path = entry.path();
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+            if path.is_file() && LogCompression::detect(path).is_some() {
                 log_files.push(path.to_path_buf());
             }
         }
@@ -53,34 +130,219 @@ impl FileLogRepository {
         Ok(log_files)
     }
 
-    /// ログファイルをパースする（生データのみ、メタデータなし）
-    fn parse_log_file_raw(file_path: &PathBuf) -> Result<Vec<SessionLogInput>> {
-        let content = fs::read_to_string(file_path)
+    /// ファイルの圧縮形式（未知の拡張子は非圧縮として扱う）
+    fn compression(file_path: &Path) -> LogCompression {
+        LogCompression::detect(file_path).unwrap_or(LogCompression::Plain)
+    }
+
+    /// ファイルの同一性情報（inode, サイズ, 更新時刻）を取得
+    fn file_fingerprint(metadata: &fs::Metadata) -> (Option<u64>, u64, Option<i64>) {
+        #[cfg(unix)]
+        let inode = {
+            use std::os::unix::fs::MetadataExt;
+            Some(metadata.ino())
+        };
+        #[cfg(not(unix))]
+        let inode = None;
+
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64);
+
+        (inode, metadata.len(), mtime)
+    }
+
+    /// ログファイルが封印済みか削除済みかを判定（同期処理）
+    ///
+    /// アーカイブ内のエントリ（`archive!/entry`）はアーカイブが削除されていれば封印済みとする。
+    fn is_sealed_sync(file_path: &Path, checkpoint: Option<&FileCheckpoint>) -> Result<bool> {
+        if let Ok((archive_path, _)) = ArchiveLogRepository::split_entry_path(file_path) {
+            return Ok(!archive_path.exists());
+        }
+
+        let metadata = match fs::metadata(file_path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+            Err(e) => {
+                return Err(e).context(format!("Failed to read log file: {}", file_path.display()))
+            }
+        };
+        let Some(cp) = checkpoint else {
+            return Ok(false);
+        };
+
+        let (inode, size, mtime) = Self::file_fingerprint(&metadata);
+        Ok(match Self::compression(file_path) {
+            LogCompression::Plain => cp.is_unchanged(inode, size, mtime),
+            // 圧縮ファイルのオフセットは展開後のバイト数なので、読み込み後に変更がないことだけを確認する
+            _ => cp.is_same_file(inode, size, mtime),
+        })
+    }
+
+    /// チェックポイント以降を1行ずつ読み込む（同期処理）
+    ///
+    /// 圧縮ファイルのオフセットは展開後のバイト数で記録するため、ファイルサイズとは比較せず、
+    /// 同一性（inode / サイズ / 更新時刻）が変わっていなければ読み終えたものとして扱う。
+    /// 行の扱いは `read_lines` を参照。
+    ///
+    /// # Returns
+    ///
+    /// 読み込み後の新しいチェックポイント
+    fn read_log_file_from_checkpoint<F>(
+        file_path: &Path,
+        checkpoint: Option<&FileCheckpoint>,
+        on_item: F,
+    ) -> Result<FileCheckpoint>
+    where
+        F: FnMut(LogStreamItem) -> bool,
+    {
+        let metadata = fs::metadata(file_path)
             .context(format!("Failed to read log file: {}", file_path.display()))?;
+        let (inode, size, mtime) = Self::file_fingerprint(&metadata);
+        let compression = Self::compression(file_path);
+        let compressed = compression != LogCompression::Plain;
+
+        let (start_offset, start_line) = match checkpoint {
+            Some(cp)
+                if cp.is_unchanged(inode, size, mtime)
+                    || (compressed && cp.is_same_file(inode, size, mtime)) =>
+            {
+                debug!("Skipping unchanged log file: {}", file_path.display());
+                return Ok(cp.clone());
+            }
+            Some(cp) => {
+                let replaced = if compressed {
+                    cp.is_rewritten(inode, size, mtime)
+                } else {
+                    cp.is_replaced(inode, size, mtime)
+                };
+                if replaced {
+                    info!(
+                        "Log file was truncated or replaced, rescanning: {}",
+                        file_path.display()
+                    );
+                    (0, 0)
+                } else {
+                    (cp.offset, cp.lines)
+                }
+            }
+            None => (0, 0),
+        };
 
-        let mut parsed_logs = Vec::new();
+        let mut reader = compression.open(file_path, start_offset)?;
+        let (offset, line_num) =
+            Self::read_lines(&mut reader, file_path, start_offset, start_line, on_item)?;
 
-        for (line_num, line) in content.lines().enumerate() {
-            if line.trim().is_empty() {
-                continue;
+        Ok(FileCheckpoint::new(inode, size, mtime, offset, line_num))
+    }
+
+    /// `offset` バイト目（`line_num` 行目の後）から1行ずつ読み込む（同期処理）
+    ///
+    /// 改行で終わっていない末尾の行は書き込み途中の可能性があるため、
+    /// オフセットを進めずに保留する（JSONとして完結していれば取り込みのみ行う）。
+    /// パースできなかった完結行は `LogStreamItem::Rejected` として渡す。
+    /// `on_item` が `false` を返した場合は読み込みを中断する。
+    ///
+    /// # Returns
+    ///
+    /// 読み込み後のオフセットと行数
+    pub(crate) fn read_lines<F>(
+        reader: &mut dyn BufRead,
+        file_path: &Path,
+        mut offset: u64,
+        mut line_num: u64,
+        mut on_item: F,
+    ) -> Result<(u64, u64)>
+    where
+        F: FnMut(LogStreamItem) -> bool,
+    {
+        let mut buf = Vec::new();
+
+        loop {
+            buf.clear();
+            let read = reader
+                .read_until(b'\n', &mut buf)
+                .context(format!("Failed to read log file: {}", file_path.display()))?;
+            if read == 0 {
+                break;
             }
 
-            match serde_json::from_str::<SessionLogInput>(line) {
-                Ok(input) => {
-                    parsed_logs.push(input);
+            let complete = buf.ends_with(b"\n");
+            let line = String::from_utf8_lossy(&buf);
+            let line = line.trim();
+
+            if !complete {
+                // 書き込み途中の可能性がある末尾行: オフセットは進めない
+                if let Ok(record) = TranscriptRecord::from_json_line(line) {
+                    on_item(LogStreamItem::Record(Box::new(record)));
+                } else {
+                    debug!(
+                        "Holding back incomplete trailing line in {}",
+                        file_path.display()
+                    );
                 }
+                break;
+            }
+
+            offset += read as u64;
+            line_num += 1;
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let item = match TranscriptRecord::from_json_line(line) {
+                Ok(record) => LogStreamItem::Record(Box::new(record)),
                 Err(e) => {
-                    log::warn!(
+                    warn!(
                         "Failed to parse line {} in {}: {}",
-                        line_num + 1,
+                        line_num,
                         file_path.display(),
                         e
                     );
+                    LogStreamItem::Rejected(Box::new(RejectedLine::new(
+                        file_path.to_string_lossy(),
+                        line_num,
+                        e.to_string(),
+                        line,
+                    )))
                 }
+            };
+            if !on_item(item) {
+                break;
             }
         }
 
-        Ok(parsed_logs)
+        Ok((offset, line_num))
+    }
+
+    /// 読み込みをブロッキングスレッドで行い、有界チャネル経由で1行ずつ流すストリームを作成
+    ///
+    /// 消費側が遅い場合は読み込み側が待機するため、メモリ使用量はチャネル容量で抑えられる。
+    /// `read` は読み込んだ要素を渡された関数に渡し（`false` が返れば中断する）、
+    /// 読み終えた時点のチェックポイント（対応していなければ `None`）を返す。
+    pub(crate) fn spawn_stream<'a, F>(read: F) -> LogStream<'a>
+    where
+        F: FnOnce(&mut dyn FnMut(LogStreamItem) -> bool) -> Result<Option<FileCheckpoint>>
+            + Send
+            + 'static,
+    {
+        stream::once(async move {
+            let (tx, rx) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+            tokio::task::spawn_blocking(move || {
+                let result = read(&mut |item| tx.blocking_send(Ok(item)).is_ok());
+                if let Some(item) = result.transpose() {
+                    let _ = tx.blocking_send(item.map(LogStreamItem::Checkpoint));
+                }
+            });
+            stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|item| (item, rx))
+            })
+        })
+        .flatten()
+        .boxed()
     }
 }
 
@@ -95,13 +357,34 @@ impl LogRepository for FileLogRepository {
             .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
     }
 
-    async fn parse_log_file(&self, file_path: &Path) -> Result<Vec<SessionLogInput>> {
-        // 生のJSONをパースするだけ（メタデータ付与は上位層で行う）
+    async fn modified_at(&self, file_path: &Path) -> Result<Option<DateTime<Utc>>> {
+        let metadata = tokio::fs::metadata(file_path)
+            .await
+            .context(format!("Failed to read log file: {}", file_path.display()))?;
+        Ok(metadata.modified().ok().map(DateTime::<Utc>::from))
+    }
+
+    async fn is_sealed(
+        &self,
+        file_path: &Path,
+        checkpoint: Option<&FileCheckpoint>,
+    ) -> Result<bool> {
         let file_path = file_path.to_path_buf();
-        tokio::task::spawn_blocking(move || Self::parse_log_file_raw(&file_path))
+        let checkpoint = checkpoint.cloned();
+        tokio::task::spawn_blocking(move || Self::is_sealed_sync(&file_path, checkpoint.as_ref()))
             .await
             .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
     }
+
+    fn stream_log_file(
+        &self,
+        file_path: PathBuf,
+        checkpoint: Option<FileCheckpoint>,
+    ) -> LogStream<'_> {
+        Self::spawn_stream(move |on_item| {
+            Self::read_log_file_from_checkpoint(&file_path, checkpoint.as_ref(), on_item).map(Some)
+        })
+    }
 }
 
 impl Default for FileLogRepository {
@@ -144,6 +427,33 @@ mod tests {
         assert!(result.iter().all(|p| p.extension().unwrap() == "jsonl"));
     }
 
+    #[tokio::test]
+    async fn test_discover_log_files_finds_compressed_logs() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path();
+
+        create_test_log_file(log_dir, "session1.jsonl", "");
+        create_test_log_file(log_dir, "session2.jsonl.gz", "");
+        create_test_log_file(log_dir, "session3.jsonl.zst", "");
+        create_test_log_file(log_dir, "backup.tar.gz", ""); // Should be ignored
+
+        let repo = FileLogRepository::new();
+        let mut result = repo
+            .discover_log_files(log_dir.to_str().unwrap())
+            .await
+            .unwrap();
+        result.sort();
+
+        let names: Vec<_> = result
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["session1.jsonl", "session2.jsonl.gz", "session3.jsonl.zst"]
+        );
+    }
+
     #[tokio::test]
     async fn test_discover_log_files_empty_directory() {
         let temp_dir = TempDir::new().unwrap();
@@ -205,7 +515,7 @@ mod tests {
         let result = repo.parse_log_file(&file_path).await.unwrap();
 
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0].uuid, "550e8400-e29b-41d4-a716-446655440000");
+        assert_eq!(result[0].identity(), "550e8400-e29b-41d4-a716-446655440000");
     }
 
     #[tokio::test]
@@ -233,8 +543,8 @@ invalid json line
 
         // Should parse 2 valid lines and skip the invalid one
         assert_eq!(result.len(), 2);
-        assert_eq!(result[0].uuid, "550e8400-e29b-41d4-a716-446655440000");
-        assert_eq!(result[1].uuid, "550e8400-e29b-41d4-a716-446655440001");
+        assert_eq!(result[0].identity(), "550e8400-e29b-41d4-a716-446655440000");
+        assert_eq!(result[1].identity(), "550e8400-e29b-41d4-a716-446655440001");
     }
 
     #[tokio::test]
@@ -246,4 +556,433 @@ invalid json line
 
         assert!(result.is_err());
     }
+
+    const LINE_1: &str = r#"{"uuid":"uuid-1","timestamp":"2024-01-01T00:00:00Z","sessionId":"s1","type":"user","message":{}}"#;
+    const LINE_2: &str = r#"{"uuid":"uuid-2","timestamp":"2024-01-01T00:00:01Z","sessionId":"s1","type":"user","message":{}}"#;
+    const LINE_3: &str = r#"{"uuid":"uuid-3","timestamp":"2024-01-01T00:00:02Z","sessionId":"s1","type":"user","message":{}}"#;
+
+    fn append(path: &Path, content: &str) {
+        let mut file = fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_parse_incremental_resumes_from_checkpoint() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_log_file(
+            temp_dir.path(),
+            "session.jsonl",
+            &format!("{}\n{}\n", LINE_1, LINE_2),
+        );
+
+        let repo = FileLogRepository::new();
+        let first = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap();
+        assert_eq!(first.records.len(), 2);
+        let checkpoint = first.checkpoint.unwrap();
+        assert_eq!(checkpoint.lines, 2);
+        assert_eq!(checkpoint.offset, fs::metadata(&file_path).unwrap().len());
+
+        append(&file_path, &format!("{}\n", LINE_3));
+
+        let second = repo
+            .parse_log_file_incremental(&file_path, Some(&checkpoint))
+            .await
+            .unwrap();
+        assert_eq!(second.records.len(), 1);
+        assert_eq!(second.records[0].identity(), "uuid-3");
+        assert_eq!(second.checkpoint.unwrap().lines, 3);
+    }
+
+    #[tokio::test]
+    async fn test_parse_incremental_unchanged_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path =
+            create_test_log_file(temp_dir.path(), "session.jsonl", &format!("{}\n", LINE_1));
+
+        let repo = FileLogRepository::new();
+        let first = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap();
+        let checkpoint = first.checkpoint.unwrap();
+
+        let second = repo
+            .parse_log_file_incremental(&file_path, Some(&checkpoint))
+            .await
+            .unwrap();
+        assert!(second.records.is_empty());
+        assert_eq!(second.checkpoint.unwrap(), checkpoint);
+    }
+
+    #[tokio::test]
+    async fn test_parse_incremental_holds_back_partial_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let partial = &LINE_2[..20];
+        let file_path = create_test_log_file(
+            temp_dir.path(),
+            "session.jsonl",
+            &format!("{}\n{}", LINE_1, partial),
+        );
+
+        let repo = FileLogRepository::new();
+        let first = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap();
+        assert_eq!(first.records.len(), 1);
+        let checkpoint = first.checkpoint.unwrap();
+        assert_eq!(checkpoint.offset, (LINE_1.len() + 1) as u64);
+
+        // 残りが書き込まれたら保留していた行を読み込む
+        append(&file_path, &format!("{}\n", &LINE_2[20..]));
+
+        let second = repo
+            .parse_log_file_incremental(&file_path, Some(&checkpoint))
+            .await
+            .unwrap();
+        assert_eq!(second.records.len(), 1);
+        assert_eq!(second.records[0].identity(), "uuid-2");
+    }
+
+    #[tokio::test]
+    async fn test_parse_incremental_complete_line_without_newline() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_log_file(temp_dir.path(), "session.jsonl", LINE_1);
+
+        let repo = FileLogRepository::new();
+        let result = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap();
+
+        // 取り込むがオフセットは進めない（次回再読込し、UUIDで重複排除される）
+        assert_eq!(result.records.len(), 1);
+        assert_eq!(result.checkpoint.unwrap().offset, 0);
+    }
+
+    #[tokio::test]
+    async fn test_parse_incremental_rejects_invalid_lines_with_absolute_line_number() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_log_file(
+            temp_dir.path(),
+            "session.jsonl",
+            &format!("{}\n{}\n", LINE_1, LINE_2),
+        );
+
+        let repo = FileLogRepository::new();
+        let checkpoint = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap()
+            .checkpoint
+            .unwrap();
+
+        append(&file_path, &format!("{{\"uuid\":42}}\n{}\n", LINE_3));
+
+        let result = repo
+            .parse_log_file_incremental(&file_path, Some(&checkpoint))
+            .await
+            .unwrap();
+        assert_eq!(result.records.len(), 1);
+        assert_eq!(result.rejected.len(), 1);
+        let reject = &result.rejected[0];
+        assert_eq!(reject.line_number, 3);
+        assert_eq!(reject.raw, r#"{"uuid":42}"#);
+        assert_eq!(reject.source_file, file_path.to_string_lossy());
+        assert!(!reject.error.is_empty());
+        // 隔離した行もコミット対象（次回は読み飛ばす）
+        assert_eq!(result.checkpoint.unwrap().lines, 4);
+    }
+
+    #[tokio::test]
+    async fn test_parse_incremental_reads_records_without_uuid() {
+        let temp_dir = TempDir::new().unwrap();
+        let content = format!(
+            "{}\n{}\n{}\n",
+            r#"{"type":"summary","summary":"Fix login bug","leafUuid":"uuid-1"}"#,
+            r#"{"type":"file-history-snapshot","messageId":"m1","snapshot":{},"isSnapshotUpdate":false}"#,
+            LINE_1
+        );
+        let file_path = create_test_log_file(temp_dir.path(), "session.jsonl", &content);
+
+        let repo = FileLogRepository::new();
+        let result = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap();
+
+        // uuid / sessionId を持たないレコードも隔離されずに読み込まれる
+        assert!(result.rejected.is_empty());
+        assert_eq!(result.records.len(), 3);
+        assert!(matches!(result.records[0], TranscriptRecord::Summary(_)));
+        assert!(matches!(
+            result.records[1],
+            TranscriptRecord::FileHistorySnapshot(_)
+        ));
+        assert!(matches!(result.records[2], TranscriptRecord::Message(_)));
+    }
+
+    #[tokio::test]
+    async fn test_parse_incremental_rescans_truncated_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_log_file(
+            temp_dir.path(),
+            "session.jsonl",
+            &format!("{}\n{}\n", LINE_1, LINE_2),
+        );
+
+        let repo = FileLogRepository::new();
+        let checkpoint = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap()
+            .checkpoint
+            .unwrap();
+
+        // ファイルを短い内容で書き直す
+        fs::write(&file_path, format!("{}\n", LINE_3)).unwrap();
+
+        let result = repo
+            .parse_log_file_incremental(&file_path, Some(&checkpoint))
+            .await
+            .unwrap();
+        assert_eq!(result.records.len(), 1);
+        assert_eq!(result.records[0].identity(), "uuid-3");
+        assert_eq!(result.checkpoint.unwrap().lines, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_log_file_yields_records_then_checkpoint() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_log_file(
+            temp_dir.path(),
+            "session.jsonl",
+            &format!("{}\ninvalid json line\n{}\n", LINE_1, LINE_2),
+        );
+
+        let repo = FileLogRepository::new();
+        let items: Vec<LogStreamItem> = repo
+            .stream_log_file(file_path.clone(), None)
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(items.len(), 4);
+        assert!(
+            matches!(&items[0], LogStreamItem::Record(record) if record.identity() == "uuid-1")
+        );
+        assert!(
+            matches!(&items[1], LogStreamItem::Rejected(reject) if reject.line_number == 2 && reject.raw == "invalid json line")
+        );
+        assert!(
+            matches!(&items[2], LogStreamItem::Record(record) if record.identity() == "uuid-2")
+        );
+        match &items[3] {
+            LogStreamItem::Checkpoint(cp) => {
+                assert_eq!(cp.lines, 3);
+                assert_eq!(cp.offset, fs::metadata(&file_path).unwrap().len());
+            }
+            other => panic!("expected checkpoint, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_log_file_nonexistent() {
+        let repo = FileLogRepository::new();
+        let items: Vec<Result<LogStreamItem>> = repo
+            .stream_log_file(PathBuf::from("/nonexistent/file.jsonl"), None)
+            .collect()
+            .await;
+
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_modified_at() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_log_file(temp_dir.path(), "session.jsonl", "");
+
+        let repo = FileLogRepository::new();
+        let modified_at = repo.modified_at(&file_path).await.unwrap().unwrap();
+
+        assert!(modified_at <= Utc::now());
+        assert!(repo
+            .modified_at(Path::new("/nonexistent/file.jsonl"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_is_sealed() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path =
+            create_test_log_file(temp_dir.path(), "session.jsonl", &format!("{}\n", LINE_1));
+
+        let repo = FileLogRepository::new();
+        let checkpoint = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap()
+            .checkpoint
+            .unwrap();
+
+        // 最後まで読み込み済みで変更なし
+        assert!(repo.is_sealed(&file_path, Some(&checkpoint)).await.unwrap());
+        // チェックポイントが無ければ未確認
+        assert!(!repo.is_sealed(&file_path, None).await.unwrap());
+
+        // 追記されたら封印済みではない
+        append(&file_path, &format!("{}\n", LINE_2));
+        assert!(!repo.is_sealed(&file_path, Some(&checkpoint)).await.unwrap());
+
+        // 削除済み
+        fs::remove_file(&file_path).unwrap();
+        assert!(repo.is_sealed(&file_path, Some(&checkpoint)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_is_sealed_archive_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive = create_test_log_file(temp_dir.path(), "bundle.tar", "");
+        let entry = ArchiveLogRepository::entry_path(&archive, "app/session.jsonl");
+
+        let repo = FileLogRepository::new();
+        assert!(!repo.is_sealed(&entry, None).await.unwrap());
+
+        fs::remove_file(&archive).unwrap();
+        assert!(repo.is_sealed(&entry, None).await.unwrap());
+    }
+
+    fn gzip(content: &str) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_gzip_log_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("session.jsonl.gz");
+        fs::write(
+            &file_path,
+            gzip(&format!("{}\nnot json\n{}\n", LINE_1, LINE_2)),
+        )
+        .unwrap();
+
+        let repo = FileLogRepository::new();
+        let result = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.records.len(), 2);
+        assert_eq!(result.records[1].identity(), "uuid-2");
+        // 隔離行の元ファイルは圧縮ファイルのパス
+        assert_eq!(result.rejected.len(), 1);
+        assert_eq!(result.rejected[0].source_file, file_path.to_string_lossy());
+        assert_eq!(result.rejected[0].line_number, 2);
+        assert_eq!(repo.parse_log_file(&file_path).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_parse_zstd_log_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("session.jsonl.zst");
+        let content = format!("{}\n{}\n", LINE_1, LINE_2);
+        fs::write(
+            &file_path,
+            zstd::stream::encode_all(content.as_bytes(), 0).unwrap(),
+        )
+        .unwrap();
+
+        let repo = FileLogRepository::new();
+        let result = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.records.len(), 2);
+        let checkpoint = result.checkpoint.unwrap();
+        // オフセットは展開後のバイト数
+        assert_eq!(checkpoint.offset, content.len() as u64);
+        assert_eq!(checkpoint.lines, 2);
+    }
+
+    #[tokio::test]
+    async fn test_parse_gzip_resumes_after_appended_member() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("session.jsonl.gz");
+        fs::write(&file_path, gzip(&format!("{}\n{}\n", LINE_1, LINE_2))).unwrap();
+
+        let repo = FileLogRepository::new();
+        let checkpoint = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap()
+            .checkpoint
+            .unwrap();
+
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&file_path)
+            .unwrap();
+        file.write_all(&gzip(&format!("{}\n", LINE_3))).unwrap();
+
+        let result = repo
+            .parse_log_file_incremental(&file_path, Some(&checkpoint))
+            .await
+            .unwrap();
+        assert_eq!(result.records.len(), 1);
+        assert_eq!(result.records[0].identity(), "uuid-3");
+        assert_eq!(result.checkpoint.unwrap().lines, 3);
+    }
+
+    #[tokio::test]
+    async fn test_compressed_checkpoint_skips_read_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("session.jsonl.gz");
+        // 展開後のサイズが圧縮後のサイズを大きく上回るログ
+        let content = format!("{}\n", LINE_1).repeat(200);
+        fs::write(&file_path, gzip(&content)).unwrap();
+
+        let repo = FileLogRepository::new();
+        let checkpoint = repo
+            .parse_log_file_incremental(&file_path, None)
+            .await
+            .unwrap()
+            .checkpoint
+            .unwrap();
+        assert_eq!(checkpoint.offset, content.len() as u64);
+        assert!(checkpoint.offset > checkpoint.size);
+
+        // 変更がなければ読み終えたものとして扱う
+        assert!(repo.is_sealed(&file_path, Some(&checkpoint)).await.unwrap());
+        let result = repo
+            .parse_log_file_incremental(&file_path, Some(&checkpoint))
+            .await
+            .unwrap();
+        assert!(result.records.is_empty());
+        assert_eq!(result.checkpoint.as_ref(), Some(&checkpoint));
+
+        // 追記されたメンバーだけを読み込む
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&file_path)
+            .unwrap();
+        file.write_all(&gzip(&format!("{}\n", LINE_3))).unwrap();
+        drop(file);
+
+        assert!(!repo.is_sealed(&file_path, Some(&checkpoint)).await.unwrap());
+        let result = repo
+            .parse_log_file_incremental(&file_path, Some(&checkpoint))
+            .await
+            .unwrap();
+        assert_eq!(result.records.len(), 1);
+        assert_eq!(result.records[0].identity(), "uuid-3");
+        assert_eq!(result.checkpoint.unwrap().lines, 201);
+    }
 }
@@ -4,26 +4,40 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use log::info;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
+use crate::adapter::atomic_write::write_atomic;
+use crate::domain::entities::file_checkpoint::FileCheckpoint;
 use crate::domain::repositories::state_repository::{
-    StateRepository, UploadState as DomainUploadState,
+    RecordHash, SessionUploads, StateRepository, UploadState as DomainUploadState,
 };
 
 /// JSONファイルベースの状態リポジトリ
+///
+/// 保存時はディスク上の最新の状態を読み直してマージし、一時ファイルに書き込んでから
+/// 置き換える（書き込み途中でプロセスが終了しても状態ファイルが壊れない）。
 pub struct JsonStateRepository;
 
 /// アップロード状態（JSON永続化用の内部表現）
-#[derive(Debug, Deserialize, Serialize)]
+///
+/// セッションに紐付いたUUIDは `sessions` 側にだけ書き、`uploaded_uuids` には
+/// セッション情報を持たない（記録前の）UUIDだけを残す。
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct UploadStateJson {
     last_upload_timestamp: Option<String>,
     uploaded_uuids: HashSet<String>,
     last_upload_batch_id: Option<String>,
     total_uploaded: u64,
+    #[serde(default)]
+    file_checkpoints: HashMap<String, FileCheckpoint>,
+    #[serde(default)]
+    sessions: HashMap<String, SessionUploads>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    record_hashes: HashMap<String, RecordHash>,
 }
 
 impl JsonStateRepository {
@@ -43,6 +57,9 @@ impl JsonStateRepository {
                 uploaded_uuids: HashSet::new(),
                 last_upload_batch_id: None,
                 total_uploaded: 0,
+                file_checkpoints: HashMap::new(),
+                sessions: HashMap::new(),
+                record_hashes: HashMap::new(),
             });
         }
 
@@ -60,7 +77,19 @@ impl JsonStateRepository {
     }
 
     /// ファイルに状態を保存する（同期処理）
+    ///
+    /// 読み込み後に他のプロセスが保存した内容を失わないよう、ディスク上の状態とマージしてから書き込む。
     fn save_sync(path: &str, state: &UploadStateJson) -> Result<()> {
+        let merged = match Self::read_existing(Path::new(path)) {
+            Some(on_disk) => Self::merge(on_disk, state),
+            None => state.clone(),
+        };
+
+        Self::replace_sync(path, &merged)
+    }
+
+    /// マージせずに状態を書き込む（同期処理）
+    fn replace_sync(path: &str, state: &UploadStateJson) -> Result<()> {
         let path = Path::new(path);
 
         // Create parent directory if it doesn't exist
@@ -71,7 +100,7 @@ impl JsonStateRepository {
         let json =
             serde_json::to_string_pretty(state).context("Failed to serialize upload state")?;
 
-        fs::write(path, json).context("Failed to write upload state file")?;
+        write_atomic(path, json.as_bytes()).context("Failed to write upload state file")?;
 
         info!(
             "Saved upload state: {} total records uploaded",
@@ -81,23 +110,104 @@ impl JsonStateRepository {
         Ok(())
     }
 
+    /// マージ用にディスク上の状態を読み込む（存在しない・壊れている場合は `None`）
+    fn read_existing(path: &Path) -> Option<UploadStateJson> {
+        let content = fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                warn!(
+                    "Overwriting unreadable upload state {}: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// ディスク上の状態に保存する状態をマージ
+    ///
+    /// UUIDとセッションは和集合を取り、チェックポイントは保存する側を優先して統合する。
+    /// 内容のハッシュはバージョンの新しい方（同じなら保存する側）を残す。
+    /// 最終アップロード情報は保存する側に無ければディスク上の値を残す。
+    fn merge(on_disk: UploadStateJson, state: &UploadStateJson) -> UploadStateJson {
+        let mut merged = on_disk;
+
+        merged
+            .uploaded_uuids
+            .extend(state.uploaded_uuids.iter().cloned());
+        for (session_id, session) in &state.sessions {
+            match merged.sessions.get_mut(session_id) {
+                Some(existing) => existing.merge(session),
+                None => {
+                    merged.sessions.insert(session_id.clone(), session.clone());
+                }
+            }
+        }
+        for (uuid, hash) in &state.record_hashes {
+            if merged
+                .record_hashes
+                .get(uuid)
+                .is_none_or(|existing| existing.version <= hash.version)
+            {
+                merged.record_hashes.insert(uuid.clone(), hash.clone());
+            }
+        }
+        merged.file_checkpoints.extend(
+            state
+                .file_checkpoints
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+        if state.last_upload_timestamp.is_some() {
+            merged.last_upload_timestamp = state.last_upload_timestamp.clone();
+        }
+        if state.last_upload_batch_id.is_some() {
+            merged.last_upload_batch_id = state.last_upload_batch_id.clone();
+        }
+        merged.total_uploaded = merged.total_uploaded.max(state.total_uploaded);
+
+        merged
+    }
+
     /// JSON形式からDomain形式に変換
     fn to_domain_state(json_state: UploadStateJson) -> DomainUploadState {
+        let mut uploaded_uuids = json_state.uploaded_uuids;
+        for session in json_state.sessions.values() {
+            uploaded_uuids.extend(session.uuids.iter().cloned());
+        }
+
         DomainUploadState {
             last_upload_timestamp: json_state.last_upload_timestamp,
-            uploaded_uuids: json_state.uploaded_uuids,
+            uploaded_uuids,
             last_upload_batch_id: json_state.last_upload_batch_id,
             total_uploaded: json_state.total_uploaded,
+            file_checkpoints: json_state.file_checkpoints,
+            sessions: json_state.sessions,
+            record_hashes: json_state.record_hashes,
+            records_omitted: false,
         }
     }
 
     /// Domain形式からJSON形式に変換
     fn from_domain_state(domain_state: &DomainUploadState) -> UploadStateJson {
+        // セッション側に書くUUIDは重複して書かない
+        let mut uploaded_uuids = domain_state.uploaded_uuids.clone();
+        for session in domain_state.sessions.values() {
+            for uuid in &session.uuids {
+                uploaded_uuids.remove(uuid);
+            }
+        }
+
         UploadStateJson {
             last_upload_timestamp: domain_state.last_upload_timestamp.clone(),
-            uploaded_uuids: domain_state.uploaded_uuids.clone(),
+            uploaded_uuids,
             last_upload_batch_id: domain_state.last_upload_batch_id.clone(),
             total_uploaded: domain_state.total_uploaded,
+            file_checkpoints: domain_state.file_checkpoints.clone(),
+            sessions: domain_state.sessions.clone(),
+            record_hashes: domain_state.record_hashes.clone(),
         }
     }
 }
@@ -122,6 +232,24 @@ impl StateRepository for JsonStateRepository {
 
         Ok(())
     }
+
+    async fn replace(&self, path: &str, state: &DomainUploadState) -> Result<()> {
+        let path = path.to_string();
+        let json_state = Self::from_domain_state(state);
+        tokio::task::spawn_blocking(move || Self::replace_sync(&path, &json_state))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))??;
+
+        Ok(())
+    }
+
+    async fn storage_size(&self, path: &str) -> Result<Option<u64>> {
+        match fs::metadata(path) {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).context("Failed to read upload state file size"),
+        }
+    }
 }
 
 impl Default for JsonStateRepository {
@@ -136,6 +264,20 @@ mod tests {
     use std::io::Write;
     use tempfile::{NamedTempFile, TempDir};
 
+    #[tokio::test]
+    async fn test_storage_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+        let path = state_path.to_str().unwrap();
+        let repo = JsonStateRepository::new();
+
+        assert_eq!(repo.storage_size(path).await.unwrap(), None);
+
+        repo.save(path, &DomainUploadState::new()).await.unwrap();
+        let size = repo.storage_size(path).await.unwrap();
+        assert_eq!(size, Some(fs::metadata(path).unwrap().len()));
+    }
+
     #[test]
     fn test_load_nonexistent_file() {
         let result = JsonStateRepository::load_sync("/nonexistent/path/state.json");
@@ -178,6 +320,12 @@ mod tests {
             uploaded_uuids: HashSet::from(["uuid-a".to_string(), "uuid-b".to_string()]),
             last_upload_batch_id: Some("batch-test".to_string()),
             total_uploaded: 50,
+            file_checkpoints: HashMap::from([(
+                "/logs/a.jsonl".to_string(),
+                FileCheckpoint::new(Some(7), 300, Some(1_000), 300, 3),
+            )]),
+            sessions: HashMap::new(),
+            record_hashes: HashMap::new(),
         };
 
         JsonStateRepository::save_sync(state_path.to_str().unwrap(), &state).unwrap();
@@ -189,6 +337,175 @@ mod tests {
         assert!(loaded.uploaded_uuids.contains("uuid-b"));
         assert_eq!(loaded.last_upload_batch_id.unwrap(), "batch-test");
         assert_eq!(loaded.total_uploaded, 50);
+        assert_eq!(loaded.file_checkpoints["/logs/a.jsonl"].offset, 300);
+    }
+
+    fn json_state(uuids: &[&str], total_uploaded: u64) -> UploadStateJson {
+        UploadStateJson {
+            last_upload_timestamp: None,
+            uploaded_uuids: uuids.iter().map(|u| u.to_string()).collect(),
+            last_upload_batch_id: None,
+            total_uploaded,
+            file_checkpoints: HashMap::new(),
+            sessions: HashMap::new(),
+            record_hashes: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_save_merges_concurrent_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+        let path = state_path.to_str().unwrap();
+
+        // 2つのプロセスが同じ状態を読み込み、それぞれアップロードした
+        let mut first = json_state(&["uuid-0", "uuid-a"], 2);
+        first.file_checkpoints.insert(
+            "/logs/a.jsonl".to_string(),
+            FileCheckpoint::new(Some(1), 100, Some(1), 100, 1),
+        );
+        let mut second = json_state(&["uuid-0", "uuid-b"], 2);
+        second.last_upload_batch_id = Some("batch-b".to_string());
+        second.file_checkpoints.insert(
+            "/logs/b.jsonl".to_string(),
+            FileCheckpoint::new(Some(2), 200, Some(2), 200, 2),
+        );
+
+        JsonStateRepository::save_sync(path, &first).unwrap();
+        JsonStateRepository::save_sync(path, &second).unwrap();
+
+        let loaded = JsonStateRepository::load_sync(path).unwrap();
+        assert_eq!(
+            loaded.uploaded_uuids,
+            HashSet::from([
+                "uuid-0".to_string(),
+                "uuid-a".to_string(),
+                "uuid-b".to_string()
+            ])
+        );
+        assert_eq!(loaded.file_checkpoints.len(), 2);
+        assert_eq!(loaded.last_upload_batch_id.as_deref(), Some("batch-b"));
+    }
+
+    #[test]
+    fn test_save_leaves_no_temp_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+
+        JsonStateRepository::save_sync(state_path.to_str().unwrap(), &json_state(&["u"], 1))
+            .unwrap();
+
+        let entries: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(entries, vec!["state.json"]);
+    }
+
+    #[test]
+    fn test_save_replaces_truncated_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+        fs::write(&state_path, r#"{"uploaded_uuids": ["uuid-1""#).unwrap();
+
+        JsonStateRepository::save_sync(state_path.to_str().unwrap(), &json_state(&["uuid-2"], 1))
+            .unwrap();
+
+        let loaded = JsonStateRepository::load_sync(state_path.to_str().unwrap()).unwrap();
+        assert_eq!(loaded.uploaded_uuids, HashSet::from(["uuid-2".to_string()]));
+    }
+
+    fn session(uuids: &[&str]) -> SessionUploads {
+        SessionUploads {
+            last_uploaded_at: chrono::Utc::now(),
+            latest_record_at: Some(chrono::Utc::now()),
+            source_files: ["/logs/a.jsonl".to_string()].into(),
+            uuids: uuids.iter().map(|u| u.to_string()).collect(),
+            compacted_until: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_uuids_are_written_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+        let path = state_path.to_str().unwrap();
+        let repo = JsonStateRepository::new();
+
+        let mut state = DomainUploadState::new();
+        state.uploaded_uuids = HashSet::from(["uuid-legacy".to_string(), "uuid-1".to_string()]);
+        state
+            .sessions
+            .insert("session-1".to_string(), session(&["uuid-1"]));
+        repo.save(path, &state).await.unwrap();
+
+        let on_disk = JsonStateRepository::load_sync(path).unwrap();
+        assert_eq!(
+            on_disk.uploaded_uuids,
+            HashSet::from(["uuid-legacy".to_string()])
+        );
+
+        let loaded = repo.load(path).await.unwrap();
+        assert_eq!(loaded.uploaded_uuids, state.uploaded_uuids);
+        assert_eq!(loaded.sessions, state.sessions);
+    }
+
+    #[tokio::test]
+    async fn test_record_hashes_keep_newest_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+        let path = state_path.to_str().unwrap();
+        let repo = JsonStateRepository::new();
+        let hash = |hash: &str, version: u32| RecordHash {
+            hash: hash.to_string(),
+            version,
+        };
+
+        // 別のプロセスが新しいバージョンを保存済み
+        let mut newer = DomainUploadState::new();
+        newer.record_hashes = HashMap::from([
+            ("uuid-1".to_string(), hash("hash-b", 1)),
+            ("uuid-2".to_string(), hash("hash-c", 0)),
+        ]);
+        repo.save(path, &newer).await.unwrap();
+
+        let mut stale = DomainUploadState::new();
+        stale.record_hashes = HashMap::from([
+            ("uuid-1".to_string(), hash("hash-a", 0)),
+            ("uuid-3".to_string(), hash("hash-d", 0)),
+        ]);
+        repo.save(path, &stale).await.unwrap();
+
+        let loaded = repo.load(path).await.unwrap();
+        assert_eq!(loaded.record_hashes.len(), 3);
+        assert_eq!(loaded.record_hashes["uuid-1"], hash("hash-b", 1));
+        assert!(loaded.is_content_changed("uuid-3", "hash-x"));
+    }
+
+    #[tokio::test]
+    async fn test_replace_drops_forgotten_uuids() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+        let path = state_path.to_str().unwrap();
+        let repo = JsonStateRepository::new();
+
+        let mut state = DomainUploadState::new();
+        state.uploaded_uuids = HashSet::from(["uuid-1".to_string()]);
+        state
+            .sessions
+            .insert("session-1".to_string(), session(&["uuid-1"]));
+        repo.save(path, &state).await.unwrap();
+
+        state.compact(chrono::Utc::now() + chrono::Duration::days(1), |_| true);
+
+        // saveはマージするので忘れたUUIDが戻る
+        repo.save(path, &state).await.unwrap();
+        assert!(repo.load(path).await.unwrap().is_uploaded("uuid-1"));
+
+        repo.replace(path, &state).await.unwrap();
+        let loaded = repo.load(path).await.unwrap();
+        assert!(!loaded.is_uploaded("uuid-1"));
+        assert!(loaded.sessions["session-1"].compacted_until.is_some());
     }
 
     #[test]
@@ -198,6 +515,9 @@ mod tests {
             uploaded_uuids: HashSet::from(["uuid-1".to_string()]),
             last_upload_batch_id: Some("batch-001".to_string()),
             total_uploaded: 10,
+            file_checkpoints: HashMap::new(),
+            sessions: HashMap::new(),
+            record_hashes: HashMap::new(),
         };
 
         let domain_state = JsonStateRepository::to_domain_state(json_state);
@@ -218,6 +538,10 @@ mod tests {
             uploaded_uuids: HashSet::from(["uuid-1".to_string()]),
             last_upload_batch_id: Some("batch-001".to_string()),
             total_uploaded: 10,
+            file_checkpoints: HashMap::new(),
+            sessions: HashMap::new(),
+            record_hashes: HashMap::new(),
+            records_omitted: false,
         };
 
         let json_state = JsonStateRepository::from_domain_state(&domain_state);
@@ -0,0 +1,186 @@
+//! JSONL Journal Repository Implementation
+//!
+//! JournalRepositoryのJSONL実装（エントリを1行1JSONで追記し、書き込みごとにディスクへ同期）
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use log::warn;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+use crate::domain::entities::journal_entry::JournalEntry;
+use crate::domain::repositories::journal_repository::JournalRepository;
+
+/// JSONLファイルベースのアップロードジャーナル
+pub struct JsonlJournalRepository;
+
+impl JsonlJournalRepository {
+    /// 新しいリポジトリを作成
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// ファイルからエントリを読み込む（同期処理）
+    fn load_sync(path: &str) -> Result<Vec<JournalEntry>> {
+        let path = Path::new(path);
+
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(path).context("Failed to read upload journal")?;
+
+        let mut entries = Vec::new();
+        for (line_num, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            // A run killed mid-write leaves a truncated last line
+            match serde_json::from_str::<JournalEntry>(line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!(
+                    "Skipping corrupt entry at line {} in {}: {}",
+                    line_num + 1,
+                    path.display(),
+                    e
+                ),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// ファイルにエントリを追記し、ディスクに同期する（同期処理）
+    fn append_sync(path: &str, entry: &JournalEntry) -> Result<()> {
+        let path = Path::new(path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create upload journal directory")?;
+        }
+
+        let mut line = serde_json::to_string(entry).context("Failed to serialize journal entry")?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context("Failed to open upload journal")?;
+        file.write_all(line.as_bytes())
+            .context("Failed to write upload journal")?;
+        file.sync_data().context("Failed to sync upload journal")?;
+
+        Ok(())
+    }
+
+    /// ファイルを削除する（同期処理）
+    fn clear_sync(path: &str) -> Result<()> {
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).context("Failed to remove upload journal"),
+        }
+    }
+}
+
+#[async_trait]
+impl JournalRepository for JsonlJournalRepository {
+    async fn load(&self, path: &str) -> Result<Vec<JournalEntry>> {
+        let path = path.to_string();
+        tokio::task::spawn_blocking(move || Self::load_sync(&path))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn append(&self, path: &str, entry: &JournalEntry) -> Result<()> {
+        let path = path.to_string();
+        let entry = entry.clone();
+        tokio::task::spawn_blocking(move || Self::append_sync(&path, &entry))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn clear(&self, path: &str) -> Result<()> {
+        let path = path.to_string();
+        tokio::task::spawn_blocking(move || Self::clear_sync(&path))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+}
+
+impl Default for JsonlJournalRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn journal_path(dir: &TempDir) -> String {
+        dir.path()
+            .join("sessync/upload-journal.jsonl")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn committed(batch_key: &str) -> JournalEntry {
+        JournalEntry::Committed {
+            batch_key: batch_key.to_string(),
+            uploaded_uuids: vec!["uuid-1".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_nonexistent_journal() {
+        let repo = JsonlJournalRepository::new();
+        let entries = repo
+            .load("/nonexistent/upload-journal.jsonl")
+            .await
+            .unwrap();
+        assert!(entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_append_load_and_clear() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = journal_path(&temp_dir);
+        let repo = JsonlJournalRepository::new();
+
+        repo.append(
+            &path,
+            &JournalEntry::InFlight {
+                batch_key: "batch-1".to_string(),
+                logs: vec![],
+            },
+        )
+        .await
+        .unwrap();
+        repo.append(&path, &committed("batch-1")).await.unwrap();
+
+        let entries = repo.load(&path).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(entries[0], JournalEntry::InFlight { .. }));
+        assert_eq!(entries[1].batch_key(), "batch-1");
+
+        repo.clear(&path).await.unwrap();
+        assert!(!Path::new(&path).exists());
+        // 存在しないジャーナルの削除はエラーにしない
+        repo.clear(&path).await.unwrap();
+    }
+
+    #[test]
+    fn test_load_skips_truncated_last_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = journal_path(&temp_dir);
+        let valid = serde_json::to_string(&committed("batch-1")).unwrap();
+        fs::create_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+        fs::write(&path, format!("{}\n{{\"status\":\"in_fl", valid)).unwrap();
+
+        let entries = JsonlJournalRepository::load_sync(&path).unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+}
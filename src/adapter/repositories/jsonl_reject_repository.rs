@@ -0,0 +1,218 @@
+//! JSONL Reject Repository Implementation
+//!
+//! RejectRepositoryのJSONL実装（隔離した行を1行1JSONで追記保存）
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use log::{info, warn};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::domain::entities::rejected_line::RejectedLine;
+use crate::domain::repositories::reject_repository::RejectRepository;
+
+/// JSONLファイルベースの隔離ストア
+pub struct JsonlRejectRepository;
+
+impl JsonlRejectRepository {
+    /// 新しいリポジトリを作成
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// ファイルから隔離行を読み込む（同期処理）
+    fn load_sync(path: &str) -> Result<Vec<RejectedLine>> {
+        let path = Path::new(path);
+
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(path).context("Failed to read reject store")?;
+
+        let mut rejects = Vec::new();
+        for (line_num, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<RejectedLine>(line) {
+                Ok(reject) => rejects.push(reject),
+                Err(e) => warn!(
+                    "Skipping corrupt entry at line {} in {}: {}",
+                    line_num + 1,
+                    path.display(),
+                    e
+                ),
+            }
+        }
+
+        Ok(rejects)
+    }
+
+    /// 隔離行をJSONLにシリアライズ
+    fn to_jsonl(rejects: &[RejectedLine]) -> Result<String> {
+        let mut out = String::new();
+        for reject in rejects {
+            out.push_str(&serde_json::to_string(reject).context("Failed to serialize reject")?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// ファイルに隔離行を追記する（同期処理）
+    fn append_sync(path: &str, rejects: &[RejectedLine]) -> Result<()> {
+        let path = Path::new(path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create reject store directory")?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context("Failed to open reject store")?;
+        file.write_all(Self::to_jsonl(rejects)?.as_bytes())
+            .context("Failed to write reject store")?;
+
+        info!("Quarantined {} rejected lines", rejects.len());
+
+        Ok(())
+    }
+
+    /// ファイルの内容を置き換える（同期処理）
+    fn replace_sync(path: &str, rejects: &[RejectedLine]) -> Result<()> {
+        let path = Path::new(path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create reject store directory")?;
+        }
+
+        fs::write(path, Self::to_jsonl(rejects)?).context("Failed to write reject store")?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RejectRepository for JsonlRejectRepository {
+    async fn load(&self, path: &str) -> Result<Vec<RejectedLine>> {
+        let path = path.to_string();
+        tokio::task::spawn_blocking(move || Self::load_sync(&path))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn append(&self, path: &str, rejects: &[RejectedLine]) -> Result<()> {
+        if rejects.is_empty() {
+            return Ok(());
+        }
+        let path = path.to_string();
+        let rejects = rejects.to_vec();
+        tokio::task::spawn_blocking(move || Self::append_sync(&path, &rejects))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn replace(&self, path: &str, rejects: &[RejectedLine]) -> Result<()> {
+        let path = path.to_string();
+        let rejects = rejects.to_vec();
+        tokio::task::spawn_blocking(move || Self::replace_sync(&path, &rejects))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+}
+
+impl Default for JsonlRejectRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn store_path(dir: &TempDir) -> String {
+        dir.path()
+            .join("sessync/rejects.jsonl")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_load_nonexistent_store() {
+        let repo = JsonlRejectRepository::new();
+        let rejects = repo.load("/nonexistent/rejects.jsonl").await.unwrap();
+        assert!(rejects.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_append_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = store_path(&temp_dir);
+        let repo = JsonlRejectRepository::new();
+
+        repo.append(&path, &[RejectedLine::new("/logs/a.jsonl", 1, "err", "x")])
+            .await
+            .unwrap();
+        repo.append(&path, &[RejectedLine::new("/logs/a.jsonl", 5, "err", "y")])
+            .await
+            .unwrap();
+
+        let rejects = repo.load(&path).await.unwrap();
+        assert_eq!(rejects.len(), 2);
+        assert_eq!(rejects[0].line_number, 1);
+        assert_eq!(rejects[1].raw, "y");
+    }
+
+    #[tokio::test]
+    async fn test_append_empty_does_not_create_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = store_path(&temp_dir);
+        let repo = JsonlRejectRepository::new();
+
+        repo.append(&path, &[]).await.unwrap();
+
+        assert!(!Path::new(&path).exists());
+    }
+
+    #[tokio::test]
+    async fn test_replace() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = store_path(&temp_dir);
+        let repo = JsonlRejectRepository::new();
+
+        repo.append(
+            &path,
+            &[
+                RejectedLine::new("/logs/a.jsonl", 1, "err", "x"),
+                RejectedLine::new("/logs/a.jsonl", 2, "err", "y"),
+            ],
+        )
+        .await
+        .unwrap();
+        repo.replace(&path, &[RejectedLine::new("/logs/a.jsonl", 2, "err", "y")])
+            .await
+            .unwrap();
+
+        let rejects = repo.load(&path).await.unwrap();
+        assert_eq!(rejects.len(), 1);
+        assert_eq!(rejects[0].line_number, 2);
+    }
+
+    #[test]
+    fn test_load_skips_corrupt_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = store_path(&temp_dir);
+        let valid =
+            serde_json::to_string(&RejectedLine::new("/logs/a.jsonl", 1, "e", "x")).unwrap();
+        fs::create_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+        fs::write(&path, format!("{}\nnot json\n", valid)).unwrap();
+
+        let rejects = JsonlRejectRepository::load_sync(&path).unwrap();
+        assert_eq!(rejects.len(), 1);
+    }
+}
@@ -2,6 +2,17 @@
 //!
 //! Domain層のRepositoryトレイトの実装
 
+pub mod archive_log_repository;
+pub mod bigquery_remote_record_repository;
 pub mod bigquery_upload_repository;
 pub mod file_log_repository;
 pub mod json_state_repository;
+pub mod jsonl_journal_repository;
+pub mod jsonl_reject_repository;
+pub mod ndjson_upload_repository;
+pub mod parquet_upload_repository;
+pub mod postgres_upload_repository;
+pub mod s3_upload_repository;
+pub mod sqlite_state_repository;
+pub mod sqlite_upload_repository;
+pub mod webhook_upload_repository;
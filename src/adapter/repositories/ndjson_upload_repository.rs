@@ -0,0 +1,459 @@
+//! NDJSON Upload Repository Implementation
+//!
+//! UploadRepositoryのローカルNDJSON実装（BigQueryに送る行と同じ形式をファイルに書き出す）
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use log::warn;
+use serde::Deserialize;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::adapter::bigquery::models::{insert_id, SessionLogOutput};
+use crate::domain::entities::upload_batch::UploadBatch;
+use crate::domain::repositories::upload_repository::{UploadRepository, UploadResult};
+
+/// 書き込み済みの行から重複排除に必要な列だけを読む
+#[derive(Deserialize)]
+struct WrittenRow {
+    uuid: String,
+    #[serde(default)]
+    record_version: u32,
+}
+
+/// ローカルディレクトリにNDJSONを書き出すアップロードリポジトリ
+///
+/// 行は `<dir>/<project_name>/<YYYY-MM-DD>.ndjson` に書き込む（日付はBigQueryのパーティションと同じ `uploaded_at`）。
+/// 新しい行だけをファイルの末尾に追記し、同じ insert ID の行は一度しか書き込まない。
+/// 書き込み済みの insert ID はファイルごとに最初の書き込み時に一度だけ読み込み、以後はメモリ上で管理する。
+pub struct NdjsonUploadRepository {
+    dir: PathBuf,
+    /// ファイルごとの書き込み済みの insert ID
+    written: Arc<Mutex<HashMap<PathBuf, HashSet<String>>>>,
+}
+
+impl NdjsonUploadRepository {
+    /// 新しいリポジトリを作成
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - 書き出し先のディレクトリ（`~` を展開）
+    pub fn new(dir: &str) -> Self {
+        Self {
+            dir: PathBuf::from(shellexpand::tilde(dir).as_ref()),
+            written: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 行の書き込み先のファイル
+    fn file_path(dir: &Path, row: &SessionLogOutput) -> PathBuf {
+        dir.join(file_name_safe(&row.project_name))
+            .join(format!("{}.ndjson", row.uploaded_at.format("%Y-%m-%d")))
+    }
+
+    /// 行をファイルごとに書き込み、書き込めた（または書き込み済みだった）UUIDを返す（同期処理）
+    ///
+    /// 書き込みに失敗したファイルの行は失敗として扱い、他のファイルの書き込みは続ける。
+    fn upload_sync(
+        dir: &Path,
+        rows: Vec<SessionLogOutput>,
+        written: &Mutex<HashMap<PathBuf, HashSet<String>>>,
+    ) -> Vec<String> {
+        let mut files: BTreeMap<PathBuf, Vec<SessionLogOutput>> = BTreeMap::new();
+        for row in rows {
+            files
+                .entry(Self::file_path(dir, &row))
+                .or_default()
+                .push(row);
+        }
+
+        let mut written = written
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut uploaded_uuids = Vec::new();
+        for (path, rows) in files {
+            let result = match written.entry(path.clone()) {
+                Entry::Occupied(entry) => Self::append_rows(&path, &rows, entry.into_mut()),
+                Entry::Vacant(entry) => Self::read_written(&path)
+                    .and_then(|ids| Self::append_rows(&path, &rows, entry.insert(ids))),
+            };
+            match result {
+                Ok(()) => uploaded_uuids.extend(rows.into_iter().map(|row| row.uuid)),
+                Err(e) => warn!(
+                    "Failed to write {} rows to {}: {:#}",
+                    rows.len(),
+                    path.display(),
+                    e
+                ),
+            }
+        }
+
+        uploaded_uuids
+    }
+
+    /// ファイルに書き込み済みの行の insert ID を読み込む（同期処理）
+    ///
+    /// 末尾の改行のない行は、追記の途中で止まった書き込みとして切り詰める
+    /// （その行のレコードは状態に記録されていないため、次の送信で書き直される）。
+    /// 途中の行が読めない場合は、行番号を含むエラーを返す。
+    fn read_written(path: &Path) -> Result<HashSet<String>> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(HashSet::new()),
+            Err(e) => return Err(e).context("Failed to read NDJSON file"),
+        };
+
+        let mut reader = BufReader::new(file);
+        let mut written = HashSet::new();
+        let mut line = Vec::new();
+        let mut offset = 0u64;
+        let mut line_number = 0u64;
+        loop {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .context("Failed to read NDJSON file")?;
+            if read == 0 {
+                break;
+            }
+            line_number += 1;
+            if line.last() != Some(&b'\n') {
+                warn!(
+                    "Truncating an incomplete line {} of {} left by an interrupted write",
+                    line_number,
+                    path.display()
+                );
+                fs::OpenOptions::new()
+                    .write(true)
+                    .open(path)
+                    .and_then(|file| file.set_len(offset))
+                    .context("Failed to truncate NDJSON file")?;
+                break;
+            }
+            offset += read as u64;
+
+            let row: WrittenRow = serde_json::from_slice(&line)
+                .with_context(|| format!("Corrupt line {} in {}", line_number, path.display()))?;
+            written.insert(insert_id(&row.uuid, row.record_version));
+        }
+        Ok(written)
+    }
+
+    /// 書き込み済みでない行をファイルの末尾に追記する（同期処理）
+    ///
+    /// BigQueryと同じく insert ID で判定するため、再送されたバッチの行は重複しない。
+    /// `written` は書き込みに成功した場合だけ更新する。
+    fn append_rows(
+        path: &Path,
+        rows: &[SessionLogOutput],
+        written: &mut HashSet<String>,
+    ) -> Result<()> {
+        let mut ids = HashSet::new();
+        let mut content = String::new();
+        for row in rows {
+            let id = row.insert_id();
+            if written.contains(&id) || !ids.insert(id) {
+                continue;
+            }
+            let line = serde_json::to_string(row).context("Failed to serialize row")?;
+            content.push_str(&line);
+            content.push('\n');
+        }
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create NDJSON directory")?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .context("Failed to open NDJSON file")?;
+
+        // 末尾の改行がない行（切り詰めに失敗した行など）に続けて書かない
+        if file.metadata().context("Failed to read NDJSON file")?.len() > 0 {
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::End(-1))
+                .and_then(|_| file.read_exact(&mut last))
+                .context("Failed to read NDJSON file")?;
+            if last[0] != b'\n' {
+                content.insert(0, '\n');
+            }
+        }
+
+        // 書き込みに失敗した場合は追記前の長さに戻す（書きかけの行を残さない）
+        let len = file.metadata().context("Failed to read NDJSON file")?.len();
+        if let Err(e) = file
+            .write_all(content.as_bytes())
+            .and_then(|_| file.sync_data())
+        {
+            if let Err(truncate_error) = file.set_len(len) {
+                warn!(
+                    "Failed to truncate {} after a failed write: {}",
+                    path.display(),
+                    truncate_error
+                );
+            }
+            return Err(e).context("Failed to write NDJSON file");
+        }
+        written.extend(ids);
+        Ok(())
+    }
+}
+
+/// パスの1要素として使えるよう、英数字と `.` `-` `_` 以外を `_` に置き換える
+pub(crate) fn file_name_safe(name: &str) -> String {
+    let safe: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    match safe.as_str() {
+        "" | "." | ".." => "_".to_string(),
+        _ => safe,
+    }
+}
+
+#[async_trait]
+impl UploadRepository for NdjsonUploadRepository {
+    async fn upload_batch(&self, batch: &UploadBatch) -> Result<UploadResult> {
+        let rows: Vec<SessionLogOutput> = batch.logs().iter().map(SessionLogOutput::from).collect();
+        let dir = self.dir.clone();
+        let written = self.written.clone();
+
+        let uploaded_uuids =
+            tokio::task::spawn_blocking(move || Self::upload_sync(&dir, rows, &written))
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?;
+
+        let uploaded_count = uploaded_uuids.len();
+        Ok(UploadResult::new(
+            uploaded_count,
+            batch.len() - uploaded_count,
+            uploaded_uuids,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::session_log::{LogMetadata, SessionLog};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn create_test_log(uuid: &str, project_name: &str, day: u32) -> SessionLog {
+        SessionLog {
+            uuid: uuid.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, 12, day, 10, 0, 0).unwrap(),
+            session_id: "session-001".to_string(),
+            agent_id: None,
+            is_sidechain: None,
+            parent_uuid: None,
+            user_type: None,
+            message_type: "user".to_string(),
+            slug: None,
+            request_id: None,
+            cwd: None,
+            git_branch: None,
+            version: None,
+            message: json!({"role": "user", "content": "Hello"}),
+            tool_use_result: None,
+            extra: None,
+            lineage: None,
+            record_version: 0,
+            metadata: LogMetadata {
+                developer_id: "dev-001".to_string(),
+                hostname: "test-host".to_string(),
+                user_email: "test@example.com".to_string(),
+                project_name: project_name.to_string(),
+                upload_batch_id: "batch-001".to_string(),
+                source_file: "/path/to/log.jsonl".to_string(),
+                uploaded_at: Utc.with_ymd_and_hms(2024, 12, day, 12, 0, 0).unwrap(),
+            },
+        }
+    }
+
+    fn read_rows(path: &Path) -> Vec<serde_json::Value> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_rotates_by_project_and_day() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = NdjsonUploadRepository::new(temp_dir.path().to_str().unwrap());
+
+        let batch = UploadBatch::new(vec![
+            create_test_log("uuid-1", "app", 25),
+            create_test_log("uuid-2", "app", 25),
+            create_test_log("uuid-3", "app", 26),
+            create_test_log("uuid-4", "team/api", 25),
+        ]);
+        let result = repo.upload_batch(&batch).await.unwrap();
+
+        assert!(result.is_success());
+        assert_eq!(result.uploaded_count, 4);
+
+        let rows = read_rows(&temp_dir.path().join("app/2024-12-25.ndjson"));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["uuid"], "uuid-1");
+        // BigQueryに送る行と同じ形式（JSON列は文字列）
+        assert!(rows[0]["message"].is_string());
+        assert_eq!(rows[0]["type"], "user");
+        assert_eq!(
+            read_rows(&temp_dir.path().join("app/2024-12-26.ndjson")).len(),
+            1
+        );
+        assert_eq!(
+            read_rows(&temp_dir.path().join("team_api/2024-12-25.ndjson")).len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_deduplicates_by_insert_id() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = NdjsonUploadRepository::new(temp_dir.path().to_str().unwrap());
+        let path = temp_dir.path().join("app/2024-12-25.ndjson");
+
+        let batch = UploadBatch::new(vec![create_test_log("uuid-1", "app", 25)]);
+        repo.upload_batch(&batch).await.unwrap();
+
+        // 再送は成功として扱い、行は増えない
+        let result = repo.upload_batch(&batch).await.unwrap();
+        assert_eq!(result.uploaded_uuids, vec!["uuid-1"]);
+        assert_eq!(read_rows(&path).len(), 1);
+
+        // 内容の変わった新しいバージョンは別の行として書き込む
+        let mut changed = create_test_log("uuid-1", "app", 25);
+        changed.record_version = 1;
+        repo.upload_batch(&UploadBatch::new(vec![changed]))
+            .await
+            .unwrap();
+        let rows = read_rows(&path);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["record_version"], 1);
+
+        // 次の実行（新しいリポジトリ）でもファイルの内容から書き込み済みの行を判定する
+        let repo = NdjsonUploadRepository::new(temp_dir.path().to_str().unwrap());
+        let result = repo.upload_batch(&batch).await.unwrap();
+        assert_eq!(result.uploaded_uuids, vec!["uuid-1"]);
+        assert_eq!(read_rows(&path).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_appends_to_existing_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = NdjsonUploadRepository::new(temp_dir.path().to_str().unwrap());
+        let path = temp_dir.path().join("app/2024-12-25.ndjson");
+        repo.upload_batch(&UploadBatch::new(vec![create_test_log(
+            "uuid-1", "app", 25,
+        )]))
+        .await
+        .unwrap();
+
+        // ファイルを置き換えずに追記するため、ハードリンク先からも新しい行が見える
+        let link = temp_dir.path().join("link.ndjson");
+        fs::hard_link(&path, &link).unwrap();
+        repo.upload_batch(&UploadBatch::new(vec![create_test_log(
+            "uuid-2", "app", 25,
+        )]))
+        .await
+        .unwrap();
+
+        let rows = read_rows(&link);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["uuid"], "uuid-2");
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_reports_unwritable_files_as_failed() {
+        let temp_dir = TempDir::new().unwrap();
+        // プロジェクトのディレクトリを作れないようにファイルを置く
+        fs::write(temp_dir.path().join("blocked"), "").unwrap();
+        let repo = NdjsonUploadRepository::new(temp_dir.path().to_str().unwrap());
+
+        let batch = UploadBatch::new(vec![
+            create_test_log("uuid-1", "app", 25),
+            create_test_log("uuid-2", "blocked", 25),
+        ]);
+        let result = repo.upload_batch(&batch).await.unwrap();
+
+        assert_eq!(result.uploaded_uuids, vec!["uuid-1"]);
+        assert_eq!(result.failed_count, 1);
+    }
+
+    #[test]
+    fn test_read_written_truncates_interrupted_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("2024-12-25.ndjson");
+        let line = serde_json::to_string(&SessionLogOutput::from(&create_test_log(
+            "uuid-1", "app", 25,
+        )))
+        .unwrap();
+        // 追記の途中で止まった行（末尾の改行がない）
+        fs::write(&path, format!("{}\n{{\"uuid\":\"uuid-", line)).unwrap();
+
+        let mut written = NdjsonUploadRepository::read_written(&path).unwrap();
+        assert_eq!(written.len(), 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), format!("{}\n", line));
+
+        let rows = vec![
+            SessionLogOutput::from(&create_test_log("uuid-1", "app", 25)),
+            SessionLogOutput::from(&create_test_log("uuid-2", "app", 25)),
+        ];
+        NdjsonUploadRepository::append_rows(&path, &rows, &mut written).unwrap();
+
+        let rows = read_rows(&path);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["uuid"], "uuid-2");
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_fails_on_corrupt_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app/2024-12-25.ndjson");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "not json\n").unwrap();
+
+        let err = NdjsonUploadRepository::read_written(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains("Corrupt line 1"));
+
+        // 壊れた行のあるファイルには書き込まず、失敗として扱う
+        let repo = NdjsonUploadRepository::new(temp_dir.path().to_str().unwrap());
+        let result = repo
+            .upload_batch(&UploadBatch::new(vec![create_test_log(
+                "uuid-1", "app", 25,
+            )]))
+            .await
+            .unwrap();
+        assert_eq!(result.failed_count, 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not json\n");
+    }
+
+    #[test]
+    fn test_file_name_safe() {
+        assert_eq!(file_name_safe("my-project_1.0"), "my-project_1.0");
+        assert_eq!(file_name_safe("team/api"), "team_api");
+        assert_eq!(file_name_safe(".."), "_");
+        assert_eq!(file_name_safe(""), "_");
+    }
+}
@@ -0,0 +1,442 @@
+//! Parquet Upload Repository Implementation
+//!
+//! UploadRepositoryのParquet実装（Hive形式のパーティションでデータレイクに書き出す）
+
+use anyhow::{Context, Result};
+use arrow_array::{
+    ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::warn;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::adapter::atomic_write::write_atomic;
+use crate::adapter::bigquery::models::SessionLogOutput;
+use crate::adapter::config::json_config::ParquetCompression;
+use crate::domain::entities::upload_batch::UploadBatch;
+use crate::domain::repositories::upload_repository::{UploadRepository, UploadResult};
+
+/// Parquetファイルを書き出すアップロードリポジトリ
+///
+/// 行は `<dir>/project_name=<name>/date=<YYYY-MM-DD>/part-<hash>.parquet` に書き込む
+/// （日付はBigQueryのパーティションと同じ `uploaded_at`）。
+/// ファイル名は行の insert ID から決まるため、同じバッチを再送しても同じファイルを置き換えるだけで重複しない。
+///
+/// 各ファイルはアップロードの1バッチ分の行を `row_group_size` 行ごとの行グループに分けて持つ。
+/// ファイルを閉じてからアップロード状態をコミットするため、バッチをまたいでファイルを開いたままにはしない。
+pub struct ParquetUploadRepository {
+    dir: PathBuf,
+    properties: WriterProperties,
+}
+
+impl ParquetUploadRepository {
+    /// 新しいリポジトリを作成
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - 書き出し先のディレクトリ（`~` を展開）
+    /// * `row_group_size` - 1つの行グループの最大行数
+    /// * `compression` - 列の圧縮方式
+    pub fn new(dir: &str, row_group_size: usize, compression: ParquetCompression) -> Self {
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(row_group_size.max(1))
+            .set_compression(Self::compression(compression))
+            .build();
+
+        Self {
+            dir: PathBuf::from(shellexpand::tilde(dir).as_ref()),
+            properties,
+        }
+    }
+
+    /// 設定の圧縮方式をParquetの圧縮方式に変換
+    fn compression(compression: ParquetCompression) -> Compression {
+        match compression {
+            ParquetCompression::None => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        }
+    }
+
+    /// BigQueryのテーブルと同じ列（JSON列は文字列）
+    fn schema() -> SchemaRef {
+        let timestamp = || DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()));
+
+        Arc::new(Schema::new(vec![
+            Field::new("uuid", DataType::Utf8, false),
+            Field::new("timestamp", timestamp(), false),
+            Field::new("session_id", DataType::Utf8, false),
+            Field::new("agent_id", DataType::Utf8, true),
+            Field::new("is_sidechain", DataType::Boolean, true),
+            Field::new("parent_uuid", DataType::Utf8, true),
+            Field::new("user_type", DataType::Utf8, true),
+            Field::new("type", DataType::Utf8, false),
+            Field::new("slug", DataType::Utf8, true),
+            Field::new("request_id", DataType::Utf8, true),
+            Field::new("cwd", DataType::Utf8, true),
+            Field::new("git_branch", DataType::Utf8, true),
+            Field::new("version", DataType::Utf8, true),
+            Field::new("message", DataType::Utf8, false),
+            Field::new("tool_use_result", DataType::Utf8, true),
+            Field::new("extra", DataType::Utf8, true),
+            Field::new("root_session_id", DataType::Utf8, true),
+            Field::new("depth", DataType::Int64, true),
+            Field::new("spawning_tool_use_id", DataType::Utf8, true),
+            Field::new("developer_id", DataType::Utf8, false),
+            Field::new("hostname", DataType::Utf8, false),
+            Field::new("user_email", DataType::Utf8, false),
+            Field::new("project_name", DataType::Utf8, false),
+            Field::new("record_version", DataType::Int64, true),
+            Field::new("upload_batch_id", DataType::Utf8, false),
+            Field::new("source_file", DataType::Utf8, false),
+            Field::new("uploaded_at", timestamp(), false),
+        ]))
+    }
+
+    /// 行を列形式に変換
+    fn record_batch(rows: &[SessionLogOutput]) -> Result<RecordBatch> {
+        fn strings<'a>(
+            rows: &'a [SessionLogOutput],
+            value: impl Fn(&'a SessionLogOutput) -> Option<&'a str>,
+        ) -> ArrayRef {
+            Arc::new(rows.iter().map(value).collect::<StringArray>())
+        }
+        fn json(
+            rows: &[SessionLogOutput],
+            value: impl Fn(&SessionLogOutput) -> Option<&serde_json::Value>,
+        ) -> ArrayRef {
+            Arc::new(
+                rows.iter()
+                    .map(|row| value(row).map(|v| v.to_string()))
+                    .collect::<StringArray>(),
+            )
+        }
+        fn timestamps(
+            rows: &[SessionLogOutput],
+            value: impl Fn(&SessionLogOutput) -> DateTime<Utc>,
+        ) -> ArrayRef {
+            let micros: Vec<i64> = rows
+                .iter()
+                .map(|row| value(row).timestamp_micros())
+                .collect();
+            Arc::new(TimestampMicrosecondArray::from(micros).with_timezone("UTC"))
+        }
+
+        let columns: Vec<ArrayRef> = vec![
+            strings(rows, |r| Some(&r.uuid)),
+            timestamps(rows, |r| r.timestamp),
+            strings(rows, |r| Some(&r.session_id)),
+            strings(rows, |r| r.agent_id.as_deref()),
+            Arc::new(
+                rows.iter()
+                    .map(|r| r.is_sidechain)
+                    .collect::<BooleanArray>(),
+            ),
+            strings(rows, |r| r.parent_uuid.as_deref()),
+            strings(rows, |r| r.user_type.as_deref()),
+            strings(rows, |r| Some(&r.message_type)),
+            strings(rows, |r| r.slug.as_deref()),
+            strings(rows, |r| r.request_id.as_deref()),
+            strings(rows, |r| r.cwd.as_deref()),
+            strings(rows, |r| r.git_branch.as_deref()),
+            strings(rows, |r| r.version.as_deref()),
+            json(rows, |r| Some(&r.message)),
+            json(rows, |r| r.tool_use_result.as_ref()),
+            json(rows, |r| r.extra.as_ref()),
+            strings(rows, |r| r.root_session_id.as_deref()),
+            Arc::new(
+                rows.iter()
+                    .map(|r| r.depth.map(i64::from))
+                    .collect::<Int64Array>(),
+            ),
+            strings(rows, |r| r.spawning_tool_use_id.as_deref()),
+            strings(rows, |r| Some(&r.developer_id)),
+            strings(rows, |r| Some(&r.hostname)),
+            strings(rows, |r| Some(&r.user_email)),
+            strings(rows, |r| Some(&r.project_name)),
+            Arc::new(
+                rows.iter()
+                    .map(|r| Some(i64::from(r.record_version)))
+                    .collect::<Int64Array>(),
+            ),
+            strings(rows, |r| Some(&r.upload_batch_id)),
+            strings(rows, |r| Some(&r.source_file)),
+            timestamps(rows, |r| r.uploaded_at),
+        ];
+
+        RecordBatch::try_new(Self::schema(), columns).context("Failed to build Parquet columns")
+    }
+
+    /// 行のパーティションのディレクトリ
+    fn partition_dir(dir: &Path, row: &SessionLogOutput) -> PathBuf {
+        dir.join(format!("project_name={}", hive_escape(&row.project_name)))
+            .join(format!("date={}", row.uploaded_at.format("%Y-%m-%d")))
+    }
+
+    /// 行の insert ID から決まるファイル名
+    fn file_name(rows: &[SessionLogOutput]) -> String {
+        let mut hasher = sha1_smol::Sha1::new();
+        for row in rows {
+            hasher.update(row.insert_id().as_bytes());
+            hasher.update(b"\n");
+        }
+        format!("part-{}.parquet", &hasher.digest().to_string()[..16])
+    }
+
+    /// 行をパーティションごとにファイルに書き込み、書き込めたUUIDを返す（同期処理）
+    ///
+    /// 書き込みに失敗したパーティションの行は失敗として扱い、他のパーティションの書き込みは続ける。
+    fn upload_sync(
+        dir: &Path,
+        properties: &WriterProperties,
+        rows: Vec<SessionLogOutput>,
+    ) -> Vec<String> {
+        let mut partitions: BTreeMap<PathBuf, Vec<SessionLogOutput>> = BTreeMap::new();
+        for row in rows {
+            partitions
+                .entry(Self::partition_dir(dir, &row))
+                .or_default()
+                .push(row);
+        }
+
+        let mut uploaded_uuids = Vec::new();
+        for (partition, rows) in partitions {
+            let path = partition.join(Self::file_name(&rows));
+            match Self::write_file(&path, properties, &rows) {
+                Ok(()) => uploaded_uuids.extend(rows.into_iter().map(|row| row.uuid)),
+                Err(e) => warn!(
+                    "Failed to write {} rows to {}: {:#}",
+                    rows.len(),
+                    path.display(),
+                    e
+                ),
+            }
+        }
+
+        uploaded_uuids
+    }
+
+    /// 行をParquetファイルとして書き込む（同期処理）
+    fn write_file(
+        path: &Path,
+        properties: &WriterProperties,
+        rows: &[SessionLogOutput],
+    ) -> Result<()> {
+        let batch = Self::record_batch(rows)?;
+
+        let mut buffer = Vec::new();
+        let mut writer =
+            ArrowWriter::try_new(&mut buffer, batch.schema(), Some(properties.clone()))
+                .context("Failed to create Parquet writer")?;
+        writer
+            .write(&batch)
+            .context("Failed to encode Parquet rows")?;
+        writer.close().context("Failed to finish Parquet file")?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create Parquet partition directory")?;
+        }
+        write_atomic(path, &buffer).context("Failed to write Parquet file")
+    }
+}
+
+/// Hive形式のパーティション値として使えるよう、英数字と `.` `-` `_` 以外をパーセントエンコードする
+fn hive_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'-' | b'_') {
+            escaped.push(char::from(byte));
+        } else {
+            escaped.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    escaped
+}
+
+#[async_trait]
+impl UploadRepository for ParquetUploadRepository {
+    async fn upload_batch(&self, batch: &UploadBatch) -> Result<UploadResult> {
+        let rows: Vec<SessionLogOutput> = batch.logs().iter().map(SessionLogOutput::from).collect();
+        let dir = self.dir.clone();
+        let properties = self.properties.clone();
+
+        let uploaded_uuids =
+            tokio::task::spawn_blocking(move || Self::upload_sync(&dir, &properties, rows))
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?;
+
+        let uploaded_count = uploaded_uuids.len();
+        Ok(UploadResult::new(
+            uploaded_count,
+            batch.len() - uploaded_count,
+            uploaded_uuids,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::session_log::{LogMetadata, SessionLog};
+    use arrow_array::Array;
+    use chrono::TimeZone;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn create_test_log(uuid: &str, project_name: &str, day: u32) -> SessionLog {
+        SessionLog {
+            uuid: uuid.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, 12, day, 10, 0, 0).unwrap(),
+            session_id: "session-001".to_string(),
+            agent_id: None,
+            is_sidechain: Some(false),
+            parent_uuid: None,
+            user_type: None,
+            message_type: "user".to_string(),
+            slug: None,
+            request_id: None,
+            cwd: None,
+            git_branch: None,
+            version: None,
+            message: json!({"role": "user", "content": "Hello"}),
+            tool_use_result: None,
+            extra: None,
+            lineage: None,
+            record_version: 0,
+            metadata: LogMetadata {
+                developer_id: "dev-001".to_string(),
+                hostname: "test-host".to_string(),
+                user_email: "test@example.com".to_string(),
+                project_name: project_name.to_string(),
+                upload_batch_id: "batch-001".to_string(),
+                source_file: "/path/to/log.jsonl".to_string(),
+                uploaded_at: Utc.with_ymd_and_hms(2024, 12, day, 12, 0, 0).unwrap(),
+            },
+        }
+    }
+
+    /// パーティションのディレクトリ内のParquetファイル
+    fn parquet_files(partition: &Path) -> Vec<PathBuf> {
+        fs::read_dir(partition)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "parquet"))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_writes_hive_partitions() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = ParquetUploadRepository::new(
+            temp_dir.path().to_str().unwrap(),
+            1000,
+            ParquetCompression::Snappy,
+        );
+
+        let batch = UploadBatch::new(vec![
+            create_test_log("uuid-1", "app", 25),
+            create_test_log("uuid-2", "app", 25),
+            create_test_log("uuid-3", "app", 26),
+            create_test_log("uuid-4", "team/api", 25),
+        ]);
+        let result = repo.upload_batch(&batch).await.unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.uploaded_count, 4);
+
+        let partition = temp_dir.path().join("project_name=app/date=2024-12-25");
+        let files = parquet_files(&partition);
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            parquet_files(&temp_dir.path().join("project_name=app/date=2024-12-26")).len(),
+            1
+        );
+        assert_eq!(
+            parquet_files(
+                &temp_dir
+                    .path()
+                    .join("project_name=team%2Fapi/date=2024-12-25")
+            )
+            .len(),
+            1
+        );
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(fs::File::open(&files[0]).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(batches[0].schema(), ParquetUploadRepository::schema());
+
+        let message = batches[0]
+            .column_by_name("message")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(message.value(0), r#"{"content":"Hello","role":"user"}"#);
+        let tool_use_result = batches[0].column_by_name("tool_use_result").unwrap();
+        assert!(tool_use_result.is_null(0));
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_resend_replaces_same_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = ParquetUploadRepository::new(
+            temp_dir.path().to_str().unwrap(),
+            1000,
+            ParquetCompression::None,
+        );
+        let batch = UploadBatch::new(vec![create_test_log("uuid-1", "app", 25)]);
+
+        repo.upload_batch(&batch).await.unwrap();
+        repo.upload_batch(&batch).await.unwrap();
+
+        let partition = temp_dir.path().join("project_name=app/date=2024-12-25");
+        assert_eq!(parquet_files(&partition).len(), 1);
+    }
+
+    #[test]
+    fn test_row_group_size_and_compression() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = ParquetUploadRepository::new(
+            temp_dir.path().to_str().unwrap(),
+            2,
+            ParquetCompression::Zstd,
+        );
+        let rows: Vec<SessionLogOutput> = ["uuid-1", "uuid-2", "uuid-3"]
+            .iter()
+            .map(|uuid| SessionLogOutput::from(&create_test_log(uuid, "app", 25)))
+            .collect();
+        let path = temp_dir.path().join("part.parquet");
+
+        ParquetUploadRepository::write_file(&path, &repo.properties, &rows).unwrap();
+
+        let builder =
+            ParquetRecordBatchReaderBuilder::try_new(fs::File::open(&path).unwrap()).unwrap();
+        let metadata = builder.metadata();
+        assert_eq!(metadata.num_row_groups(), 2);
+        assert_eq!(metadata.row_group(0).num_rows(), 2);
+        assert!(matches!(
+            metadata.row_group(0).column(0).compression(),
+            Compression::ZSTD(_)
+        ));
+    }
+
+    #[test]
+    fn test_hive_escape() {
+        assert_eq!(hive_escape("my-project_1.0"), "my-project_1.0");
+        assert_eq!(hive_escape("team/api"), "team%2Fapi");
+        assert_eq!(hive_escape("a=b c"), "a%3Db%20c");
+    }
+}
@@ -0,0 +1,509 @@
+//! PostgreSQL Upload Repository Implementation
+//!
+//! UploadRepositoryのPostgreSQL実装（BigQueryと同じ列のテーブルを自動作成し、冪等に書き込む）
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use log::warn;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Duration};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Client;
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+use crate::adapter::bigquery::models::SessionLogOutput;
+use crate::adapter::bigquery::retry::{
+    calculate_retry_delay, error_chain_to_string, is_connection_error, is_transient_error,
+    MAX_CONNECTION_RESETS, MAX_RETRIES,
+};
+use crate::domain::entities::upload_batch::UploadBatch;
+use crate::domain::repositories::upload_repository::{UploadRepository, UploadResult};
+
+/// テーブルの列と型（BigQueryのテーブルと同じ列。JSON列は `jsonb`）
+const COLUMNS: [(&str, &str); 27] = [
+    ("uuid", "TEXT PRIMARY KEY"),
+    ("timestamp", "TIMESTAMPTZ NOT NULL"),
+    ("session_id", "TEXT NOT NULL"),
+    ("agent_id", "TEXT"),
+    ("is_sidechain", "BOOLEAN"),
+    ("parent_uuid", "TEXT"),
+    ("user_type", "TEXT"),
+    ("type", "TEXT NOT NULL"),
+    ("slug", "TEXT"),
+    ("request_id", "TEXT"),
+    ("cwd", "TEXT"),
+    ("git_branch", "TEXT"),
+    ("version", "TEXT"),
+    ("message", "JSONB NOT NULL"),
+    ("tool_use_result", "JSONB"),
+    ("extra", "JSONB"),
+    ("root_session_id", "TEXT"),
+    ("depth", "BIGINT"),
+    ("spawning_tool_use_id", "TEXT"),
+    ("developer_id", "TEXT NOT NULL"),
+    ("hostname", "TEXT NOT NULL"),
+    ("user_email", "TEXT NOT NULL"),
+    ("project_name", "TEXT NOT NULL"),
+    ("record_version", "BIGINT NOT NULL DEFAULT 0"),
+    ("upload_batch_id", "TEXT NOT NULL"),
+    ("source_file", "TEXT NOT NULL"),
+    ("uploaded_at", "TIMESTAMPTZ NOT NULL"),
+];
+
+/// インデックスを作成する列（BigQueryのクラスタリング・パーティションと同じ）
+const INDEXED_COLUMNS: [&str; 3] = ["session_id", "developer_id", "uploaded_at"];
+
+/// 1つのINSERT文に含める最大行数（パラメータ数の上限 65535 を超えないようにする）
+const ROWS_PER_STATEMENT: usize = 1000;
+
+/// SQLの識別子として引用符で囲む
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// PostgreSQLに書き込むアップロードリポジトリ
+///
+/// テーブルは最初の接続時に作成する。`uuid` を主キーとし、再送された行は
+/// `ON CONFLICT (uuid)` で読み飛ばすため、同じバッチを何度送っても行は増えない。
+/// 内容が変わって再アップロードされたレコード（`record_version` が大きい行）だけは既存の行を置き換える。
+///
+/// 接続・一時的なエラーの判定とリトライはBigQueryへのアップロードと同じ規則に従う。
+pub struct PostgresUploadRepository {
+    config: tokio_postgres::Config,
+    /// スキーマ名を含めて引用符で囲んだテーブル名
+    table: String,
+    /// テーブル名（スキーマ名を除く。インデックス名に使う）
+    table_name: String,
+    client: Mutex<Option<Client>>,
+}
+
+impl PostgresUploadRepository {
+    /// 新しいリポジトリを作成
+    ///
+    /// 接続文字列にパスワードがなければ環境変数 `PGPASSWORD` を使う。
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - 接続文字列（`postgres://user@host/db` または `host=... dbname=...`）
+    /// * `table` - テーブル名（`schema.table` も可）
+    ///
+    /// # Errors
+    ///
+    /// 接続文字列またはテーブル名が不正な場合
+    pub fn new(url: &str, table: &str) -> Result<Self> {
+        let mut config: tokio_postgres::Config = url
+            .parse()
+            .context("Invalid PostgreSQL connection string")?;
+        if config.get_password().is_none() {
+            if let Ok(password) = std::env::var("PGPASSWORD") {
+                config.password(password);
+            }
+        }
+
+        let parts: Vec<&str> = table.split('.').collect();
+        if parts.len() > 2 || parts.iter().any(|part| part.is_empty()) {
+            bail!("Invalid PostgreSQL table name: {}", table);
+        }
+
+        Ok(Self {
+            config,
+            table: parts
+                .iter()
+                .map(|part| quote_identifier(part))
+                .collect::<Vec<_>>()
+                .join("."),
+            table_name: parts[parts.len() - 1].to_string(),
+            client: Mutex::new(None),
+        })
+    }
+
+    /// TLS接続の設定（サーバーが対応していなければ `sslmode` に従って平文で接続する）
+    fn tls() -> Result<MakeRustlsConnect> {
+        let roots = rustls::RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .context("Failed to configure TLS")?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+        Ok(MakeRustlsConnect::new(config))
+    }
+
+    /// テーブルとインデックスを作成するSQL
+    fn create_table_sql(&self) -> String {
+        let columns = COLUMNS
+            .iter()
+            .map(|(name, definition)| format!("    {} {}", quote_identifier(name), definition))
+            .collect::<Vec<_>>()
+            .join(",\n");
+
+        let mut sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (\n{}\n);\n",
+            self.table, columns
+        );
+        for column in INDEXED_COLUMNS {
+            sql.push_str(&format!(
+                "CREATE INDEX IF NOT EXISTS {} ON {} ({});\n",
+                quote_identifier(&format!("{}_{}_idx", self.table_name, column)),
+                self.table,
+                quote_identifier(column)
+            ));
+        }
+        sql
+    }
+
+    /// `rows` 行を書き込むINSERT文
+    fn insert_sql(&self, rows: usize) -> String {
+        let columns = COLUMNS
+            .iter()
+            .map(|(name, _)| quote_identifier(name))
+            .collect::<Vec<_>>();
+        let values = (0..rows)
+            .map(|row| {
+                let placeholders = (1..=COLUMNS.len())
+                    .map(|column| format!("${}", row * COLUMNS.len() + column))
+                    .collect::<Vec<_>>();
+                format!("({})", placeholders.join(", "))
+            })
+            .collect::<Vec<_>>();
+        let updates = columns
+            .iter()
+            .skip(1)
+            .map(|column| format!("{} = EXCLUDED.{}", column, column))
+            .collect::<Vec<_>>();
+
+        format!(
+            "INSERT INTO {} AS t ({}) VALUES {} \
+             ON CONFLICT (\"uuid\") DO UPDATE SET {} \
+             WHERE EXCLUDED.\"record_version\" > t.\"record_version\"",
+            self.table,
+            columns.join(", "),
+            values.join(", "),
+            updates.join(", ")
+        )
+    }
+
+    /// 行をINSERT文のパラメータに変換
+    fn params(row: &SessionLogOutput) -> Vec<Box<dyn ToSql + Sync + Send>> {
+        vec![
+            Box::new(row.uuid.clone()),
+            Box::new(row.timestamp),
+            Box::new(row.session_id.clone()),
+            Box::new(row.agent_id.clone()),
+            Box::new(row.is_sidechain),
+            Box::new(row.parent_uuid.clone()),
+            Box::new(row.user_type.clone()),
+            Box::new(row.message_type.clone()),
+            Box::new(row.slug.clone()),
+            Box::new(row.request_id.clone()),
+            Box::new(row.cwd.clone()),
+            Box::new(row.git_branch.clone()),
+            Box::new(row.version.clone()),
+            Box::new(row.message.clone()),
+            Box::new(row.tool_use_result.clone()),
+            Box::new(row.extra.clone()),
+            Box::new(row.root_session_id.clone()),
+            Box::new(row.depth.map(i64::from)),
+            Box::new(row.spawning_tool_use_id.clone()),
+            Box::new(row.developer_id.clone()),
+            Box::new(row.hostname.clone()),
+            Box::new(row.user_email.clone()),
+            Box::new(row.project_name.clone()),
+            Box::new(i64::from(row.record_version)),
+            Box::new(row.upload_batch_id.clone()),
+            Box::new(row.source_file.clone()),
+            Box::new(row.uploaded_at),
+        ]
+    }
+
+    /// 接続してテーブルを作成する
+    async fn connect(&self) -> Result<Client> {
+        let (client, connection) = self
+            .config
+            .connect(Self::tls()?)
+            .await
+            .context("Failed to connect to PostgreSQL")?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                warn!("PostgreSQL connection closed: {}", e);
+            }
+        });
+
+        client
+            .batch_execute(&self.create_table_sql())
+            .await
+            .context("Failed to create PostgreSQL table")?;
+
+        Ok(client)
+    }
+
+    /// 行を1トランザクションで書き込む（接続がなければ接続する）
+    async fn insert(&self, rows: &[SessionLogOutput]) -> Result<()> {
+        let mut guard = self.client.lock().await;
+        if !matches!(guard.as_ref(), Some(client) if !client.is_closed()) {
+            *guard = Some(self.connect().await?);
+        }
+        let client = guard.as_mut().expect("client was just connected");
+
+        let tx = client.transaction().await?;
+        for chunk in rows.chunks(ROWS_PER_STATEMENT) {
+            let params: Vec<_> = chunk.iter().flat_map(Self::params).collect();
+            let refs: Vec<&(dyn ToSql + Sync)> = params
+                .iter()
+                .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+                .collect();
+            tx.execute(&self.insert_sql(chunk.len()), &refs)
+                .await
+                .context("Failed to insert rows into PostgreSQL")?;
+        }
+        tx.commit()
+            .await
+            .context("Failed to commit rows to PostgreSQL")?;
+
+        Ok(())
+    }
+}
+
+/// UUIDごとに `record_version` が最大の行だけを残す
+///
+/// 1つのINSERT文で同じ行を2回更新するとエラーになるため、バッチ内の重複を先に取り除く。
+fn latest_versions(rows: Vec<SessionLogOutput>) -> Vec<SessionLogOutput> {
+    let mut latest: HashMap<String, usize> = HashMap::new();
+    let mut unique: Vec<SessionLogOutput> = Vec::with_capacity(rows.len());
+    for row in rows {
+        match latest.get(&row.uuid) {
+            Some(&index) => {
+                if row.record_version > unique[index].record_version {
+                    unique[index] = row;
+                }
+            }
+            None => {
+                latest.insert(row.uuid.clone(), unique.len());
+                unique.push(row);
+            }
+        }
+    }
+    unique
+}
+
+#[async_trait]
+impl UploadRepository for PostgresUploadRepository {
+    async fn upload_batch(&self, batch: &UploadBatch) -> Result<UploadResult> {
+        let uploaded_uuids: Vec<String> = batch.logs().iter().map(|log| log.uuid.clone()).collect();
+        let rows = latest_versions(batch.logs().iter().map(SessionLogOutput::from).collect());
+
+        let mut retry_count = 0;
+        let mut connection_reset_count = 0;
+
+        loop {
+            let error = match self.insert(&rows).await {
+                Ok(()) => return Ok(UploadResult::new(uploaded_uuids.len(), 0, uploaded_uuids)),
+                Err(e) => e,
+            };
+            let error_msg = error_chain_to_string(&error);
+
+            // Connection error - drop the client so the next attempt reconnects
+            if is_connection_error(&error_msg) {
+                connection_reset_count += 1;
+                *self.client.lock().await = None;
+
+                if connection_reset_count > MAX_CONNECTION_RESETS {
+                    return Err(error).context("Too many connection resets");
+                }
+
+                let delay = calculate_retry_delay(connection_reset_count);
+                warn!(
+                    "PostgreSQL connection error (reset #{}), reconnecting in {}ms: {}",
+                    connection_reset_count, delay, error_msg
+                );
+                sleep(Duration::from_millis(delay)).await;
+                retry_count = 0;
+                continue;
+            }
+
+            // Transient error - retry with the same connection
+            if is_transient_error(&error_msg) && retry_count < MAX_RETRIES {
+                retry_count += 1;
+                let delay = calculate_retry_delay(retry_count);
+                warn!(
+                    "PostgreSQL transient error (attempt {}), retrying in {}ms: {}",
+                    retry_count, delay, error_msg
+                );
+                sleep(Duration::from_millis(delay)).await;
+                continue;
+            }
+
+            return Err(error).context("Failed to upload to PostgreSQL");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::session_log::{LogMetadata, SessionLog};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    fn create_test_log(uuid: &str) -> SessionLog {
+        SessionLog {
+            uuid: uuid.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, 12, 25, 10, 0, 0).unwrap(),
+            session_id: "session-001".to_string(),
+            agent_id: None,
+            is_sidechain: Some(false),
+            parent_uuid: None,
+            user_type: None,
+            message_type: "assistant".to_string(),
+            slug: None,
+            request_id: None,
+            cwd: None,
+            git_branch: None,
+            version: None,
+            message: json!({"role": "assistant", "content": [{"type": "tool_use", "name": "Bash"}]}),
+            tool_use_result: None,
+            extra: None,
+            lineage: None,
+            record_version: 0,
+            metadata: LogMetadata {
+                developer_id: "dev-001".to_string(),
+                hostname: "test-host".to_string(),
+                user_email: "test@example.com".to_string(),
+                project_name: "app".to_string(),
+                upload_batch_id: "batch-001".to_string(),
+                source_file: "/path/to/log.jsonl".to_string(),
+                uploaded_at: Utc.with_ymd_and_hms(2024, 12, 25, 12, 0, 0).unwrap(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_table_name() {
+        let url = "postgres://sessync@localhost/sessync";
+        assert!(PostgresUploadRepository::new(url, "session_logs").is_ok());
+        assert!(PostgresUploadRepository::new(url, "analytics.session_logs").is_ok());
+        assert!(PostgresUploadRepository::new(url, "a.b.c").is_err());
+        assert!(PostgresUploadRepository::new(url, "analytics.").is_err());
+        assert!(PostgresUploadRepository::new("postgres://[bad", "session_logs").is_err());
+    }
+
+    #[test]
+    fn test_create_table_sql_quotes_identifiers() {
+        let repo = PostgresUploadRepository::new(
+            "host=localhost dbname=sessync",
+            "analytics.session_logs",
+        )
+        .unwrap();
+        let sql = repo.create_table_sql();
+
+        assert!(sql.starts_with("CREATE TABLE IF NOT EXISTS \"analytics\".\"session_logs\" ("));
+        assert!(sql.contains("\"message\" JSONB NOT NULL"));
+        assert!(sql.contains("\"tool_use_result\" JSONB"));
+        assert!(sql.contains(
+            "CREATE INDEX IF NOT EXISTS \"session_logs_session_id_idx\" ON \"analytics\".\"session_logs\" (\"session_id\");"
+        ));
+    }
+
+    #[test]
+    fn test_insert_sql_numbers_placeholders_per_row() {
+        let repo = PostgresUploadRepository::new("host=localhost", "session_logs").unwrap();
+        let sql = repo.insert_sql(2);
+
+        assert!(sql.contains("VALUES ($1, $2, "));
+        assert!(sql.contains("$27), ($28, "));
+        assert!(sql.contains(
+            "$54) ON CONFLICT (\"uuid\") DO UPDATE SET \"timestamp\" = EXCLUDED.\"timestamp\""
+        ));
+        assert!(sql.ends_with("WHERE EXCLUDED.\"record_version\" > t.\"record_version\""));
+        assert_eq!(
+            PostgresUploadRepository::params(&SessionLogOutput::from(&create_test_log("uuid-1")))
+                .len(),
+            COLUMNS.len()
+        );
+    }
+
+    #[test]
+    fn test_latest_versions_keeps_highest_record_version() {
+        let mut changed = create_test_log("uuid-1");
+        changed.record_version = 2;
+        let rows = [
+            create_test_log("uuid-1"),
+            create_test_log("uuid-2"),
+            changed,
+        ]
+        .iter()
+        .map(SessionLogOutput::from)
+        .collect();
+
+        let rows = latest_versions(rows);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].uuid, "uuid-1");
+        assert_eq!(rows[0].record_version, 2);
+    }
+
+    /// テスト用のPostgreSQLの接続文字列（`SESSYNC_TEST_POSTGRES_URL`。未設定ならテストを省略する）
+    fn test_database_url() -> Option<String> {
+        std::env::var("SESSYNC_TEST_POSTGRES_URL").ok()
+    }
+
+    /// 一意な名前のテーブルに書き込むリポジトリ
+    fn test_repository(url: &str) -> PostgresUploadRepository {
+        let table = format!("session_logs_test_{}", uuid::Uuid::new_v4().simple());
+        PostgresUploadRepository::new(url, &table).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_keeps_latest_version_per_uuid() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let repo = test_repository(&url);
+
+        let batch = UploadBatch::new(vec![create_test_log("uuid-1"), create_test_log("uuid-2")]);
+        let result = repo.upload_batch(&batch).await.unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.uploaded_count, 2);
+
+        // 再送しても行は増えない
+        let result = repo.upload_batch(&batch).await.unwrap();
+        assert_eq!(result.uploaded_count, 2);
+
+        // 新しいバージョンは行を置き換える
+        let mut changed = create_test_log("uuid-1");
+        changed.record_version = 1;
+        changed.message = json!({"role": "assistant", "content": "edited"});
+        repo.upload_batch(&UploadBatch::new(vec![changed]))
+            .await
+            .unwrap();
+
+        // 古いバージョンを再送しても新しいバージョンは巻き戻らない
+        repo.upload_batch(&batch).await.unwrap();
+
+        let client = repo.connect().await.unwrap();
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT uuid, record_version, message->>'content' FROM {} ORDER BY uuid",
+                    repo.table
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get::<_, i64>(1), 1);
+        assert_eq!(rows[0].get::<_, String>(2), "edited");
+        assert_eq!(rows[1].get::<_, i64>(1), 0);
+
+        client
+            .batch_execute(&format!("DROP TABLE {}", repo.table))
+            .await
+            .unwrap();
+    }
+}
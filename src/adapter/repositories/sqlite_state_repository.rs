@@ -0,0 +1,1281 @@
+//! SQLite State Repository Implementation
+//!
+//! StateRepositoryのSQLite実装（アップロード状態をSQLiteデータベースで永続化）
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::domain::entities::file_checkpoint::FileCheckpoint;
+use crate::domain::repositories::state_repository::{
+    RecordHash, SessionUploads, StateRepository, StateUpdate, UploadState,
+};
+
+/// スキーマのバージョン（`PRAGMA user_version`）
+const SCHEMA_VERSION: i64 = 4;
+
+/// 他のプロセスが書き込み中の場合に待機する時間
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// テーブル定義
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS upload_summary (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    last_upload_timestamp TEXT,
+    last_upload_batch_id TEXT,
+    total_uploaded INTEGER NOT NULL DEFAULT 0
+);
+INSERT OR IGNORE INTO upload_summary (id) VALUES (1);
+
+CREATE TABLE IF NOT EXISTS uploaded_records (
+    uuid TEXT PRIMARY KEY,
+    session_id TEXT,
+    source_file TEXT,
+    batch_id TEXT,
+    uploaded_at TEXT,
+    content_hash TEXT,
+    record_version INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS idx_uploaded_records_session_id ON uploaded_records (session_id);
+CREATE INDEX IF NOT EXISTS idx_uploaded_records_source_file ON uploaded_records (source_file);
+
+CREATE TABLE IF NOT EXISTS session_uploads (
+    session_id TEXT PRIMARY KEY,
+    uploaded_count INTEGER NOT NULL,
+    last_upload_timestamp TEXT,
+    last_upload_batch_id TEXT,
+    latest_record_at TEXT,
+    compacted_until TEXT
+);
+
+CREATE TABLE IF NOT EXISTS session_files (
+    session_id TEXT NOT NULL,
+    source_file TEXT NOT NULL,
+    PRIMARY KEY (session_id, source_file)
+);
+
+CREATE TABLE IF NOT EXISTS file_checkpoints (
+    source_file TEXT PRIMARY KEY,
+    inode INTEGER,
+    size INTEGER NOT NULL,
+    mtime INTEGER,
+    offset INTEGER NOT NULL,
+    lines INTEGER NOT NULL,
+    lineage TEXT
+);
+";
+
+/// バージョン1からの移行（セッションの透かしとログファイルの記録を追加）
+///
+/// 移行前のセッションはレコードのタイムスタンプを記録していないため圧縮の対象にならない。
+const MIGRATE_V2: &str = "
+ALTER TABLE session_uploads ADD COLUMN latest_record_at TEXT;
+ALTER TABLE session_uploads ADD COLUMN compacted_until TEXT;
+
+CREATE TABLE IF NOT EXISTS session_files (
+    session_id TEXT NOT NULL,
+    source_file TEXT NOT NULL,
+    PRIMARY KEY (session_id, source_file)
+);
+INSERT OR IGNORE INTO session_files (session_id, source_file)
+    SELECT DISTINCT session_id, source_file FROM uploaded_records
+    WHERE session_id IS NOT NULL AND source_file IS NOT NULL;
+";
+
+/// バージョン2からの移行（レコードの内容のハッシュを追加）
+///
+/// 移行前のレコードはハッシュを持たないため、内容が変わっても検出されない。
+const MIGRATE_V3: &str = "
+ALTER TABLE uploaded_records ADD COLUMN content_hash TEXT;
+ALTER TABLE uploaded_records ADD COLUMN record_version INTEGER NOT NULL DEFAULT 0;
+";
+
+/// バージョン3からの移行（チェックポイントにセッションツリーの紐付け情報を追加）
+///
+/// 移行前のチェックポイントは紐付け情報を持たないため、次回の実行で一度だけ先頭から読み直す。
+const MIGRATE_V4: &str = "
+ALTER TABLE file_checkpoints ADD COLUMN lineage TEXT;
+";
+
+/// 内容のハッシュを更新する条件（ハッシュが既知で、バージョンが記録済み以上の場合）
+const NEWER_HASH: &str =
+    "excluded.content_hash IS NOT NULL AND excluded.record_version >= record_version";
+
+/// 日時を保存用の文字列に変換する（桁を揃えて文字列の比較で大小を判定できるようにする）
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// 保存された日時を読み込む
+fn parse_time(value: Option<String>) -> Option<DateTime<Utc>> {
+    value
+        .and_then(|v| DateTime::parse_from_rfc3339(&v).ok())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// SQLiteベースの状態リポジトリ
+///
+/// アップロード済みUUIDを主キー付きのテーブルで管理し、セッション・ファイルごとの行を持つ。
+/// アップロード結果の反映（`commit`）は追加分のみを1トランザクションで書き込むため、
+/// 状態全体を書き直すJSON実装と違い、履歴が大きくなっても保存コストが増えない。
+///
+/// データベースを初めて作成する際、同じディレクトリに `upload-state.json` があれば
+/// その内容を取り込み、JSONファイルは `upload-state.json.migrated` に名前を変えて残す。
+pub struct SqliteStateRepository;
+
+impl SqliteStateRepository {
+    /// 新しいリポジトリを作成
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 移行元のJSON状態ファイルのパス（拡張子を `.json` に置き換えたもの）
+    fn legacy_json_path(path: &Path) -> PathBuf {
+        path.with_extension("json")
+    }
+
+    /// データベースを開く（初回はスキーマを作成し、JSON状態ファイルから移行する）
+    fn open(path: &str) -> Result<Connection> {
+        let path = Path::new(path);
+
+        // Create parent directory if it doesn't exist
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+
+        let mut conn = Connection::open(path).context(format!(
+            "Failed to open upload state database: {}",
+            path.display()
+        ))?;
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .context("Failed to configure upload state database")?;
+
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .context("Failed to read upload state schema version")?;
+        if version >= SCHEMA_VERSION {
+            return Ok(conn);
+        }
+
+        let legacy_path = Self::legacy_json_path(path);
+        let tx = conn.transaction()?;
+        if version == 1 {
+            tx.execute_batch(MIGRATE_V2)
+                .context("Failed to migrate upload state tables")?;
+        }
+        if version == 1 || version == 2 {
+            tx.execute_batch(MIGRATE_V3)
+                .context("Failed to migrate upload state tables")?;
+        }
+        if (1..=3).contains(&version) {
+            tx.execute_batch(MIGRATE_V4)
+                .context("Failed to migrate upload state tables")?;
+        }
+        tx.execute_batch(SCHEMA)
+            .context("Failed to create upload state tables")?;
+        let migrated = if version == 0 && legacy_path.exists() {
+            let content =
+                fs::read_to_string(&legacy_path).context("Failed to read upload state file")?;
+            let state: UploadState =
+                serde_json::from_str(&content).context("Failed to parse upload state JSON")?;
+            Self::merge_state(&tx, &state)?;
+            Some(state.uploaded_uuids.len())
+        } else {
+            None
+        };
+        tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        tx.commit()
+            .context("Failed to initialize upload state database")?;
+
+        if let Some(count) = migrated {
+            let backup = PathBuf::from(format!("{}.migrated", legacy_path.display()));
+            fs::rename(&legacy_path, &backup)
+                .context("Failed to rename migrated upload state file")?;
+            info!(
+                "Migrated {} uploaded UUIDs from {} (kept as {})",
+                count,
+                legacy_path.display(),
+                backup.display()
+            );
+        }
+
+        Ok(conn)
+    }
+
+    /// データベースから状態を読み込む（同期処理）
+    ///
+    /// `with_records` が偽の場合はアップロード済みレコードを読み込まず、`records_omitted` を立てる
+    /// （照合は `find_records_sync` で主キーを引く）。
+    fn load_sync(path: &str, with_records: bool) -> Result<UploadState> {
+        let db_path = Path::new(path);
+        if !db_path.exists() && !Self::legacy_json_path(db_path).exists() {
+            info!("No existing upload state found, creating new state");
+            return Ok(UploadState::new());
+        }
+
+        let conn = Self::open(path)?;
+        let mut state = UploadState::new();
+
+        let (timestamp, batch_id, total) = conn
+            .query_row(
+                "SELECT last_upload_timestamp, last_upload_batch_id, total_uploaded
+                 FROM upload_summary WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, i64>(2)?)),
+            )
+            .context("Failed to read upload state")?;
+        state.last_upload_timestamp = timestamp;
+        state.last_upload_batch_id = batch_id;
+        state.total_uploaded = total as u64;
+
+        let mut stmt = conn.prepare(
+            "SELECT session_id, last_upload_timestamp, latest_record_at, compacted_until
+             FROM session_uploads",
+        )?;
+        state.sessions = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    SessionUploads {
+                        last_uploaded_at: parse_time(row.get(1)?).unwrap_or_default(),
+                        latest_record_at: parse_time(row.get(2)?),
+                        source_files: Default::default(),
+                        uuids: Default::default(),
+                        compacted_until: parse_time(row.get(3)?),
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<HashMap<_, _>>>()
+            .context("Failed to read session uploads")?;
+
+        let mut stmt = conn.prepare("SELECT session_id, source_file FROM session_files")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let session_id: String = row.get(0)?;
+            if let Some(session) = state.sessions.get_mut(&session_id) {
+                session.source_files.insert(row.get(1)?);
+            }
+        }
+
+        if with_records {
+            let mut stmt = conn.prepare(
+                "SELECT uuid, session_id, content_hash, record_version FROM uploaded_records",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let uuid: String = row.get(0)?;
+                let session_id: Option<String> = row.get(1)?;
+                if let Some(session) = session_id.and_then(|id| state.sessions.get_mut(&id)) {
+                    session.uuids.insert(uuid.clone());
+                }
+                if let Some(hash) = row.get::<_, Option<String>>(2)? {
+                    let version = row.get::<_, i64>(3)? as u32;
+                    state
+                        .record_hashes
+                        .insert(uuid.clone(), RecordHash { hash, version });
+                }
+                state.uploaded_uuids.insert(uuid);
+            }
+        } else {
+            state.records_omitted = true;
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT source_file, inode, size, mtime, offset, lines, lineage FROM file_checkpoints",
+        )?;
+        let checkpoints = stmt
+            .query_map([], |row| {
+                let checkpoint = FileCheckpoint::new(
+                    row.get::<_, Option<i64>>(1)?.map(|inode| inode as u64),
+                    row.get::<_, i64>(2)? as u64,
+                    row.get(3)?,
+                    row.get::<_, i64>(4)? as u64,
+                    row.get::<_, i64>(5)? as u64,
+                );
+                Ok((
+                    row.get::<_, String>(0)?,
+                    checkpoint,
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read file checkpoints")?;
+        for (source_file, mut checkpoint, lineage) in checkpoints {
+            checkpoint.lineage = lineage
+                .map(|lineage| serde_json::from_str(&lineage))
+                .transpose()
+                .context("Failed to parse file checkpoint lineage")?;
+            state.file_checkpoints.insert(source_file, checkpoint);
+        }
+
+        info!(
+            "Loaded upload state: {} records previously uploaded",
+            state.total_uploaded
+        );
+
+        Ok(state)
+    }
+
+    /// 状態を保存する（同期処理）
+    fn save_sync(path: &str, state: &UploadState) -> Result<()> {
+        let mut conn = Self::open(path)?;
+        let tx = conn.transaction()?;
+        Self::merge_state(&tx, state)?;
+        tx.commit().context("Failed to write upload state")?;
+
+        info!(
+            "Saved upload state: {} total records uploaded",
+            state.total_uploaded
+        );
+
+        Ok(())
+    }
+
+    /// 状態を置き換える（同期処理）
+    ///
+    /// 状態に含まれないUUID・セッション・チェックポイントを削除してから書き込む。
+    /// 残ったレコードのバッチIDやアップロード日時はそのまま保持する。
+    fn replace_sync(path: &str, state: &UploadState) -> Result<()> {
+        // レコードを読み込んでいない状態で置き換えると、全てのレコードが削除される
+        anyhow::ensure!(
+            !state.records_omitted,
+            "Cannot replace the upload state with a state loaded without its records"
+        );
+        let mut conn = Self::open(path)?;
+        let tx = conn.transaction()?;
+
+        tx.execute_batch(
+            "DROP TABLE IF EXISTS temp.keep_uuids;
+             DROP TABLE IF EXISTS temp.keep_sessions;
+             CREATE TEMP TABLE keep_uuids (uuid TEXT PRIMARY KEY);
+             CREATE TEMP TABLE keep_sessions (session_id TEXT PRIMARY KEY);",
+        )?;
+        {
+            let mut insert = tx.prepare("INSERT OR IGNORE INTO keep_uuids (uuid) VALUES (?1)")?;
+            for uuid in &state.uploaded_uuids {
+                insert.execute([uuid])?;
+            }
+            let mut insert =
+                tx.prepare("INSERT OR IGNORE INTO keep_sessions (session_id) VALUES (?1)")?;
+            for session_id in state.sessions.keys() {
+                insert.execute([session_id])?;
+            }
+        }
+        tx.execute_batch(
+            "DELETE FROM uploaded_records WHERE uuid NOT IN (SELECT uuid FROM keep_uuids);
+             DELETE FROM session_uploads
+                 WHERE session_id NOT IN (SELECT session_id FROM keep_sessions);
+             DELETE FROM session_files;
+             DELETE FROM file_checkpoints;
+             DROP TABLE temp.keep_uuids;
+             DROP TABLE temp.keep_sessions;",
+        )?;
+
+        Self::merge_state(&tx, state)?;
+        tx.execute(
+            "UPDATE upload_summary
+             SET last_upload_timestamp = ?1, last_upload_batch_id = ?2, total_uploaded = ?3
+             WHERE id = 1",
+            params![
+                state.last_upload_timestamp,
+                state.last_upload_batch_id,
+                state.total_uploaded as i64
+            ],
+        )?;
+        tx.commit().context("Failed to write upload state")?;
+
+        info!(
+            "Replaced upload state: {} uploaded UUIDs kept",
+            state.uploaded_uuids.len()
+        );
+
+        Ok(())
+    }
+
+    /// トランザクション内で状態をマージする
+    ///
+    /// JSON実装と同じく、UUIDとセッションは和集合を取り、チェックポイントは保存する側を
+    /// 優先して統合する（読み込み後に他のプロセスがコミットした内容を消さない）。
+    fn merge_state(tx: &Transaction<'_>, state: &UploadState) -> Result<()> {
+        tx.execute(
+            "UPDATE upload_summary
+             SET last_upload_timestamp = COALESCE(?1, last_upload_timestamp),
+                 last_upload_batch_id = COALESCE(?2, last_upload_batch_id),
+                 total_uploaded = MAX(total_uploaded, ?3)
+             WHERE id = 1",
+            params![
+                state.last_upload_timestamp,
+                state.last_upload_batch_id,
+                state.total_uploaded as i64
+            ],
+        )?;
+
+        let session_of: HashMap<&str, &str> = state
+            .sessions
+            .iter()
+            .flat_map(|(session_id, session)| {
+                session
+                    .uuids
+                    .iter()
+                    .map(move |uuid| (uuid.as_str(), session_id.as_str()))
+            })
+            .collect();
+        {
+            let mut insert = tx.prepare(&format!(
+                "INSERT INTO uploaded_records (uuid, session_id, content_hash, record_version)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (uuid) DO UPDATE SET
+                     session_id = COALESCE(session_id, excluded.session_id),
+                     content_hash = IIF({newer}, excluded.content_hash, content_hash),
+                     record_version = IIF({newer}, excluded.record_version, record_version)",
+                newer = NEWER_HASH
+            ))?;
+            for uuid in &state.uploaded_uuids {
+                let hash = state.record_hashes.get(uuid);
+                insert.execute(params![
+                    uuid,
+                    session_of.get(uuid.as_str()),
+                    hash.map(|h| &h.hash),
+                    hash.map_or(0, |h| h.version)
+                ])?;
+            }
+        }
+
+        for (session_id, session) in &state.sessions {
+            Self::upsert_session(tx, session_id, session.uuids.len(), session, None)?;
+        }
+
+        Self::upsert_checkpoints(tx, &state.file_checkpoints)?;
+
+        Ok(())
+    }
+
+    /// セッションの記録を追加・更新する（日時は新しい方を残し、ログファイルは追加する）
+    fn upsert_session(
+        tx: &Transaction<'_>,
+        session_id: &str,
+        uploaded_count: usize,
+        session: &SessionUploads,
+        batch_id: Option<&str>,
+    ) -> Result<()> {
+        tx.prepare_cached(
+            "INSERT INTO session_uploads
+             (session_id, uploaded_count, last_upload_timestamp, last_upload_batch_id,
+              latest_record_at, compacted_until)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (session_id) DO UPDATE SET
+                 uploaded_count = MAX(uploaded_count, excluded.uploaded_count),
+                 last_upload_timestamp = COALESCE(
+                     MAX(last_upload_timestamp, excluded.last_upload_timestamp),
+                     last_upload_timestamp, excluded.last_upload_timestamp),
+                 last_upload_batch_id = COALESCE(excluded.last_upload_batch_id, last_upload_batch_id),
+                 latest_record_at = COALESCE(
+                     MAX(latest_record_at, excluded.latest_record_at),
+                     latest_record_at, excluded.latest_record_at),
+                 compacted_until = COALESCE(
+                     MAX(compacted_until, excluded.compacted_until),
+                     compacted_until, excluded.compacted_until)",
+        )?
+        .execute(params![
+            session_id,
+            uploaded_count as i64,
+            format_time(session.last_uploaded_at),
+            batch_id,
+            session.latest_record_at.map(format_time),
+            session.compacted_until.map(format_time)
+        ])?;
+
+        let mut insert = tx.prepare_cached(
+            "INSERT OR IGNORE INTO session_files (session_id, source_file) VALUES (?1, ?2)",
+        )?;
+        for source_file in &session.source_files {
+            insert.execute(params![session_id, source_file])?;
+        }
+        Ok(())
+    }
+
+    /// チェックポイントを追加・更新する
+    fn upsert_checkpoints(
+        tx: &Transaction<'_>,
+        checkpoints: &HashMap<String, FileCheckpoint>,
+    ) -> Result<()> {
+        let mut stmt = tx.prepare(
+            "INSERT OR REPLACE INTO file_checkpoints
+                 (source_file, inode, size, mtime, offset, lines, lineage)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for (source_file, cp) in checkpoints {
+            let lineage = cp.lineage.as_ref().map(serde_json::to_string).transpose()?;
+            stmt.execute(params![
+                source_file,
+                cp.inode.map(|inode| inode as i64),
+                cp.size as i64,
+                cp.mtime,
+                cp.offset as i64,
+                cp.lines as i64,
+                lineage
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// アップロード結果を1トランザクションで反映する（同期処理）
+    fn commit_sync(path: &str, update: &StateUpdate) -> Result<()> {
+        let mut conn = Self::open(path)?;
+        let tx = conn.transaction()?;
+
+        if !update.uploaded.is_empty() {
+            let uploaded_at = format_time(update.timestamp);
+            {
+                // 新しいバージョンをアップロードした場合は内容のハッシュだけを更新する
+                let mut insert = tx.prepare(&format!(
+                    "INSERT INTO uploaded_records
+                     (uuid, session_id, source_file, batch_id, uploaded_at,
+                      content_hash, record_version)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                     ON CONFLICT (uuid) DO UPDATE SET
+                         content_hash = IIF({newer}, excluded.content_hash, content_hash),
+                         record_version = IIF({newer}, excluded.record_version, record_version)",
+                    newer = NEWER_HASH
+                ))?;
+                for record in &update.uploaded {
+                    insert.execute(params![
+                        record.uuid,
+                        record.session_id,
+                        record.source_file,
+                        update.batch_id,
+                        uploaded_at,
+                        record.content_hash,
+                        record.record_version
+                    ])?;
+                }
+            }
+
+            for (session_id, session) in update.sessions() {
+                tx.execute(
+                    "UPDATE session_uploads SET uploaded_count = uploaded_count + ?2
+                     WHERE session_id = ?1",
+                    params![session_id, session.uuids.len() as i64],
+                )?;
+                Self::upsert_session(
+                    &tx,
+                    &session_id,
+                    session.uuids.len(),
+                    &session,
+                    Some(&update.batch_id),
+                )?;
+            }
+
+            tx.execute(
+                "UPDATE upload_summary
+                 SET last_upload_timestamp = ?1, last_upload_batch_id = ?2,
+                     total_uploaded = total_uploaded + ?3
+                 WHERE id = 1",
+                params![
+                    update.timestamp.to_rfc3339(),
+                    update.batch_id,
+                    update.uploaded.len() as i64
+                ],
+            )?;
+        }
+
+        Self::upsert_checkpoints(&tx, &update.checkpoints)?;
+        tx.commit().context("Failed to write upload state")?;
+
+        info!(
+            "Committed upload state: {} records, {} checkpoints",
+            update.uploaded.len(),
+            update.checkpoints.len()
+        );
+
+        Ok(())
+    }
+
+    /// 主キーでアップロード済みのIDと内容のハッシュを照合する（同期処理）
+    fn find_records_sync(
+        path: &str,
+        ids: &[String],
+    ) -> Result<HashMap<String, Option<RecordHash>>> {
+        let db_path = Path::new(path);
+        if !db_path.exists() && !Self::legacy_json_path(db_path).exists() {
+            return Ok(HashMap::new());
+        }
+
+        let conn = Self::open(path)?;
+        let mut stmt = conn
+            .prepare("SELECT content_hash, record_version FROM uploaded_records WHERE uuid = ?1")?;
+        let mut records = HashMap::new();
+        for id in ids {
+            let found = stmt
+                .query_row([id], |row| {
+                    let hash = row.get::<_, Option<String>>(0)?;
+                    let version = row.get::<_, i64>(1)? as u32;
+                    Ok(hash.map(|hash| RecordHash { hash, version }))
+                })
+                .optional()?;
+            if let Some(hash) = found {
+                records.insert(id.clone(), hash);
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[async_trait]
+impl StateRepository for SqliteStateRepository {
+    async fn load(&self, path: &str) -> Result<UploadState> {
+        let path = path.to_string();
+        tokio::task::spawn_blocking(move || Self::load_sync(&path, true))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn load_for_upload(&self, path: &str) -> Result<UploadState> {
+        let path = path.to_string();
+        tokio::task::spawn_blocking(move || Self::load_sync(&path, false))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn save(&self, path: &str, state: &UploadState) -> Result<()> {
+        let path = path.to_string();
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || Self::save_sync(&path, &state))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn replace(&self, path: &str, state: &UploadState) -> Result<()> {
+        let path = path.to_string();
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || Self::replace_sync(&path, &state))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn commit(&self, path: &str, update: &StateUpdate) -> Result<()> {
+        let path = path.to_string();
+        let update = update.clone();
+        tokio::task::spawn_blocking(move || Self::commit_sync(&path, &update))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn find_uploaded(&self, path: &str, ids: &[String]) -> Result<HashSet<String>> {
+        let path = path.to_string();
+        let ids = ids.to_vec();
+        tokio::task::spawn_blocking(move || Self::find_records_sync(&path, &ids))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+            .map(|records| records.into_keys().collect())
+    }
+
+    async fn find_records(
+        &self,
+        path: &str,
+        ids: &[String],
+    ) -> Result<HashMap<String, Option<RecordHash>>> {
+        let path = path.to_string();
+        let ids = ids.to_vec();
+        tokio::task::spawn_blocking(move || Self::find_records_sync(&path, &ids))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    async fn storage_size(&self, path: &str) -> Result<Option<u64>> {
+        match fs::metadata(path) {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).context("Failed to read upload state database size"),
+        }
+    }
+}
+
+impl Default for SqliteStateRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::lineage_edge::LineageEdge;
+    use crate::domain::repositories::state_repository::UploadedRecord;
+    use tempfile::TempDir;
+
+    fn db_path(dir: &TempDir) -> String {
+        dir.path()
+            .join("upload-state.db")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn time(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn record(uuid: &str, session_id: &str) -> UploadedRecord {
+        UploadedRecord {
+            uuid: uuid.to_string(),
+            session_id: session_id.to_string(),
+            source_file: format!("/logs/{}.jsonl", session_id),
+            timestamp: time("2024-12-25T09:00:00Z"),
+            content_hash: None,
+            record_version: 0,
+        }
+    }
+
+    #[test]
+    fn test_load_nonexistent_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+
+        let state = SqliteStateRepository::load_sync(&path, true).unwrap();
+
+        assert!(state.uploaded_uuids.is_empty());
+        assert_eq!(state.total_uploaded, 0);
+        // 読み込みだけではデータベースを作成しない
+        assert!(!Path::new(&path).exists());
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+
+        let mut state = UploadState::new();
+        state.last_upload_timestamp = Some("2024-12-25T12:00:00Z".to_string());
+        state.last_upload_batch_id = Some("batch-test".to_string());
+        state.total_uploaded = 2;
+        state.uploaded_uuids = HashSet::from(["uuid-a".to_string(), "uuid-b".to_string()]);
+        let mut with_lineage = FileCheckpoint::new(Some(8), 100, Some(1_000), 100, 1);
+        with_lineage.lineage = Some(vec![LineageEdge {
+            uuid: "uuid-a".to_string(),
+            parent_uuid: None,
+            session_id: "session-1".to_string(),
+            agent_id: None,
+            is_sidechain: false,
+            prompt: None,
+            task_calls: Vec::new(),
+            task_results: Vec::new(),
+        }]);
+        state.file_checkpoints = HashMap::from([
+            (
+                "/logs/a.jsonl".to_string(),
+                FileCheckpoint::new(Some(7), 300, Some(1_000), 300, 3),
+            ),
+            ("/logs/b.jsonl".to_string(), with_lineage),
+        ]);
+
+        SqliteStateRepository::save_sync(&path, &state).unwrap();
+        let loaded = SqliteStateRepository::load_sync(&path, true).unwrap();
+
+        assert_eq!(loaded.uploaded_uuids, state.uploaded_uuids);
+        assert_eq!(loaded.last_upload_timestamp, state.last_upload_timestamp);
+        assert_eq!(loaded.last_upload_batch_id, state.last_upload_batch_id);
+        assert_eq!(loaded.total_uploaded, 2);
+        assert_eq!(loaded.file_checkpoints, state.file_checkpoints);
+    }
+
+    #[test]
+    fn test_save_merges_with_existing_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+
+        let mut first = UploadState::new();
+        first.uploaded_uuids = HashSet::from(["uuid-0".to_string(), "uuid-a".to_string()]);
+        first.last_upload_batch_id = Some("batch-a".to_string());
+        first.total_uploaded = 2;
+        SqliteStateRepository::save_sync(&path, &first).unwrap();
+
+        let mut second = UploadState::new();
+        second.uploaded_uuids = HashSet::from(["uuid-0".to_string(), "uuid-b".to_string()]);
+        second.total_uploaded = 2;
+        SqliteStateRepository::save_sync(&path, &second).unwrap();
+
+        let loaded = SqliteStateRepository::load_sync(&path, true).unwrap();
+        assert_eq!(
+            loaded.uploaded_uuids,
+            HashSet::from([
+                "uuid-0".to_string(),
+                "uuid-a".to_string(),
+                "uuid-b".to_string()
+            ])
+        );
+        assert_eq!(loaded.last_upload_batch_id.as_deref(), Some("batch-a"));
+        assert_eq!(loaded.total_uploaded, 2);
+    }
+
+    #[test]
+    fn test_commit_adds_records_sessions_and_checkpoints() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+
+        let update = StateUpdate {
+            batch_id: "batch-001".to_string(),
+            timestamp: time("2024-12-25T10:00:00Z"),
+            uploaded: vec![
+                record("uuid-1", "session-1"),
+                record("uuid-2", "session-1"),
+                record("uuid-3", "session-2"),
+            ],
+            checkpoints: HashMap::from([(
+                "/logs/session-1.jsonl".to_string(),
+                FileCheckpoint::new(Some(1), 100, Some(10), 100, 2),
+            )]),
+        };
+        SqliteStateRepository::commit_sync(&path, &update).unwrap();
+
+        let update = StateUpdate {
+            batch_id: "batch-002".to_string(),
+            timestamp: time("2024-12-25T11:00:00Z"),
+            uploaded: vec![record("uuid-4", "session-1")],
+            checkpoints: HashMap::from([(
+                "/logs/session-1.jsonl".to_string(),
+                FileCheckpoint::new(Some(1), 150, Some(20), 150, 3),
+            )]),
+        };
+        SqliteStateRepository::commit_sync(&path, &update).unwrap();
+
+        let state = SqliteStateRepository::load_sync(&path, true).unwrap();
+        assert_eq!(state.uploaded_uuids.len(), 4);
+        assert_eq!(state.total_uploaded, 4);
+        assert_eq!(state.last_upload_batch_id.as_deref(), Some("batch-002"));
+        assert_eq!(
+            state.checkpoint("/logs/session-1.jsonl").unwrap().offset,
+            150
+        );
+
+        let conn = Connection::open(&path).unwrap();
+        let (count, batch): (i64, String) = conn
+            .query_row(
+                "SELECT uploaded_count, last_upload_batch_id FROM session_uploads
+                 WHERE session_id = 'session-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(batch, "batch-002");
+
+        let source_file: String = conn
+            .query_row(
+                "SELECT source_file FROM uploaded_records WHERE uuid = 'uuid-3'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(source_file, "/logs/session-2.jsonl");
+    }
+
+    #[test]
+    fn test_commit_checkpoints_only_keeps_last_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+
+        SqliteStateRepository::commit_sync(
+            &path,
+            &StateUpdate {
+                batch_id: "batch-001".to_string(),
+                timestamp: time("2024-12-25T10:00:00Z"),
+                uploaded: vec![record("uuid-1", "session-1")],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        SqliteStateRepository::commit_sync(
+            &path,
+            &StateUpdate {
+                batch_id: "batch-002".to_string(),
+                checkpoints: HashMap::from([(
+                    "/logs/a.jsonl".to_string(),
+                    FileCheckpoint::new(None, 10, None, 10, 1),
+                )]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let state = SqliteStateRepository::load_sync(&path, true).unwrap();
+        assert_eq!(state.last_upload_batch_id.as_deref(), Some("batch-001"));
+        assert_eq!(state.total_uploaded, 1);
+        assert!(state.checkpoint("/logs/a.jsonl").is_some());
+    }
+
+    #[test]
+    fn test_commit_tracks_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+
+        let mut later = record("uuid-2", "session-1");
+        later.timestamp = time("2024-12-25T09:30:00Z");
+        SqliteStateRepository::commit_sync(
+            &path,
+            &StateUpdate {
+                batch_id: "batch-001".to_string(),
+                timestamp: time("2024-12-25T10:00:00Z"),
+                uploaded: vec![record("uuid-1", "session-1"), later],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        SqliteStateRepository::commit_sync(
+            &path,
+            &StateUpdate {
+                batch_id: "batch-002".to_string(),
+                timestamp: time("2024-12-25T11:00:00Z"),
+                uploaded: vec![record("uuid-3", "session-1")],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let state = SqliteStateRepository::load_sync(&path, true).unwrap();
+        let session = &state.sessions["session-1"];
+        assert_eq!(session.last_uploaded_at, time("2024-12-25T11:00:00Z"));
+        assert_eq!(session.latest_record_at, Some(time("2024-12-25T09:30:00Z")));
+        assert_eq!(session.uuids.len(), 3);
+        assert_eq!(
+            session.source_files.iter().collect::<Vec<_>>(),
+            vec!["/logs/session-1.jsonl"]
+        );
+    }
+
+    #[test]
+    fn test_replace_removes_forgotten_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+
+        SqliteStateRepository::commit_sync(
+            &path,
+            &StateUpdate {
+                batch_id: "batch-001".to_string(),
+                timestamp: time("2024-12-25T10:00:00Z"),
+                uploaded: vec![record("uuid-1", "session-1"), record("uuid-2", "session-2")],
+                checkpoints: HashMap::from([(
+                    "/logs/session-1.jsonl".to_string(),
+                    FileCheckpoint::new(Some(1), 100, Some(10), 100, 2),
+                )]),
+            },
+        )
+        .unwrap();
+
+        let mut state = SqliteStateRepository::load_sync(&path, true).unwrap();
+        let summary = state.compact(time("2024-12-26T00:00:00Z"), |file| {
+            file == "/logs/session-1.jsonl"
+        });
+        assert_eq!(summary.forgotten_uuids, 1);
+        SqliteStateRepository::replace_sync(&path, &state).unwrap();
+
+        let loaded = SqliteStateRepository::load_sync(&path, true).unwrap();
+        assert!(!loaded.is_uploaded("uuid-1"));
+        assert!(loaded.is_uploaded("uuid-2"));
+        assert_eq!(loaded.total_uploaded, 2);
+        assert!(loaded.checkpoint("/logs/session-1.jsonl").is_some());
+        assert_eq!(
+            loaded.sessions["session-1"].compacted_until,
+            Some(time("2024-12-25T09:00:00Z"))
+        );
+        assert!(loaded.is_record_uploaded(
+            "uuid-1",
+            "session-1",
+            "/logs/session-1.jsonl",
+            time("2024-12-25T09:00:00Z")
+        ));
+
+        // 残ったレコードのバッチ情報は保持される
+        let conn = Connection::open(&path).unwrap();
+        let batch: String = conn
+            .query_row(
+                "SELECT batch_id FROM uploaded_records WHERE uuid = 'uuid-2'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(batch, "batch-001");
+    }
+
+    #[test]
+    fn test_migrates_schema_version_1() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE upload_summary (
+                 id INTEGER PRIMARY KEY CHECK (id = 1),
+                 last_upload_timestamp TEXT,
+                 last_upload_batch_id TEXT,
+                 total_uploaded INTEGER NOT NULL DEFAULT 0
+             );
+             INSERT INTO upload_summary (id, total_uploaded) VALUES (1, 1);
+             CREATE TABLE uploaded_records (
+                 uuid TEXT PRIMARY KEY, session_id TEXT, source_file TEXT,
+                 batch_id TEXT, uploaded_at TEXT
+             );
+             INSERT INTO uploaded_records VALUES
+                 ('uuid-1', 'session-1', '/logs/a.jsonl', 'batch-001', '2024-12-25T10:00:00+00:00');
+             CREATE TABLE session_uploads (
+                 session_id TEXT PRIMARY KEY, uploaded_count INTEGER NOT NULL,
+                 last_upload_timestamp TEXT, last_upload_batch_id TEXT
+             );
+             INSERT INTO session_uploads VALUES
+                 ('session-1', 1, '2024-12-25T10:00:00+00:00', 'batch-001');
+             CREATE TABLE file_checkpoints (
+                 source_file TEXT PRIMARY KEY, inode INTEGER, size INTEGER NOT NULL,
+                 mtime INTEGER, offset INTEGER NOT NULL, lines INTEGER NOT NULL
+             );
+             PRAGMA user_version = 1;",
+        )
+        .unwrap();
+        drop(conn);
+
+        let mut state = SqliteStateRepository::load_sync(&path, true).unwrap();
+
+        let session = &state.sessions["session-1"];
+        assert_eq!(session.last_uploaded_at, time("2024-12-25T10:00:00Z"));
+        assert!(session.source_files.contains("/logs/a.jsonl"));
+        assert!(session.uuids.contains("uuid-1"));
+        // レコードのタイムスタンプが無いセッションは圧縮しない
+        assert_eq!(session.latest_record_at, None);
+        assert_eq!(
+            state
+                .compact(time("2025-01-01T00:00:00Z"), |_| true)
+                .sessions,
+            0
+        );
+        // 移行前のレコードは内容のハッシュを持たない
+        assert!(state.record_hashes.is_empty());
+    }
+
+    #[test]
+    fn test_commit_updates_record_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+        let hashed = |hash: &str, version: u32| UploadedRecord {
+            content_hash: Some(hash.to_string()),
+            record_version: version,
+            ..record("uuid-1", "session-1")
+        };
+        let commit = |batch_id: &str, record: UploadedRecord| {
+            SqliteStateRepository::commit_sync(
+                &path,
+                &StateUpdate {
+                    batch_id: batch_id.to_string(),
+                    timestamp: time("2024-12-25T10:00:00Z"),
+                    uploaded: vec![record],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        };
+
+        commit("batch-001", hashed("hash-a", 0));
+        commit("batch-002", hashed("hash-b", 1));
+        // 古いバージョンやハッシュ不明の記録では上書きしない
+        commit("batch-003", hashed("hash-c", 0));
+        commit("batch-004", record("uuid-1", "session-1"));
+
+        let state = SqliteStateRepository::load_sync(&path, true).unwrap();
+        assert_eq!(
+            state.record_hashes["uuid-1"],
+            RecordHash {
+                hash: "hash-b".to_string(),
+                version: 1
+            }
+        );
+        assert_eq!(state.uploaded_uuids.len(), 1);
+
+        // saveでも同じ規則でマージされる
+        let mut stale = UploadState::new();
+        stale.uploaded_uuids.insert("uuid-1".to_string());
+        stale.record_hashes.insert(
+            "uuid-1".to_string(),
+            RecordHash {
+                hash: "hash-a".to_string(),
+                version: 0,
+            },
+        );
+        SqliteStateRepository::save_sync(&path, &stale).unwrap();
+        let state = SqliteStateRepository::load_sync(&path, true).unwrap();
+        assert_eq!(state.record_version("uuid-1"), 1);
+        assert!(!state.is_content_changed("uuid-1", "hash-b"));
+    }
+
+    #[test]
+    fn test_find_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+
+        SqliteStateRepository::commit_sync(
+            &path,
+            &StateUpdate {
+                uploaded: vec![
+                    record("uuid-1", "session-1"),
+                    UploadedRecord {
+                        content_hash: Some("hash-2".to_string()),
+                        record_version: 1,
+                        ..record("uuid-2", "session-1")
+                    },
+                ],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let found = SqliteStateRepository::find_records_sync(
+            &path,
+            &[
+                "uuid-1".to_string(),
+                "uuid-2".to_string(),
+                "uuid-9".to_string(),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            found,
+            HashMap::from([
+                ("uuid-1".to_string(), None),
+                (
+                    "uuid-2".to_string(),
+                    Some(RecordHash {
+                        hash: "hash-2".to_string(),
+                        version: 1
+                    })
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_load_without_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+
+        SqliteStateRepository::commit_sync(
+            &path,
+            &StateUpdate {
+                batch_id: "batch-001".to_string(),
+                timestamp: time("2024-12-25T10:00:00Z"),
+                uploaded: vec![record("uuid-1", "session-1")],
+                checkpoints: HashMap::from([(
+                    "/logs/a.jsonl".to_string(),
+                    FileCheckpoint::new(None, 10, None, 10, 1),
+                )]),
+            },
+        )
+        .unwrap();
+
+        let state = SqliteStateRepository::load_sync(&path, false).unwrap();
+
+        assert!(state.records_omitted);
+        assert!(state.uploaded_uuids.is_empty());
+        assert!(state.sessions["session-1"].uuids.is_empty());
+        assert_eq!(state.total_uploaded, 1);
+        assert!(state.checkpoint("/logs/a.jsonl").is_some());
+        // レコードを読み込んでいない状態では置き換えない
+        assert!(SqliteStateRepository::replace_sync(&path, &state).is_err());
+        assert!(SqliteStateRepository::load_sync(&path, true)
+            .unwrap()
+            .is_uploaded("uuid-1"));
+    }
+
+    #[test]
+    fn test_migrates_legacy_json_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+        let json_path = temp_dir.path().join("upload-state.json");
+        fs::write(
+            &json_path,
+            r#"{
+                "last_upload_timestamp": "2024-12-25T10:00:00Z",
+                "uploaded_uuids": ["uuid-1", "uuid-2"],
+                "last_upload_batch_id": "batch-001",
+                "total_uploaded": 2,
+                "file_checkpoints": {
+                    "/logs/a.jsonl": {"inode": 1, "size": 10, "mtime": 5, "offset": 10, "lines": 1}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let state = SqliteStateRepository::load_sync(&path, true).unwrap();
+
+        assert_eq!(state.uploaded_uuids.len(), 2);
+        assert_eq!(state.total_uploaded, 2);
+        assert_eq!(state.last_upload_batch_id.as_deref(), Some("batch-001"));
+        assert_eq!(state.checkpoint("/logs/a.jsonl").unwrap().offset, 10);
+        assert!(!json_path.exists());
+        assert!(temp_dir.path().join("upload-state.json.migrated").exists());
+
+        // 2回目以降は移行しない
+        fs::write(&json_path, r#"{"uploaded_uuids": ["uuid-x"], "total_uploaded": 1, "last_upload_timestamp": null, "last_upload_batch_id": null}"#).unwrap();
+        let state = SqliteStateRepository::load_sync(&path, true).unwrap();
+        assert!(!state.is_uploaded("uuid-x"));
+    }
+
+    #[test]
+    fn test_invalid_legacy_json_is_not_marked_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+        let json_path = temp_dir.path().join("upload-state.json");
+        fs::write(&json_path, "{ invalid").unwrap();
+
+        assert!(SqliteStateRepository::load_sync(&path, true).is_err());
+
+        // 修正後に再試行できる
+        fs::write(&json_path, r#"{"uploaded_uuids": ["uuid-1"], "total_uploaded": 1, "last_upload_timestamp": null, "last_upload_batch_id": null}"#).unwrap();
+        let state = SqliteStateRepository::load_sync(&path, true).unwrap();
+        assert!(state.is_uploaded("uuid-1"));
+    }
+
+    #[tokio::test]
+    async fn test_trait_commit_and_find_uploaded() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+        let repo = SqliteStateRepository::new();
+
+        repo.commit(
+            &path,
+            &StateUpdate {
+                batch_id: "batch-001".to_string(),
+                timestamp: time("2024-12-25T10:00:00Z"),
+                uploaded: vec![record("uuid-1", "session-1")],
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let found = repo
+            .find_uploaded(&path, &["uuid-1".to_string()])
+            .await
+            .unwrap();
+        assert!(found.contains("uuid-1"));
+
+        let state = repo.load(&path).await.unwrap();
+        assert_eq!(state.total_uploaded, 1);
+    }
+
+    #[tokio::test]
+    async fn test_trait_storage_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = db_path(&temp_dir);
+        let repo = SqliteStateRepository::new();
+
+        assert_eq!(repo.storage_size(&path).await.unwrap(), None);
+
+        repo.save(&path, &UploadState::new()).await.unwrap();
+        assert!(repo.storage_size(&path).await.unwrap().unwrap() > 0);
+    }
+}
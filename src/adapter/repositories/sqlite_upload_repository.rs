@@ -0,0 +1,324 @@
+//! SQLite Upload Repository Implementation
+//!
+//! UploadRepositoryのSQLite実装（BigQueryと同じ列のテーブルをローカルのデータベースに作る）
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::adapter::bigquery::models::SessionLogOutput;
+use crate::domain::entities::upload_batch::UploadBatch;
+use crate::domain::repositories::upload_repository::{UploadRepository, UploadResult};
+
+/// 他のプロセスが書き込み中の場合に待機する時間
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// テーブル定義（BigQueryのテーブルと同じ列。JSON列はテキスト、日時はUTCのISO 8601文字列）
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS session_logs (
+    uuid TEXT NOT NULL PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    agent_id TEXT,
+    is_sidechain INTEGER,
+    parent_uuid TEXT,
+    user_type TEXT,
+    type TEXT NOT NULL,
+    slug TEXT,
+    request_id TEXT,
+    cwd TEXT,
+    git_branch TEXT,
+    version TEXT,
+    message TEXT NOT NULL CHECK (json_valid(message)),
+    tool_use_result TEXT CHECK (json_valid(tool_use_result)),
+    extra TEXT CHECK (json_valid(extra)),
+    root_session_id TEXT,
+    depth INTEGER,
+    spawning_tool_use_id TEXT,
+    developer_id TEXT NOT NULL,
+    hostname TEXT NOT NULL,
+    user_email TEXT NOT NULL,
+    project_name TEXT NOT NULL,
+    record_version INTEGER NOT NULL DEFAULT 0,
+    upload_batch_id TEXT NOT NULL,
+    source_file TEXT NOT NULL,
+    uploaded_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_session_logs_session_id ON session_logs (session_id);
+CREATE INDEX IF NOT EXISTS idx_session_logs_developer_id ON session_logs (developer_id);
+CREATE INDEX IF NOT EXISTS idx_session_logs_uploaded_at ON session_logs (uploaded_at);
+";
+
+/// 行を書き込むSQL
+///
+/// UUIDが同じ行は1つだけ持ち、`record_version` が大きい行（内容が変わって再アップロードされたレコード）で置き換える。
+const INSERT: &str = "
+INSERT INTO session_logs (
+    uuid, timestamp, session_id, agent_id, is_sidechain, parent_uuid, user_type, type,
+    slug, request_id, cwd, git_branch, version, message, tool_use_result, extra,
+    root_session_id, depth, spawning_tool_use_id, developer_id, hostname, user_email,
+    project_name, record_version, upload_batch_id, source_file, uploaded_at
+) VALUES (
+    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+    ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27
+)
+ON CONFLICT (uuid) DO UPDATE SET
+    timestamp = excluded.timestamp,
+    session_id = excluded.session_id,
+    agent_id = excluded.agent_id,
+    is_sidechain = excluded.is_sidechain,
+    parent_uuid = excluded.parent_uuid,
+    user_type = excluded.user_type,
+    type = excluded.type,
+    slug = excluded.slug,
+    request_id = excluded.request_id,
+    cwd = excluded.cwd,
+    git_branch = excluded.git_branch,
+    version = excluded.version,
+    message = excluded.message,
+    tool_use_result = excluded.tool_use_result,
+    extra = excluded.extra,
+    root_session_id = excluded.root_session_id,
+    depth = excluded.depth,
+    spawning_tool_use_id = excluded.spawning_tool_use_id,
+    developer_id = excluded.developer_id,
+    hostname = excluded.hostname,
+    user_email = excluded.user_email,
+    project_name = excluded.project_name,
+    record_version = excluded.record_version,
+    upload_batch_id = excluded.upload_batch_id,
+    source_file = excluded.source_file,
+    uploaded_at = excluded.uploaded_at
+WHERE excluded.record_version > session_logs.record_version
+";
+
+/// 日時を保存用の文字列に変換する（SQLiteの日付関数で扱え、文字列の比較で大小を判定できる形式）
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// ローカルのSQLiteデータベースに書き込むアップロードリポジトリ
+///
+/// `session_logs` テーブルはBigQueryのテーブルと同じ列を持ち、`message` などのJSON列は
+/// テキストとして保存するため `json_extract` で参照できる。
+/// `uuid` は一意で、同じバッチを再送しても行は増えない。
+pub struct SqliteUploadRepository {
+    path: PathBuf,
+}
+
+impl SqliteUploadRepository {
+    /// 新しいリポジトリを作成
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - データベースファイルのパス（`~` を展開）
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(shellexpand::tilde(path).as_ref()),
+        }
+    }
+
+    /// データベースを開く（テーブルがなければ作成する）
+    fn open(path: &Path) -> Result<Connection> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create database directory")?;
+        }
+
+        let conn = Connection::open(path)
+            .context(format!("Failed to open database: {}", path.display()))?;
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .context("Failed to configure database")?;
+        conn.execute_batch(SCHEMA)
+            .context("Failed to create session_logs table")?;
+
+        Ok(conn)
+    }
+
+    /// 行を1トランザクションで書き込み、UUIDを返す（同期処理）
+    fn upload_sync(path: &Path, rows: Vec<SessionLogOutput>) -> Result<Vec<String>> {
+        let mut conn = Self::open(path)?;
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(INSERT)?;
+            for row in &rows {
+                stmt.execute(params![
+                    row.uuid,
+                    format_time(row.timestamp),
+                    row.session_id,
+                    row.agent_id,
+                    row.is_sidechain,
+                    row.parent_uuid,
+                    row.user_type,
+                    row.message_type,
+                    row.slug,
+                    row.request_id,
+                    row.cwd,
+                    row.git_branch,
+                    row.version,
+                    row.message.to_string(),
+                    row.tool_use_result.as_ref().map(|v| v.to_string()),
+                    row.extra.as_ref().map(|v| v.to_string()),
+                    row.root_session_id,
+                    row.depth,
+                    row.spawning_tool_use_id,
+                    row.developer_id,
+                    row.hostname,
+                    row.user_email,
+                    row.project_name,
+                    row.record_version,
+                    row.upload_batch_id,
+                    row.source_file,
+                    format_time(row.uploaded_at),
+                ])
+                .context(format!("Failed to insert row {}", row.uuid))?;
+            }
+        }
+        tx.commit().context("Failed to commit rows")?;
+
+        Ok(rows.into_iter().map(|row| row.uuid).collect())
+    }
+}
+
+#[async_trait]
+impl UploadRepository for SqliteUploadRepository {
+    async fn upload_batch(&self, batch: &UploadBatch) -> Result<UploadResult> {
+        let rows: Vec<SessionLogOutput> = batch.logs().iter().map(SessionLogOutput::from).collect();
+        let path = self.path.clone();
+
+        let uploaded_uuids = tokio::task::spawn_blocking(move || Self::upload_sync(&path, rows))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))??;
+
+        Ok(UploadResult::new(
+            uploaded_uuids.len(),
+            batch.len() - uploaded_uuids.len(),
+            uploaded_uuids,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::session_log::{LogMetadata, SessionLog};
+    use chrono::TimeZone;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn create_test_log(uuid: &str) -> SessionLog {
+        SessionLog {
+            uuid: uuid.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, 12, 25, 10, 0, 0).unwrap(),
+            session_id: "session-001".to_string(),
+            agent_id: None,
+            is_sidechain: Some(false),
+            parent_uuid: None,
+            user_type: None,
+            message_type: "assistant".to_string(),
+            slug: None,
+            request_id: None,
+            cwd: None,
+            git_branch: None,
+            version: None,
+            message: json!({"role": "assistant", "content": [{"type": "tool_use", "name": "Bash"}]}),
+            tool_use_result: None,
+            extra: None,
+            lineage: None,
+            record_version: 0,
+            metadata: LogMetadata {
+                developer_id: "dev-001".to_string(),
+                hostname: "test-host".to_string(),
+                user_email: "test@example.com".to_string(),
+                project_name: "app".to_string(),
+                upload_batch_id: "batch-001".to_string(),
+                source_file: "/path/to/log.jsonl".to_string(),
+                uploaded_at: Utc.with_ymd_and_hms(2024, 12, 25, 12, 0, 0).unwrap(),
+            },
+        }
+    }
+
+    fn database(dir: &TempDir) -> PathBuf {
+        dir.path().join("warehouse/sessync.db")
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_creates_queryable_table() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = database(&temp_dir);
+        let repo = SqliteUploadRepository::new(path.to_str().unwrap());
+
+        let batch = UploadBatch::new(vec![create_test_log("uuid-1"), create_test_log("uuid-2")]);
+        let result = repo.upload_batch(&batch).await.unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.uploaded_count, 2);
+
+        let conn = Connection::open(&path).unwrap();
+        let (tool, day): (String, String) = conn
+            .query_row(
+                "SELECT json_extract(message, '$.content[0].name'), date(uploaded_at)
+                 FROM session_logs WHERE uuid = 'uuid-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(tool, "Bash");
+        assert_eq!(day, "2024-12-25");
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_keeps_one_row_per_uuid() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = database(&temp_dir);
+        let repo = SqliteUploadRepository::new(path.to_str().unwrap());
+        let count = || -> i64 {
+            Connection::open(&path)
+                .unwrap()
+                .query_row("SELECT COUNT(*) FROM session_logs", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        let batch = UploadBatch::new(vec![create_test_log("uuid-1")]);
+        repo.upload_batch(&batch).await.unwrap();
+        // 再送は成功として扱い、行は増えない
+        let result = repo.upload_batch(&batch).await.unwrap();
+        assert_eq!(result.uploaded_uuids, vec!["uuid-1"]);
+        assert_eq!(count(), 1);
+
+        // 新しいバージョンは行を置き換える
+        let mut changed = create_test_log("uuid-1");
+        changed.record_version = 1;
+        changed.message = json!({"role": "assistant", "content": "edited"});
+        repo.upload_batch(&UploadBatch::new(vec![changed]))
+            .await
+            .unwrap();
+        // 古いバージョンの再送では戻らない
+        repo.upload_batch(&batch).await.unwrap();
+
+        assert_eq!(count(), 1);
+        let (version, content): (i64, String) = Connection::open(&path)
+            .unwrap()
+            .query_row(
+                "SELECT record_version, json_extract(message, '$.content') FROM session_logs",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(content, "edited");
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_fails_when_database_cannot_be_opened() {
+        let temp_dir = TempDir::new().unwrap();
+        // データベースのディレクトリを作れないようにファイルを置く
+        fs::write(temp_dir.path().join("warehouse"), "").unwrap();
+        let repo = SqliteUploadRepository::new(database(&temp_dir).to_str().unwrap());
+
+        let batch = UploadBatch::new(vec![create_test_log("uuid-1")]);
+        assert!(repo.upload_batch(&batch).await.is_err());
+    }
+}
@@ -0,0 +1,500 @@
+//! Webhook Upload Repository Implementation
+//!
+//! UploadRepositoryのHTTP実装（BigQueryに送る行と同じ形式のバッチを任意のエンドポイントにPOSTする）
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use flate2::write::GzEncoder;
+use hmac::{Hmac, KeyInit, Mac};
+use log::warn;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::io::Write;
+use tokio::time::{sleep, Duration};
+
+use crate::adapter::bigquery::models::SessionLogOutput;
+use crate::adapter::bigquery::retry::{calculate_retry_delay, MAX_RETRIES};
+use crate::adapter::config::json_config::{expand_env, WebhookConfig, WebhookFormat};
+use crate::domain::entities::upload_batch::UploadBatch;
+use crate::domain::repositories::upload_repository::{UploadRepository, UploadResult};
+
+/// リクエストのタイムアウト
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// エラーに含めるレスポンス本文の最大文字数
+const MAX_ERROR_BODY_CHARS: usize = 500;
+
+/// 署名の対象にした時刻（UNIX秒）のヘッダー
+const TIMESTAMP_HEADER: &str = "x-sessync-timestamp";
+
+/// HMAC-SHA256署名のヘッダー
+const SIGNATURE_HEADER: &str = "x-sessync-signature";
+
+/// 受信側が再送を見分けるためのヘッダー（バッチの行の insert ID から決まる）
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// 受信側の応答（行ごとの受け入れ結果）
+///
+/// `accepted` があればそのUUIDの行だけを、なければ `rejected` 以外の行をアップロード済みとする。
+#[derive(Deserialize, Default)]
+struct Acknowledgement {
+    accepted: Option<Vec<String>>,
+    #[serde(default)]
+    rejected: Vec<RejectedRow>,
+}
+
+/// 受け入れられなかった行（UUIDのみ、またはUUIDと理由）
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RejectedRow {
+    Uuid(String),
+    Detail {
+        uuid: String,
+        #[serde(default)]
+        error: Option<String>,
+    },
+}
+
+impl RejectedRow {
+    fn uuid(&self) -> &str {
+        match self {
+            RejectedRow::Uuid(uuid) | RejectedRow::Detail { uuid, .. } => uuid,
+        }
+    }
+}
+
+/// HTTPエンドポイントにバッチをPOSTするアップロードリポジトリ
+///
+/// 本文はBigQueryに送る行と同じ形式（`message` などのJSON列は文字列）のNDJSONまたはJSON配列。
+/// 2xxの応答本文が `{"accepted": [...]}` / `{"rejected": [...]}` の形であれば行ごとの結果として読み、
+/// 受け入れられなかった行は失敗として扱う（それ以外の本文なら全ての行を受け入れたものとする）。
+/// 429と5xx、接続エラーはリトライする。
+pub struct WebhookUploadRepository {
+    client: reqwest::Client,
+    url: String,
+    format: WebhookFormat,
+    headers: HeaderMap,
+    signing_secret: Option<String>,
+    gzip: bool,
+}
+
+impl WebhookUploadRepository {
+    /// 新しいリポジトリを作成
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - 送信先の設定（ヘッダー・トークン・署名鍵の `$VAR` は環境変数で展開する）
+    ///
+    /// # Errors
+    ///
+    /// 環境変数が未定義の場合、ヘッダーが不正な場合、HTTPクライアントを作成できない場合
+    pub fn new(config: &WebhookConfig) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            let value = expand_env(&format!("header {}", name), value)?;
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())
+                    .context(format!("Invalid webhook header name: {}", name))?,
+                HeaderValue::from_str(&value)
+                    .context(format!("Invalid value for webhook header {}", name))?,
+            );
+        }
+        if let Some(token) = &config.bearer_token {
+            let token = expand_env("bearer_token", token)?;
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+                .context("Invalid webhook bearer token")?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+        let signing_secret = config
+            .signing_secret
+            .as_deref()
+            .map(|secret| expand_env("signing_secret", secret))
+            .transpose()?;
+
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self {
+            client,
+            url: config.url.clone(),
+            format: config.format,
+            headers,
+            signing_secret,
+            gzip: config.gzip,
+        })
+    }
+
+    /// 行をリクエスト本文に変換する（必要ならgzipで圧縮）
+    fn body(&self, rows: &[SessionLogOutput]) -> Result<Vec<u8>> {
+        let body = match self.format {
+            WebhookFormat::Ndjson => {
+                let mut body = Vec::new();
+                for row in rows {
+                    serde_json::to_writer(&mut body, row).context("Failed to serialize row")?;
+                    body.push(b'\n');
+                }
+                body
+            }
+            WebhookFormat::Array => serde_json::to_vec(rows).context("Failed to serialize rows")?,
+        };
+        if !self.gzip {
+            return Ok(body);
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(&body)
+            .context("Failed to compress webhook body")?;
+        encoder.finish().context("Failed to compress webhook body")
+    }
+
+    /// 送信する本文に対する署名（`sha256=` + `<timestamp>.<body>` のHMAC-SHA256の16進表記）
+    fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// 行の insert ID から決まる再送判定用のキー
+    fn idempotency_key(rows: &[SessionLogOutput]) -> String {
+        let mut hasher = sha1_smol::Sha1::new();
+        for row in rows {
+            hasher.update(row.insert_id().as_bytes());
+            hasher.update(b"\n");
+        }
+        hasher.digest().to_string()
+    }
+
+    /// リクエストを1回送る（署名は送るたびに現在時刻で作り直す）
+    async fn send(&self, body: &[u8], idempotency_key: &str) -> reqwest::Result<reqwest::Response> {
+        let content_type = match self.format {
+            WebhookFormat::Ndjson => "application/x-ndjson",
+            WebhookFormat::Array => "application/json",
+        };
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header(CONTENT_TYPE, content_type)
+            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key);
+        if self.gzip {
+            request = request.header(reqwest::header::CONTENT_ENCODING, "gzip");
+        }
+        if let Some(secret) = &self.signing_secret {
+            let timestamp = Utc::now().timestamp();
+            request = request
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, Self::signature(secret, timestamp, body));
+        }
+
+        request.body(body.to_vec()).send().await
+    }
+
+    /// 応答本文から受け入れられた行のUUIDを求める
+    fn acknowledged(batch: &UploadBatch, response_body: &str) -> Vec<String> {
+        let ack: Acknowledgement = serde_json::from_str(response_body).unwrap_or_default();
+
+        let accepted: Option<HashSet<&str>> = ack
+            .accepted
+            .as_ref()
+            .map(|uuids| uuids.iter().map(String::as_str).collect());
+        let rejected: HashSet<&str> = ack.rejected.iter().map(RejectedRow::uuid).collect();
+        for row in &ack.rejected {
+            if let RejectedRow::Detail {
+                uuid,
+                error: Some(error),
+            } = row
+            {
+                warn!("Webhook rejected {}: {}", uuid, error);
+            }
+        }
+
+        batch
+            .logs()
+            .iter()
+            .map(|log| log.uuid.as_str())
+            .filter(|uuid| {
+                accepted
+                    .as_ref()
+                    .is_none_or(|accepted| accepted.contains(uuid))
+            })
+            .filter(|uuid| !rejected.contains(uuid))
+            .map(String::from)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl UploadRepository for WebhookUploadRepository {
+    async fn upload_batch(&self, batch: &UploadBatch) -> Result<UploadResult> {
+        let rows: Vec<SessionLogOutput> = batch.logs().iter().map(SessionLogOutput::from).collect();
+        let body = self.body(&rows)?;
+        let idempotency_key = Self::idempotency_key(&rows);
+
+        let mut retry_count = 0;
+        loop {
+            let (status, response_body) = match self.send(&body, &idempotency_key).await {
+                Ok(response) => {
+                    let status = response.status();
+                    (status, response.text().await.unwrap_or_default())
+                }
+                Err(e) if (e.is_connect() || e.is_timeout()) && retry_count < MAX_RETRIES => {
+                    retry_count += 1;
+                    let delay = calculate_retry_delay(retry_count);
+                    warn!(
+                        "Webhook request failed (attempt {}), retrying in {}ms: {}",
+                        retry_count, delay, e
+                    );
+                    sleep(Duration::from_millis(delay)).await;
+                    continue;
+                }
+                Err(e) => return Err(e).context("Failed to send batch to webhook"),
+            };
+
+            if status.is_success() {
+                let uploaded_uuids = Self::acknowledged(batch, &response_body);
+                return Ok(UploadResult::new(
+                    uploaded_uuids.len(),
+                    batch.len() - uploaded_uuids.len(),
+                    uploaded_uuids,
+                ));
+            }
+
+            // Rate limiting and server errors are transient
+            if (status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error())
+                && retry_count < MAX_RETRIES
+            {
+                retry_count += 1;
+                let delay = calculate_retry_delay(retry_count);
+                warn!(
+                    "Webhook returned {} (attempt {}), retrying in {}ms",
+                    status, retry_count, delay
+                );
+                sleep(Duration::from_millis(delay)).await;
+                continue;
+            }
+
+            let response_body: String = response_body.chars().take(MAX_ERROR_BODY_CHARS).collect();
+            bail!("Webhook returned {}: {}", status, response_body);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::session_log::{LogMetadata, SessionLog};
+    use chrono::TimeZone;
+    use flate2::read::GzDecoder;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::io::Read;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn create_test_log(uuid: &str) -> SessionLog {
+        SessionLog {
+            uuid: uuid.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2024, 12, 25, 10, 0, 0).unwrap(),
+            session_id: "session-001".to_string(),
+            agent_id: None,
+            is_sidechain: None,
+            parent_uuid: None,
+            user_type: None,
+            message_type: "user".to_string(),
+            slug: None,
+            request_id: None,
+            cwd: None,
+            git_branch: None,
+            version: None,
+            message: json!({"role": "user", "content": "Hello"}),
+            tool_use_result: None,
+            extra: None,
+            lineage: None,
+            record_version: 0,
+            metadata: LogMetadata {
+                developer_id: "dev-001".to_string(),
+                hostname: "test-host".to_string(),
+                user_email: "test@example.com".to_string(),
+                project_name: "app".to_string(),
+                upload_batch_id: "batch-001".to_string(),
+                source_file: "/path/to/log.jsonl".to_string(),
+                uploaded_at: Utc.with_ymd_and_hms(2024, 12, 25, 12, 0, 0).unwrap(),
+            },
+        }
+    }
+
+    fn create_config(server: &MockServer) -> WebhookConfig {
+        WebhookConfig {
+            url: format!("{}/ingest", server.uri()),
+            format: WebhookFormat::Ndjson,
+            headers: BTreeMap::new(),
+            bearer_token: None,
+            signing_secret: None,
+            gzip: false,
+        }
+    }
+
+    fn create_batch() -> UploadBatch {
+        UploadBatch::new(vec![create_test_log("uuid-1"), create_test_log("uuid-2")])
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_posts_signed_ndjson() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/ingest"))
+            .and(header("authorization", "Bearer token-123"))
+            .and(header("x-team", "platform"))
+            .and(header("content-type", "application/x-ndjson"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = create_config(&server);
+        config.bearer_token = Some("token-123".to_string());
+        config.signing_secret = Some("secret".to_string());
+        config
+            .headers
+            .insert("X-Team".to_string(), "platform".to_string());
+        let repo = WebhookUploadRepository::new(&config).unwrap();
+
+        let result = repo.upload_batch(&create_batch()).await.unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.uploaded_uuids, vec!["uuid-1", "uuid-2"]);
+
+        let requests = server.received_requests().await.unwrap();
+        let request = &requests[0];
+        let lines: Vec<serde_json::Value> = String::from_utf8(request.body.clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["uuid"], "uuid-1");
+        assert!(lines[0]["message"].is_string());
+
+        let timestamp: i64 = request.headers[TIMESTAMP_HEADER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            request.headers[SIGNATURE_HEADER].to_str().unwrap(),
+            WebhookUploadRepository::signature("secret", timestamp, &request.body)
+        );
+        assert!(request.headers.contains_key(IDEMPOTENCY_KEY_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_posts_gzipped_json_array() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("content-type", "application/json"))
+            .and(header("content-encoding", "gzip"))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(&server)
+            .await;
+
+        let mut config = create_config(&server);
+        config.format = WebhookFormat::Array;
+        config.gzip = true;
+        let repo = WebhookUploadRepository::new(&config).unwrap();
+
+        let result = repo.upload_batch(&create_batch()).await.unwrap();
+        assert_eq!(result.uploaded_count, 2);
+
+        let requests = server.received_requests().await.unwrap();
+        let mut body = String::new();
+        GzDecoder::new(requests[0].body.as_slice())
+            .read_to_string(&mut body)
+            .unwrap();
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+        assert_eq!(rows.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_maps_rejected_rows_to_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "rejected": [{"uuid": "uuid-2", "error": "schema mismatch"}]
+            })))
+            .mount(&server)
+            .await;
+        let repo = WebhookUploadRepository::new(&create_config(&server)).unwrap();
+
+        let result = repo.upload_batch(&create_batch()).await.unwrap();
+        assert_eq!(result.uploaded_uuids, vec!["uuid-1"]);
+        assert_eq!(result.failed_count, 1);
+    }
+
+    #[test]
+    fn test_acknowledged_accepted_list() {
+        let batch = create_batch();
+
+        let uploaded = WebhookUploadRepository::acknowledged(&batch, r#"{"accepted": ["uuid-2"]}"#);
+        assert_eq!(uploaded, vec!["uuid-2"]);
+
+        let uploaded = WebhookUploadRepository::acknowledged(&batch, r#"{"rejected": ["uuid-1"]}"#);
+        assert_eq!(uploaded, vec!["uuid-2"]);
+
+        // 行ごとの結果を返さない受信側では全ての行を受け入れたものとする
+        let uploaded = WebhookUploadRepository::acknowledged(&batch, "OK");
+        assert_eq!(uploaded, vec!["uuid-1", "uuid-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_retries_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let repo = WebhookUploadRepository::new(&create_config(&server)).unwrap();
+
+        let result = repo.upload_batch(&create_batch()).await.unwrap();
+        assert!(result.is_success());
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_upload_batch_fails_on_client_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("invalid payload"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let repo = WebhookUploadRepository::new(&create_config(&server)).unwrap();
+
+        let err = repo.upload_batch(&create_batch()).await.unwrap_err();
+        assert!(err.to_string().contains("400"));
+        assert!(err.to_string().contains("invalid payload"));
+    }
+
+    #[tokio::test]
+    async fn test_new_rejects_undefined_environment_variable() {
+        let server = MockServer::start().await;
+        let mut config = create_config(&server);
+        config.bearer_token = Some("$SESSYNC_TEST_UNDEFINED_WEBHOOK_TOKEN".to_string());
+
+        assert!(WebhookUploadRepository::new(&config).is_err());
+    }
+}
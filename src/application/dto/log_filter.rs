@@ -0,0 +1,403 @@
+//! # Log Filter DTO
+//!
+//! ログファイル・レコードの絞り込み条件のData Transfer Object
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use glob::Pattern;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::domain::entities::session_log::SessionLog;
+
+/// 期間（`since` / `until`）の判定に使う時刻
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeBasis {
+    /// レコードのタイムスタンプ（デフォルト）
+    #[default]
+    Record,
+    /// ログファイルの更新時刻（ファイル単位で判定）
+    FileMtime,
+}
+
+impl FromStr for TimeBasis {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "record" => Ok(Self::Record),
+            "mtime" => Ok(Self::FileMtime),
+            other => anyhow::bail!("Unknown time basis: {} (expected record or mtime)", other),
+        }
+    }
+}
+
+/// ログの絞り込み条件
+///
+/// ファイルパスのglob（`include` / `exclude`）、期間、セッションIDで
+/// 発見・パースするログを絞り込む。条件を指定しなければ全てのログが対象になる。
+///
+/// 期間は `since` 以上 `until` 未満。
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// この時刻以降のログのみ（含む）
+    pub since: Option<DateTime<Utc>>,
+    /// この時刻より前のログのみ（含まない）
+    pub until: Option<DateTime<Utc>>,
+    /// 期間の判定に使う時刻
+    pub time_basis: TimeBasis,
+    /// 対象のセッションID（空なら全セッション）
+    pub sessions: Vec<String>,
+    /// 対象にするパスのglob（空なら全て）
+    include: Vec<Pattern>,
+    /// 除外するパスのglob
+    exclude: Vec<Pattern>,
+}
+
+impl LogFilter {
+    /// 条件なし（全てのログが対象）の絞り込みを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 期間を設定します。
+    pub fn with_time_range(
+        mut self,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.since = since;
+        self.until = until;
+        self
+    }
+
+    /// 期間の判定に使う時刻を設定します。
+    pub fn with_time_basis(mut self, time_basis: TimeBasis) -> Self {
+        self.time_basis = time_basis;
+        self
+    }
+
+    /// 対象のセッションIDを設定します。
+    pub fn with_sessions(mut self, sessions: Vec<String>) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    /// 対象にするパスのglobを設定します。
+    ///
+    /// # エラー
+    ///
+    /// globとして不正なパターンが含まれる場合にエラーを返します。
+    pub fn with_include(mut self, patterns: &[String]) -> Result<Self> {
+        self.include = compile_patterns(patterns)?;
+        Ok(self)
+    }
+
+    /// 除外するパスのglobを設定します。
+    ///
+    /// # エラー
+    ///
+    /// globとして不正なパターンが含まれる場合にエラーを返します。
+    pub fn with_exclude(mut self, patterns: &[String]) -> Result<Self> {
+        self.exclude = compile_patterns(patterns)?;
+        Ok(self)
+    }
+
+    /// 条件が1つも指定されていないかを判定します。
+    pub fn is_empty(&self) -> bool {
+        self.since.is_none()
+            && self.until.is_none()
+            && self.sessions.is_empty()
+            && self.include.is_empty()
+            && self.exclude.is_empty()
+    }
+
+    /// レコード単位で絞り込むかを判定します。
+    ///
+    /// レコード単位の絞り込みでは、読み飛ばしたレコードを後で取り込めるよう
+    /// チェックポイントを使わずにファイルの先頭から読み、チェックポイントも更新しません。
+    pub fn is_selective(&self) -> bool {
+        !self.sessions.is_empty()
+            || (self.time_basis == TimeBasis::Record
+                && (self.since.is_some() || self.until.is_some()))
+    }
+
+    /// 期間の判定にファイルの更新時刻が必要かを判定します。
+    pub fn needs_modified_time(&self) -> bool {
+        self.since.is_some() || self.until.is_some()
+    }
+
+    /// ファイルパスが `include` / `exclude` の条件を満たすかを判定します。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::application::dto::log_filter::LogFilter;
+    /// use std::path::Path;
+    ///
+    /// let filter = LogFilter::new()
+    ///     .with_exclude(&["*scratch*".to_string()])
+    ///     .unwrap();
+    ///
+    /// assert!(filter.matches_path(Path::new("/logs/-Users-me-app/s1.jsonl")));
+    /// assert!(!filter.matches_path(Path::new("/logs/-Users-me-scratch/s1.jsonl")));
+    /// ```
+    pub fn matches_path(&self, path: &Path) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|p| p.matches_path(path));
+        included && !self.exclude.iter().any(|p| p.matches_path(path))
+    }
+
+    /// ファイルの更新時刻からファイルを対象にするかを判定します。
+    ///
+    /// レコードのタイムスタンプで判定する場合も、`since` より前に更新が止まった
+    /// ファイルには対象のレコードが含まれないため除外します。
+    /// 更新時刻が取得できない場合は対象にします。
+    pub fn keeps_file(&self, modified_at: Option<DateTime<Utc>>) -> bool {
+        let Some(modified_at) = modified_at else {
+            return true;
+        };
+
+        let after_since = self.since.is_none_or(|since| modified_at >= since);
+        match self.time_basis {
+            TimeBasis::Record => after_since,
+            TimeBasis::FileMtime => {
+                after_since && self.until.is_none_or(|until| modified_at < until)
+            }
+        }
+    }
+
+    /// レコードを対象にするかを判定します。
+    ///
+    /// セッションIDは、サブエージェントのログも含められるよう
+    /// セッションツリーの根のセッションIDとも照合します。
+    pub fn keeps_record(&self, log: &SessionLog) -> bool {
+        let in_sessions = self.sessions.is_empty()
+            || self.sessions.iter().any(|session| {
+                *session == log.session_id
+                    || log
+                        .lineage
+                        .as_ref()
+                        .is_some_and(|lineage| *session == lineage.root_session_id)
+            });
+        if !in_sessions {
+            return false;
+        }
+
+        match self.time_basis {
+            TimeBasis::Record => {
+                self.since.is_none_or(|since| log.timestamp >= since)
+                    && self.until.is_none_or(|until| log.timestamp < until)
+            }
+            TimeBasis::FileMtime => true,
+        }
+    }
+}
+
+/// 期間の指定を時刻に変換します。
+///
+/// RFC 3339（`2024-12-24T10:00:00Z`）、日付（`2024-12-24`、UTCの0時）、
+/// 現在からの相対時間（`30m`、`12h`、`7d`、`2w`）を受け付けます。
+///
+/// # エラー
+///
+/// いずれの形式でもない場合にエラーを返します。
+///
+/// # 例
+///
+/// ```
+/// use sessync::application::dto::log_filter::parse_time;
+/// use chrono::{TimeZone, Utc};
+///
+/// let date = parse_time("2024-12-24").unwrap();
+/// assert_eq!(date, Utc.with_ymd_and_hms(2024, 12, 24, 0, 0, 0).unwrap());
+///
+/// let week_ago = parse_time("7d").unwrap();
+/// assert!(week_ago < Utc::now());
+/// ```
+pub fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    parse_time_at(value, Utc::now())
+}
+
+/// `now` を基準に期間の指定を時刻に変換
+fn parse_time_at(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    let split = value.char_indices().last().map_or(0, |(i, _)| i);
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("Invalid time: {} (use RFC 3339, YYYY-MM-DD or 7d)", value))?;
+    let duration = match unit {
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => anyhow::bail!("Invalid time: {} (use RFC 3339, YYYY-MM-DD or 7d)", value),
+    };
+
+    Ok(now - duration)
+}
+
+/// globパターンをコンパイル
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::session_log::{LogMetadata, SessionLineage};
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn create_test_log(session_id: &str, timestamp: DateTime<Utc>) -> SessionLog {
+        let metadata = LogMetadata {
+            developer_id: "dev-001".to_string(),
+            hostname: "test-host".to_string(),
+            user_email: "test@example.com".to_string(),
+            project_name: "test-project".to_string(),
+            upload_batch_id: "batch-001".to_string(),
+            source_file: "/path/to/log.jsonl".to_string(),
+            uploaded_at: Utc::now(),
+        };
+        SessionLog::new(
+            "uuid-1".to_string(),
+            timestamp,
+            session_id.to_string(),
+            None,
+            None,
+            None,
+            None,
+            "user".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            json!({}),
+            None,
+            metadata,
+        )
+        .unwrap()
+    }
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 12, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_empty_filter_keeps_everything() {
+        let filter = LogFilter::new();
+        assert!(filter.is_empty());
+        assert!(!filter.is_selective());
+        assert!(filter.matches_path(Path::new("/any/log.jsonl")));
+        assert!(filter.keeps_file(Some(at(1))));
+        assert!(filter.keeps_record(&create_test_log("s1", at(1))));
+    }
+
+    #[test]
+    fn test_record_time_range() {
+        let filter = LogFilter::new().with_time_range(Some(at(10)), Some(at(20)));
+
+        assert!(filter.is_selective());
+        assert!(!filter.keeps_record(&create_test_log("s1", at(9))));
+        assert!(filter.keeps_record(&create_test_log("s1", at(10))));
+        assert!(!filter.keeps_record(&create_test_log("s1", at(20))));
+        // 更新が since より前に止まったファイルは除外、until は判定しない
+        assert!(!filter.keeps_file(Some(at(9))));
+        assert!(filter.keeps_file(Some(at(25))));
+        assert!(filter.keeps_file(None));
+    }
+
+    #[test]
+    fn test_mtime_time_range() {
+        let filter = LogFilter::new()
+            .with_time_range(Some(at(10)), Some(at(20)))
+            .with_time_basis(TimeBasis::FileMtime);
+
+        // ファイル単位で判定するため、チェックポイントはそのまま使える
+        assert!(!filter.is_selective());
+        assert!(filter.keeps_file(Some(at(15))));
+        assert!(!filter.keeps_file(Some(at(25))));
+        assert!(filter.keeps_record(&create_test_log("s1", at(1))));
+    }
+
+    #[test]
+    fn test_sessions_match_root_session() {
+        let filter = LogFilter::new().with_sessions(vec!["parent".to_string()]);
+
+        assert!(filter.keeps_record(&create_test_log("parent", at(1))));
+        assert!(!filter.keeps_record(&create_test_log("other", at(1))));
+
+        let mut subagent = create_test_log("child", at(1));
+        subagent.lineage = Some(SessionLineage {
+            root_session_id: "parent".to_string(),
+            depth: 1,
+            spawning_tool_use_id: None,
+        });
+        assert!(filter.keeps_record(&subagent));
+    }
+
+    #[test]
+    fn test_include_and_exclude() {
+        let filter = LogFilter::new()
+            .with_include(&["/logs/**/*.jsonl".to_string()])
+            .unwrap()
+            .with_exclude(&["**/-tmp-*/**".to_string()])
+            .unwrap();
+
+        assert!(filter.matches_path(Path::new("/logs/-Users-me-app/s1.jsonl")));
+        assert!(!filter.matches_path(Path::new("/logs/-tmp-scratch/s1.jsonl")));
+        assert!(!filter.matches_path(Path::new("/other/s1.jsonl")));
+        assert!(!filter.is_selective());
+    }
+
+    #[test]
+    fn test_invalid_glob_is_error() {
+        assert!(LogFilter::new().with_include(&["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_formats() {
+        let now = Utc.with_ymd_and_hms(2024, 12, 24, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            parse_time_at("2024-12-24T10:00:00+09:00", now).unwrap(),
+            Utc.with_ymd_and_hms(2024, 12, 24, 1, 0, 0).unwrap()
+        );
+        assert_eq!(parse_time_at("2024-12-20", now).unwrap(), at(20));
+        assert_eq!(
+            parse_time_at("7d", now).unwrap(),
+            Utc.with_ymd_and_hms(2024, 12, 17, 12, 0, 0).unwrap()
+        );
+        assert_eq!(
+            parse_time_at("2w", now).unwrap(),
+            Utc.with_ymd_and_hms(2024, 12, 10, 12, 0, 0).unwrap()
+        );
+        assert_eq!(
+            parse_time_at("30m", now).unwrap(),
+            Utc.with_ymd_and_hms(2024, 12, 24, 11, 30, 0).unwrap()
+        );
+        assert!(parse_time_at("yesterday", now).is_err());
+        assert!(parse_time_at("", now).is_err());
+    }
+
+    #[test]
+    fn test_time_basis_from_str() {
+        assert_eq!("record".parse::<TimeBasis>().unwrap(), TimeBasis::Record);
+        assert_eq!("mtime".parse::<TimeBasis>().unwrap(), TimeBasis::FileMtime);
+        assert!("ctime".parse::<TimeBasis>().is_err());
+    }
+}
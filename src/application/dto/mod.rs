@@ -2,4 +2,6 @@
 //!
 //! レイヤー間でデータを転送するためのオブジェクト
 
+pub mod log_filter;
+pub mod parsed_logs;
 pub mod upload_config;
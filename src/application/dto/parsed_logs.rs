@@ -0,0 +1,167 @@
+//! # Parsed Logs DTO
+//!
+//! パース結果のData Transfer Object
+
+use std::collections::HashMap;
+
+use crate::domain::entities::file_checkpoint::FileCheckpoint;
+use crate::domain::entities::rejected_line::RejectedLine;
+use crate::domain::entities::session_log::SessionLog;
+
+/// パース結果ストリームの要素
+#[derive(Debug, Clone)]
+pub enum ParsedRecord {
+    /// 重複排除後のセッションログ
+    Log(Box<SessionLog>),
+    /// アップロード済みだが内容が変わっていたセッションログ（報告のみでアップロードしない）
+    Changed(Box<SessionLog>),
+    /// パースできなかった行（隔離ストアに保存すべきもの）
+    Rejected(Box<RejectedLine>),
+    /// ファイルを読み終えた（このファイル由来のログは全て流れた）
+    FileCompleted {
+        /// ファイルパス
+        source_file: String,
+        /// アップロード成功後にコミットすべきチェックポイント
+        checkpoint: FileCheckpoint,
+    },
+}
+
+/// パース結果
+///
+/// 重複排除後のセッションログ、パースできなかった行と、
+/// アップロード成功後にコミットすべきファイルごとのチェックポイント
+#[derive(Debug, Clone, Default)]
+pub struct ParsedLogs {
+    /// 重複排除後のセッションログ
+    pub logs: Vec<SessionLog>,
+    /// アップロード済みだが内容が変わっていたセッションログ
+    pub changed: Vec<SessionLog>,
+    /// パースできなかった行
+    pub rejected: Vec<RejectedLine>,
+    /// コミット待ちのチェックポイント（キーはファイルパス）
+    pub checkpoints: HashMap<String, FileCheckpoint>,
+}
+
+impl ParsedLogs {
+    /// 新しいパース結果を作成
+    pub fn new(logs: Vec<SessionLog>, checkpoints: HashMap<String, FileCheckpoint>) -> Self {
+        Self {
+            logs,
+            changed: Vec::new(),
+            rejected: Vec::new(),
+            checkpoints,
+        }
+    }
+
+    /// ログ数を返す
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.logs.len()
+    }
+
+    /// ログが空かどうかを返す
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.logs.is_empty()
+    }
+}
+
+impl From<ParsedLogs> for Vec<ParsedRecord> {
+    /// ログ、変更されたログ、隔離行、チェックポイントの順に並べたレコード列に変換
+    fn from(parsed: ParsedLogs) -> Self {
+        let mut checkpoints: Vec<_> = parsed.checkpoints.into_iter().collect();
+        checkpoints.sort_by(|a, b| a.0.cmp(&b.0));
+
+        parsed
+            .logs
+            .into_iter()
+            .map(|log| ParsedRecord::Log(Box::new(log)))
+            .chain(
+                parsed
+                    .changed
+                    .into_iter()
+                    .map(|log| ParsedRecord::Changed(Box::new(log))),
+            )
+            .chain(
+                parsed
+                    .rejected
+                    .into_iter()
+                    .map(|reject| ParsedRecord::Rejected(Box::new(reject))),
+            )
+            .chain(checkpoints.into_iter().map(|(source_file, checkpoint)| {
+                ParsedRecord::FileCompleted {
+                    source_file,
+                    checkpoint,
+                }
+            }))
+            .collect()
+    }
+}
+
+impl From<Vec<SessionLog>> for ParsedLogs {
+    fn from(logs: Vec<SessionLog>) -> Self {
+        Self::new(logs, HashMap::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsed_logs_from_vec() {
+        let parsed = ParsedLogs::from(Vec::new());
+        assert!(parsed.is_empty());
+        assert_eq!(parsed.len(), 0);
+        assert!(parsed.checkpoints.is_empty());
+    }
+
+    #[test]
+    fn test_parsed_logs_into_records_puts_checkpoints_last() {
+        let checkpoints = HashMap::from([
+            (
+                "/logs/b.jsonl".to_string(),
+                FileCheckpoint::new(None, 10, None, 10, 1),
+            ),
+            (
+                "/logs/a.jsonl".to_string(),
+                FileCheckpoint::new(None, 10, None, 10, 1),
+            ),
+        ]);
+        let records: Vec<ParsedRecord> = ParsedLogs::new(Vec::new(), checkpoints).into();
+
+        assert_eq!(records.len(), 2);
+        assert!(
+            matches!(&records[0], ParsedRecord::FileCompleted { source_file, .. } if source_file == "/logs/a.jsonl")
+        );
+    }
+
+    #[test]
+    fn test_parsed_logs_into_records_includes_rejected() {
+        let mut parsed = ParsedLogs::new(
+            Vec::new(),
+            HashMap::from([(
+                "/logs/a.jsonl".to_string(),
+                FileCheckpoint::new(None, 10, None, 10, 1),
+            )]),
+        );
+        parsed
+            .rejected
+            .push(RejectedLine::new("/logs/a.jsonl", 1, "err", "x"));
+        let records: Vec<ParsedRecord> = parsed.into();
+
+        assert_eq!(records.len(), 2);
+        assert!(matches!(&records[0], ParsedRecord::Rejected(reject) if reject.line_number == 1));
+        assert!(matches!(&records[1], ParsedRecord::FileCompleted { .. }));
+    }
+
+    #[test]
+    fn test_parsed_logs_new() {
+        let checkpoints = HashMap::from([(
+            "/logs/a.jsonl".to_string(),
+            FileCheckpoint::new(None, 10, None, 10, 1),
+        )]);
+        let parsed = ParsedLogs::new(Vec::new(), checkpoints);
+        assert_eq!(parsed.checkpoints.len(), 1);
+    }
+}
@@ -2,6 +2,11 @@
 //!
 //! アップロード設定のData Transfer Object
 
+use crate::domain::services::deduplication::ChangePolicy;
+
+/// ログファイルを並列にパースするワーカー数のデフォルト値
+pub const DEFAULT_PARSE_WORKERS: usize = 8;
+
 /// アップロード設定
 ///
 /// BigQueryへのアップロードに必要な設定情報
@@ -19,6 +24,8 @@ pub struct UploadConfig {
     pub batch_size: usize,
     /// 重複排除を有効にするかどうか
     pub enable_deduplication: bool,
+    /// アップロード済みのレコードの内容が変わっていた場合の扱い
+    pub change_policy: ChangePolicy,
 
     /// 開発者ID（チームコラボレーション用）
     pub developer_id: String,
@@ -26,6 +33,9 @@ pub struct UploadConfig {
     pub user_email: String,
     /// プロジェクト名
     pub project_name: String,
+
+    /// ログファイルを並列にパースするワーカー数（1以上）
+    pub parse_workers: usize,
 }
 
 impl UploadConfig {
@@ -93,11 +103,69 @@ impl UploadConfig {
             location,
             batch_size,
             enable_deduplication,
+            change_policy: ChangePolicy::default(),
             developer_id,
             user_email,
             project_name,
+            parse_workers: DEFAULT_PARSE_WORKERS,
         }
     }
+
+    /// ログファイルを並列にパースするワーカー数を設定します。
+    ///
+    /// 0を指定した場合は1（逐次処理）として扱います。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// # use sessync::application::dto::upload_config::UploadConfig;
+    /// let config = UploadConfig::new(
+    ///     "my-gcp-project".to_string(),
+    ///     "claude_logs".to_string(),
+    ///     "session_logs".to_string(),
+    ///     "US".to_string(),
+    ///     500,
+    ///     true,
+    ///     "dev-alice".to_string(),
+    ///     "alice@example.com".to_string(),
+    ///     "my-app".to_string(),
+    /// )
+    /// .with_parse_workers(16);
+    ///
+    /// assert_eq!(config.parse_workers, 16);
+    /// ```
+    pub fn with_parse_workers(mut self, parse_workers: usize) -> Self {
+        self.parse_workers = parse_workers.max(1);
+        self
+    }
+
+    /// アップロード済みのレコードの内容が変わっていた場合の扱いを設定します。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// # use sessync::application::dto::upload_config::UploadConfig;
+    /// use sessync::domain::services::deduplication::ChangePolicy;
+    ///
+    /// let config = UploadConfig::new(
+    ///     "my-gcp-project".to_string(),
+    ///     "claude_logs".to_string(),
+    ///     "session_logs".to_string(),
+    ///     "US".to_string(),
+    ///     500,
+    ///     true,
+    ///     "dev-alice".to_string(),
+    ///     "alice@example.com".to_string(),
+    ///     "my-app".to_string(),
+    /// )
+    /// .with_change_policy(ChangePolicy::NewVersion);
+    ///
+    /// assert_eq!(config.change_policy, ChangePolicy::NewVersion);
+    /// ```
+    pub fn with_change_policy(mut self, change_policy: ChangePolicy) -> Self {
+        self.change_policy = change_policy;
+        self
+    }
 }
 
 #[cfg(test)]
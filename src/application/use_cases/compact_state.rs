@@ -0,0 +1,290 @@
+//! # Compact State Use Case
+//!
+//! アップロード状態の圧縮ユースケース
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use log::warn;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::domain::repositories::log_repository::LogRepository;
+use crate::domain::repositories::state_repository::{CompactionSummary, StateRepository};
+
+/// 状態圧縮ユースケース
+///
+/// 保持期間を過ぎ、ログファイルが封印済みか削除済みのセッションについて、
+/// アップロード済みUUIDをタイムスタンプの透かしに置き換えて状態を小さくする
+pub struct CompactStateUseCase<L: LogRepository, S: StateRepository + ?Sized> {
+    log_repository: Arc<L>,
+    state_repository: Arc<S>,
+}
+
+impl<L: LogRepository, S: StateRepository + ?Sized> CompactStateUseCase<L, S> {
+    /// 新しいユースケースを作成
+    ///
+    /// # Arguments
+    ///
+    /// * `log_repository` - ログリポジトリ（ファイルの封印状態の確認に使用）
+    /// * `state_repository` - 状態リポジトリ
+    pub fn new(log_repository: Arc<L>, state_repository: Arc<S>) -> Self {
+        Self {
+            log_repository,
+            state_repository,
+        }
+    }
+
+    /// 状態を圧縮します。
+    ///
+    /// 最後のアップロードから `retention` 以上経過したセッションのうち、
+    /// ログファイルが全て封印済みか削除済みのものが対象です。
+    /// 状態を書き直すため、実行ロックを取得した上で呼び出してください。
+    ///
+    /// # 引数
+    ///
+    /// * `state_path` - 状態ファイルのパス
+    /// * `retention` - アップロード済みUUIDを保持する期間
+    /// * `dry_run` - `true` の場合は状態を書き換えずに結果だけを返す
+    ///
+    /// # 戻り値
+    ///
+    /// 圧縮したセッション数と忘れたUUIDの数
+    ///
+    /// # エラー
+    ///
+    /// 状態の読み込みまたは書き込みに失敗した場合にエラーを返します。
+    ///
+    /// # 例
+    ///
+    /// ```no_run
+    /// use sessync::application::use_cases::compact_state::CompactStateUseCase;
+    /// use sessync::adapter::repositories::file_log_repository::FileLogRepository;
+    /// use sessync::adapter::repositories::json_state_repository::JsonStateRepository;
+    /// use chrono::Duration;
+    /// use std::sync::Arc;
+    ///
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let use_case = CompactStateUseCase::new(
+    ///     Arc::new(FileLogRepository::new()),
+    ///     Arc::new(JsonStateRepository::new()),
+    /// );
+    ///
+    /// let summary = use_case
+    ///     .execute("./.claude/sessync/upload-state.json", Duration::days(30), false)
+    ///     .await?;
+    /// println!("{}個のUUIDを圧縮", summary.forgotten_uuids);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute(
+        &self,
+        state_path: &str,
+        retention: Duration,
+        dry_run: bool,
+    ) -> Result<CompactionSummary> {
+        let mut state = self.state_repository.load(state_path).await?;
+        let cutoff = Utc::now() - retention;
+
+        let mut sealed = HashSet::new();
+        for source_file in state.compaction_candidates(cutoff) {
+            let checkpoint = state.checkpoint(&source_file);
+            match self
+                .log_repository
+                .is_sealed(Path::new(&source_file), checkpoint)
+                .await
+            {
+                Ok(true) => {
+                    sealed.insert(source_file);
+                }
+                Ok(false) => {}
+                Err(e) => warn!("Keeping UUIDs for {}: {}", source_file, e),
+            }
+        }
+
+        let summary = state.compact(cutoff, |source_file| sealed.contains(source_file));
+        if summary.sessions > 0 && !dry_run {
+            self.state_repository.replace(state_path, &state).await?;
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::file_checkpoint::FileCheckpoint;
+    use crate::domain::repositories::log_repository::LogStream;
+    use crate::domain::repositories::state_repository::{StateUpdate, UploadState, UploadedRecord};
+    use async_trait::async_trait;
+    use chrono::{DateTime, TimeZone};
+    use futures::stream::{self, StreamExt};
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    struct MockLogRepository {
+        sealed: HashSet<String>,
+    }
+
+    #[async_trait]
+    impl LogRepository for MockLogRepository {
+        async fn discover_log_files(&self, _log_dir: &str) -> Result<Vec<PathBuf>> {
+            Ok(vec![])
+        }
+
+        fn stream_log_file(
+            &self,
+            _file_path: PathBuf,
+            _checkpoint: Option<FileCheckpoint>,
+        ) -> LogStream<'_> {
+            stream::empty().boxed()
+        }
+
+        async fn is_sealed(
+            &self,
+            file_path: &Path,
+            _checkpoint: Option<&FileCheckpoint>,
+        ) -> Result<bool> {
+            Ok(self.sealed.contains(file_path.to_str().unwrap()))
+        }
+    }
+
+    struct MockStateRepository {
+        state: Mutex<UploadState>,
+        replaced: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl StateRepository for MockStateRepository {
+        async fn load(&self, _path: &str) -> Result<UploadState> {
+            Ok(self.state.lock().unwrap().clone())
+        }
+
+        async fn save(&self, _path: &str, _state: &UploadState) -> Result<()> {
+            Ok(())
+        }
+
+        async fn replace(&self, _path: &str, state: &UploadState) -> Result<()> {
+            *self.state.lock().unwrap() = state.clone();
+            *self.replaced.lock().unwrap() = true;
+            Ok(())
+        }
+    }
+
+    fn record_time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap()
+    }
+
+    /// session-old は60日前、session-new は今アップロードされた状態
+    fn create_state() -> UploadState {
+        let mut state = UploadState::new();
+        for (session_id, uploaded_at) in [
+            ("session-old", Utc::now() - Duration::days(60)),
+            ("session-new", Utc::now()),
+        ] {
+            StateUpdate {
+                batch_id: "batch-001".to_string(),
+                timestamp: uploaded_at,
+                uploaded: vec![UploadedRecord {
+                    uuid: format!("{}-uuid", session_id),
+                    session_id: session_id.to_string(),
+                    source_file: format!("/logs/{}.jsonl", session_id),
+                    timestamp: record_time(),
+                    content_hash: None,
+                    record_version: 0,
+                }],
+                ..Default::default()
+            }
+            .apply(&mut state);
+        }
+        state
+    }
+
+    fn create_use_case(
+        sealed: &[&str],
+    ) -> (
+        CompactStateUseCase<MockLogRepository, MockStateRepository>,
+        Arc<MockStateRepository>,
+    ) {
+        let log_repo = Arc::new(MockLogRepository {
+            sealed: sealed.iter().map(|s| s.to_string()).collect(),
+        });
+        let state_repo = Arc::new(MockStateRepository {
+            state: Mutex::new(create_state()),
+            replaced: Mutex::new(false),
+        });
+        (
+            CompactStateUseCase::new(log_repo, state_repo.clone()),
+            state_repo,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_compacts_expired_sealed_sessions() {
+        let (use_case, state_repo) =
+            create_use_case(&["/logs/session-old.jsonl", "/logs/session-new.jsonl"]);
+
+        let summary = use_case
+            .execute("state.json", Duration::days(30), false)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            CompactionSummary {
+                sessions: 1,
+                forgotten_uuids: 1
+            }
+        );
+        assert!(*state_repo.replaced.lock().unwrap());
+
+        let state = state_repo.state.lock().unwrap().clone();
+        assert!(!state.is_uploaded("session-old-uuid"));
+        assert!(state.is_uploaded("session-new-uuid"));
+        // 忘れたUUIDも透かしで重複と判定される
+        assert!(state.is_record_uploaded(
+            "session-old-uuid",
+            "session-old",
+            "/logs/session-old.jsonl",
+            record_time()
+        ));
+        assert!(!state.is_record_uploaded(
+            "session-old-later",
+            "session-old",
+            "/logs/session-old.jsonl",
+            record_time() + Duration::seconds(1)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_keeps_sessions_with_unsealed_files() {
+        let (use_case, state_repo) = create_use_case(&[]);
+
+        let summary = use_case
+            .execute("state.json", Duration::days(30), false)
+            .await
+            .unwrap();
+
+        assert_eq!(summary, CompactionSummary::default());
+        assert!(!*state_repo.replaced.lock().unwrap());
+        assert!(state_repo
+            .state
+            .lock()
+            .unwrap()
+            .is_uploaded("session-old-uuid"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_does_not_write() {
+        let (use_case, state_repo) = create_use_case(&["/logs/session-old.jsonl"]);
+
+        let summary = use_case
+            .execute("state.json", Duration::days(30), true)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.sessions, 1);
+        assert!(!*state_repo.replaced.lock().unwrap());
+    }
+}
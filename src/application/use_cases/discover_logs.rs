@@ -3,9 +3,11 @@
 //! ログファイル発見ユースケース
 
 use anyhow::Result;
+use log::debug;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use crate::application::dto::log_filter::LogFilter;
 use crate::domain::repositories::log_repository::LogRepository;
 
 /// ログファイル発見ユースケース
@@ -27,9 +29,13 @@ impl<R: LogRepository> DiscoverLogsUseCase<R> {
 
     /// ログファイルを発見します。
     ///
+    /// パスのglob（`include` / `exclude`）とファイルの更新時刻で絞り込みます。
+    /// レコード単位の条件（セッションID、レコードのタイムスタンプ）はパース時に適用されます。
+    ///
     /// # 引数
     ///
     /// * `log_dir` - ログディレクトリのパス
+    /// * `filter` - 絞り込み条件
     ///
     /// # 戻り値
     ///
@@ -43,6 +49,7 @@ impl<R: LogRepository> DiscoverLogsUseCase<R> {
     ///
     /// ```no_run
     /// use sessync::application::use_cases::discover_logs::DiscoverLogsUseCase;
+    /// use sessync::application::dto::log_filter::LogFilter;
     /// use sessync::adapter::repositories::file_log_repository::FileLogRepository;
     /// use std::sync::Arc;
     ///
@@ -50,37 +57,82 @@ impl<R: LogRepository> DiscoverLogsUseCase<R> {
     /// let log_repo = Arc::new(FileLogRepository::new());
     /// let use_case = DiscoverLogsUseCase::new(log_repo);
     ///
-    /// let files = use_case.execute("/path/to/logs").await?;
+    /// let files = use_case.execute("/path/to/logs", &LogFilter::new()).await?;
     /// println!("{}個のファイルを発見", files.len());
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn execute(&self, log_dir: &str) -> Result<Vec<PathBuf>> {
-        self.log_repository.discover_log_files(log_dir).await
+    pub async fn execute(&self, log_dir: &str, filter: &LogFilter) -> Result<Vec<PathBuf>> {
+        let files = self.log_repository.discover_log_files(log_dir).await?;
+
+        let mut kept = Vec::with_capacity(files.len());
+        for file in files {
+            if !filter.matches_path(&file) {
+                debug!("Excluded by path filter: {}", file.display());
+                continue;
+            }
+            if filter.needs_modified_time() {
+                let modified_at = self.log_repository.modified_at(&file).await?;
+                if !filter.keeps_file(modified_at) {
+                    debug!("Excluded by time filter: {}", file.display());
+                    continue;
+                }
+            }
+            kept.push(file);
+        }
+
+        Ok(kept)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::entities::file_checkpoint::FileCheckpoint;
+    use crate::domain::repositories::log_repository::LogStream;
     use async_trait::async_trait;
+    use futures::stream::{self, StreamExt};
     use std::path::PathBuf;
 
     struct MockLogRepository {
         files: Vec<PathBuf>,
     }
 
+    impl MockLogRepository {
+        /// ファイル名の数字をその日の更新時刻とする
+        fn day_of(path: &std::path::Path) -> Option<u32> {
+            path.file_stem()?
+                .to_str()?
+                .strip_prefix("day")?
+                .parse()
+                .ok()
+        }
+    }
+
     #[async_trait]
     impl LogRepository for MockLogRepository {
         async fn discover_log_files(&self, _log_dir: &str) -> Result<Vec<PathBuf>> {
             Ok(self.files.clone())
         }
 
-        async fn parse_log_file(
+        async fn modified_at(
+            &self,
+            file_path: &std::path::Path,
+        ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+            use chrono::TimeZone;
+            Ok(Self::day_of(file_path).map(|day| {
+                chrono::Utc
+                    .with_ymd_and_hms(2024, 12, day, 0, 0, 0)
+                    .unwrap()
+            }))
+        }
+
+        fn stream_log_file(
             &self,
-            _file_path: &std::path::Path,
-        ) -> Result<Vec<crate::domain::entities::session_log::SessionLogInput>> {
-            Ok(vec![])
+            _file_path: PathBuf,
+            _checkpoint: Option<FileCheckpoint>,
+        ) -> LogStream<'_> {
+            stream::empty().boxed()
         }
     }
 
@@ -95,7 +147,7 @@ mod tests {
         });
         let use_case = DiscoverLogsUseCase::new(mock_repo);
 
-        let result = use_case.execute("/path/to/logs").await;
+        let result = use_case.execute("/path/to/logs", &LogFilter::new()).await;
 
         assert!(result.is_ok());
         let discovered = result.unwrap();
@@ -109,10 +161,43 @@ mod tests {
         let mock_repo = Arc::new(MockLogRepository { files: vec![] });
         let use_case = DiscoverLogsUseCase::new(mock_repo);
 
-        let result = use_case.execute("/path/to/empty").await;
+        let result = use_case.execute("/path/to/empty", &LogFilter::new()).await;
 
         assert!(result.is_ok());
         let discovered = result.unwrap();
         assert_eq!(discovered.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_discover_logs_with_filter() {
+        use chrono::TimeZone;
+
+        let mock_repo = Arc::new(MockLogRepository {
+            files: vec![
+                PathBuf::from("/logs/app/day1.jsonl"),
+                PathBuf::from("/logs/app/day20.jsonl"),
+                PathBuf::from("/logs/scratch/day21.jsonl"),
+                PathBuf::from("/logs/app/unknown.jsonl"),
+            ],
+        });
+        let use_case = DiscoverLogsUseCase::new(mock_repo);
+        let filter = LogFilter::new()
+            .with_time_range(
+                Some(chrono::Utc.with_ymd_and_hms(2024, 12, 10, 0, 0, 0).unwrap()),
+                None,
+            )
+            .with_exclude(&["/logs/scratch/*".to_string()])
+            .unwrap();
+
+        let discovered = use_case.execute("/logs", &filter).await.unwrap();
+
+        // 更新時刻が取得できないファイルは残す
+        assert_eq!(
+            discovered,
+            vec![
+                PathBuf::from("/logs/app/day20.jsonl"),
+                PathBuf::from("/logs/app/unknown.jsonl"),
+            ]
+        );
+    }
 }
@@ -0,0 +1,582 @@
+//! # Manage Rejects Use Case
+//!
+//! パースできなかったログ行の隔離・一覧・再試行ユースケース
+
+use anyhow::Result;
+use futures::stream::{BoxStream, StreamExt};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::application::dto::parsed_logs::ParsedRecord;
+use crate::application::dto::upload_config::UploadConfig;
+use crate::application::use_cases::parse_logs::convert_record_to_session_log;
+use crate::domain::entities::rejected_line::RejectedLine;
+use crate::domain::entities::session_log::SessionLog;
+use crate::domain::entities::transcript_record::TranscriptRecord;
+use crate::domain::repositories::reject_repository::RejectRepository;
+use crate::domain::repositories::state_repository::StateRepository;
+use crate::domain::services::deduplication::DeduplicationService;
+
+/// 再試行でパースに成功した行
+#[derive(Debug, Clone)]
+pub struct RecoveredLine {
+    /// 隔離されていた元の行
+    pub line: RejectedLine,
+    /// 変換後のセッションログ
+    pub log: SessionLog,
+}
+
+/// 再試行の結果
+#[derive(Debug, Clone, Default)]
+pub struct RetryOutcome {
+    /// パースに成功し、アップロードすべき行
+    pub recovered: Vec<RecoveredLine>,
+    /// パースに成功したが、既にアップロード済みだった行の数
+    pub duplicates: usize,
+    /// 現在のパーサーでもパースできない行（エラーは最新のものに更新済み）
+    pub remaining: Vec<RejectedLine>,
+}
+
+/// 複数の送信先への再試行結果を、隔離ストアに反映する1つの結果にまとめます。
+///
+/// パースに成功した行は、その行を必要とした全ての送信先が受け入れた場合だけ
+/// アップロード済みとして扱います。
+///
+/// # 引数
+///
+/// * `results` - 送信先ごとの `retry` の結果と、その送信先が受け入れたUUID
+///
+/// # 戻り値
+///
+/// `commit_retry` に渡す結果と、全ての送信先にアップロードされたUUID
+pub fn merge_retry_results(
+    results: Vec<(RetryOutcome, Vec<String>)>,
+) -> (RetryOutcome, Vec<String>) {
+    let mut merged = RetryOutcome::default();
+    let mut recovered = BTreeMap::new();
+    let mut missing = HashSet::new();
+    for (outcome, uploaded_uuids) in results {
+        let uploaded: HashSet<String> = uploaded_uuids.into_iter().collect();
+        for line in outcome.recovered {
+            if !uploaded.contains(&line.log.uuid) {
+                missing.insert(line.log.uuid.clone());
+            }
+            recovered
+                .entry((line.line.source_file.clone(), line.line.line_number))
+                .or_insert(line);
+        }
+        // パース結果は送信先に依存しないため、パースできない行はどの結果でも同じ
+        merged.remaining = outcome.remaining;
+        merged.duplicates += outcome.duplicates;
+    }
+
+    merged.recovered = recovered.into_values().collect();
+    let uploaded = merged
+        .recovered
+        .iter()
+        .map(|line| line.log.uuid.clone())
+        .filter(|uuid| !missing.contains(uuid))
+        .collect();
+    (merged, uploaded)
+}
+
+/// 隔離ストア管理ユースケース
+///
+/// パースできなかった行を隔離ストアに保存し、一覧・集計・再試行を提供する
+pub struct ManageRejectsUseCase<R: RejectRepository, S: StateRepository + ?Sized> {
+    reject_repository: Arc<R>,
+    state_repository: Arc<S>,
+}
+
+impl<R: RejectRepository, S: StateRepository + ?Sized> ManageRejectsUseCase<R, S> {
+    /// 新しいユースケースを作成
+    ///
+    /// # Arguments
+    ///
+    /// * `reject_repository` - 隔離ストアのリポジトリ
+    /// * `state_repository` - 状態リポジトリ
+    pub fn new(reject_repository: Arc<R>, state_repository: Arc<S>) -> Self {
+        Self {
+            reject_repository,
+            state_repository,
+        }
+    }
+
+    /// パース結果のストリームを流しながら、パースできなかった行を隔離ストアに保存します。
+    ///
+    /// 行はそのまま下流に流れるため、アップロード側で件数を集計できます。
+    /// 下流でチェックポイントがコミットされる前に保存されるので、
+    /// アップロードが途中で失敗しても隔離した行は失われません。
+    /// 再スキャンで同じ行が再度流れてきた場合は二重に保存しません。
+    ///
+    /// # 引数
+    ///
+    /// * `records` - `ParseLogsUseCase::stream` または `ParseLogsUseCase::read` が返すパース結果のストリーム
+    /// * `rejects_path` - 隔離ストアのパス
+    ///
+    /// # エラー
+    ///
+    /// 隔離ストアの読み込みに失敗した場合にエラーを返します。
+    /// 保存の失敗はストリームの要素として返されます。
+    pub async fn quarantine<'a>(
+        &self,
+        records: BoxStream<'a, Result<ParsedRecord>>,
+        rejects_path: &str,
+    ) -> Result<BoxStream<'a, Result<ParsedRecord>>>
+    where
+        R: 'a,
+    {
+        let existing = self.reject_repository.load(rejects_path).await?;
+        let seen: HashSet<(String, u64)> = existing
+            .into_iter()
+            .map(|line| (line.source_file, line.line_number))
+            .collect();
+        let seen = Arc::new(Mutex::new(seen));
+        let repository = self.reject_repository.clone();
+        let rejects_path: Arc<str> = Arc::from(rejects_path);
+
+        let records = records.then(move |record| {
+            let repository = repository.clone();
+            let rejects_path = rejects_path.clone();
+            let seen = seen.clone();
+            async move {
+                if let Ok(ParsedRecord::Rejected(reject)) = &record {
+                    let is_new = seen
+                        .lock()
+                        .unwrap()
+                        .insert((reject.source_file.clone(), reject.line_number));
+                    if is_new {
+                        repository
+                            .append(&rejects_path, std::slice::from_ref(reject.as_ref()))
+                            .await?;
+                    }
+                }
+                record
+            }
+        });
+
+        Ok(records.boxed())
+    }
+
+    /// 隔離された行を全て返します。
+    ///
+    /// # エラー
+    ///
+    /// 隔離ストアの読み込みに失敗した場合にエラーを返します。
+    pub async fn list(&self, rejects_path: &str) -> Result<Vec<RejectedLine>> {
+        self.reject_repository.load(rejects_path).await
+    }
+
+    /// 隔離された行数をファイルごとに集計します。
+    ///
+    /// # 戻り値
+    ///
+    /// ファイルパスをキーとした行数（パス順）
+    ///
+    /// # エラー
+    ///
+    /// 隔離ストアの読み込みに失敗した場合にエラーを返します。
+    pub async fn count_by_file(&self, rejects_path: &str) -> Result<BTreeMap<String, usize>> {
+        let mut counts = BTreeMap::new();
+        for line in self.reject_repository.load(rejects_path).await? {
+            *counts.entry(line.source_file).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    /// 隔離された行を現在のパーサーで再パースします。
+    ///
+    /// 隔離ストアは変更しません。パースに成功した行をアップロードした後、
+    /// `commit_retry` で隔離ストアを更新してください。
+    ///
+    /// 隔離した行は圧縮済みセッションの透かしの範囲に含まれていてもアップロードされていないため、
+    /// 透かしは使わずUUIDだけで重複を判定します。
+    ///
+    /// # 引数
+    ///
+    /// * `rejects_path` - 隔離ストアのパス
+    /// * `config` - アップロード設定
+    /// * `state_path` - 状態ファイルのパス（重複排除に使用）
+    /// * `batch_id` - アップロードバッチID
+    ///
+    /// # 戻り値
+    ///
+    /// パースに成功した行、既にアップロード済みだった行数、まだパースできない行
+    ///
+    /// # エラー
+    ///
+    /// 隔離ストアまたは状態の読み込みに失敗した場合にエラーを返します。
+    pub async fn retry(
+        &self,
+        rejects_path: &str,
+        config: &UploadConfig,
+        state_path: &str,
+        batch_id: &str,
+    ) -> Result<RetryOutcome> {
+        let mut outcome = RetryOutcome::default();
+
+        let mut parsed = Vec::new();
+        for mut line in self.reject_repository.load(rejects_path).await? {
+            match TranscriptRecord::from_json_line(&line.raw) {
+                Ok(record) => parsed.push((line, record)),
+                Err(e) => {
+                    line.error = e.to_string();
+                    outcome.remaining.push(line);
+                }
+            }
+        }
+
+        // 再パースできた行のIDだけを状態と照合する
+        let identities: Vec<String> = parsed.iter().map(|(_, r)| r.identity()).collect();
+        let uploaded = self
+            .state_repository
+            .find_uploaded(state_path, &identities)
+            .await?;
+
+        for ((line, record), identity) in parsed.into_iter().zip(identities) {
+            if DeduplicationService::is_duplicate(&identity, &uploaded, config.enable_deduplication)
+            {
+                outcome.duplicates += 1;
+                continue;
+            }
+            let log = convert_record_to_session_log(
+                record,
+                Path::new(&line.source_file),
+                config,
+                batch_id,
+            )?;
+            outcome.recovered.push(RecoveredLine { line, log });
+        }
+
+        Ok(outcome)
+    }
+
+    /// 再試行の結果を隔離ストアに反映します。
+    ///
+    /// まだパースできない行と、パースできたがアップロードに失敗した行だけを残します。
+    ///
+    /// # 引数
+    ///
+    /// * `rejects_path` - 隔離ストアのパス
+    /// * `outcome` - `retry` の結果
+    /// * `uploaded_uuids` - アップロードに成功したUUID
+    ///
+    /// # 戻り値
+    ///
+    /// 隔離ストアに残った行数
+    ///
+    /// # エラー
+    ///
+    /// 隔離ストアへの書き込みに失敗した場合にエラーを返します。
+    pub async fn commit_retry(
+        &self,
+        rejects_path: &str,
+        outcome: RetryOutcome,
+        uploaded_uuids: &[String],
+    ) -> Result<usize> {
+        let uploaded: HashSet<&str> = uploaded_uuids.iter().map(String::as_str).collect();
+        let mut remaining = outcome.remaining;
+        remaining.extend(
+            outcome
+                .recovered
+                .into_iter()
+                .filter(|recovered| !uploaded.contains(recovered.log.uuid.as_str()))
+                .map(|recovered| recovered.line),
+        );
+
+        self.reject_repository
+            .replace(rejects_path, &remaining)
+            .await?;
+
+        Ok(remaining.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::file_checkpoint::FileCheckpoint;
+    use crate::domain::repositories::state_repository::{StateUpdate, UploadState, UploadedRecord};
+    use async_trait::async_trait;
+    use chrono::{Duration, TimeZone, Utc};
+    use futures::stream::{self, TryStreamExt};
+
+    const VALID_LINE: &str = r#"{"uuid":"uuid-1","timestamp":"2024-01-01T00:00:00Z","sessionId":"s1","type":"user","message":{}}"#;
+    const UPLOADED_LINE: &str = r#"{"uuid":"uuid-done","timestamp":"2024-01-01T00:00:00Z","sessionId":"s1","type":"user","message":{}}"#;
+
+    struct MockRejectRepository {
+        lines: Mutex<Vec<RejectedLine>>,
+    }
+
+    impl MockRejectRepository {
+        fn new(lines: Vec<RejectedLine>) -> Self {
+            Self {
+                lines: Mutex::new(lines),
+            }
+        }
+
+        fn lines(&self) -> Vec<RejectedLine> {
+            self.lines.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl RejectRepository for MockRejectRepository {
+        async fn load(&self, _path: &str) -> Result<Vec<RejectedLine>> {
+            Ok(self.lines())
+        }
+
+        async fn append(&self, _path: &str, rejects: &[RejectedLine]) -> Result<()> {
+            self.lines.lock().unwrap().extend_from_slice(rejects);
+            Ok(())
+        }
+
+        async fn replace(&self, _path: &str, rejects: &[RejectedLine]) -> Result<()> {
+            *self.lines.lock().unwrap() = rejects.to_vec();
+            Ok(())
+        }
+    }
+
+    struct MockStateRepository {
+        state: UploadState,
+    }
+
+    #[async_trait]
+    impl StateRepository for MockStateRepository {
+        async fn load(&self, _path: &str) -> Result<UploadState> {
+            Ok(self.state.clone())
+        }
+
+        async fn save(&self, _path: &str, _state: &UploadState) -> Result<()> {
+            Ok(())
+        }
+
+        async fn replace(&self, _path: &str, _state: &UploadState) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn create_use_case(
+        lines: Vec<RejectedLine>,
+    ) -> (
+        ManageRejectsUseCase<MockRejectRepository, MockStateRepository>,
+        Arc<MockRejectRepository>,
+    ) {
+        let mut state = UploadState::new();
+        state.uploaded_uuids.insert("uuid-done".to_string());
+        let reject_repo = Arc::new(MockRejectRepository::new(lines));
+        let use_case =
+            ManageRejectsUseCase::new(reject_repo.clone(), Arc::new(MockStateRepository { state }));
+        (use_case, reject_repo)
+    }
+
+    fn create_test_config() -> UploadConfig {
+        UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        )
+    }
+
+    fn reject(line_number: u64, raw: &str) -> RejectedLine {
+        RejectedLine::new("/logs/a.jsonl", line_number, "old error", raw)
+    }
+
+    #[tokio::test]
+    async fn test_quarantine_saves_new_rejects_and_passes_records_through() {
+        let (use_case, reject_repo) = create_use_case(vec![reject(1, "already quarantined")]);
+
+        let records = vec![
+            Ok(ParsedRecord::Rejected(Box::new(reject(
+                1,
+                "already quarantined",
+            )))),
+            Ok(ParsedRecord::Rejected(Box::new(reject(2, "new")))),
+            Ok(ParsedRecord::FileCompleted {
+                source_file: "/logs/a.jsonl".to_string(),
+                checkpoint: FileCheckpoint::new(None, 10, None, 10, 2),
+            }),
+        ];
+
+        let passed: Vec<ParsedRecord> = use_case
+            .quarantine(stream::iter(records).boxed(), "/rejects.jsonl")
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(passed.len(), 3);
+        let stored = reject_repo.lines();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[1].line_number, 2);
+    }
+
+    #[tokio::test]
+    async fn test_count_by_file() {
+        let (use_case, _) = create_use_case(vec![
+            reject(1, "x"),
+            reject(2, "y"),
+            RejectedLine::new("/logs/b.jsonl", 1, "err", "z"),
+        ]);
+
+        let counts = use_case.count_by_file("/rejects.jsonl").await.unwrap();
+        assert_eq!(counts["/logs/a.jsonl"], 2);
+        assert_eq!(counts["/logs/b.jsonl"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_classifies_lines() {
+        let (use_case, _) = create_use_case(vec![
+            reject(1, VALID_LINE),
+            reject(2, UPLOADED_LINE),
+            reject(3, "still broken"),
+        ]);
+
+        let outcome = use_case
+            .retry(
+                "/rejects.jsonl",
+                &create_test_config(),
+                "/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.recovered.len(), 1);
+        assert_eq!(outcome.recovered[0].log.uuid, "uuid-1");
+        assert_eq!(
+            outcome.recovered[0].log.metadata.source_file,
+            "/logs/a.jsonl"
+        );
+        assert_eq!(outcome.duplicates, 1);
+        assert_eq!(outcome.remaining.len(), 1);
+        assert_ne!(outcome.remaining[0].error, "old error");
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_compaction_ignores_watermark() {
+        // 隔離した行と同じ時刻のレコードをアップロードした後、セッションを圧縮
+        let recorded_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let mut state = UploadState::new();
+        StateUpdate {
+            timestamp: recorded_at,
+            uploaded: vec![UploadedRecord {
+                uuid: "uuid-done".to_string(),
+                session_id: "s1".to_string(),
+                source_file: "/logs/a.jsonl".to_string(),
+                timestamp: recorded_at,
+                content_hash: None,
+                record_version: 0,
+            }],
+            ..Default::default()
+        }
+        .apply(&mut state);
+        state.compact(recorded_at + Duration::days(30), |_| true);
+        assert!(state.is_record_uploaded("uuid-1", "s1", "/logs/a.jsonl", recorded_at));
+
+        let use_case = ManageRejectsUseCase::new(
+            Arc::new(MockRejectRepository::new(vec![
+                reject(1, VALID_LINE),
+                reject(2, UPLOADED_LINE),
+            ])),
+            Arc::new(MockStateRepository { state }),
+        );
+        let outcome = use_case
+            .retry(
+                "/rejects.jsonl",
+                &create_test_config(),
+                "/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+
+        // UUIDを忘れた行も、隔離されていた行と同様に再アップロードの対象になる
+        let recovered: Vec<&str> = outcome
+            .recovered
+            .iter()
+            .map(|recovered| recovered.log.uuid.as_str())
+            .collect();
+        assert_eq!(recovered, vec!["uuid-1", "uuid-done"]);
+        assert_eq!(outcome.duplicates, 0);
+    }
+
+    #[tokio::test]
+    async fn test_commit_retry_keeps_unparsed_and_failed_uploads() {
+        let (use_case, reject_repo) = create_use_case(vec![
+            reject(1, VALID_LINE),
+            reject(2, UPLOADED_LINE),
+            reject(3, "still broken"),
+        ]);
+        let config = create_test_config();
+
+        let outcome = use_case
+            .retry("/rejects.jsonl", &config, "/state.json", "batch-001")
+            .await
+            .unwrap();
+        let remaining = use_case
+            .commit_retry("/rejects.jsonl", outcome.clone(), &["uuid-1".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(remaining, 1);
+        assert_eq!(reject_repo.lines()[0].line_number, 3);
+
+        // アップロードに失敗した行は隔離ストアに残る
+        let remaining = use_case
+            .commit_retry("/rejects.jsonl", outcome, &[])
+            .await
+            .unwrap();
+        assert_eq!(remaining, 2);
+    }
+
+    #[tokio::test]
+    async fn test_merge_retry_results_requires_every_destination() {
+        let (use_case, _) = create_use_case(vec![reject(1, VALID_LINE), reject(3, "still broken")]);
+        let config = create_test_config();
+        let outcome = use_case
+            .retry("/rejects.jsonl", &config, "/state.json", "batch-001")
+            .await
+            .unwrap();
+
+        // 両方の送信先が受け入れた行だけがアップロード済みになる
+        let (merged, uploaded) = merge_retry_results(vec![
+            (outcome.clone(), vec!["uuid-1".to_string()]),
+            (outcome.clone(), vec!["uuid-1".to_string()]),
+        ]);
+        assert_eq!(merged.recovered.len(), 1);
+        assert_eq!(merged.remaining.len(), 1);
+        assert_eq!(uploaded, vec!["uuid-1".to_string()]);
+
+        // 一方の送信先で失敗した行は隔離ストアに残る
+        let (merged, uploaded) = merge_retry_results(vec![
+            (outcome.clone(), vec!["uuid-1".to_string()]),
+            (outcome.clone(), vec![]),
+        ]);
+        assert!(uploaded.is_empty());
+        let remaining = use_case
+            .commit_retry("/rejects.jsonl", merged, &uploaded)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 2);
+
+        // アップロード済みで再送が不要だった送信先は判定に含めない
+        let already_uploaded = RetryOutcome {
+            recovered: vec![],
+            duplicates: 1,
+            remaining: outcome.remaining.clone(),
+        };
+        let (_, uploaded) = merge_retry_results(vec![
+            (outcome, vec!["uuid-1".to_string()]),
+            (already_uploaded, vec![]),
+        ]);
+        assert_eq!(uploaded, vec!["uuid-1".to_string()]);
+    }
+}
@@ -0,0 +1,599 @@
+//! # Manage State Use Case
+//!
+//! アップロード状態の確認・エクスポート・インポート・削除ユースケース
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::domain::repositories::state_repository::{
+    ForgetSummary, SessionUploads, StateRepository, UploadState,
+};
+
+/// アップロード状態の概要
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateReport {
+    /// 累計アップロード数
+    pub total_uploaded: u64,
+    /// 状態に記録されているアップロード済みUUIDの数
+    pub tracked_uuids: usize,
+    /// セッションに紐付かないUUIDの数（セッション情報を記録する前にアップロードしたもの）
+    pub unattributed_uuids: usize,
+    /// チェックポイントを持つログファイルの数
+    pub file_checkpoints: usize,
+    /// 最後のアップロードバッチID
+    pub last_upload_batch_id: Option<String>,
+    /// 最後のアップロード日時
+    pub last_upload_timestamp: Option<String>,
+    /// 保存先のバイト数（不明な場合は `None`）
+    pub storage_size: Option<u64>,
+    /// プロジェクトごとの集計（キーはプロジェクト名）
+    pub projects: BTreeMap<String, ProjectStats>,
+    /// セッションごとの集計（最後にアップロードした日時の新しい順）
+    pub sessions: Vec<SessionStats>,
+}
+
+/// プロジェクトごとの集計
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectStats {
+    /// セッション数
+    pub sessions: usize,
+    /// 記録されているUUIDの数
+    pub uuids: usize,
+}
+
+/// セッションごとの集計
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStats {
+    /// セッションID
+    pub session_id: String,
+    /// プロジェクト名（ログファイルのパスから求めたもの）
+    pub project: String,
+    /// 記録されているUUIDの数（圧縮済みの場合は0）
+    pub uuids: usize,
+    /// 最後にアップロードした日時
+    pub last_uploaded_at: DateTime<Utc>,
+    /// 圧縮済みかどうか
+    pub compacted: bool,
+}
+
+/// 忘れる対象のセッション
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForgetTarget {
+    /// 指定したIDのセッション
+    Session(String),
+    /// 指定した日時以降のレコードを含むセッション
+    Since(DateTime<Utc>),
+}
+
+impl ForgetTarget {
+    /// セッションが対象かどうかを判定
+    fn matches(&self, session_id: &str, session: &SessionUploads) -> bool {
+        match self {
+            Self::Session(id) => session_id == id,
+            Self::Since(since) => {
+                session.latest_record_at.unwrap_or(session.last_uploaded_at) >= *since
+            }
+        }
+    }
+}
+
+/// 状態管理ユースケース
+///
+/// `StateRepository` を通じて状態を扱うため、どのバックエンドでも同じように動作する。
+/// エクスポート・インポートには持ち運び可能な形式（JSONの状態ファイル）のリポジトリを使う。
+pub struct ManageStateUseCase<S: StateRepository + ?Sized, P: StateRepository> {
+    state_repository: Arc<S>,
+    portable_repository: Arc<P>,
+}
+
+impl<S: StateRepository + ?Sized, P: StateRepository> ManageStateUseCase<S, P> {
+    /// 新しいユースケースを作成
+    ///
+    /// # Arguments
+    ///
+    /// * `state_repository` - 状態リポジトリ
+    /// * `portable_repository` - エクスポート・インポートに使う状態リポジトリ
+    pub fn new(state_repository: Arc<S>, portable_repository: Arc<P>) -> Self {
+        Self {
+            state_repository,
+            portable_repository,
+        }
+    }
+
+    /// 状態の概要を集計します。
+    ///
+    /// # 引数
+    ///
+    /// * `state_path` - 状態ファイルのパス
+    ///
+    /// # 戻り値
+    ///
+    /// 累計、プロジェクト・セッションごとの件数、最後のバッチと保存先のサイズ
+    ///
+    /// # エラー
+    ///
+    /// 状態の読み込みに失敗した場合にエラーを返します。
+    ///
+    /// # 例
+    ///
+    /// ```no_run
+    /// use sessync::application::use_cases::manage_state::ManageStateUseCase;
+    /// use sessync::adapter::repositories::json_state_repository::JsonStateRepository;
+    /// use std::sync::Arc;
+    ///
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let use_case = ManageStateUseCase::new(
+    ///     Arc::new(JsonStateRepository::new()),
+    ///     Arc::new(JsonStateRepository::new()),
+    /// );
+    ///
+    /// let report = use_case.show("./.claude/sessync/upload-state.json").await?;
+    /// println!("{}個のセッション", report.sessions.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn show(&self, state_path: &str) -> Result<StateReport> {
+        let state = self.state_repository.load(state_path).await?;
+        let storage_size = self.state_repository.storage_size(state_path).await?;
+
+        let mut projects: BTreeMap<String, ProjectStats> = BTreeMap::new();
+        let mut sessions: Vec<SessionStats> = state
+            .sessions
+            .iter()
+            .map(|(session_id, session)| {
+                let project = session
+                    .source_files
+                    .iter()
+                    .next()
+                    .map_or_else(|| UNKNOWN_PROJECT.to_string(), |file| project_of(file));
+                let stats = projects.entry(project.clone()).or_default();
+                stats.sessions += 1;
+                stats.uuids += session.uuids.len();
+
+                SessionStats {
+                    session_id: session_id.clone(),
+                    project,
+                    uuids: session.uuids.len(),
+                    last_uploaded_at: session.last_uploaded_at,
+                    compacted: session.compacted_until.is_some(),
+                }
+            })
+            .collect();
+        sessions.sort_by(|a, b| {
+            b.last_uploaded_at
+                .cmp(&a.last_uploaded_at)
+                .then_with(|| a.session_id.cmp(&b.session_id))
+        });
+
+        let attributed: usize = sessions.iter().map(|session| session.uuids).sum();
+        Ok(StateReport {
+            total_uploaded: state.total_uploaded,
+            tracked_uuids: state.uploaded_uuids.len(),
+            unattributed_uuids: state.uploaded_uuids.len().saturating_sub(attributed),
+            file_checkpoints: state.file_checkpoints.len(),
+            last_upload_batch_id: state.last_upload_batch_id,
+            last_upload_timestamp: state.last_upload_timestamp,
+            storage_size,
+            projects,
+            sessions,
+        })
+    }
+
+    /// 状態を持ち運び可能な形式で書き出します。
+    ///
+    /// # 引数
+    ///
+    /// * `state_path` - 状態ファイルのパス
+    /// * `export_path` - 書き出し先のパス（既存のファイルは上書き）
+    ///
+    /// # 戻り値
+    ///
+    /// 書き出したアップロード済みUUIDの数
+    ///
+    /// # エラー
+    ///
+    /// 状態の読み込みまたは書き出しに失敗した場合にエラーを返します。
+    pub async fn export(&self, state_path: &str, export_path: &str) -> Result<usize> {
+        let state = self.state_repository.load(state_path).await?;
+        self.portable_repository
+            .replace(export_path, &state)
+            .await?;
+        Ok(state.uploaded_uuids.len())
+    }
+
+    /// 書き出した状態を取り込みます。
+    ///
+    /// 現在の状態とマージするため、記録が消えることはありません。
+    ///
+    /// # 引数
+    ///
+    /// * `state_path` - 状態ファイルのパス
+    /// * `import_path` - `export` で書き出したファイルのパス
+    /// * `dry_run` - `true` の場合は状態を書き換えずに結果だけを返す
+    ///
+    /// # 戻り値
+    ///
+    /// 新たに記録されるアップロード済みUUIDの数
+    ///
+    /// # エラー
+    ///
+    /// ファイルの読み込みまたは状態の保存に失敗した場合にエラーを返します。
+    pub async fn import(
+        &self,
+        state_path: &str,
+        import_path: &str,
+        dry_run: bool,
+    ) -> Result<usize> {
+        let imported = self.portable_repository.load(import_path).await?;
+        let current = self.state_repository.load(state_path).await?;
+        let added = imported
+            .uploaded_uuids
+            .iter()
+            .filter(|uuid| !current.is_uploaded(uuid))
+            .count();
+
+        if !dry_run {
+            self.state_repository.save(state_path, &imported).await?;
+        }
+
+        Ok(added)
+    }
+
+    /// 対象のセッションのアップロード記録を忘れ、次回の実行で再アップロードされるようにします。
+    ///
+    /// 状態を書き直すため、実行ロックを取得した上で呼び出してください。
+    ///
+    /// # 引数
+    ///
+    /// * `state_path` - 状態ファイルのパス
+    /// * `target` - 忘れる対象のセッション
+    /// * `dry_run` - `true` の場合は状態を書き換えずに結果だけを返す
+    ///
+    /// # 戻り値
+    ///
+    /// 忘れたセッション数・UUIDの数・チェックポイントの数
+    ///
+    /// # エラー
+    ///
+    /// 状態の読み込みまたは書き込みに失敗した場合にエラーを返します。
+    pub async fn forget(
+        &self,
+        state_path: &str,
+        target: &ForgetTarget,
+        dry_run: bool,
+    ) -> Result<ForgetSummary> {
+        let mut state = self.state_repository.load(state_path).await?;
+
+        let summary =
+            state.forget_sessions(|session_id, session| target.matches(session_id, session));
+        if summary.sessions > 0 && !dry_run {
+            self.state_repository.replace(state_path, &state).await?;
+        }
+
+        Ok(summary)
+    }
+
+    /// 状態を空にします。次回の実行で全てのログが再アップロードされます。
+    ///
+    /// 状態を書き直すため、実行ロックを取得した上で呼び出してください。
+    ///
+    /// # 引数
+    ///
+    /// * `state_path` - 状態ファイルのパス
+    /// * `dry_run` - `true` の場合は状態を書き換えずに結果だけを返す
+    ///
+    /// # 戻り値
+    ///
+    /// 忘れたアップロード済みUUIDの数
+    ///
+    /// # エラー
+    ///
+    /// 状態の読み込みまたは書き込みに失敗した場合にエラーを返します。
+    pub async fn reset(&self, state_path: &str, dry_run: bool) -> Result<usize> {
+        let state = self.state_repository.load(state_path).await?;
+
+        if !dry_run {
+            self.state_repository
+                .replace(state_path, &UploadState::new())
+                .await?;
+        }
+
+        Ok(state.uploaded_uuids.len())
+    }
+}
+
+/// プロジェクトを特定できないセッションの表示名
+const UNKNOWN_PROJECT: &str = "(unknown)";
+
+/// ログファイルのパスからプロジェクト名を求める
+///
+/// `~/.claude/projects/<project>/...` の `<project>`、それ以外は親ディレクトリ名
+fn project_of(source_file: &str) -> String {
+    let components: Vec<&str> = source_file
+        .split(['/', '\\'])
+        .filter(|component| !component.is_empty())
+        .collect();
+
+    let under_projects = components
+        .iter()
+        .position(|component| *component == "projects")
+        .filter(|index| index + 2 < components.len())
+        .map(|index| components[index + 1]);
+
+    under_projects
+        .or_else(|| components.iter().rev().nth(1).copied())
+        .unwrap_or(UNKNOWN_PROJECT)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::file_checkpoint::FileCheckpoint;
+    use crate::domain::repositories::state_repository::{StateUpdate, UploadedRecord};
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// パスごとに状態を保持するモック（`save` はUUIDの和集合を取る）
+    #[derive(Default)]
+    struct MockStateRepository {
+        states: Mutex<HashMap<String, UploadState>>,
+    }
+
+    impl MockStateRepository {
+        fn state(&self, path: &str) -> UploadState {
+            self.states
+                .lock()
+                .unwrap()
+                .get(path)
+                .cloned()
+                .unwrap_or_default()
+        }
+    }
+
+    #[async_trait]
+    impl StateRepository for MockStateRepository {
+        async fn load(&self, path: &str) -> Result<UploadState> {
+            Ok(self.state(path))
+        }
+
+        async fn save(&self, path: &str, state: &UploadState) -> Result<()> {
+            let mut states = self.states.lock().unwrap();
+            let existing = states.entry(path.to_string()).or_default();
+            existing
+                .uploaded_uuids
+                .extend(state.uploaded_uuids.iter().cloned());
+            existing.sessions.extend(state.sessions.clone());
+            Ok(())
+        }
+
+        async fn replace(&self, path: &str, state: &UploadState) -> Result<()> {
+            self.states
+                .lock()
+                .unwrap()
+                .insert(path.to_string(), state.clone());
+            Ok(())
+        }
+
+        async fn storage_size(&self, path: &str) -> Result<Option<u64>> {
+            Ok(self.states.lock().unwrap().get(path).map(|_| 1024))
+        }
+    }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 12, 25, hour, 0, 0).unwrap()
+    }
+
+    fn record(uuid: &str, session_id: &str, source_file: &str, hour: u32) -> UploadedRecord {
+        UploadedRecord {
+            uuid: uuid.to_string(),
+            session_id: session_id.to_string(),
+            source_file: source_file.to_string(),
+            timestamp: at(hour),
+            content_hash: None,
+            record_version: 0,
+        }
+    }
+
+    /// proj-a に2セッション、proj-b に1セッション、セッションに紐付かないUUIDが1つ
+    fn create_state() -> UploadState {
+        let mut state = UploadState::new();
+        StateUpdate {
+            batch_id: "batch-001".to_string(),
+            timestamp: at(10),
+            uploaded: vec![
+                record(
+                    "uuid-1",
+                    "session-1",
+                    "/h/.claude/projects/proj-a/session-1.jsonl",
+                    1,
+                ),
+                record(
+                    "uuid-2",
+                    "session-1",
+                    "/h/.claude/projects/proj-a/session-1.jsonl",
+                    2,
+                ),
+                record(
+                    "uuid-3",
+                    "session-2",
+                    "/h/.claude/projects/proj-b/session-2.jsonl",
+                    5,
+                ),
+            ],
+            checkpoints: HashMap::from([(
+                "/h/.claude/projects/proj-a/session-1.jsonl".to_string(),
+                FileCheckpoint::new(None, 10, None, 10, 2),
+            )]),
+        }
+        .apply(&mut state);
+        StateUpdate {
+            batch_id: "batch-002".to_string(),
+            timestamp: at(12),
+            uploaded: vec![record(
+                "uuid-4",
+                "session-3",
+                "/h/.claude/projects/proj-a/session-3/subagents/agent-1.jsonl",
+                8,
+            )],
+            ..Default::default()
+        }
+        .apply(&mut state);
+        state.uploaded_uuids.insert("uuid-legacy".to_string());
+        state
+    }
+
+    fn create_use_case() -> (
+        ManageStateUseCase<MockStateRepository, MockStateRepository>,
+        Arc<MockStateRepository>,
+        Arc<MockStateRepository>,
+    ) {
+        let state_repo = Arc::new(MockStateRepository::default());
+        state_repo
+            .states
+            .lock()
+            .unwrap()
+            .insert("state".to_string(), create_state());
+        let portable_repo = Arc::new(MockStateRepository::default());
+        (
+            ManageStateUseCase::new(state_repo.clone(), portable_repo.clone()),
+            state_repo,
+            portable_repo,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_show() {
+        let (use_case, _, _) = create_use_case();
+
+        let report = use_case.show("state").await.unwrap();
+
+        assert_eq!(report.total_uploaded, 4);
+        assert_eq!(report.tracked_uuids, 5);
+        assert_eq!(report.unattributed_uuids, 1);
+        assert_eq!(report.file_checkpoints, 1);
+        assert_eq!(report.last_upload_batch_id.as_deref(), Some("batch-002"));
+        assert_eq!(report.storage_size, Some(1024));
+        assert_eq!(
+            report.projects["proj-a"],
+            ProjectStats {
+                sessions: 2,
+                uuids: 3
+            }
+        );
+        assert_eq!(report.projects["proj-b"].uuids, 1);
+        // 最後にアップロードした日時の新しい順
+        assert_eq!(report.sessions[0].session_id, "session-3");
+        assert_eq!(report.sessions[0].project, "proj-a");
+        assert_eq!(report.sessions[1].uuids, 2);
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import() {
+        let (use_case, state_repo, portable_repo) = create_use_case();
+
+        let exported = use_case.export("state", "export.json").await.unwrap();
+        assert_eq!(exported, 5);
+        assert_eq!(portable_repo.state("export.json").uploaded_uuids.len(), 5);
+
+        // 別の状態に取り込む
+        let added = use_case.import("other", "export.json", true).await.unwrap();
+        assert_eq!(added, 5);
+        assert!(state_repo.state("other").uploaded_uuids.is_empty());
+
+        let added = use_case
+            .import("other", "export.json", false)
+            .await
+            .unwrap();
+        assert_eq!(added, 5);
+        assert!(state_repo.state("other").is_uploaded("uuid-legacy"));
+
+        let added = use_case
+            .import("other", "export.json", false)
+            .await
+            .unwrap();
+        assert_eq!(added, 0);
+    }
+
+    #[tokio::test]
+    async fn test_forget_session() {
+        let (use_case, state_repo, _) = create_use_case();
+
+        let summary = use_case
+            .forget(
+                "state",
+                &ForgetTarget::Session("session-1".to_string()),
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            ForgetSummary {
+                sessions: 1,
+                uuids: 2,
+                checkpoints: 1
+            }
+        );
+        let state = state_repo.state("state");
+        assert!(!state.is_uploaded("uuid-1"));
+        assert!(state.is_uploaded("uuid-3"));
+        assert!(state.file_checkpoints.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_forget_since() {
+        let (use_case, state_repo, _) = create_use_case();
+
+        let summary = use_case
+            .forget("state", &ForgetTarget::Since(at(5)), true)
+            .await
+            .unwrap();
+        assert_eq!(summary.sessions, 2);
+        assert!(state_repo.state("state").is_uploaded("uuid-3"));
+
+        use_case
+            .forget("state", &ForgetTarget::Since(at(5)), false)
+            .await
+            .unwrap();
+        let state = state_repo.state("state");
+        assert!(!state.is_uploaded("uuid-3"));
+        assert!(!state.is_uploaded("uuid-4"));
+        assert!(state.is_uploaded("uuid-1"));
+    }
+
+    #[tokio::test]
+    async fn test_reset() {
+        let (use_case, state_repo, _) = create_use_case();
+
+        assert_eq!(use_case.reset("state", true).await.unwrap(), 5);
+        assert_eq!(state_repo.state("state").uploaded_uuids.len(), 5);
+
+        assert_eq!(use_case.reset("state", false).await.unwrap(), 5);
+        let state = state_repo.state("state");
+        assert!(state.uploaded_uuids.is_empty());
+        assert_eq!(state.total_uploaded, 0);
+    }
+
+    #[test]
+    fn test_project_of() {
+        assert_eq!(
+            project_of("/home/u/.claude/projects/-home-u-app/session.jsonl"),
+            "-home-u-app"
+        );
+        assert_eq!(
+            project_of("/home/u/.claude/projects/-home-u-app/s1/subagents/agent-1.jsonl"),
+            "-home-u-app"
+        );
+        assert_eq!(
+            project_of("/tmp/bundle.tar.gz!/-home-u-app/session.jsonl"),
+            "-home-u-app"
+        );
+        assert_eq!(project_of("session.jsonl"), UNKNOWN_PROJECT);
+    }
+}
@@ -7,7 +7,15 @@
 //! - **DiscoverLogsUseCase**: ログファイルの発見
 //! - **ParseLogsUseCase**: ログのパースと重複排除
 //! - **UploadLogsUseCase**: ログのアップロード
+//! - **ManageRejectsUseCase**: パースできなかった行の隔離と再試行
+//! - **CompactStateUseCase**: アップロード状態の圧縮
+//! - **ManageStateUseCase**: アップロード状態の確認・エクスポート・インポート・削除
+//! - **RebuildStateUseCase**: 送信先からのアップロード状態の再構築
 
+pub mod compact_state;
 pub mod discover_logs;
+pub mod manage_rejects;
+pub mod manage_state;
 pub mod parse_logs;
+pub mod rebuild_state;
 pub mod upload_logs;
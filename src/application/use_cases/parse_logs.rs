@@ -4,24 +4,43 @@
 
 use anyhow::Result;
 use chrono::Utc;
-use std::path::Path;
+use futures::channel::mpsc;
+use futures::future::{self, BoxFuture, FutureExt};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use futures::SinkExt;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::application::dto::log_filter::LogFilter;
+use crate::application::dto::parsed_logs::{ParsedLogs, ParsedRecord};
 use crate::application::dto::upload_config::UploadConfig;
-use crate::domain::entities::session_log::{LogMetadata, SessionLog, SessionLogInput};
-use crate::domain::repositories::log_repository::LogRepository;
-use crate::domain::repositories::state_repository::StateRepository;
-use crate::domain::services::deduplication::DeduplicationService;
+use crate::domain::entities::file_checkpoint::FileCheckpoint;
+use crate::domain::entities::session_log::{LogMetadata, SessionLog};
+use crate::domain::entities::transcript_record::TranscriptRecord;
+use crate::domain::repositories::log_repository::{LogRepository, LogStreamItem};
+use crate::domain::repositories::state_repository::{StateRepository, UploadState};
+use crate::domain::services::deduplication::ChangePolicy;
+use crate::domain::services::session_tree::SessionTree;
+
+/// 重複排除でアップロード済みレコードをまとめて照合するレコード数
+/// （状態がレコードを読み込んでいない場合、この単位で `StateRepository::find_records` を呼ぶ）
+const DEDUPLICATION_CHUNK_SIZE: usize = 500;
+
+/// `fan_out` の各ストリームに溜めておけるレコード数（遅い送信先を待つ間も読み込みを続けられる量）
+const FAN_OUT_CAPACITY: usize = 1000;
 
 /// ログパースと重複排除ユースケース
 ///
 /// ログファイルをパースし、重複を排除してSessionLogに変換する
-pub struct ParseLogsUseCase<L: LogRepository, S: StateRepository> {
+pub struct ParseLogsUseCase<L: LogRepository, S: StateRepository + ?Sized> {
     log_repository: Arc<L>,
     state_repository: Arc<S>,
 }
 
-impl<L: LogRepository, S: StateRepository> ParseLogsUseCase<L, S> {
+impl<L: LogRepository, S: StateRepository + ?Sized> ParseLogsUseCase<L, S> {
     /// 新しいユースケースを作成
     ///
     /// # Arguments
@@ -37,16 +56,21 @@ impl<L: LogRepository, S: StateRepository> ParseLogsUseCase<L, S> {
 
     /// ログファイルをパースし、重複排除を適用します。
     ///
+    /// 状態に保存されたチェックポイント以降のみを読み込みます。
+    /// 新しいチェックポイントは戻り値に含まれ、アップロード成功後にコミットされます。
+    ///
     /// # 引数
     ///
     /// * `file_paths` - ログファイルのパスのリスト
     /// * `config` - アップロード設定
+    /// * `filter` - レコード単位の絞り込み条件
     /// * `state_path` - 状態ファイルのパス
     /// * `batch_id` - アップロードバッチID
     ///
     /// # 戻り値
     ///
-    /// パース後の（重複排除後の）セッションログのリスト
+    /// パース後の（重複排除後の）セッションログ、パースできなかった行と
+    /// コミット待ちのチェックポイント
     ///
     /// # エラー
     ///
@@ -57,6 +81,7 @@ impl<L: LogRepository, S: StateRepository> ParseLogsUseCase<L, S> {
     /// ```no_run
     /// use sessync::application::use_cases::parse_logs::ParseLogsUseCase;
     /// use sessync::application::dto::upload_config::UploadConfig;
+    /// use sessync::application::dto::log_filter::LogFilter;
     /// use sessync::adapter::repositories::file_log_repository::FileLogRepository;
     /// use sessync::adapter::repositories::json_state_repository::JsonStateRepository;
     /// use std::sync::Arc;
@@ -80,14 +105,15 @@ impl<L: LogRepository, S: StateRepository> ParseLogsUseCase<L, S> {
     /// );
     ///
     /// let files = vec![PathBuf::from("/logs/session1.jsonl")];
-    /// let logs = use_case.execute(
+    /// let parsed = use_case.execute(
     ///     &files,
     ///     &config,
+    ///     &LogFilter::new(),
     ///     "/state/upload.json",
     ///     "batch-001"
     /// ).await?;
     ///
-    /// println!("{}個のログをパース", logs.len());
+    /// println!("{}個のログをパース", parsed.len());
     /// # Ok(())
     /// # }
     /// ```
@@ -95,42 +121,481 @@ impl<L: LogRepository, S: StateRepository> ParseLogsUseCase<L, S> {
         &self,
         file_paths: &[impl AsRef<Path>],
         config: &UploadConfig,
+        filter: &LogFilter,
         state_path: &str,
         batch_id: &str,
-    ) -> Result<Vec<SessionLog>> {
-        // 状態を読み込み
-        let state = self.state_repository.load(state_path).await?;
-
-        // 全ログファイルをパース
-        let mut all_logs = Vec::new();
-        for file_path in file_paths {
-            let inputs = self
+    ) -> Result<ParsedLogs> {
+        let records: Vec<ParsedRecord> = self
+            .stream(file_paths, config, filter, state_path, batch_id)
+            .await?
+            .try_collect()
+            .await?;
+
+        let mut parsed = ParsedLogs::default();
+        for record in records {
+            match record {
+                ParsedRecord::Log(log) => parsed.logs.push(*log),
+                ParsedRecord::Changed(log) => parsed.changed.push(*log),
+                ParsedRecord::Rejected(reject) => parsed.rejected.push(*reject),
+                ParsedRecord::FileCompleted {
+                    source_file,
+                    checkpoint,
+                } => {
+                    parsed.checkpoints.insert(source_file, checkpoint);
+                }
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// ログファイルを1行ずつパースし、重複排除を適用したストリームを返します。
+    ///
+    /// ファイル単位・行単位の順序を保ったまま、各ファイルのログ（パースできなかった行は
+    /// `ParsedRecord::Rejected`）の後に `ParsedRecord::FileCompleted` を流します。全ログをメモリに載せないため、
+    /// アップロード側はバッチが埋まるたびに送信できます。
+    ///
+    /// `filter` がレコード単位で絞り込む場合は、チェックポイントを使わずに先頭から読み、
+    /// `ParsedRecord::FileCompleted` を流しません（読み飛ばしたレコードを後で取り込めるように）。
+    ///
+    /// 最大 `config.parse_workers` 個のファイルを並行して読み込みますが、
+    /// 出力の順序はファイルの順、行の順のまま変わりません。
+    ///
+    /// アップロード済みのレコードの内容が変わっていた場合は `config.change_policy` に従い、
+    /// 無視するか、`record_version` を増やした `ParsedRecord::Log` として流すか、
+    /// `ParsedRecord::Changed` として流します。
+    ///
+    /// 各ログには `SessionTree` によるツリー上の位置が設定されます。
+    /// サブエージェントのトランスクリプトはメインのトランスクリプトの後に読み込みます。
+    /// チェックポイント以降だけを読むファイルも紐付けられるよう、新しい行があるセッションの
+    /// 既読部分のツリーをチェックポイントに保存した紐付け情報から復元してから読み込みを始めます。
+    /// 読み込んだ行の紐付け情報は `ParsedRecord::FileCompleted` のチェックポイントに保存します。
+    ///
+    /// # 引数
+    ///
+    /// * `file_paths` - ログファイルのパスのリスト
+    /// * `config` - アップロード設定
+    /// * `filter` - レコード単位の絞り込み条件
+    /// * `state_path` - 状態ファイルのパス
+    /// * `batch_id` - アップロードバッチID
+    ///
+    /// # エラー
+    ///
+    /// 状態の読み込みに失敗した場合にエラーを返します。
+    /// ファイルの読み込みエラーはストリームの要素として返されます。
+    pub async fn stream(
+        &self,
+        file_paths: &[impl AsRef<Path>],
+        config: &UploadConfig,
+        filter: &LogFilter,
+        state_path: &str,
+        batch_id: &str,
+    ) -> Result<BoxStream<'_, Result<ParsedRecord>>> {
+        // 状態を読み込み（アップロード済みレコードは実装によってはチャンクごとに照合する）
+        let state = Arc::new(self.state_repository.load_for_upload(state_path).await?);
+        let records = self
+            .read(
+                file_paths,
+                config,
+                filter,
+                std::slice::from_ref(&state),
+                batch_id,
+            )
+            .await;
+
+        Ok(deduplicate_records(
+            records,
+            state,
+            self.state_repository.clone(),
+            state_path,
+            config,
+        ))
+    }
+
+    /// 複数の状態（送信先）に共通の読み込み位置からログファイルを1回だけ読み、
+    /// 重複排除する前のストリームを返します。
+    ///
+    /// 全ての状態で同じチェックポイントがあるファイルはその位置から、そうでないファイルは
+    /// 先頭から読みます（先に進んでいる状態にとって既読の行は、`deduplicate_records` で
+    /// アップロード済みとして取り除かれます）。
+    ///
+    /// ログにはツリー上の位置を設定し、`filter` で絞り込みます。
+    /// 順序や並行読み込み、`ParsedRecord::FileCompleted` の扱いは `stream` と同じです。
+    ///
+    /// # 引数
+    ///
+    /// * `file_paths` - ログファイルのパスのリスト
+    /// * `config` - アップロード設定
+    /// * `filter` - レコード単位の絞り込み条件
+    /// * `states` - 読み込み位置を決める状態（送信先ごと）
+    /// * `batch_id` - アップロードバッチID
+    pub async fn read(
+        &self,
+        file_paths: &[impl AsRef<Path>],
+        config: &UploadConfig,
+        filter: &LogFilter,
+        states: &[Arc<UploadState>],
+        batch_id: &str,
+    ) -> BoxStream<'_, Result<ParsedRecord>> {
+        let config = Arc::new(config.clone());
+        let batch_id: Arc<str> = Arc::from(batch_id);
+        let mut files: Vec<PathBuf> = file_paths
+            .iter()
+            .map(|p| p.as_ref().to_path_buf())
+            .collect();
+        // サブエージェントを起動元に紐付けられるよう、メインのトランスクリプトを先に読む
+        files.sort_by_key(|path| SessionTree::is_subagent_transcript(path));
+        let workers = config.parse_workers.max(1);
+        let selective = filter.is_selective();
+        let filter = filter.clone();
+
+        // 絞り込み時は先頭から読むため、チェックポイントを使わない
+        let checkpoints: HashMap<String, FileCheckpoint> = if selective {
+            HashMap::new()
+        } else {
+            files
+                .iter()
+                .filter_map(|file| {
+                    let source_file = file.to_string_lossy().to_string();
+                    shared_checkpoint(states, &source_file)
+                        .map(|checkpoint| (source_file, checkpoint))
+                })
+                .collect()
+        };
+
+        // 前回までに読んだ起動元や親メッセージをツリーに登録しておく
+        let mut tree = SessionTree::new();
+        self.prime_tree(&mut tree, &files, &checkpoints, &config, &batch_id)
+            .await;
+
+        let files = stream::iter(files).map(move |file_path| {
+            let source_file = file_path.to_string_lossy().to_string();
+            let checkpoint = checkpoints.get(&source_file).cloned();
+            let config = config.clone();
+            let batch_id = batch_id.clone();
+
+            // チェックポイント以降を読み込み、SessionLogに変換
+            let file_records = self
                 .log_repository
-                .parse_log_file(file_path.as_ref())
-                .await?;
-
-            // SessionLogInputをSessionLogに変換
-            for input in inputs {
-                let session_log =
-                    convert_input_to_session_log(input, file_path.as_ref(), config, batch_id)?;
-                all_logs.push(session_log);
+                .stream_log_file(file_path.clone(), checkpoint)
+                .map(move |item| match item {
+                    Ok(LogStreamItem::Record(record)) => {
+                        convert_record_to_session_log(*record, &file_path, &config, &batch_id)
+                            .map(|log| ParsedRecord::Log(Box::new(log)))
+                    }
+                    Ok(LogStreamItem::Rejected(reject)) => Ok(ParsedRecord::Rejected(reject)),
+                    Ok(LogStreamItem::Checkpoint(checkpoint)) => Ok(ParsedRecord::FileCompleted {
+                        source_file: source_file.clone(),
+                        checkpoint,
+                    }),
+                    Err(e) => Err(e),
+                });
+
+            // 最初の要素を先読みしてファイルの読み込みを開始させる
+            async move {
+                let mut file_records = file_records.boxed();
+                let first = file_records.next().await;
+                stream::iter(first).chain(file_records)
+            }
+        });
+
+        // 最大 `workers` ファイルを並行して読み込み、ファイルの順序どおりに連結する
+        // （重複したログもツリーの紐付けに使うため、重複排除の前にツリーに登録する）
+        let records = files.buffered(workers).flatten().filter_map(move |item| {
+            let record = match item {
+                Ok(mut record) => {
+                    let keep = match &mut record {
+                        ParsedRecord::Log(log) | ParsedRecord::Changed(log) => {
+                            tree.attach(log);
+                            filter.keeps_record(log)
+                        }
+                        ParsedRecord::Rejected(_) => true,
+                        ParsedRecord::FileCompleted {
+                            source_file,
+                            checkpoint,
+                        } => {
+                            // 次回の実行でツリーを復元できるよう、紐付け情報を保存する
+                            if let Some(edges) = tree.take_edges(source_file) {
+                                checkpoint.lineage = Some(edges);
+                            }
+                            !selective
+                        }
+                    };
+                    keep.then_some(Ok(record))
+                }
+                Err(e) => Some(Err(e)),
+            };
+            future::ready(record)
+        });
+
+        records.boxed()
+    }
+
+    /// 新しい行があるセッションの既読部分をツリーに登録する
+    ///
+    /// 差分読み込みではチェックポイント以降しか読まないため、前回までの実行で読んだ
+    /// 起動元のTaskや親メッセージが分からず、サブエージェントや続きの行が紐付かない。
+    /// 封印されていないファイルと同じセッションのトランスクリプトのうち、
+    /// チェックポイント（`checkpoints`）から読むものは、チェックポイントに保存した
+    /// 紐付け情報からツリーを復元する。封印されたファイルはチェックポイントがそのまま
+    /// 引き継がれるため、紐付け情報も失われない。
+    ///
+    /// 紐付け情報を保存していないチェックポイント（以前のバージョンで作成したもの）の
+    /// ファイルだけは先頭から読み直す。読み込みに失敗したファイルは読み飛ばす
+    /// （エラーは本来の読み込みでストリームに流れる）。
+    async fn prime_tree(
+        &self,
+        tree: &mut SessionTree,
+        files: &[PathBuf],
+        checkpoints: &HashMap<String, FileCheckpoint>,
+        config: &UploadConfig,
+        batch_id: &str,
+    ) {
+        let mut active = HashSet::new();
+        for file in files {
+            let checkpoint = checkpoints.get(file.to_string_lossy().as_ref());
+            let sealed = checkpoint.is_some()
+                && self
+                    .log_repository
+                    .is_sealed(file, checkpoint)
+                    .await
+                    .unwrap_or(false);
+            if !sealed {
+                active.insert(transcript_group(file));
             }
         }
 
-        // 重複排除
-        let filtered_logs = DeduplicationService::filter_duplicates(
-            all_logs,
-            &state.uploaded_uuids,
-            config.enable_deduplication,
-        );
+        for file in files {
+            let in_active_group = active.contains(&transcript_group(file))
+                || file.parent().is_some_and(|dir| active.contains(dir));
+            let source_file = file.to_string_lossy();
+            let Some(checkpoint) = checkpoints.get(source_file.as_ref()) else {
+                continue;
+            };
+            if !in_active_group {
+                continue;
+            }
+            if let Some(edges) = &checkpoint.lineage {
+                tree.restore(&source_file, edges);
+                continue;
+            }
 
-        Ok(filtered_logs)
+            let mut records = self.log_repository.stream_log_file(file.clone(), None);
+            while let Some(item) = records.next().await {
+                match item {
+                    Ok(LogStreamItem::Record(record)) => {
+                        if let Ok(mut log) =
+                            convert_record_to_session_log(*record, file, config, batch_id)
+                        {
+                            tree.attach(&mut log);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(_) => break,
+                }
+            }
+        }
     }
 }
 
-/// SessionLogInputをSessionLogに変換
-fn convert_input_to_session_log(
-    input: SessionLogInput,
+/// トランスクリプトが属するセッションのまとまりを返す
+///
+/// `<dir>/<session>.jsonl` と `<dir>/<session>/subagents/` 配下は `<dir>/<session>` になる。
+/// パスからセッションが分からない旧形式の `<dir>/agent-<id>.jsonl` は、
+/// ディレクトリ全体を表す `<dir>` になる。
+fn transcript_group(path: &Path) -> PathBuf {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    if dir.file_name().is_some_and(|name| name == "subagents") {
+        return dir.parent().unwrap_or(dir).to_path_buf();
+    }
+    if SessionTree::is_subagent_transcript(path) {
+        return dir.to_path_buf();
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = name.split('.').next().unwrap_or_default();
+    dir.join(stem)
+}
+
+/// 全ての状態に同じチェックポイントがあればそれを返す
+fn shared_checkpoint(states: &[Arc<UploadState>], source_file: &str) -> Option<FileCheckpoint> {
+    let (first, rest) = states.split_first()?;
+    let checkpoint = first.checkpoint(source_file)?;
+    rest.iter()
+        .all(|state| state.checkpoint(source_file) == Some(checkpoint))
+        .then(|| checkpoint.clone())
+}
+
+/// `ParseLogsUseCase::read` のストリームに、1つの状態（送信先）に対する重複排除と変更検出を適用します。
+///
+/// アップロード済みのログは取り除き、内容が変わっていたログは `config.change_policy` に従って
+/// 流します（`ParseLogsUseCase::stream` を参照）。
+///
+/// `state` がアップロード済みレコードを読み込んでいない場合（`UploadState::records_omitted`）は、
+/// 最大 `DEDUPLICATION_CHUNK_SIZE` 件ずつ `state_repository` で照合します。
+/// 照合に失敗した場合はエラーを流します。
+pub fn deduplicate_records<'a, S: StateRepository + ?Sized + 'a>(
+    records: BoxStream<'a, Result<ParsedRecord>>,
+    state: Arc<UploadState>,
+    state_repository: Arc<S>,
+    state_path: &str,
+    config: &UploadConfig,
+) -> BoxStream<'a, Result<ParsedRecord>> {
+    let config = config.clone();
+    let state_path: Arc<str> = Arc::from(state_path);
+    let mut state = Arc::unwrap_or_clone(state);
+    let omitted = state.records_omitted;
+
+    records
+        .ready_chunks(DEDUPLICATION_CHUNK_SIZE)
+        .then(move |chunk| {
+            let state_repository = state_repository.clone();
+            let state_path = state_path.clone();
+            async move {
+                if !omitted {
+                    return (chunk, Ok(None));
+                }
+                let ids: Vec<String> = chunk
+                    .iter()
+                    .filter_map(|item| match item {
+                        Ok(ParsedRecord::Log(log)) => Some(log.uuid.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                if ids.is_empty() {
+                    return (chunk, Ok(Some(HashMap::new())));
+                }
+                let found = state_repository.find_records(&state_path, &ids).await;
+                (chunk, found.map(Some))
+            }
+        })
+        .flat_map(move |(chunk, found)| {
+            let records: Vec<Result<ParsedRecord>> = match found {
+                Ok(found) => {
+                    if let Some(found) = found {
+                        state.replace_records(found);
+                    }
+                    chunk
+                        .into_iter()
+                        .filter_map(|item| match item {
+                            Ok(ParsedRecord::Log(log)) => {
+                                match deduplicate(*log, &state, &config) {
+                                    (_, true) => None,
+                                    (record, false) => Some(Ok(record)),
+                                }
+                            }
+                            other => Some(other),
+                        })
+                        .collect()
+                }
+                Err(e) => vec![Err(e.context("Failed to look up uploaded records"))],
+            };
+            stream::iter(records)
+        })
+        .boxed()
+}
+
+/// 1つのストリームを `count` 個のストリームに複製します。
+///
+/// 戻り値の処理（`BoxFuture`）が元のストリームを読み、各ストリームに同じ要素を順に流します。
+/// 各ストリームと並行して実行してください。読み終えていないストリームが溜めておける要素数には
+/// 上限があるため、遅いストリームに合わせて読み進めます。
+/// 溜めておける上限に達したストリームが `stall_timeout` の間1件も読まなければ、そのストリームには
+/// 以降流さず、溜まった要素の後にエラーを流して終わらせます（止まった送信先が他の送信先を止めないように）。
+/// 途中で破棄されたストリームには流さず、全て破棄されたら読むのをやめます。
+/// エラーは（エラーチェーンを含むメッセージとして）全てのストリームに流します。
+pub fn fan_out<'a>(
+    mut records: BoxStream<'a, Result<ParsedRecord>>,
+    count: usize,
+    stall_timeout: Duration,
+) -> (BoxFuture<'a, ()>, Vec<BoxStream<'a, Result<ParsedRecord>>>) {
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..count)
+        .map(|_| {
+            let (sender, receiver) = mpsc::channel(FAN_OUT_CAPACITY);
+            let stalled = Arc::new(AtomicBool::new(false));
+            ((sender, stalled.clone()), (receiver, stalled))
+        })
+        .unzip();
+
+    let feed = async move {
+        let mut senders = senders;
+        while let Some(item) = records.next().await {
+            if senders.is_empty() {
+                break;
+            }
+            let mut open = Vec::with_capacity(senders.len());
+            for (mut sender, stalled) in senders {
+                let item = match &item {
+                    Ok(record) => Ok(record.clone()),
+                    Err(e) => Err(anyhow::anyhow!("{:#}", e)),
+                };
+                match tokio::time::timeout(stall_timeout, sender.send(item)).await {
+                    Ok(Ok(())) => open.push((sender, stalled)),
+                    Ok(Err(_)) => {}
+                    Err(_) => stalled.store(true, Ordering::SeqCst),
+                }
+            }
+            senders = open;
+        }
+    };
+
+    let streams = receivers
+        .into_iter()
+        .map(|(receiver, stalled)| {
+            let stalled_error = stream::once(async move {
+                stalled.load(Ordering::SeqCst).then(|| {
+                    Err(anyhow::anyhow!(
+                        "Stopped reading records: no record was consumed for {}s",
+                        stall_timeout.as_secs()
+                    ))
+                })
+            })
+            .filter_map(future::ready);
+            receiver.chain(stalled_error).boxed()
+        })
+        .collect();
+    (feed.boxed(), streams)
+}
+
+/// 重複排除と変更検出を適用し、流すレコードと重複かどうかを返す
+///
+/// `SessionLog::uuid` はレコードの重複排除用ID（`TranscriptRecord::identity`）。
+fn deduplicate(
+    mut log: SessionLog,
+    state: &UploadState,
+    config: &UploadConfig,
+) -> (ParsedRecord, bool) {
+    let identity = log.uuid.clone();
+    let identity = identity.as_str();
+    // 圧縮済みセッションは透かし以前のレコードもアップロード済みとみなす
+    if !config.enable_deduplication
+        || !state.is_record_uploaded(
+            identity,
+            &log.session_id,
+            &log.metadata.source_file,
+            log.timestamp,
+        )
+    {
+        return (ParsedRecord::Log(Box::new(log)), false);
+    }
+
+    if config.change_policy == ChangePolicy::Skip
+        || !state.is_content_changed(identity, &log.content_hash())
+    {
+        return (ParsedRecord::Log(Box::new(log)), true);
+    }
+
+    match config.change_policy {
+        ChangePolicy::NewVersion => {
+            log.record_version = state.record_version(identity) + 1;
+            (ParsedRecord::Log(Box::new(log)), false)
+        }
+        _ => (ParsedRecord::Changed(Box::new(log)), false),
+    }
+}
+
+/// TranscriptRecordをSessionLogに変換
+pub(crate) fn convert_record_to_session_log(
+    record: TranscriptRecord,
     source_file: &Path,
     config: &UploadConfig,
     batch_id: &str,
@@ -148,37 +613,27 @@ fn convert_input_to_session_log(
         uploaded_at: Utc::now(),
     };
 
-    SessionLog::new(
-        input.uuid,
-        input.timestamp,
-        input.session_id,
-        input.agent_id,
-        input.is_sidechain,
-        input.parent_uuid,
-        input.user_type,
-        input.message_type,
-        input.slug,
-        input.request_id,
-        input.cwd,
-        input.git_branch,
-        input.version,
-        input.message,
-        input.tool_use_result,
-        metadata,
-    )
+    record.into_session_log(metadata)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::repositories::state_repository::UploadState;
+    use crate::domain::entities::file_checkpoint::FileCheckpoint;
+    use crate::domain::entities::rejected_line::RejectedLine;
+    use crate::domain::entities::session_log::{SessionLineage, SessionLogInput};
+    use crate::domain::repositories::log_repository::{LogStream, ParsedLogFile};
+    use crate::domain::repositories::state_repository::{
+        RecordHash, StateUpdate, UploadState, UploadedRecord,
+    };
     use async_trait::async_trait;
     use chrono::TimeZone;
     use serde_json::json;
+    use std::collections::HashMap;
     use std::path::PathBuf;
 
     struct MockLogRepository {
-        logs: Vec<SessionLogInput>,
+        logs: Vec<TranscriptRecord>,
     }
 
     #[async_trait]
@@ -187,8 +642,12 @@ mod tests {
             Ok(vec![])
         }
 
-        async fn parse_log_file(&self, _file_path: &Path) -> Result<Vec<SessionLogInput>> {
-            Ok(self.logs.clone())
+        fn stream_log_file(
+            &self,
+            _file_path: PathBuf,
+            _checkpoint: Option<FileCheckpoint>,
+        ) -> LogStream<'_> {
+            ParsedLogFile::from_records(self.logs.clone()).into_stream()
         }
     }
 
@@ -205,10 +664,14 @@ mod tests {
         async fn save(&self, _path: &str, _state: &UploadState) -> Result<()> {
             Ok(())
         }
+
+        async fn replace(&self, _path: &str, _state: &UploadState) -> Result<()> {
+            Ok(())
+        }
     }
 
-    fn create_test_input(uuid: &str) -> SessionLogInput {
-        SessionLogInput {
+    fn create_test_input(uuid: &str) -> TranscriptRecord {
+        TranscriptRecord::Message(SessionLogInput {
             uuid: uuid.to_string(),
             timestamp: Utc.with_ymd_and_hms(2024, 12, 25, 10, 0, 0).unwrap(),
             session_id: "session-001".to_string(),
@@ -224,7 +687,8 @@ mod tests {
             version: None,
             message: json!({}),
             tool_use_result: None,
-        }
+            extra: serde_json::Map::new(),
+        })
     }
 
     #[tokio::test]
@@ -256,16 +720,264 @@ mod tests {
 
         let file_paths = vec![PathBuf::from("/path/to/log.jsonl")];
         let result = use_case
-            .execute(&file_paths, &config, "/path/to/state.json", "batch-001")
+            .execute(
+                &file_paths,
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.json",
+                "batch-001",
+            )
             .await;
 
         assert!(result.is_ok());
-        let logs = result.unwrap();
+        let logs = result.unwrap().logs;
         assert_eq!(logs.len(), 2); // uuid-2 が除外される
         assert_eq!(logs[0].uuid, "uuid-1");
         assert_eq!(logs[1].uuid, "uuid-3");
     }
 
+    #[tokio::test]
+    async fn test_parse_logs_skips_records_before_compaction_watermark() {
+        let mut later = create_test_input("uuid-later");
+        if let TranscriptRecord::Message(input) = &mut later {
+            input.timestamp = Utc.with_ymd_and_hms(2024, 12, 25, 11, 0, 0).unwrap();
+        }
+        let inputs = vec![create_test_input("uuid-forgotten"), later];
+        let mock_log_repo = Arc::new(MockLogRepository { logs: inputs });
+
+        // uuid-forgotten は圧縮でUUIDを忘れたが、透かし以前のレコード
+        let mut state = UploadState::new();
+        StateUpdate {
+            timestamp: Utc.with_ymd_and_hms(2024, 12, 26, 0, 0, 0).unwrap(),
+            uploaded: vec![UploadedRecord {
+                uuid: "uuid-forgotten".to_string(),
+                session_id: "session-001".to_string(),
+                source_file: "/path/to/log.jsonl".to_string(),
+                timestamp: Utc.with_ymd_and_hms(2024, 12, 25, 10, 0, 0).unwrap(),
+                content_hash: None,
+                record_version: 0,
+            }],
+            ..Default::default()
+        }
+        .apply(&mut state);
+        state.compact(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(), |_| true);
+        assert!(!state.is_uploaded("uuid-forgotten"));
+        let mock_state_repo = Arc::new(MockStateRepository { state });
+
+        let use_case = ParseLogsUseCase::new(mock_log_repo, mock_state_repo);
+
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        );
+
+        let file_paths = vec![PathBuf::from("/path/to/log.jsonl")];
+        let logs = use_case
+            .execute(
+                &file_paths,
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap()
+            .logs;
+
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].uuid, "uuid-later");
+    }
+
+    /// uuid-1 は同じ内容、uuid-2 は別の内容でアップロード済みの状態で、各ポリシーでパースする
+    async fn parse_with_change_policy(policy: ChangePolicy) -> ParsedLogs {
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        )
+        .with_change_policy(policy);
+        let file_paths = vec![PathBuf::from("/path/to/log.jsonl")];
+        let inputs = vec![create_test_input("uuid-1"), create_test_input("uuid-2")];
+        let mock_log_repo = Arc::new(MockLogRepository { logs: inputs });
+
+        let mut state = UploadState::new();
+        StateUpdate {
+            uploaded: ["uuid-1", "uuid-2"]
+                .into_iter()
+                .map(|uuid| UploadedRecord {
+                    uuid: uuid.to_string(),
+                    session_id: "session-001".to_string(),
+                    source_file: "/path/to/log.jsonl".to_string(),
+                    timestamp: Utc.with_ymd_and_hms(2024, 12, 25, 10, 0, 0).unwrap(),
+                    content_hash: Some(if uuid == "uuid-1" {
+                        convert_record_to_session_log(
+                            create_test_input(uuid),
+                            &file_paths[0],
+                            &config,
+                            "batch-000",
+                        )
+                        .unwrap()
+                        .content_hash()
+                    } else {
+                        "stale-hash".to_string()
+                    }),
+                    record_version: 1,
+                })
+                .collect(),
+            ..Default::default()
+        }
+        .apply(&mut state);
+        let mock_state_repo = Arc::new(MockStateRepository { state });
+
+        let use_case = ParseLogsUseCase::new(mock_log_repo, mock_state_repo);
+
+        use_case
+            .execute(
+                &file_paths,
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_logs_change_policy() {
+        // skip: 内容が変わっていても重複として除外する
+        let parsed = parse_with_change_policy(ChangePolicy::Skip).await;
+        assert!(parsed.logs.is_empty());
+        assert!(parsed.changed.is_empty());
+
+        // new_version: 変わったレコードだけを次のバージョンとして流す
+        let parsed = parse_with_change_policy(ChangePolicy::NewVersion).await;
+        assert_eq!(parsed.logs.len(), 1);
+        assert_eq!(parsed.logs[0].uuid, "uuid-2");
+        assert_eq!(parsed.logs[0].record_version, 2);
+        assert!(parsed.changed.is_empty());
+
+        // report: アップロードせずに報告する
+        let parsed = parse_with_change_policy(ChangePolicy::Report).await;
+        assert!(parsed.logs.is_empty());
+        assert_eq!(parsed.changed.len(), 1);
+        assert_eq!(parsed.changed[0].uuid, "uuid-2");
+        assert_eq!(parsed.changed[0].record_version, 0);
+    }
+
+    /// アップロード済みレコードを読み込まず、`find_records` で照合させる状態リポジトリ
+    struct LookupStateRepository {
+        state: UploadState,
+        lookups: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl StateRepository for LookupStateRepository {
+        async fn load(&self, _path: &str) -> Result<UploadState> {
+            Ok(self.state.clone())
+        }
+
+        async fn load_for_upload(&self, _path: &str) -> Result<UploadState> {
+            let mut state = self.state.clone();
+            state.replace_records(HashMap::new());
+            state.records_omitted = true;
+            Ok(state)
+        }
+
+        async fn save(&self, _path: &str, _state: &UploadState) -> Result<()> {
+            Ok(())
+        }
+
+        async fn replace(&self, _path: &str, _state: &UploadState) -> Result<()> {
+            Ok(())
+        }
+
+        async fn find_records(
+            &self,
+            _path: &str,
+            ids: &[String],
+        ) -> Result<HashMap<String, Option<RecordHash>>> {
+            self.lookups.lock().unwrap().extend(ids.iter().cloned());
+            Ok(ids
+                .iter()
+                .filter(|id| self.state.is_uploaded(id))
+                .map(|id| (id.clone(), self.state.record_hashes.get(id).cloned()))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_logs_looks_up_records_omitted_from_state() {
+        let inputs = vec![
+            create_test_input("uuid-1"),
+            create_test_input("uuid-2"),
+            create_test_input("uuid-3"),
+        ];
+        let mock_log_repo = Arc::new(MockLogRepository { logs: inputs });
+
+        let mut state = UploadState::new();
+        state.uploaded_uuids.insert("uuid-1".to_string());
+        state.uploaded_uuids.insert("uuid-2".to_string());
+        state.record_hashes.insert(
+            "uuid-2".to_string(),
+            RecordHash {
+                hash: "stale-hash".to_string(),
+                version: 1,
+            },
+        );
+        let state_repo = Arc::new(LookupStateRepository {
+            state,
+            lookups: Default::default(),
+        });
+
+        let use_case = ParseLogsUseCase::new(mock_log_repo, state_repo.clone());
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        )
+        .with_change_policy(ChangePolicy::NewVersion);
+
+        let parsed = use_case
+            .execute(
+                &[PathBuf::from("/path/to/log.jsonl")],
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.db",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+
+        // uuid-1 は重複、uuid-2 は内容が変わったため次のバージョンとして流れる
+        let uuids: Vec<_> = parsed.logs.iter().map(|log| log.uuid.as_str()).collect();
+        assert_eq!(uuids, vec!["uuid-2", "uuid-3"]);
+        assert_eq!(parsed.logs[0].record_version, 2);
+        assert_eq!(
+            *state_repo.lookups.lock().unwrap(),
+            vec!["uuid-1", "uuid-2", "uuid-3"]
+        );
+    }
+
     #[tokio::test]
     async fn test_parse_logs_without_deduplication() {
         let inputs = vec![create_test_input("uuid-1"), create_test_input("uuid-2")];
@@ -291,11 +1003,17 @@ mod tests {
 
         let file_paths = vec![PathBuf::from("/path/to/log.jsonl")];
         let result = use_case
-            .execute(&file_paths, &config, "/path/to/state.json", "batch-001")
+            .execute(
+                &file_paths,
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.json",
+                "batch-001",
+            )
             .await;
 
         assert!(result.is_ok());
-        let logs = result.unwrap();
+        let logs = result.unwrap().logs;
         assert_eq!(logs.len(), 2); // 重複排除しない
     }
 
@@ -322,11 +1040,676 @@ mod tests {
 
         let file_paths = vec![PathBuf::from("/path/to/empty.jsonl")];
         let result = use_case
-            .execute(&file_paths, &config, "/path/to/state.json", "batch-001")
+            .execute(
+                &file_paths,
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.json",
+                "batch-001",
+            )
             .await;
 
         assert!(result.is_ok());
-        let logs = result.unwrap();
-        assert_eq!(logs.len(), 0);
+        let parsed = result.unwrap();
+        assert_eq!(parsed.len(), 0);
+        // 差分読み込み非対応のリポジトリではチェックポイントなし
+        assert!(parsed.checkpoints.is_empty());
+    }
+
+    struct CheckpointingLogRepository;
+
+    #[async_trait]
+    impl LogRepository for CheckpointingLogRepository {
+        async fn discover_log_files(&self, _log_dir: &str) -> Result<Vec<PathBuf>> {
+            Ok(vec![])
+        }
+
+        fn stream_log_file(
+            &self,
+            _file_path: PathBuf,
+            checkpoint: Option<FileCheckpoint>,
+        ) -> LogStream<'_> {
+            // 前回のチェックポイントがあれば新しい行だけを返す
+            let (inputs, lines) = match checkpoint {
+                Some(cp) => (vec![create_test_input("uuid-new")], cp.lines + 1),
+                None => (
+                    vec![create_test_input("uuid-1"), create_test_input("uuid-new")],
+                    2,
+                ),
+            };
+            ParsedLogFile {
+                records: inputs,
+                rejected: vec![RejectedLine::new(
+                    "/path/to/log.jsonl",
+                    lines,
+                    "expected value",
+                    "not json",
+                )],
+                checkpoint: Some(FileCheckpoint::new(None, 0, None, 0, lines)),
+            }
+            .into_stream()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_logs_resumes_from_checkpoint() {
+        let mut state = UploadState::new();
+        state.update_checkpoints(HashMap::from([(
+            "/path/to/log.jsonl".to_string(),
+            FileCheckpoint::new(None, 0, None, 0, 1),
+        )]));
+        let mock_state_repo = Arc::new(MockStateRepository { state });
+
+        let use_case = ParseLogsUseCase::new(Arc::new(CheckpointingLogRepository), mock_state_repo);
+
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        );
+
+        let file_paths = vec![PathBuf::from("/path/to/log.jsonl")];
+        let parsed = use_case
+            .execute(
+                &file_paths,
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed.logs[0].uuid, "uuid-new");
+        assert_eq!(parsed.checkpoints["/path/to/log.jsonl"].lines, 2);
+        // パースできなかった行は重複排除されずにそのまま流れる
+        assert_eq!(parsed.rejected.len(), 1);
+        assert_eq!(parsed.rejected[0].line_number, 2);
+    }
+
+    #[tokio::test]
+    async fn test_parse_logs_selective_filter_ignores_checkpoints() {
+        let mut state = UploadState::new();
+        state.update_checkpoints(HashMap::from([(
+            "/path/to/log.jsonl".to_string(),
+            FileCheckpoint::new(None, 0, None, 0, 1),
+        )]));
+        let mock_state_repo = Arc::new(MockStateRepository { state });
+
+        let use_case = ParseLogsUseCase::new(Arc::new(CheckpointingLogRepository), mock_state_repo);
+
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        );
+        let filter = LogFilter::new().with_sessions(vec!["session-001".to_string()]);
+
+        let file_paths = vec![PathBuf::from("/path/to/log.jsonl")];
+        let parsed = use_case
+            .execute(
+                &file_paths,
+                &config,
+                &filter,
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+
+        // 先頭から読み直し、チェックポイントは更新しない
+        assert_eq!(parsed.len(), 2);
+        assert!(parsed.checkpoints.is_empty());
+
+        let other = LogFilter::new().with_sessions(vec!["session-999".to_string()]);
+        let parsed = use_case
+            .execute(
+                &file_paths,
+                &config,
+                &other,
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+        assert_eq!(parsed.len(), 0);
+    }
+
+    struct PerFileLogRepository {
+        files: HashMap<PathBuf, Vec<TranscriptRecord>>,
+    }
+
+    #[async_trait]
+    impl LogRepository for PerFileLogRepository {
+        async fn discover_log_files(&self, _log_dir: &str) -> Result<Vec<PathBuf>> {
+            Ok(vec![])
+        }
+
+        fn stream_log_file(
+            &self,
+            file_path: PathBuf,
+            _checkpoint: Option<FileCheckpoint>,
+        ) -> LogStream<'_> {
+            let records = self.files.get(&file_path).cloned().unwrap_or_default();
+            ParsedLogFile::from_records(records).into_stream()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_logs_links_subagent_to_spawning_task() {
+        let mut task_call = create_test_input("main-1");
+        let mut task_result = create_test_input("main-2");
+        let mut subagent = create_test_input("sub-1");
+        if let (
+            TranscriptRecord::Message(call),
+            TranscriptRecord::Message(result),
+            TranscriptRecord::Message(sub),
+        ) = (&mut task_call, &mut task_result, &mut subagent)
+        {
+            call.message = json!({"content": [
+                {"type": "tool_use", "id": "toolu_01", "name": "Task", "input": {"prompt": "調査して"}}
+            ]});
+            result.parent_uuid = Some("main-1".to_string());
+            result.message =
+                json!({"content": [{"type": "tool_result", "tool_use_id": "toolu_01"}]});
+            result.tool_use_result = Some(json!({"agentId": "a1"}));
+            sub.agent_id = Some("a1".to_string());
+            sub.is_sidechain = Some(true);
+            sub.message = json!({"content": "調査して"});
+        }
+        let main_file = PathBuf::from("/logs/session-001.jsonl");
+        let agent_file = PathBuf::from("/logs/agent-a1.jsonl");
+        let log_repo = Arc::new(PerFileLogRepository {
+            files: HashMap::from([
+                (main_file.clone(), vec![task_call, task_result]),
+                (agent_file.clone(), vec![subagent]),
+            ]),
+        });
+        let state_repo = Arc::new(MockStateRepository {
+            state: UploadState::new(),
+        });
+        let use_case = ParseLogsUseCase::new(log_repo, state_repo);
+
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        );
+
+        // サブエージェントのファイルが先に渡されても、メインを先に読む
+        let parsed = use_case
+            .execute(
+                &[agent_file, main_file],
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+
+        let uuids: Vec<&str> = parsed.logs.iter().map(|log| log.uuid.as_str()).collect();
+        assert_eq!(uuids, vec!["main-1", "main-2", "sub-1"]);
+        assert_eq!(
+            parsed.logs[0].lineage,
+            Some(SessionLineage::root("session-001"))
+        );
+        let lineage = parsed.logs[2].lineage.as_ref().unwrap();
+        assert_eq!(lineage.root_session_id, "session-001");
+        assert_eq!(lineage.depth, 1);
+        assert_eq!(lineage.spawning_tool_use_id.as_deref(), Some("toolu_01"));
+    }
+
+    /// 行数をチェックポイントにして差分を返すリポジトリ（複数回の実行の確認用）
+    struct IncrementalLogRepository {
+        files: HashMap<PathBuf, Vec<TranscriptRecord>>,
+        /// 読み込んだファイルと読み始めた行
+        reads: std::sync::Mutex<Vec<(PathBuf, u64)>>,
+    }
+
+    impl IncrementalLogRepository {
+        fn new(files: HashMap<PathBuf, Vec<TranscriptRecord>>) -> Self {
+            Self {
+                files,
+                reads: std::sync::Mutex::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LogRepository for IncrementalLogRepository {
+        async fn discover_log_files(&self, _log_dir: &str) -> Result<Vec<PathBuf>> {
+            Ok(vec![])
+        }
+
+        async fn is_sealed(
+            &self,
+            file_path: &Path,
+            checkpoint: Option<&FileCheckpoint>,
+        ) -> Result<bool> {
+            let lines = self.files.get(file_path).map_or(0, Vec::len) as u64;
+            Ok(checkpoint.is_some_and(|cp| cp.lines == lines))
+        }
+
+        fn stream_log_file(
+            &self,
+            file_path: PathBuf,
+            checkpoint: Option<FileCheckpoint>,
+        ) -> LogStream<'_> {
+            let records = self.files.get(&file_path).cloned().unwrap_or_default();
+            let lines = records.len() as u64;
+            let start = checkpoint.as_ref().map_or(0, |cp| cp.lines);
+            self.reads.lock().unwrap().push((file_path, start));
+            // 変更がなければコミット済みのチェックポイントをそのまま返す
+            let checkpoint = checkpoint
+                .filter(|cp| cp.lines == lines)
+                .unwrap_or_else(|| FileCheckpoint::new(None, 0, None, 0, lines));
+            ParsedLogFile {
+                records: records[start as usize..].to_vec(),
+                rejected: Vec::new(),
+                checkpoint: Some(checkpoint),
+            }
+            .into_stream()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_logs_links_subagent_across_runs() {
+        let mut task_call = create_test_input("main-1");
+        let mut first = create_test_input("sub-1");
+        let mut continuation = create_test_input("sub-2");
+        let mut second_agent = create_test_input("other-1");
+        if let (
+            TranscriptRecord::Message(call),
+            TranscriptRecord::Message(first),
+            TranscriptRecord::Message(continuation),
+            TranscriptRecord::Message(second_agent),
+        ) = (
+            &mut task_call,
+            &mut first,
+            &mut continuation,
+            &mut second_agent,
+        ) {
+            call.message = json!({"content": [
+                {"type": "tool_use", "id": "toolu_01", "name": "Task", "input": {"prompt": "調査して"}},
+                {"type": "tool_use", "id": "toolu_02", "name": "Task", "input": {"prompt": "修正して"}}
+            ]});
+            first.agent_id = Some("a1".to_string());
+            first.is_sidechain = Some(true);
+            first.message = json!({"content": "調査して"});
+            continuation.parent_uuid = Some("sub-1".to_string());
+            continuation.agent_id = Some("a1".to_string());
+            continuation.is_sidechain = Some(true);
+            continuation.message = json!({"content": "続き"});
+            second_agent.agent_id = Some("a2".to_string());
+            second_agent.is_sidechain = Some(true);
+            second_agent.message = json!({"content": "修正して"});
+        }
+        let main_file = PathBuf::from("/logs/session-001.jsonl");
+        let agent_file = PathBuf::from("/logs/session-001/subagents/agent-a1.jsonl");
+        let new_agent_file = PathBuf::from("/logs/session-001/subagents/agent-a2.jsonl");
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        );
+
+        // 1回目: メインと1つ目のサブエージェントを読み、チェックポイントを残す
+        let use_case = ParseLogsUseCase::new(
+            Arc::new(IncrementalLogRepository::new(HashMap::from([
+                (main_file.clone(), vec![task_call.clone()]),
+                (agent_file.clone(), vec![first.clone()]),
+            ]))),
+            Arc::new(MockStateRepository {
+                state: UploadState::new(),
+            }),
+        );
+        let parsed = use_case
+            .execute(
+                &[main_file.clone(), agent_file.clone()],
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+        let mut state = UploadState::new();
+        state.update_checkpoints(parsed.checkpoints);
+
+        // 2回目: 続きの行と新しいサブエージェントだけが差分として読まれる
+        let repository = Arc::new(IncrementalLogRepository::new(HashMap::from([
+            (main_file.clone(), vec![task_call]),
+            (agent_file.clone(), vec![first, continuation]),
+            (new_agent_file.clone(), vec![second_agent]),
+        ])));
+        let use_case =
+            ParseLogsUseCase::new(repository.clone(), Arc::new(MockStateRepository { state }));
+        let parsed = use_case
+            .execute(
+                &[
+                    main_file.clone(),
+                    agent_file.clone(),
+                    new_agent_file.clone(),
+                ],
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.json",
+                "batch-002",
+            )
+            .await
+            .unwrap();
+
+        let uuids: Vec<&str> = parsed.logs.iter().map(|log| log.uuid.as_str()).collect();
+        assert_eq!(uuids, vec!["sub-2", "other-1"]);
+        let continued = parsed.logs[0].lineage.as_ref().unwrap();
+        assert_eq!(continued.depth, 1);
+        assert_eq!(continued.spawning_tool_use_id.as_deref(), Some("toolu_01"));
+        let spawned = parsed.logs[1].lineage.as_ref().unwrap();
+        assert_eq!(spawned.root_session_id, "session-001");
+        assert_eq!(spawned.depth, 1);
+        assert_eq!(spawned.spawning_tool_use_id.as_deref(), Some("toolu_02"));
+
+        // 既読部分は読み直さず、チェックポイントに保存した紐付け情報からツリーを復元する
+        assert_eq!(
+            *repository.reads.lock().unwrap(),
+            vec![(main_file, 1), (agent_file, 1), (new_agent_file, 0)]
+        );
+    }
+
+    /// 後のファイルほど早く読み終わるリポジトリ（並列読み込みの順序確認用）
+    struct SlowFirstLogRepository;
+
+    #[async_trait]
+    impl LogRepository for SlowFirstLogRepository {
+        async fn discover_log_files(&self, _log_dir: &str) -> Result<Vec<PathBuf>> {
+            Ok(vec![])
+        }
+
+        fn stream_log_file(
+            &self,
+            file_path: PathBuf,
+            _checkpoint: Option<FileCheckpoint>,
+        ) -> LogStream<'_> {
+            let index: u64 = file_path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+                .unwrap();
+            stream::once(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(40 - index * 10)).await;
+                ParsedLogFile::from_records(
+                    (0..2)
+                        .map(|line| create_test_input(&format!("{}-{}", index, line)))
+                        .collect(),
+                )
+                .into_stream()
+            })
+            .flatten()
+            .boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_logs_concurrently_keeps_file_order() {
+        let state_repo = Arc::new(MockStateRepository {
+            state: UploadState::new(),
+        });
+        let use_case = ParseLogsUseCase::new(Arc::new(SlowFirstLogRepository), state_repo);
+
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        )
+        .with_parse_workers(4);
+
+        let files: Vec<PathBuf> = (0..4)
+            .map(|i| PathBuf::from(format!("/logs/{}.jsonl", i)))
+            .collect();
+        let parsed = use_case
+            .execute(
+                &files,
+                &config,
+                &LogFilter::new(),
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+
+        let uuids: Vec<&str> = parsed.logs.iter().map(|log| log.uuid.as_str()).collect();
+        assert_eq!(
+            uuids,
+            vec!["0-0", "0-1", "1-0", "1-1", "2-0", "2-1", "3-0", "3-1"]
+        );
+    }
+
+    fn uuids(records: &[ParsedRecord]) -> Vec<&str> {
+        records
+            .iter()
+            .filter_map(|record| match record {
+                ParsedRecord::Log(log) => Some(log.uuid.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_read_resumes_only_from_checkpoint_shared_by_all_states() {
+        let file = PathBuf::from("/logs/session-001.jsonl");
+        let use_case = ParseLogsUseCase::new(
+            Arc::new(IncrementalLogRepository::new(HashMap::from([(
+                file.clone(),
+                vec![
+                    create_test_input("uuid-1"),
+                    create_test_input("uuid-2"),
+                    create_test_input("uuid-3"),
+                ],
+            )]))),
+            Arc::new(MockStateRepository {
+                state: UploadState::new(),
+            }),
+        );
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        );
+
+        // 2行目まで送った送信先
+        let mut uploaded = UploadState::new();
+        uploaded.uploaded_uuids.insert("uuid-1".to_string());
+        uploaded.uploaded_uuids.insert("uuid-2".to_string());
+        uploaded.update_checkpoints(HashMap::from([(
+            file.to_string_lossy().to_string(),
+            FileCheckpoint::new(None, 0, None, 0, 2),
+        )]));
+        let uploaded = Arc::new(uploaded);
+
+        let read = |states: Vec<Arc<UploadState>>| {
+            let use_case = &use_case;
+            let config = &config;
+            let file = file.clone();
+            async move {
+                use_case
+                    .read(&[file], config, &LogFilter::new(), &states, "batch-001")
+                    .await
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // 全ての送信先が同じ位置まで読んでいれば、その続きだけを読む
+        let records = read(vec![uploaded.clone(), uploaded.clone()]).await;
+        assert_eq!(uuids(&records), vec!["uuid-3"]);
+
+        // 新しい送信先があれば先頭から読み、送信先ごとの重複排除で既読の行を取り除く
+        let fresh = Arc::new(UploadState::new());
+        let records = read(vec![uploaded.clone(), fresh.clone()]).await;
+        assert_eq!(uuids(&records), vec!["uuid-1", "uuid-2", "uuid-3"]);
+        assert!(matches!(
+            records.last(),
+            Some(ParsedRecord::FileCompleted { checkpoint, .. }) if checkpoint.lines == 3
+        ));
+
+        let deduplicated: Vec<ParsedRecord> = deduplicate_records(
+            stream::iter(records.clone().into_iter().map(Ok)).boxed(),
+            uploaded,
+            use_case.state_repository.clone(),
+            "/state/upload.json",
+            &config,
+        )
+        .try_collect()
+        .await
+        .unwrap();
+        assert_eq!(uuids(&deduplicated), vec!["uuid-3"]);
+        assert_eq!(deduplicated.len(), 2);
+
+        let deduplicated: Vec<ParsedRecord> = deduplicate_records(
+            stream::iter(records.into_iter().map(Ok)).boxed(),
+            fresh,
+            use_case.state_repository.clone(),
+            "/state/upload.json",
+            &config,
+        )
+        .try_collect()
+        .await
+        .unwrap();
+        assert_eq!(uuids(&deduplicated), vec!["uuid-1", "uuid-2", "uuid-3"]);
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_feeds_every_stream() {
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        );
+        let log = |uuid: &str| {
+            let log = convert_record_to_session_log(
+                create_test_input(uuid),
+                Path::new("/path/to/log.jsonl"),
+                &config,
+                "batch-001",
+            )
+            .unwrap();
+            Ok(ParsedRecord::Log(Box::new(log)))
+        };
+        let records = stream::iter(vec![
+            log("uuid-1"),
+            log("uuid-2"),
+            Err(anyhow::anyhow!("broken line")),
+        ])
+        .boxed();
+
+        let (feed, mut streams) = fan_out(records, 3, Duration::from_secs(5));
+        // 破棄されたストリームは残りのストリームを止めない
+        drop(streams.pop());
+        let (_, received) = futures::join!(
+            feed,
+            future::join_all(streams.into_iter().map(|stream| stream.collect::<Vec<_>>()))
+        );
+
+        assert_eq!(received.len(), 2);
+        for records in received {
+            assert_eq!(records.len(), 3);
+            assert!(matches!(&records[1], Ok(ParsedRecord::Log(log)) if log.uuid == "uuid-2"));
+            let error = records[2].as_ref().unwrap_err();
+            assert!(error.to_string().contains("broken line"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_stops_feeding_stalled_stream() {
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        );
+        let log = convert_record_to_session_log(
+            create_test_input("uuid-1"),
+            Path::new("/path/to/log.jsonl"),
+            &config,
+            "batch-001",
+        )
+        .unwrap();
+        let total = FAN_OUT_CAPACITY * 3;
+        let records =
+            stream::iter((0..total).map(move |_| Ok(ParsedRecord::Log(Box::new(log.clone())))))
+                .boxed();
+
+        let (feed, mut streams) = fan_out(records, 3, Duration::from_millis(50));
+        // 読まれないストリーム（止まった送信先）があっても、他のストリームは最後まで読める
+        let stalled = streams.pop().unwrap();
+        let (_, received) = futures::join!(
+            feed,
+            future::join_all(streams.into_iter().map(|stream| stream.collect::<Vec<_>>()))
+        );
+        for records in received {
+            assert_eq!(records.len(), total);
+            assert!(records.iter().all(|record| record.is_ok()));
+        }
+
+        // 止まったストリームは溜まっていた分を読んだ後にエラーで終わる
+        let records: Vec<_> = stalled.collect().await;
+        assert!(records.len() < total);
+        let (error, buffered) = records.split_last().unwrap();
+        assert!(buffered.iter().all(|record| record.is_ok()));
+        assert!(error
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("Stopped reading records"));
     }
 }
@@ -0,0 +1,577 @@
+//! # Rebuild State Use Case
+//!
+//! 送信先からのアップロード状態の再構築ユースケース
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::application::dto::upload_config::UploadConfig;
+use crate::application::use_cases::parse_logs::convert_record_to_session_log;
+use crate::domain::entities::file_checkpoint::FileCheckpoint;
+use crate::domain::repositories::log_repository::{LogRepository, LogStreamItem};
+use crate::domain::repositories::remote_record_repository::RemoteRecordRepository;
+use crate::domain::repositories::state_repository::{
+    StateRepository, StateUpdate, UploadState, UploadedRecord,
+};
+
+/// 状態の再構築結果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RebuildSummary {
+    /// ローカルのトランスクリプトに含まれるレコード数
+    pub local_records: usize,
+    /// 送信先に存在したレコード数
+    pub remote_records: usize,
+    /// 状態に追加したレコード数
+    pub added_records: usize,
+    /// コミットしたチェックポイント数
+    pub checkpoints: usize,
+}
+
+/// ローカルのレコード（送信先との照合に使う）
+struct LocalRecord {
+    session_id: String,
+    source_file: String,
+    timestamp: DateTime<Utc>,
+}
+
+impl LocalRecord {
+    /// 状態に既にアップロード済みとして記録されているか（圧縮済みセッションの透かしを含む）
+    fn is_uploaded(&self, state: &UploadState, uuid: &str) -> bool {
+        state.is_record_uploaded(uuid, &self.session_id, &self.source_file, self.timestamp)
+    }
+}
+
+/// ローカルのログファイルの読み込み結果
+struct LocalFile {
+    identities: Vec<String>,
+    has_rejects: bool,
+    checkpoint: Option<FileCheckpoint>,
+}
+
+/// 状態再構築ユースケース
+///
+/// ローカルの状態が失われた場合に、送信先に既に存在するレコードを照会して
+/// アップロード済みUUIDとチェックポイントを復元する
+pub struct RebuildStateUseCase<
+    L: LogRepository,
+    R: RemoteRecordRepository + ?Sized,
+    S: StateRepository + ?Sized,
+> {
+    log_repository: Arc<L>,
+    remote_repository: Arc<R>,
+    state_repository: Arc<S>,
+}
+
+impl<L: LogRepository, R: RemoteRecordRepository + ?Sized, S: StateRepository + ?Sized>
+    RebuildStateUseCase<L, R, S>
+{
+    /// 新しいユースケースを作成
+    ///
+    /// # Arguments
+    ///
+    /// * `log_repository` - ログリポジトリ
+    /// * `remote_repository` - 送信先のレコードを照会するリポジトリ
+    /// * `state_repository` - 状態リポジトリ
+    pub fn new(
+        log_repository: Arc<L>,
+        remote_repository: Arc<R>,
+        state_repository: Arc<S>,
+    ) -> Self {
+        Self {
+            log_repository,
+            remote_repository,
+            state_repository,
+        }
+    }
+
+    /// 送信先に存在するレコードから状態を再構築します。
+    ///
+    /// ログファイルを先頭から読み、レコードのタイムスタンプの範囲で送信先を照会します。
+    /// 送信先に存在するレコードをアップロード済みとして状態に加え、
+    /// 全レコードが送信済みでパースできない行もないファイルはチェックポイントもコミットします。
+    /// 既存の状態とはマージされるため、状態から何かが消えることはありません。
+    ///
+    /// # 引数
+    ///
+    /// * `file_paths` - ログファイルのパスのリスト
+    /// * `config` - アップロード設定（ローカルのレコードの変換に使用）
+    /// * `state_path` - 状態ファイルのパス
+    /// * `dry_run` - `true` の場合は状態を書き換えずに結果だけを返す
+    ///
+    /// # 戻り値
+    ///
+    /// 照合したレコード数と状態に追加したレコード数
+    ///
+    /// # エラー
+    ///
+    /// ログファイルの読み込み、送信先の照会、状態の読み書きに失敗した場合にエラーを返します。
+    ///
+    /// # 例
+    ///
+    /// ```no_run
+    /// use sessync::application::use_cases::rebuild_state::RebuildStateUseCase;
+    /// use sessync::application::dto::upload_config::UploadConfig;
+    /// use sessync::adapter::bigquery::client::RealClientFactory;
+    /// use sessync::adapter::config::Config;
+    /// use sessync::adapter::repositories::bigquery_remote_record_repository::BigQueryRemoteRecordRepository;
+    /// use sessync::adapter::repositories::file_log_repository::FileLogRepository;
+    /// use sessync::adapter::repositories::json_state_repository::JsonStateRepository;
+    /// use std::path::PathBuf;
+    /// use std::sync::Arc;
+    ///
+    /// # async fn example(config: Config, upload_config: UploadConfig) -> anyhow::Result<()> {
+    /// let factory = Arc::new(RealClientFactory::new(config.service_account_key_path.clone()));
+    /// let use_case = RebuildStateUseCase::new(
+    ///     Arc::new(FileLogRepository::new()),
+    ///     Arc::new(BigQueryRemoteRecordRepository::new(factory, config)),
+    ///     Arc::new(JsonStateRepository),
+    /// );
+    ///
+    /// let files = vec![PathBuf::from("/logs/session1.jsonl")];
+    /// let summary = use_case
+    ///     .execute(&files, &upload_config, "./.claude/sessync/upload-state.json", false)
+    ///     .await?;
+    /// println!("{}個のレコードを復元", summary.added_records);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute(
+        &self,
+        file_paths: &[impl AsRef<Path>],
+        config: &UploadConfig,
+        state_path: &str,
+        dry_run: bool,
+    ) -> Result<RebuildSummary> {
+        let batch_id = format!("rebuild-{}", uuid::Uuid::new_v4());
+        let mut records: HashMap<String, LocalRecord> = HashMap::new();
+        let mut files: BTreeMap<String, LocalFile> = BTreeMap::new();
+        let mut window: Option<(DateTime<Utc>, DateTime<Utc>)> = None;
+        let mut summary = RebuildSummary::default();
+
+        // チェックポイントを使わずに全体を読み、ローカルのレコードと期間を集める
+        for file_path in file_paths {
+            let file_path = file_path.as_ref().to_path_buf();
+            let source_file = file_path.to_string_lossy().to_string();
+            let file = self
+                .read_local_file(file_path, config, &batch_id, &mut records, &mut window)
+                .await?;
+            summary.local_records += file.identities.len();
+            files.insert(source_file, file);
+        }
+
+        let Some((since, until)) = window else {
+            return Ok(summary);
+        };
+
+        let mut state = self.state_repository.load(state_path).await?;
+
+        // 送信先に存在するローカルのレコードをセッションごとにまとめる
+        let mut sessions: BTreeMap<String, StateUpdate> = BTreeMap::new();
+        let mut found = HashSet::new();
+        for remote in self.remote_repository.find_records(since, until).await? {
+            let Some(local) = records.get(&remote.uuid) else {
+                continue;
+            };
+            if !found.insert(remote.uuid.clone()) {
+                continue;
+            }
+            summary.remote_records += 1;
+            if local.is_uploaded(&state, &remote.uuid) {
+                continue;
+            }
+
+            let update = sessions
+                .entry(local.session_id.clone())
+                .or_insert_with(|| StateUpdate {
+                    batch_id: batch_id.clone(),
+                    timestamp: remote.uploaded_at,
+                    ..Default::default()
+                });
+            update.timestamp = update.timestamp.max(remote.uploaded_at);
+            update.uploaded.push(UploadedRecord {
+                uuid: remote.uuid,
+                session_id: local.session_id.clone(),
+                source_file: local.source_file.clone(),
+                timestamp: remote.timestamp,
+                content_hash: None,
+                record_version: 0,
+            });
+        }
+
+        for update in sessions.values() {
+            summary.added_records += update.uploaded.len();
+            update.apply(&mut state);
+        }
+
+        // 全レコードが送信済みのファイルは、次回以降読み飛ばせるようにする
+        let mut checkpoints = HashMap::new();
+        for (source_file, file) in files {
+            let Some(checkpoint) = file.checkpoint else {
+                continue;
+            };
+            let complete = !file.has_rejects
+                && file.identities.iter().all(|identity| {
+                    found.contains(identity) || records[identity].is_uploaded(&state, identity)
+                });
+            if complete && state.checkpoint(&source_file) != Some(&checkpoint) {
+                checkpoints.insert(source_file, checkpoint);
+            }
+        }
+        summary.checkpoints = checkpoints.len();
+        state.update_checkpoints(checkpoints);
+
+        if !dry_run && (summary.added_records > 0 || summary.checkpoints > 0) {
+            self.state_repository.save(state_path, &state).await?;
+        }
+
+        Ok(summary)
+    }
+
+    /// ログファイルを先頭から読み、レコードのIDと期間を集める
+    async fn read_local_file(
+        &self,
+        file_path: PathBuf,
+        config: &UploadConfig,
+        batch_id: &str,
+        records: &mut HashMap<String, LocalRecord>,
+        window: &mut Option<(DateTime<Utc>, DateTime<Utc>)>,
+    ) -> Result<LocalFile> {
+        let source_file = file_path.to_string_lossy().to_string();
+        let mut file = LocalFile {
+            identities: Vec::new(),
+            has_rejects: false,
+            checkpoint: None,
+        };
+
+        let mut stream = self.log_repository.stream_log_file(file_path.clone(), None);
+        while let Some(item) = stream.next().await {
+            match item? {
+                LogStreamItem::Record(record) => {
+                    let log = convert_record_to_session_log(*record, &file_path, config, batch_id)?;
+                    *window = Some(match *window {
+                        Some((since, until)) => {
+                            (since.min(log.timestamp), until.max(log.timestamp))
+                        }
+                        None => (log.timestamp, log.timestamp),
+                    });
+                    file.identities.push(log.uuid.clone());
+                    records.insert(
+                        log.uuid,
+                        LocalRecord {
+                            session_id: log.session_id,
+                            source_file: source_file.clone(),
+                            timestamp: log.timestamp,
+                        },
+                    );
+                }
+                LogStreamItem::Rejected(_) => file.has_rejects = true,
+                LogStreamItem::Checkpoint(checkpoint) => file.checkpoint = Some(checkpoint),
+            }
+        }
+
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::rejected_line::RejectedLine;
+    use crate::domain::entities::session_log::SessionLogInput;
+    use crate::domain::entities::transcript_record::TranscriptRecord;
+    use crate::domain::repositories::log_repository::LogStream;
+    use crate::domain::repositories::remote_record_repository::RemoteRecord;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use futures::stream;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// (uuid, 何時のレコードか) のリストと、パースできない行を持つか
+    type MockFile = (Vec<(&'static str, u32)>, bool);
+
+    struct MockLogRepository {
+        files: HashMap<String, MockFile>,
+    }
+
+    #[async_trait]
+    impl LogRepository for MockLogRepository {
+        async fn discover_log_files(&self, _log_dir: &str) -> Result<Vec<PathBuf>> {
+            Ok(vec![])
+        }
+
+        fn stream_log_file(
+            &self,
+            file_path: PathBuf,
+            _checkpoint: Option<FileCheckpoint>,
+        ) -> LogStream<'_> {
+            let source_file = file_path.to_string_lossy().to_string();
+            let (records, has_rejects) = self.files[&source_file].clone();
+            let mut items: Vec<Result<LogStreamItem>> = records
+                .into_iter()
+                .map(|(uuid, hour)| Ok(LogStreamItem::Record(Box::new(create_record(uuid, hour)))))
+                .collect();
+            if has_rejects {
+                items.push(Ok(LogStreamItem::Rejected(Box::new(RejectedLine {
+                    source_file: source_file.clone(),
+                    line_number: 99,
+                    raw: "{".to_string(),
+                    error: "EOF".to_string(),
+                    rejected_at: Utc::now(),
+                }))));
+            }
+            items.push(Ok(LogStreamItem::Checkpoint(FileCheckpoint::new(
+                None, 100, None, 100, 10,
+            ))));
+            stream::iter(items).boxed()
+        }
+    }
+
+    struct MockRemoteRecordRepository {
+        records: Vec<RemoteRecord>,
+        window: Mutex<Option<(DateTime<Utc>, DateTime<Utc>)>>,
+    }
+
+    #[async_trait]
+    impl RemoteRecordRepository for MockRemoteRecordRepository {
+        async fn find_records(
+            &self,
+            since: DateTime<Utc>,
+            until: DateTime<Utc>,
+        ) -> Result<Vec<RemoteRecord>> {
+            *self.window.lock().unwrap() = Some((since, until));
+            Ok(self.records.clone())
+        }
+    }
+
+    struct MockStateRepository {
+        state: Mutex<UploadState>,
+        saved: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl StateRepository for MockStateRepository {
+        async fn load(&self, _path: &str) -> Result<UploadState> {
+            Ok(self.state.lock().unwrap().clone())
+        }
+
+        async fn save(&self, _path: &str, state: &UploadState) -> Result<()> {
+            *self.state.lock().unwrap() = state.clone();
+            *self.saved.lock().unwrap() = true;
+            Ok(())
+        }
+
+        async fn replace(&self, _path: &str, _state: &UploadState) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn time(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    fn create_record(uuid: &str, hour: u32) -> TranscriptRecord {
+        TranscriptRecord::Message(SessionLogInput {
+            uuid: uuid.to_string(),
+            timestamp: time(hour),
+            session_id: "session-1".to_string(),
+            agent_id: None,
+            is_sidechain: None,
+            parent_uuid: None,
+            user_type: None,
+            message_type: "user".to_string(),
+            slug: None,
+            request_id: None,
+            cwd: None,
+            git_branch: None,
+            version: None,
+            message: json!({}),
+            tool_use_result: None,
+            extra: serde_json::Map::new(),
+        })
+    }
+
+    fn remote(uuid: &str, hour: u32) -> RemoteRecord {
+        RemoteRecord {
+            uuid: uuid.to_string(),
+            session_id: "session-1".to_string(),
+            source_file: "/other-host/a.jsonl".to_string(),
+            timestamp: time(hour),
+            uploaded_at: time(20),
+        }
+    }
+
+    fn create_config() -> UploadConfig {
+        UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        )
+    }
+
+    type TestUseCase =
+        RebuildStateUseCase<MockLogRepository, MockRemoteRecordRepository, MockStateRepository>;
+
+    fn create_use_case(
+        remote_records: Vec<RemoteRecord>,
+        state: UploadState,
+    ) -> (
+        TestUseCase,
+        Arc<MockRemoteRecordRepository>,
+        Arc<MockStateRepository>,
+    ) {
+        let log_repo = Arc::new(MockLogRepository {
+            files: HashMap::from([
+                (
+                    "/logs/a.jsonl".to_string(),
+                    (vec![("uuid-1", 1), ("uuid-2", 2)], false),
+                ),
+                (
+                    "/logs/b.jsonl".to_string(),
+                    (vec![("uuid-3", 3), ("uuid-4", 4)], false),
+                ),
+                ("/logs/c.jsonl".to_string(), (vec![("uuid-5", 5)], true)),
+            ]),
+        });
+        let remote_repo = Arc::new(MockRemoteRecordRepository {
+            records: remote_records,
+            window: Mutex::new(None),
+        });
+        let state_repo = Arc::new(MockStateRepository {
+            state: Mutex::new(state),
+            saved: Mutex::new(false),
+        });
+        (
+            RebuildStateUseCase::new(log_repo, remote_repo.clone(), state_repo.clone()),
+            remote_repo,
+            state_repo,
+        )
+    }
+
+    fn files() -> Vec<PathBuf> {
+        ["/logs/a.jsonl", "/logs/b.jsonl", "/logs/c.jsonl"]
+            .iter()
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_rebuilds_uploaded_uuids_and_checkpoints() {
+        let (use_case, remote_repo, state_repo) = create_use_case(
+            vec![
+                remote("uuid-1", 1),
+                remote("uuid-2", 2),
+                remote("uuid-3", 3),
+                remote("uuid-5", 5),
+                // 同じレコードが重複して返っても1件として数える
+                remote("uuid-1", 1),
+                // ローカルにないレコードは無視する
+                remote("uuid-other", 2),
+            ],
+            UploadState::new(),
+        );
+
+        let summary = use_case
+            .execute(&files(), &create_config(), "state.json", false)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            RebuildSummary {
+                local_records: 5,
+                remote_records: 4,
+                added_records: 4,
+                checkpoints: 1,
+            }
+        );
+        assert_eq!(
+            *remote_repo.window.lock().unwrap(),
+            Some((time(1), time(5)))
+        );
+        assert!(*state_repo.saved.lock().unwrap());
+
+        let state = state_repo.state.lock().unwrap().clone();
+        assert!(state.is_uploaded("uuid-1"));
+        assert!(!state.is_uploaded("uuid-4"));
+        assert!(!state.is_uploaded("uuid-other"));
+        assert_eq!(state.total_uploaded, 4);
+        // 全レコードが送信済みのファイルだけチェックポイントをコミットする
+        assert!(state.checkpoint("/logs/a.jsonl").is_some());
+        assert!(state.checkpoint("/logs/b.jsonl").is_none());
+        // パースできない行があるファイルは隔離されるまで読み直す
+        assert!(state.checkpoint("/logs/c.jsonl").is_none());
+
+        let session = &state.sessions["session-1"];
+        assert_eq!(session.last_uploaded_at, time(20));
+        assert_eq!(session.latest_record_at, Some(time(5)));
+        assert!(session.source_files.contains("/logs/a.jsonl"));
+    }
+
+    #[tokio::test]
+    async fn test_keeps_records_already_in_state() {
+        let mut state = UploadState::new();
+        state.uploaded_uuids.insert("uuid-4".to_string());
+        let (use_case, _, state_repo) =
+            create_use_case(vec![remote("uuid-3", 3), remote("uuid-4", 4)], state);
+
+        let summary = use_case
+            .execute(&files(), &create_config(), "state.json", false)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.remote_records, 2);
+        assert_eq!(summary.added_records, 1);
+        assert_eq!(summary.checkpoints, 1);
+
+        let state = state_repo.state.lock().unwrap().clone();
+        assert!(state.is_uploaded("uuid-3"));
+        assert!(state.is_uploaded("uuid-4"));
+        assert!(state.checkpoint("/logs/b.jsonl").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_does_not_write() {
+        let (use_case, _, state_repo) =
+            create_use_case(vec![remote("uuid-1", 1)], UploadState::new());
+
+        let summary = use_case
+            .execute(&files(), &create_config(), "state.json", true)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.added_records, 1);
+        assert!(!*state_repo.saved.lock().unwrap());
+        assert!(!state_repo.state.lock().unwrap().is_uploaded("uuid-1"));
+    }
+
+    #[tokio::test]
+    async fn test_no_local_records_skips_query() {
+        let (use_case, remote_repo, state_repo) =
+            create_use_case(vec![remote("uuid-1", 1)], UploadState::new());
+
+        let summary = use_case
+            .execute(
+                &Vec::<PathBuf>::new(),
+                &create_config(),
+                "state.json",
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(summary, RebuildSummary::default());
+        assert!(remote_repo.window.lock().unwrap().is_none());
+        assert!(!*state_repo.saved.lock().unwrap());
+    }
+}
@@ -4,12 +4,20 @@
 
 use anyhow::Result;
 use chrono::Utc;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use crate::application::dto::parsed_logs::{ParsedLogs, ParsedRecord};
 use crate::application::dto::upload_config::UploadConfig;
+use crate::domain::entities::file_checkpoint::FileCheckpoint;
+use crate::domain::entities::journal_entry::JournalEntry;
 use crate::domain::entities::session_log::SessionLog;
 use crate::domain::entities::upload_batch::UploadBatch;
-use crate::domain::repositories::state_repository::StateRepository;
+use crate::domain::repositories::journal_repository::JournalRepository;
+use crate::domain::repositories::state_repository::{
+    StateRepository, StateUpdate, UploadState, UploadedRecord,
+};
 use crate::domain::repositories::upload_repository::UploadRepository;
 
 /// アップロード結果のサマリー
@@ -19,19 +27,41 @@ pub struct UploadSummary {
     pub uploaded_count: usize,
     /// 失敗したログの数
     pub failed_count: usize,
-    /// アップロードされたUUID
+    /// アップロードされたUUID（`execute` のみ。`execute_stream` では件数だけを集計し空）
     pub uploaded_uuids: Vec<String>,
+    /// パースできなかった（隔離された）行の数
+    pub rejected_count: usize,
+    /// アップロード済みだが内容が変わっていた（報告のみの）ログの数
+    pub changed_count: usize,
+}
+
+/// ジャーナルからの復旧結果のサマリー
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecoverySummary {
+    /// 状態に反映されていなかったバッチの数
+    pub batches: usize,
+    /// 状態に記録したログの数（再送に成功したものを含む）
+    pub recorded_count: usize,
+    /// 挿入されたか分からず再送したログの数
+    pub resent_count: usize,
 }
 
 /// ログアップロードユースケース
 ///
 /// セッションログをBigQueryにアップロードし、状態を更新する
-pub struct UploadLogsUseCase<U: UploadRepository, S: StateRepository> {
+pub struct UploadLogsUseCase<U: UploadRepository + ?Sized, S: StateRepository + ?Sized> {
     upload_repository: Arc<U>,
     state_repository: Arc<S>,
+    journal: Option<Journal>,
 }
 
-impl<U: UploadRepository, S: StateRepository> UploadLogsUseCase<U, S> {
+/// アップロードジャーナルの保存先
+struct Journal {
+    repository: Arc<dyn JournalRepository>,
+    path: String,
+}
+
+impl<U: UploadRepository + ?Sized, S: StateRepository + ?Sized> UploadLogsUseCase<U, S> {
     /// 新しいユースケースを作成
     ///
     /// # Arguments
@@ -42,14 +72,155 @@ impl<U: UploadRepository, S: StateRepository> UploadLogsUseCase<U, S> {
         Self {
             upload_repository,
             state_repository,
+            journal: None,
+        }
+    }
+
+    /// アップロードジャーナルを設定します。
+    ///
+    /// 各バッチを送信前に「送信中」、挿入成功後に「送信済み」としてジャーナルに記録するため、
+    /// 実行が途中で強制終了されても、送信済みのバッチは次回の [`recover`](Self::recover) で状態に反映されます。
+    ///
+    /// # 引数
+    ///
+    /// * `journal_repository` - ジャーナルリポジトリ
+    /// * `journal_path` - ジャーナルのパス
+    ///
+    /// # 例
+    ///
+    /// ```no_run
+    /// use sessync::application::use_cases::upload_logs::UploadLogsUseCase;
+    /// use sessync::adapter::repositories::bigquery_upload_repository::BigQueryUploadRepository;
+    /// use sessync::adapter::repositories::json_state_repository::JsonStateRepository;
+    /// use sessync::adapter::repositories::jsonl_journal_repository::JsonlJournalRepository;
+    /// use std::sync::Arc;
+    ///
+    /// # let client_factory = todo!(); // BigQuery client factory
+    /// # let bigquery_config = todo!(); // BigQuery config
+    /// let upload_repo = Arc::new(BigQueryUploadRepository::new(client_factory, bigquery_config));
+    /// let use_case = UploadLogsUseCase::new(upload_repo, Arc::new(JsonStateRepository))
+    ///     .with_journal(
+    ///         Arc::new(JsonlJournalRepository::new()),
+    ///         "./.claude/sessync/upload-journal.jsonl",
+    ///     );
+    /// ```
+    pub fn with_journal(
+        mut self,
+        journal_repository: Arc<dyn JournalRepository>,
+        journal_path: &str,
+    ) -> Self {
+        self.journal = Some(Journal {
+            repository: journal_repository,
+            path: journal_path.to_string(),
+        });
+        self
+    }
+
+    /// 前回の実行で状態に反映されなかったバッチをジャーナルから復旧します。
+    ///
+    /// 「送信済み」のバッチはそのまま状態に記録し、「送信中」のまま終わったバッチは
+    /// 同じ insert ID で再送してから記録します（BigQuery 側の重複排除で二重挿入を防ぐ）。
+    /// 復旧したログが再度アップロードされないよう、ログのパースより前に呼び出してください。
+    /// ジャーナルが設定されていない場合は何もしません。
+    ///
+    /// # 引数
+    ///
+    /// * `state_path` - 状態ファイルのパス
+    /// * `batch_id` - 状態に記録するアップロードバッチID
+    ///
+    /// # 戻り値
+    ///
+    /// 復旧結果のサマリー
+    ///
+    /// # エラー
+    ///
+    /// ジャーナルの読み込み、再送または状態の保存に失敗した場合にエラーを返します。
+    /// その場合ジャーナルは残り、次回の実行で再び復旧を試みます。
+    pub async fn recover(&self, state_path: &str, batch_id: &str) -> Result<RecoverySummary> {
+        let Some(journal) = &self.journal else {
+            return Ok(RecoverySummary::default());
+        };
+        let entries = journal.repository.load(&journal.path).await?;
+        if entries.is_empty() {
+            return Ok(RecoverySummary::default());
+        }
+
+        let mut in_flight = Vec::new();
+        let mut committed: HashMap<String, HashSet<String>> = HashMap::new();
+        for entry in entries {
+            match entry {
+                JournalEntry::InFlight { batch_key, logs } => in_flight.push((batch_key, logs)),
+                JournalEntry::Committed {
+                    batch_key,
+                    uploaded_uuids,
+                } => committed
+                    .entry(batch_key)
+                    .or_default()
+                    .extend(uploaded_uuids),
+            }
+        }
+
+        let mut state = self.state_repository.load_for_upload(state_path).await?;
+        if state.records_omitted {
+            let ids: Vec<String> = in_flight
+                .iter()
+                .flat_map(|(_, logs)| logs.iter().map(|log| log.uuid.clone()))
+                .collect();
+            let records = self.state_repository.find_records(state_path, &ids).await?;
+            state.replace_records(records);
+        }
+        let mut summary = RecoverySummary::default();
+        let mut uploaded = Vec::new();
+        for (batch_key, logs) in in_flight {
+            // 状態の保存まで終わっていたログは対象外
+            let logs: Vec<SessionLog> = logs
+                .into_iter()
+                .filter(|log| !is_recorded(&state, log))
+                .collect();
+            if logs.is_empty() {
+                continue;
+            }
+            summary.batches += 1;
+
+            let uploaded_uuids = match committed.remove(&batch_key) {
+                Some(uuids) => uuids,
+                None => {
+                    summary.resent_count += logs.len();
+                    let batch = UploadBatch::new(logs.clone());
+                    let result = self.upload_repository.upload_batch(&batch).await?;
+                    result.uploaded_uuids.into_iter().collect()
+                }
+            };
+            uploaded.extend(
+                logs.iter()
+                    .filter(|log| uploaded_uuids.contains(&log.uuid))
+                    .map(uploaded_record),
+            );
         }
+        summary.recorded_count = uploaded.len();
+
+        if !uploaded.is_empty() {
+            let update = StateUpdate {
+                batch_id: batch_id.to_string(),
+                timestamp: Utc::now(),
+                uploaded,
+                ..Default::default()
+            };
+            self.state_repository.commit(state_path, &update).await?;
+        }
+        journal.repository.clear(&journal.path).await?;
+
+        Ok(summary)
     }
 
     /// ログをBigQueryにアップロードします。
     ///
+    /// バッチサイズごとに分割して送信します。ファイルのチェックポイントは、そのファイル由来のログが全てアップロードされた
+    /// 場合にのみコミットされます。
+    ///
     /// # 引数
     ///
-    /// * `logs` - アップロードするセッションログ
+    /// * `parsed` - アップロードするセッションログとコミット待ちのチェックポイント
     /// * `config` - アップロード設定
     /// * `state_path` - 状態ファイルのパス
     /// * `batch_id` - アップロードバッチID
@@ -90,9 +261,9 @@ impl<U: UploadRepository, S: StateRepository> UploadLogsUseCase<U, S> {
     ///     "app".to_string(),
     /// );
     ///
-    /// # let logs = vec![]; // セッションログ
+    /// # let parsed = Default::default(); // パース結果
     /// let summary = use_case.execute(
-    ///     logs,
+    ///     parsed,
     ///     &config,
     ///     "/state/upload.json",
     ///     "batch-001"
@@ -106,53 +277,236 @@ impl<U: UploadRepository, S: StateRepository> UploadLogsUseCase<U, S> {
     /// ```
     pub async fn execute(
         &self,
-        logs: Vec<SessionLog>,
+        parsed: ParsedLogs,
         config: &UploadConfig,
         state_path: &str,
         batch_id: &str,
     ) -> Result<UploadSummary> {
-        if logs.is_empty() {
-            return Ok(UploadSummary {
-                uploaded_count: 0,
-                failed_count: 0,
-                uploaded_uuids: vec![],
-            });
+        let records: Vec<ParsedRecord> = parsed.into();
+        // 入力が既にメモリ上にあるので、呼び出し元に返すUUIDも集める
+        self.upload_stream(
+            stream::iter(records.into_iter().map(Ok)).boxed(),
+            config,
+            state_path,
+            batch_id,
+            true,
+        )
+        .await
+    }
+
+    /// パース結果のストリームを消費しながらアップロードします。
+    ///
+    /// バッチサイズ分のログが溜まるたびに送信し、送信できたバッチはその都度状態に保存するため、
+    /// メモリ使用量はコーパス全体ではなくバッチサイズで抑えられます。
+    /// ストリームやアップロードが途中で失敗した場合も、それまでに成功した
+    /// バッチの状態は保存済みです。
+    /// サマリーの `uploaded_uuids` は集めません（件数のみ）。
+    /// ジャーナルが設定されている場合は、先に [`recover`](Self::recover) で前回の未完了分を復旧します。
+    ///
+    /// # 引数
+    ///
+    /// * `records` - `ParseLogsUseCase::stream` が返すパース結果のストリーム
+    /// * `config` - アップロード設定
+    /// * `state_path` - 状態ファイルのパス
+    /// * `batch_id` - アップロードバッチID
+    ///
+    /// # 戻り値
+    ///
+    /// アップロード結果のサマリー
+    ///
+    /// # エラー
+    ///
+    /// パース、アップロードまたは状態の保存に失敗した場合にエラーを返します。
+    pub async fn execute_stream(
+        &self,
+        records: BoxStream<'_, Result<ParsedRecord>>,
+        config: &UploadConfig,
+        state_path: &str,
+        batch_id: &str,
+    ) -> Result<UploadSummary> {
+        self.upload_stream(records, config, state_path, batch_id, false)
+            .await
+    }
+
+    /// ストリームを消費してバッチごとに送信・保存する（`collect_uuids` でUUIDを集めるか選ぶ）
+    async fn upload_stream(
+        &self,
+        mut records: BoxStream<'_, Result<ParsedRecord>>,
+        config: &UploadConfig,
+        state_path: &str,
+        batch_id: &str,
+        collect_uuids: bool,
+    ) -> Result<UploadSummary> {
+        self.recover(state_path, batch_id).await?;
+
+        let mut progress = UploadProgress {
+            collect_uuids,
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+
+        while let Some(record) = records.next().await {
+            match record? {
+                ParsedRecord::Log(log) => {
+                    buffer.push(*log);
+                    if config.batch_size > 0 && buffer.len() >= config.batch_size {
+                        self.flush(&mut buffer, &mut progress, state_path, batch_id)
+                            .await?;
+                    }
+                }
+                ParsedRecord::Changed(_) => {
+                    progress.changed_count += 1;
+                }
+                ParsedRecord::Rejected(_) => {
+                    progress.rejected_count += 1;
+                }
+                ParsedRecord::FileCompleted {
+                    source_file,
+                    checkpoint,
+                } => {
+                    progress.pending_checkpoints.push((source_file, checkpoint));
+                }
+            }
         }
 
-        // バッチサイズで分割
-        let batch = UploadBatch::new(logs);
-        let batches = batch.split_by_size(config.batch_size);
+        // 残りのログを送信し、保留中のチェックポイントを確定
+        self.flush(&mut buffer, &mut progress, state_path, batch_id)
+            .await?;
 
-        // 全バッチをアップロード
-        let mut total_uploaded = 0;
-        let mut total_failed = 0;
-        let mut all_uploaded_uuids = Vec::new();
+        Ok(UploadSummary {
+            uploaded_count: progress.uploaded_count,
+            failed_count: progress.failed_count,
+            uploaded_uuids: progress.uploaded_uuids,
+            rejected_count: progress.rejected_count,
+            changed_count: progress.changed_count,
+        })
+    }
+
+    /// バッファ内のログを送信し、送信できたログと読み終えたファイルのチェックポイントを状態に保存する
+    ///
+    /// 保存後はジャーナルの内容が全て状態に反映されているため、ジャーナルを空にする。
+    async fn flush(
+        &self,
+        buffer: &mut Vec<SessionLog>,
+        progress: &mut UploadProgress,
+        state_path: &str,
+        batch_id: &str,
+    ) -> Result<()> {
+        let mut update = StateUpdate {
+            batch_id: batch_id.to_string(),
+            timestamp: Utc::now(),
+            ..Default::default()
+        };
 
-        for batch in batches {
+        if !buffer.is_empty() {
+            let batch = UploadBatch::new(std::mem::take(buffer));
+            let batch_key = self.journal_in_flight(&batch).await?;
             let result = self.upload_repository.upload_batch(&batch).await?;
+            self.journal_committed(batch_key, &result.uploaded_uuids)
+                .await?;
+
+            // 1件でも失敗したファイルのチェックポイントは進めない（次回再読込）
+            let uploaded: HashSet<&str> =
+                result.uploaded_uuids.iter().map(String::as_str).collect();
+            for log in batch.logs() {
+                if uploaded.contains(log.uuid.as_str()) {
+                    update.uploaded.push(uploaded_record(log));
+                } else {
+                    progress
+                        .failed_files
+                        .insert(log.metadata.source_file.clone());
+                }
+            }
 
-            total_uploaded += result.uploaded_count;
-            total_failed += result.failed_count;
-            all_uploaded_uuids.extend(result.uploaded_uuids);
+            progress.uploaded_count += result.uploaded_count;
+            progress.failed_count += result.failed_count;
+            if progress.collect_uuids {
+                progress.uploaded_uuids.extend(result.uploaded_uuids);
+            }
         }
 
-        // 状態を更新して保存
-        if !all_uploaded_uuids.is_empty() {
-            let mut state = self.state_repository.load(state_path).await?;
-            let timestamp = Utc::now().to_rfc3339();
+        // 読み終えたファイルのログは全て送信済みなのでコミットできる
+        for (source_file, checkpoint) in progress.pending_checkpoints.drain(..) {
+            if !progress.failed_files.contains(&source_file) {
+                update.checkpoints.insert(source_file, checkpoint);
+            }
+        }
 
-            state.add_uploaded(all_uploaded_uuids.clone(), batch_id.to_string(), timestamp);
-            state.total_uploaded += total_uploaded as u64;
+        if update.is_empty() {
+            return Ok(());
+        }
+        self.state_repository.commit(state_path, &update).await?;
 
-            self.state_repository.save(state_path, &state).await?;
+        // 送信済みのバッチは全て状態に反映されたのでジャーナルは不要
+        if let Some(journal) = &self.journal {
+            journal.repository.clear(&journal.path).await?;
         }
 
-        Ok(UploadSummary {
-            uploaded_count: total_uploaded,
-            failed_count: total_failed,
-            uploaded_uuids: all_uploaded_uuids,
-        })
+        Ok(())
     }
+
+    /// 送信前にバッチを「送信中」としてジャーナルに記録し、そのIDを返す
+    async fn journal_in_flight(&self, batch: &UploadBatch) -> Result<Option<String>> {
+        let Some(journal) = &self.journal else {
+            return Ok(None);
+        };
+        let batch_key = uuid::Uuid::new_v4().to_string();
+        let entry = JournalEntry::InFlight {
+            batch_key: batch_key.clone(),
+            logs: batch.logs().to_vec(),
+        };
+        journal.repository.append(&journal.path, &entry).await?;
+        Ok(Some(batch_key))
+    }
+
+    /// 挿入に成功したバッチを「送信済み」としてジャーナルに記録する
+    async fn journal_committed(
+        &self,
+        batch_key: Option<String>,
+        uploaded_uuids: &[String],
+    ) -> Result<()> {
+        let (Some(journal), Some(batch_key)) = (&self.journal, batch_key) else {
+            return Ok(());
+        };
+        let entry = JournalEntry::Committed {
+            batch_key,
+            uploaded_uuids: uploaded_uuids.to_vec(),
+        };
+        journal.repository.append(&journal.path, &entry).await
+    }
+}
+
+/// アップロードしたログを状態に記録するレコードに変換
+fn uploaded_record(log: &SessionLog) -> UploadedRecord {
+    UploadedRecord {
+        uuid: log.uuid.clone(),
+        session_id: log.session_id.clone(),
+        source_file: log.metadata.source_file.clone(),
+        timestamp: log.timestamp,
+        content_hash: Some(log.content_hash()),
+        record_version: log.record_version,
+    }
+}
+
+/// ログ（のこのバージョン）が既に状態に記録されているかどうか
+fn is_recorded(state: &UploadState, log: &SessionLog) -> bool {
+    state.is_uploaded(&log.uuid) && state.record_version(&log.uuid) >= log.record_version
+}
+
+/// ストリームアップロードの進捗
+#[derive(Default)]
+struct UploadProgress {
+    uploaded_count: usize,
+    failed_count: usize,
+    /// `uploaded_uuids` を集めるかどうか
+    collect_uuids: bool,
+    uploaded_uuids: Vec<String>,
+    rejected_count: usize,
+    changed_count: usize,
+    /// 読み終えたが、ログの送信がまだ完了していないファイル
+    pending_checkpoints: Vec<(String, FileCheckpoint)>,
+    /// 送信に失敗したログを含むファイル
+    failed_files: HashSet<String>,
 }
 
 #[cfg(test)]
@@ -162,6 +516,7 @@ mod tests {
     use chrono::TimeZone;
     use serde_json::json;
 
+    use crate::domain::entities::rejected_line::RejectedLine;
     use crate::domain::entities::session_log::LogMetadata;
     use crate::domain::repositories::state_repository::UploadState;
     use crate::domain::repositories::upload_repository::UploadResult;
@@ -209,6 +564,11 @@ mod tests {
             *self.state.lock().unwrap() = state.clone();
             Ok(())
         }
+
+        async fn replace(&self, _path: &str, state: &UploadState) -> Result<()> {
+            *self.state.lock().unwrap() = state.clone();
+            Ok(())
+        }
     }
 
     fn create_test_log(uuid: &str) -> SessionLog {
@@ -242,6 +602,9 @@ mod tests {
             version: None,
             message: json!({}),
             tool_use_result: None,
+            extra: None,
+            lineage: None,
+            record_version: 0,
             metadata,
         }
     }
@@ -274,7 +637,7 @@ mod tests {
         );
 
         let result = use_case
-            .execute(logs, &config, "/path/to/state.json", "batch-001")
+            .execute(logs.into(), &config, "/path/to/state.json", "batch-001")
             .await;
 
         assert!(result.is_ok());
@@ -311,7 +674,12 @@ mod tests {
         );
 
         let result = use_case
-            .execute(vec![], &config, "/path/to/state.json", "batch-001")
+            .execute(
+                ParsedLogs::default(),
+                &config,
+                "/path/to/state.json",
+                "batch-001",
+            )
             .await;
 
         assert!(result.is_ok());
@@ -350,7 +718,7 @@ mod tests {
         );
 
         let result = use_case
-            .execute(logs, &config, "/path/to/state.json", "batch-001")
+            .execute(logs.into(), &config, "/path/to/state.json", "batch-001")
             .await;
 
         assert!(result.is_ok());
@@ -383,9 +751,491 @@ mod tests {
         );
 
         let result = use_case
-            .execute(logs, &config, "/path/to/state.json", "batch-001")
+            .execute(logs.into(), &config, "/path/to/state.json", "batch-001")
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    fn create_test_log_from(uuid: &str, source_file: &str) -> SessionLog {
+        let mut log = create_test_log(uuid);
+        log.metadata.source_file = source_file.to_string();
+        log
+    }
+
+    /// 指定したUUIDのみ失敗扱いにするモック
+    struct PartialUploadRepository {
+        failing_uuid: String,
+    }
+
+    #[async_trait]
+    impl UploadRepository for PartialUploadRepository {
+        async fn upload_batch(&self, batch: &UploadBatch) -> Result<UploadResult> {
+            let uuids: Vec<String> = DeduplicationService::extract_uuids(batch.logs())
+                .into_iter()
+                .filter(|uuid| *uuid != self.failing_uuid)
+                .collect();
+            Ok(UploadResult::new(
+                uuids.len(),
+                batch.len() - uuids.len(),
+                uuids,
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_logs_commits_checkpoints_only_for_fully_uploaded_files() {
+        let mock_upload_repo = Arc::new(PartialUploadRepository {
+            failing_uuid: "uuid-b2".to_string(),
+        });
+        let mock_state_repo = Arc::new(MockStateRepository::new());
+        let use_case = UploadLogsUseCase::new(mock_upload_repo, mock_state_repo.clone());
+
+        let logs = vec![
+            create_test_log_from("uuid-a1", "/logs/a.jsonl"),
+            create_test_log_from("uuid-b1", "/logs/b.jsonl"),
+            create_test_log_from("uuid-b2", "/logs/b.jsonl"),
+        ];
+        let checkpoints = std::collections::HashMap::from([
+            (
+                "/logs/a.jsonl".to_string(),
+                FileCheckpoint::new(None, 10, None, 10, 1),
+            ),
+            (
+                "/logs/b.jsonl".to_string(),
+                FileCheckpoint::new(None, 20, None, 20, 2),
+            ),
+            (
+                "/logs/c.jsonl".to_string(),
+                FileCheckpoint::new(None, 30, None, 30, 3),
+            ),
+        ]);
+
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        );
+
+        let summary = use_case
+            .execute(
+                ParsedLogs::new(logs, checkpoints),
+                &config,
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+        assert_eq!(summary.uploaded_count, 2);
+        assert_eq!(summary.failed_count, 1);
+
+        let state = mock_state_repo.get_state();
+        assert!(state.checkpoint("/logs/a.jsonl").is_some());
+        // 失敗したログを含むファイルはコミットされない
+        assert!(state.checkpoint("/logs/b.jsonl").is_none());
+        // 新規ログのないファイルもコミットされる
+        assert!(state.checkpoint("/logs/c.jsonl").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_upload_logs_commits_checkpoints_without_new_logs() {
+        let mock_upload_repo = Arc::new(MockUploadRepository {
+            should_succeed: false,
+        });
+        let mock_state_repo = Arc::new(MockStateRepository::new());
+        let use_case = UploadLogsUseCase::new(mock_upload_repo, mock_state_repo.clone());
+
+        let checkpoints = std::collections::HashMap::from([(
+            "/logs/a.jsonl".to_string(),
+            FileCheckpoint::new(None, 10, None, 10, 1),
+        )]);
+
+        let config = UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            100,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        );
+
+        let summary = use_case
+            .execute(
+                ParsedLogs::new(vec![], checkpoints),
+                &config,
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+        assert_eq!(summary.uploaded_count, 0);
+
+        let state = mock_state_repo.get_state();
+        assert!(state.checkpoint("/logs/a.jsonl").is_some());
+        assert!(state.last_upload_batch_id.is_none());
+    }
+
+    /// 受け取ったバッチのサイズを記録するモック
+    struct RecordingUploadRepository {
+        batch_sizes: std::sync::Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl UploadRepository for RecordingUploadRepository {
+        async fn upload_batch(&self, batch: &UploadBatch) -> Result<UploadResult> {
+            self.batch_sizes.lock().unwrap().push(batch.len());
+            let uuids = DeduplicationService::extract_uuids(batch.logs());
+            Ok(UploadResult::new(batch.len(), 0, uuids))
+        }
+    }
+
+    fn create_small_batch_config() -> UploadConfig {
+        UploadConfig::new(
+            "test-project".to_string(),
+            "test_dataset".to_string(),
+            "test_table".to_string(),
+            "US".to_string(),
+            2,
+            true,
+            "dev-001".to_string(),
+            "test@example.com".to_string(),
+            "test-project".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_execute_stream_sends_batches_as_they_fill() {
+        let upload_repo = Arc::new(RecordingUploadRepository {
+            batch_sizes: std::sync::Mutex::new(Vec::new()),
+        });
+        let mock_state_repo = Arc::new(MockStateRepository::new());
+        let use_case = UploadLogsUseCase::new(upload_repo.clone(), mock_state_repo.clone());
+
+        let records = vec![
+            Ok(ParsedRecord::Log(Box::new(create_test_log_from(
+                "uuid-1",
+                "/logs/a.jsonl",
+            )))),
+            Ok(ParsedRecord::Log(Box::new(create_test_log_from(
+                "uuid-2",
+                "/logs/a.jsonl",
+            )))),
+            Ok(ParsedRecord::Rejected(Box::new(RejectedLine::new(
+                "/logs/a.jsonl",
+                3,
+                "expected value",
+                "not json",
+            )))),
+            Ok(ParsedRecord::Log(Box::new(create_test_log_from(
+                "uuid-3",
+                "/logs/a.jsonl",
+            )))),
+            Ok(ParsedRecord::Changed(Box::new(create_test_log_from(
+                "uuid-0",
+                "/logs/a.jsonl",
+            )))),
+            Ok(ParsedRecord::FileCompleted {
+                source_file: "/logs/a.jsonl".to_string(),
+                checkpoint: FileCheckpoint::new(None, 30, None, 30, 4),
+            }),
+        ];
+
+        let summary = use_case
+            .execute_stream(
+                stream::iter(records).boxed(),
+                &create_small_batch_config(),
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(summary.uploaded_count, 3);
+        assert!(summary.uploaded_uuids.is_empty());
+        assert_eq!(summary.rejected_count, 1);
+        assert_eq!(summary.changed_count, 1);
+        assert_eq!(*upload_repo.batch_sizes.lock().unwrap(), vec![2, 1]);
+
+        let state = mock_state_repo.get_state();
+        assert_eq!(state.total_uploaded, 3);
+        assert!(state.checkpoint("/logs/a.jsonl").is_some());
+    }
+
+    /// 送信のたびに、その時点で状態に保存済みの件数を記録するアップロードリポジトリ
+    struct StateObservingUploadRepository {
+        state_repository: Arc<MockStateRepository>,
+        saved_before_upload: std::sync::Mutex<Vec<u64>>,
+    }
+
+    #[async_trait]
+    impl UploadRepository for StateObservingUploadRepository {
+        async fn upload_batch(&self, batch: &UploadBatch) -> Result<UploadResult> {
+            let saved = self.state_repository.get_state().total_uploaded;
+            self.saved_before_upload.lock().unwrap().push(saved);
+            let uuids = DeduplicationService::extract_uuids(batch.logs());
+            Ok(UploadResult::new(batch.len(), 0, uuids))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_stream_saves_each_batch_before_sending_the_next() {
+        let mock_state_repo = Arc::new(MockStateRepository::new());
+        let upload_repo = Arc::new(StateObservingUploadRepository {
+            state_repository: mock_state_repo.clone(),
+            saved_before_upload: std::sync::Mutex::new(Vec::new()),
+        });
+        let journal = Arc::new(MockJournalRepository::default());
+        let use_case = UploadLogsUseCase::new(upload_repo.clone(), mock_state_repo.clone())
+            .with_journal(journal.clone(), "journal.jsonl");
+
+        let records = (1..=5)
+            .map(|i| {
+                Ok(ParsedRecord::Log(Box::new(create_test_log_from(
+                    &format!("uuid-{}", i),
+                    "/logs/a.jsonl",
+                ))))
+            })
+            .collect::<Vec<_>>();
+        let summary = use_case
+            .execute_stream(
+                stream::iter(records).boxed(),
+                &create_small_batch_config(),
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+
+        // 前のバッチは次のバッチを送る前に状態に保存されている
+        assert_eq!(
+            *upload_repo.saved_before_upload.lock().unwrap(),
+            vec![0, 2, 4]
+        );
+        assert_eq!(summary.uploaded_count, 5);
+        assert_eq!(mock_state_repo.get_state().total_uploaded, 5);
+        assert!(journal.entries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_stream_saves_progress_before_returning_error() {
+        let upload_repo = Arc::new(RecordingUploadRepository {
+            batch_sizes: std::sync::Mutex::new(Vec::new()),
+        });
+        let mock_state_repo = Arc::new(MockStateRepository::new());
+        let use_case = UploadLogsUseCase::new(upload_repo, mock_state_repo.clone());
+
+        let records = vec![
+            Ok(ParsedRecord::Log(Box::new(create_test_log_from(
+                "uuid-1",
+                "/logs/a.jsonl",
+            )))),
+            Ok(ParsedRecord::Log(Box::new(create_test_log_from(
+                "uuid-2",
+                "/logs/a.jsonl",
+            )))),
+            Ok(ParsedRecord::FileCompleted {
+                source_file: "/logs/a.jsonl".to_string(),
+                checkpoint: FileCheckpoint::new(None, 20, None, 20, 2),
+            }),
+            Ok(ParsedRecord::Log(Box::new(create_test_log_from(
+                "uuid-3",
+                "/logs/b.jsonl",
+            )))),
+            Err(anyhow::anyhow!("Failed to read log file: /logs/b.jsonl")),
+        ];
+
+        let result = use_case
+            .execute_stream(
+                stream::iter(records).boxed(),
+                &create_small_batch_config(),
+                "/path/to/state.json",
+                "batch-001",
+            )
             .await;
 
         assert!(result.is_err());
+
+        // 最初のバッチは送信済みなので状態に残る
+        let state = mock_state_repo.get_state();
+        assert!(state.is_uploaded("uuid-1"));
+        assert!(state.is_uploaded("uuid-2"));
+        assert!(!state.is_uploaded("uuid-3"));
+        // a.jsonl はバッチ送信後に読み終えたため、次のバッチ送信まで確定しない
+        assert!(state.checkpoint("/logs/b.jsonl").is_none());
+    }
+
+    #[derive(Default)]
+    struct MockJournalRepository {
+        /// 現在のジャーナルの内容
+        entries: std::sync::Mutex<Vec<JournalEntry>>,
+        /// これまでに追記された全エントリ
+        appended: std::sync::Mutex<Vec<JournalEntry>>,
+    }
+
+    #[async_trait]
+    impl JournalRepository for MockJournalRepository {
+        async fn load(&self, _path: &str) -> Result<Vec<JournalEntry>> {
+            Ok(self.entries.lock().unwrap().clone())
+        }
+
+        async fn append(&self, _path: &str, entry: &JournalEntry) -> Result<()> {
+            self.entries.lock().unwrap().push(entry.clone());
+            self.appended.lock().unwrap().push(entry.clone());
+            Ok(())
+        }
+
+        async fn clear(&self, _path: &str) -> Result<()> {
+            self.entries.lock().unwrap().clear();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_journals_each_batch_and_clears_after_saving() {
+        let journal = Arc::new(MockJournalRepository::default());
+        let mock_state_repo = Arc::new(MockStateRepository::new());
+        let use_case = UploadLogsUseCase::new(
+            Arc::new(MockUploadRepository {
+                should_succeed: true,
+            }),
+            mock_state_repo.clone(),
+        )
+        .with_journal(journal.clone(), "journal.jsonl");
+
+        let parsed = ParsedLogs::from(vec![
+            create_test_log("uuid-1"),
+            create_test_log("uuid-2"),
+            create_test_log("uuid-3"),
+        ]);
+        use_case
+            .execute(
+                parsed,
+                &create_small_batch_config(),
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await
+            .unwrap();
+
+        let appended = journal.appended.lock().unwrap().clone();
+        assert_eq!(appended.len(), 4);
+        assert!(matches!(&appended[0], JournalEntry::InFlight { logs, .. } if logs.len() == 2));
+        assert!(
+            matches!(&appended[1], JournalEntry::Committed { uploaded_uuids, .. } if uploaded_uuids.len() == 2)
+        );
+        assert_eq!(appended[0].batch_key(), appended[1].batch_key());
+        assert_ne!(appended[0].batch_key(), appended[2].batch_key());
+        assert!(journal.entries.lock().unwrap().is_empty());
+        assert_eq!(mock_state_repo.get_state().total_uploaded, 3);
+    }
+
+    #[tokio::test]
+    async fn test_execute_keeps_in_flight_batch_when_upload_fails() {
+        let journal = Arc::new(MockJournalRepository::default());
+        let use_case = UploadLogsUseCase::new(
+            Arc::new(MockUploadRepository {
+                should_succeed: false,
+            }),
+            Arc::new(MockStateRepository::new()),
+        )
+        .with_journal(journal.clone(), "journal.jsonl");
+
+        let result = use_case
+            .execute(
+                ParsedLogs::from(vec![create_test_log("uuid-1")]),
+                &create_small_batch_config(),
+                "/path/to/state.json",
+                "batch-001",
+            )
+            .await;
+
+        assert!(result.is_err());
+        let entries = journal.entries.lock().unwrap().clone();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(&entries[0], JournalEntry::InFlight { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_recover_records_committed_and_resends_in_flight_batches() {
+        let journal = Arc::new(MockJournalRepository::default());
+        *journal.entries.lock().unwrap() = vec![
+            // 挿入済みだが状態を保存する前に終了したバッチ
+            JournalEntry::InFlight {
+                batch_key: "a".to_string(),
+                logs: vec![create_test_log("uuid-1")],
+            },
+            JournalEntry::Committed {
+                batch_key: "a".to_string(),
+                uploaded_uuids: vec!["uuid-1".to_string()],
+            },
+            // 送信中に終了したバッチ
+            JournalEntry::InFlight {
+                batch_key: "b".to_string(),
+                logs: vec![create_test_log("uuid-2")],
+            },
+            // 状態の保存まで終わっていたバッチ
+            JournalEntry::InFlight {
+                batch_key: "c".to_string(),
+                logs: vec![create_test_log("uuid-3")],
+            },
+        ];
+        let mock_state_repo = Arc::new(MockStateRepository::new());
+        mock_state_repo
+            .state
+            .lock()
+            .unwrap()
+            .uploaded_uuids
+            .insert("uuid-3".to_string());
+        let upload_repo = Arc::new(RecordingUploadRepository {
+            batch_sizes: std::sync::Mutex::new(Vec::new()),
+        });
+        let use_case = UploadLogsUseCase::new(upload_repo.clone(), mock_state_repo.clone())
+            .with_journal(journal.clone(), "journal.jsonl");
+
+        let summary = use_case
+            .recover("/path/to/state.json", "batch-002")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            RecoverySummary {
+                batches: 2,
+                recorded_count: 2,
+                resent_count: 1,
+            }
+        );
+        // 再送されたのは送信中のバッチのみ
+        assert_eq!(*upload_repo.batch_sizes.lock().unwrap(), vec![1]);
+        let state = mock_state_repo.get_state();
+        assert!(state.is_uploaded("uuid-1"));
+        assert!(state.is_uploaded("uuid-2"));
+        assert!(journal.entries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recover_without_journal_does_nothing() {
+        let use_case = UploadLogsUseCase::new(
+            Arc::new(MockUploadRepository {
+                should_succeed: false,
+            }),
+            Arc::new(MockStateRepository::new()),
+        );
+
+        let summary = use_case
+            .recover("/path/to/state.json", "batch-001")
+            .await
+            .unwrap();
+
+        assert_eq!(summary, RecoverySummary::default());
     }
 }
@@ -0,0 +1,166 @@
+//! # FileCheckpoint Value Object
+//!
+//! ログファイルごとの読み込み位置（チェックポイント）のバリューオブジェクト
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::lineage_edge::LineageEdge;
+
+/// ログファイルのチェックポイント
+///
+/// 前回どこまでファイルを読み込みコミットしたかを表す。
+/// ファイルの同一性（inode / サイズ / 更新時刻）も保持し、
+/// ファイルが切り詰められたり置き換えられた場合は先頭から再スキャンする。
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FileCheckpoint {
+    /// ファイルのinode番号（取得できないプラットフォームでは `None`）
+    pub inode: Option<u64>,
+    /// チェックポイント作成時のファイルサイズ（バイト）
+    pub size: u64,
+    /// チェックポイント作成時の更新時刻（UNIXエポックからのミリ秒）
+    pub mtime: Option<i64>,
+    /// コミット済みのバイトオフセット（次回はここから読み込む）
+    pub offset: u64,
+    /// コミット済みの行数（再開時の行番号計算に使用）
+    #[serde(default)]
+    pub lines: u64,
+    /// 読み込み済みの行のセッションツリー上の紐付け（記録していない場合は `None`）
+    ///
+    /// 次回の実行では既読部分を読み直さずに、これからツリーを復元する。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<Vec<LineageEdge>>,
+}
+
+impl FileCheckpoint {
+    /// 新しいチェックポイントを作成
+    pub fn new(inode: Option<u64>, size: u64, mtime: Option<i64>, offset: u64, lines: u64) -> Self {
+        Self {
+            inode,
+            size,
+            mtime,
+            offset,
+            lines,
+            lineage: None,
+        }
+    }
+
+    /// ファイルが置き換えられた、または切り詰められたかを判定
+    pub fn is_replaced(&self, inode: Option<u64>, size: u64, mtime: Option<i64>) -> bool {
+        // オフセットより短い → 切り詰められた
+        self.is_rewritten(inode, size, mtime) || size < self.offset
+    }
+
+    /// ファイルが置き換えられたかを、オフセットを使わずに同一性だけで判定
+    ///
+    /// 圧縮ファイルのオフセットは展開後のバイト数で、ファイルサイズとは比較できないため、
+    /// 圧縮ファイルにはこちらを使う。
+    pub fn is_rewritten(&self, inode: Option<u64>, size: u64, mtime: Option<i64>) -> bool {
+        // inodeが変わった → 別ファイルに置き換えられた
+        if let (Some(old), Some(new)) = (self.inode, inode) {
+            if old != new {
+                return true;
+            }
+        }
+
+        // サイズが縮んだ → 切り詰められた
+        if size < self.size {
+            return true;
+        }
+
+        // 更新時刻が巻き戻った → 古いコピーで上書きされた
+        if let (Some(old), Some(new)) = (self.mtime, mtime) {
+            if new < old {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// 前回から変更がないかを判定（読み込み自体を省略できる）
+    #[inline]
+    pub fn is_unchanged(&self, inode: Option<u64>, size: u64, mtime: Option<i64>) -> bool {
+        self.is_same_file(inode, size, mtime) && self.offset == size
+    }
+
+    /// チェックポイント作成時からファイルの同一性（inode / サイズ / 更新時刻）が変わっていないかを判定
+    ///
+    /// 圧縮ファイルは読み終えた位置をファイルサイズと比較できないため、
+    /// 同一性が変わっていなければ読み終えたものとして扱う。
+    #[inline]
+    pub fn is_same_file(&self, inode: Option<u64>, size: u64, mtime: Option<i64>) -> bool {
+        self.inode == inode && self.size == size && self.mtime == mtime
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint() -> FileCheckpoint {
+        FileCheckpoint::new(Some(100), 2048, Some(1_000), 2048, 20)
+    }
+
+    #[test]
+    fn test_is_replaced_appended() {
+        let cp = checkpoint();
+        assert!(!cp.is_replaced(Some(100), 4096, Some(2_000)));
+    }
+
+    #[test]
+    fn test_is_replaced_truncated() {
+        let cp = checkpoint();
+        assert!(cp.is_replaced(Some(100), 1024, Some(2_000)));
+    }
+
+    #[test]
+    fn test_is_replaced_inode_changed() {
+        let cp = checkpoint();
+        assert!(cp.is_replaced(Some(200), 4096, Some(2_000)));
+    }
+
+    #[test]
+    fn test_is_replaced_mtime_went_backwards() {
+        let cp = checkpoint();
+        assert!(cp.is_replaced(Some(100), 4096, Some(500)));
+    }
+
+    #[test]
+    fn test_is_replaced_without_inode() {
+        let cp = FileCheckpoint::new(None, 2048, None, 1024, 10);
+        assert!(!cp.is_replaced(None, 2048, None));
+    }
+
+    #[test]
+    fn test_is_unchanged() {
+        let cp = checkpoint();
+        assert!(cp.is_unchanged(Some(100), 2048, Some(1_000)));
+        assert!(!cp.is_unchanged(Some(100), 4096, Some(2_000)));
+    }
+
+    #[test]
+    fn test_is_unchanged_with_held_back_partial_line() {
+        // 末尾の未完了行を保留している場合は変更なしとみなさない
+        let cp = FileCheckpoint::new(Some(100), 2048, Some(1_000), 2000, 20);
+        assert!(!cp.is_unchanged(Some(100), 2048, Some(1_000)));
+    }
+
+    #[test]
+    fn test_is_rewritten_ignores_offset() {
+        // 圧縮ファイルでは展開後のオフセットがファイルサイズを超える
+        let cp = FileCheckpoint::new(Some(100), 512, Some(1_000), 4096, 20);
+        assert!(cp.is_replaced(Some(100), 1024, Some(2_000)));
+        assert!(!cp.is_rewritten(Some(100), 1024, Some(2_000)));
+        assert!(cp.is_rewritten(Some(100), 256, Some(2_000)));
+        assert!(cp.is_same_file(Some(100), 512, Some(1_000)));
+        assert!(!cp.is_unchanged(Some(100), 512, Some(1_000)));
+    }
+
+    #[test]
+    fn test_deserialize_without_lines() {
+        let json = r#"{"inode":1,"size":10,"mtime":5,"offset":10}"#;
+        let cp: FileCheckpoint = serde_json::from_str(json).unwrap();
+        assert_eq!(cp.lines, 0);
+        assert_eq!(cp.offset, 10);
+    }
+}
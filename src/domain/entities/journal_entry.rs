@@ -0,0 +1,74 @@
+//! # JournalEntry Entity
+//!
+//! アップロードジャーナル（先行書き込みログ）のエントリ
+
+use serde::{Deserialize, Serialize};
+
+use super::session_log::SessionLog;
+
+/// アップロードジャーナルのエントリ
+///
+/// バッチを送信する前に `InFlight`、挿入に成功した後に `Committed` を追記する。
+/// 実行が途中で強制終了された場合、次回の実行は `Committed` のバッチを状態に反映し、
+/// 対応する `Committed` のない `InFlight` のバッチを同じ insert ID で再送する。
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// 送信中のバッチ
+    InFlight {
+        /// ジャーナル内でバッチを識別するID
+        batch_key: String,
+        /// バッチのセッションログ（再送に使用）
+        logs: Vec<SessionLog>,
+    },
+    /// 挿入に成功したバッチ
+    Committed {
+        /// 対応する `InFlight` のID
+        batch_key: String,
+        /// 挿入に成功したUUID
+        uploaded_uuids: Vec<String>,
+    },
+}
+
+impl JournalEntry {
+    /// エントリが属するバッチのIDを返す
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::domain::entities::journal_entry::JournalEntry;
+    ///
+    /// let entry = JournalEntry::Committed {
+    ///     batch_key: "batch-001-1".to_string(),
+    ///     uploaded_uuids: vec!["uuid-1".to_string()],
+    /// };
+    ///
+    /// assert_eq!(entry.batch_key(), "batch-001-1");
+    /// ```
+    pub fn batch_key(&self) -> &str {
+        match self {
+            Self::InFlight { batch_key, .. } | Self::Committed { batch_key, .. } => batch_key,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_with_status_tag() {
+        let entry = JournalEntry::Committed {
+            batch_key: "batch-001-1".to_string(),
+            uploaded_uuids: vec!["uuid-1".to_string()],
+        };
+
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["status"], "committed");
+        assert_eq!(json["uploaded_uuids"][0], "uuid-1");
+
+        let in_flight: JournalEntry =
+            serde_json::from_str(r#"{"status":"in_flight","batch_key":"b","logs":[]}"#).unwrap();
+        assert!(matches!(in_flight, JournalEntry::InFlight { logs, .. } if logs.is_empty()));
+    }
+}
@@ -0,0 +1,55 @@
+//! # LineageEdge Value Object
+//!
+//! セッションツリーの組み立てに使う、ログ1件分の紐付け情報のバリューオブジェクト
+
+use serde::{Deserialize, Serialize};
+
+/// セッションツリーの組み立てに使う、ログ1件分の紐付け情報
+///
+/// ログ本体のうち `SessionTree` が参照する情報（親メッセージ・セッション・
+/// サブエージェントの起動と結果）だけを持つ。チェックポイントとともに保存し、
+/// 次回の実行では既読部分を読み直さずにツリーを復元する。
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LineageEdge {
+    /// ログのUUID
+    pub uuid: String,
+    /// 親メッセージのUUID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_uuid: Option<String>,
+    /// セッションID
+    pub session_id: String,
+    /// サブエージェントのID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    /// サイドチェーンのログかどうか
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_sidechain: bool,
+    /// 起動元の Task との照合に使ったメッセージのテキスト（照合した場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// このログが呼び出した Task
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub task_calls: Vec<TaskCall>,
+    /// このログが受け取った Task の結果
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub task_results: Vec<TaskResult>,
+}
+
+/// Task ツールの呼び出し
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TaskCall {
+    /// tool_use のID
+    pub tool_use_id: String,
+    /// サブエージェントに渡したプロンプト
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+}
+
+/// Task ツールの結果（起動されたサブエージェント）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TaskResult {
+    /// 結果を返した tool_use のID
+    pub tool_use_id: String,
+    /// 起動されたサブエージェントのID
+    pub agent_id: String,
+}
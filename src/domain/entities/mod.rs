@@ -6,6 +6,16 @@
 //!
 //! - **SessionLog**: セッションログのビジネス表現
 //! - **UploadBatch**: アップロードバッチのバリューオブジェクト
+//! - **FileCheckpoint**: ログファイルごとの読み込み位置
+//! - **JournalEntry**: アップロードジャーナルのエントリ
+//! - **LineageEdge**: セッションツリーの組み立てに使うログ1件分の紐付け情報
+//! - **RejectedLine**: パースできなかったログ行
+//! - **TranscriptRecord**: トランスクリプト1行のレコード種別ごとの表現
 
+pub mod file_checkpoint;
+pub mod journal_entry;
+pub mod lineage_edge;
+pub mod rejected_line;
 pub mod session_log;
+pub mod transcript_record;
 pub mod upload_batch;
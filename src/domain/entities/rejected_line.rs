@@ -0,0 +1,93 @@
+//! # RejectedLine Entity
+//!
+//! パースできなかったログ行（隔離ストアに保存される）のエンティティ
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// パースできなかったログ行
+///
+/// スキーマの変化などで `TranscriptRecord` として読めなかった行を、
+/// 元のテキストのまま保持する。パーサー更新後に再試行できるよう、
+/// 元ファイルと行番号で一意に識別する。
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RejectedLine {
+    /// 元のログファイルのパス
+    pub source_file: String,
+    /// 行番号（1始まり）
+    pub line_number: u64,
+    /// パースエラー（serdeのエラーメッセージ）
+    pub error: String,
+    /// 行の生テキスト
+    pub raw: String,
+    /// 隔離された日時
+    pub rejected_at: DateTime<Utc>,
+}
+
+impl RejectedLine {
+    /// 新しい隔離行を作成（隔離日時は現在時刻）
+    pub fn new(
+        source_file: impl Into<String>,
+        line_number: u64,
+        error: impl Into<String>,
+        raw: impl Into<String>,
+    ) -> Self {
+        Self {
+            source_file: source_file.into(),
+            line_number,
+            error: error.into(),
+            raw: raw.into(),
+            rejected_at: Utc::now(),
+        }
+    }
+
+    /// 同じログ行を指しているかを判定します。
+    ///
+    /// 同じファイルの同じ行が再スキャンで再度隔離された場合の重複を防ぐために使用します。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::domain::entities::rejected_line::RejectedLine;
+    ///
+    /// let a = RejectedLine::new("/logs/a.jsonl", 3, "expected value", "{oops");
+    /// let b = RejectedLine::new("/logs/a.jsonl", 3, "EOF while parsing", "{oops");
+    /// let c = RejectedLine::new("/logs/a.jsonl", 4, "expected value", "{oops");
+    ///
+    /// assert!(a.is_same_line(&b));
+    /// assert!(!a.is_same_line(&c));
+    /// ```
+    #[inline]
+    pub fn is_same_line(&self, other: &RejectedLine) -> bool {
+        self.source_file == other.source_file && self.line_number == other.line_number
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_sets_fields() {
+        let line = RejectedLine::new("/logs/a.jsonl", 7, "missing field `uuid`", "{}");
+        assert_eq!(line.source_file, "/logs/a.jsonl");
+        assert_eq!(line.line_number, 7);
+        assert_eq!(line.error, "missing field `uuid`");
+        assert_eq!(line.raw, "{}");
+    }
+
+    #[test]
+    fn test_is_same_line_different_file() {
+        let a = RejectedLine::new("/logs/a.jsonl", 1, "err", "x");
+        let b = RejectedLine::new("/logs/b.jsonl", 1, "err", "x");
+        assert!(!a.is_same_line(&b));
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let line = RejectedLine::new("/logs/a.jsonl", 2, "err", "not json");
+        let json = serde_json::to_string(&line).unwrap();
+        let restored: RejectedLine = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, line);
+    }
+}
@@ -47,6 +47,31 @@ pub struct LogMetadata {
     pub uploaded_at: DateTime<Utc>,
 }
 
+/// セッションツリー上の位置
+///
+/// サブエージェント（Task ツール）やサイドチェーンのログを、
+/// それを起動したセッションに紐付けるための派生情報
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionLineage {
+    /// ツリーの根にあたるセッションのID
+    pub root_session_id: String,
+    /// サブエージェントのネストの深さ（メインのセッションは0）
+    pub depth: u32,
+    /// このログを含むサブエージェントを起動した tool_use のID
+    pub spawning_tool_use_id: Option<String>,
+}
+
+impl SessionLineage {
+    /// メインのセッション（深さ0）の位置を作成
+    pub fn root(session_id: impl Into<String>) -> Self {
+        Self {
+            root_session_id: session_id.into(),
+            depth: 0,
+            spawning_tool_use_id: None,
+        }
+    }
+}
+
 /// セッションログのドメインエンティティ
 ///
 /// Claude Code のセッションログを表現するビジネスエンティティ
@@ -78,6 +103,20 @@ pub struct SessionLog {
     #[serde(serialize_with = "serialize_option_json_value_as_string")]
     pub tool_use_result: Option<serde_json::Value>,
 
+    /// 既知のフィールド以外のトップレベルのキー（JSON形式）
+    /// Claude Code 側で追加された新しいフィールドを失わないために保持する
+    /// BigQuery JSON型カラム用にカスタムシリアライザを使用
+    #[serde(default, serialize_with = "serialize_option_json_value_as_string")]
+    pub extra: Option<serde_json::Value>,
+
+    /// セッションツリー上の位置（`SessionTree` が設定する）
+    #[serde(default, flatten)]
+    pub lineage: Option<SessionLineage>,
+
+    /// 同じUUIDの内容が変わって再アップロードされた回数（最初のアップロードは0）
+    #[serde(default)]
+    pub record_version: u32,
+
     /// メタデータ（チームコラボレーション、アップロード情報）
     #[serde(flatten)]
     pub metadata: LogMetadata,
@@ -219,9 +258,112 @@ impl SessionLog {
             version,
             message,
             tool_use_result,
+            extra: None,
+            lineage: None,
+            record_version: 0,
             metadata,
         })
     }
+
+    /// レコードの内容のハッシュを返します。
+    ///
+    /// Claude Code が記録した内容（と sessync による変換結果）だけから計算し、
+    /// アップロード時のメタデータやセッションツリー上の位置は含めません。
+    /// アップロード時刻で補った `timestamp` も含めません。
+    /// 同じUUIDのレコードが書き換えられたかどうかの判定に使います。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// # use sessync::domain::entities::session_log::{SessionLog, LogMetadata};
+    /// # use chrono::Utc;
+    /// # use serde_json::json;
+    /// # let metadata = LogMetadata {
+    /// #     developer_id: "dev".to_string(),
+    /// #     hostname: "host".to_string(),
+    /// #     user_email: "user@example.com".to_string(),
+    /// #     project_name: "proj".to_string(),
+    /// #     upload_batch_id: "batch".to_string(),
+    /// #     source_file: "/log".to_string(),
+    /// #     uploaded_at: Utc::now(),
+    /// # };
+    /// # let log = SessionLog::new(
+    /// #     "uuid-1".to_string(), Utc::now(), "session".to_string(),
+    /// #     None, None, None, None, "user".to_string(),
+    /// #     None, None, None, None, None, json!({"content": "a"}), None, metadata,
+    /// # ).unwrap();
+    /// let mut rewritten = log.clone();
+    /// rewritten.metadata.upload_batch_id = "batch-2".to_string();
+    /// assert_eq!(log.content_hash(), rewritten.content_hash());
+    ///
+    /// rewritten.message = json!({"content": "b"});
+    /// assert_ne!(log.content_hash(), rewritten.content_hash());
+    /// ```
+    pub fn content_hash(&self) -> String {
+        // 独自の timestamp を持たないレコード（summary など）はアップロード時刻で補われ、
+        // 実行ごとに値が変わるためハッシュに含めない
+        let timestamp = (self.timestamp != self.metadata.uploaded_at).then_some(&self.timestamp);
+        let content = (
+            &self.uuid,
+            timestamp,
+            &self.session_id,
+            &self.agent_id,
+            &self.is_sidechain,
+            &self.parent_uuid,
+            &self.user_type,
+            &self.message_type,
+            &self.slug,
+            &self.request_id,
+            &self.cwd,
+            &self.git_branch,
+            &self.version,
+            &self.message,
+            &self.tool_use_result,
+            &self.extra,
+        );
+        // JSONオブジェクトのキーは常にソートされるため、同じ内容からは同じハッシュが得られる
+        let bytes = serde_json::to_vec(&content).unwrap_or_default();
+        sha1_smol::Sha1::from(bytes).digest().to_string()
+    }
+
+    /// 既知のフィールド以外のトップレベルのキーを設定します。
+    ///
+    /// 空のマップの場合は `extra` を `None`（BigQuery上はNULL）のままにします。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// # use sessync::domain::entities::session_log::{SessionLog, LogMetadata};
+    /// # use chrono::Utc;
+    /// # use serde_json::json;
+    /// # let metadata = LogMetadata {
+    /// #     developer_id: "dev".to_string(),
+    /// #     hostname: "host".to_string(),
+    /// #     user_email: "user@example.com".to_string(),
+    /// #     project_name: "proj".to_string(),
+    /// #     upload_batch_id: "batch".to_string(),
+    /// #     source_file: "/log".to_string(),
+    /// #     uploaded_at: Utc::now(),
+    /// # };
+    /// # let log = SessionLog::new(
+    /// #     "uuid-1".to_string(), Utc::now(), "session".to_string(),
+    /// #     None, None, None, None, "user".to_string(),
+    /// #     None, None, None, None, None, json!({}), None, metadata,
+    /// # ).unwrap();
+    /// let mut extra = serde_json::Map::new();
+    /// extra.insert("isMeta".to_string(), json!(true));
+    ///
+    /// let log = log.with_extra(extra);
+    /// assert_eq!(log.extra, Some(json!({"isMeta": true})));
+    /// ```
+    pub fn with_extra(mut self, extra: serde_json::Map<String, serde_json::Value>) -> Self {
+        self.extra = if extra.is_empty() {
+            None
+        } else {
+            Some(serde_json::Value::Object(extra))
+        };
+        self
+    }
 }
 
 /// JSONLファイルからの入力用構造体
@@ -246,6 +388,9 @@ pub struct SessionLogInput {
     pub version: Option<String>,
     pub message: serde_json::Value,
     pub tool_use_result: Option<serde_json::Value>,
+    /// 上記以外のトップレベルのキー（未知のフィールドを捨てずに保持する）
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// BigQuery出力用構造体
@@ -286,6 +431,9 @@ mod tests {
             version: Some("1.0.0".to_string()),
             message: json!({"role": "user", "content": "Hello"}),
             tool_use_result: Some(json!({"output": "success"})),
+            extra: None,
+            lineage: None,
+            record_version: 0,
             metadata,
         }
     }
@@ -419,5 +567,50 @@ mod tests {
         assert!(input.agent_id.is_none());
         assert!(input.is_sidechain.is_none());
         assert!(input.tool_use_result.is_none());
+        assert!(input.extra.is_empty());
+    }
+
+    #[test]
+    fn test_session_log_input_captures_unknown_fields() {
+        let json_str = r#"{
+            "uuid": "extra-uuid",
+            "timestamp": "2024-12-25T10:00:00Z",
+            "sessionId": "session-extra",
+            "type": "user",
+            "parentUuid": null,
+            "message": {"content": "test"},
+            "isMeta": true,
+            "toolUseID": "toolu_01",
+            "todos": [{"content": "write tests", "status": "pending"}]
+        }"#;
+
+        let input: SessionLogInput = serde_json::from_str(json_str).unwrap();
+
+        // 既知のフィールドは extra に含まれない
+        assert_eq!(input.extra.len(), 3);
+        assert!(!input.extra.contains_key("uuid"));
+        assert!(!input.extra.contains_key("parentUuid"));
+        assert_eq!(input.extra["isMeta"], json!(true));
+        assert_eq!(input.extra["toolUseID"], json!("toolu_01"));
+        assert_eq!(input.extra["todos"][0]["status"], json!("pending"));
+    }
+
+    #[test]
+    fn test_with_extra_empty_map_is_none() {
+        let log = create_test_log().with_extra(serde_json::Map::new());
+        assert!(log.extra.is_none());
+    }
+
+    #[test]
+    fn test_extra_serialized_as_json_string() {
+        let mut extra = serde_json::Map::new();
+        extra.insert("isCompactSummary".to_string(), json!(false));
+        let log = create_test_log().with_extra(extra);
+
+        let json_str = serde_json::to_string(&log).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+
+        assert!(parsed["extra"].is_string());
+        assert_eq!(parsed["extra"], r#"{"isCompactSummary":false}"#);
     }
 }
@@ -0,0 +1,640 @@
+//! # TranscriptRecord Entity
+//!
+//! トランスクリプト（JSONL）1行分のレコード種別ごとの型付き表現
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+use uuid::Uuid;
+
+use super::session_log::{LogMetadata, SessionLog, SessionLogInput};
+
+/// 派生IDを生成するための名前空間（UUID v5）
+///
+/// 値を変えると既存の派生IDと一致しなくなり重複排除が効かなくなるため、変更しないこと
+const IDENTITY_NAMESPACE: Uuid = Uuid::from_u128(0x5e55_c0de_7a1e_4b0e_9d1c_0c1a_0de5_e55c);
+
+/// 会話要約レコード（`"type": "summary"`）
+///
+/// セッションタイトルやコンパクション時の要約。`uuid` / `timestamp` / `sessionId` を持たない。
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryRecord {
+    /// 要約テキスト
+    pub summary: String,
+    /// 要約対象の会話の末端メッセージのUUID
+    pub leaf_uuid: Option<String>,
+    /// 上記以外のトップレベルのキー
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// ファイル履歴スナップショットレコード（`"type": "file-history-snapshot"`）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistorySnapshotRecord {
+    /// スナップショットが紐づくメッセージのID
+    pub message_id: Option<String>,
+    /// スナップショット本体
+    #[serde(default)]
+    pub snapshot: Value,
+    /// 既存スナップショットの更新かどうか
+    pub is_snapshot_update: Option<bool>,
+    /// 上記以外のトップレベルのキー
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// システムレコード（`"type": "system"`、コンパクション境界など）
+///
+/// `message` を持たず、`subtype` / `content` / `level` で内容を表す。
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemRecord {
+    pub uuid: String,
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    pub agent_id: Option<String>,
+    pub is_sidechain: Option<bool>,
+    pub parent_uuid: Option<String>,
+    pub user_type: Option<String>,
+    /// システムメッセージの種類（例: `compact_boundary`）
+    pub subtype: Option<String>,
+    pub content: Option<Value>,
+    pub level: Option<String>,
+    pub cwd: Option<String>,
+    pub git_branch: Option<String>,
+    pub version: Option<String>,
+    /// 上記以外のトップレベルのキー（`compactMetadata` など）
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl SystemRecord {
+    /// コンパクション境界かどうか
+    #[inline]
+    pub fn is_compact_boundary(&self) -> bool {
+        self.subtype.as_deref() == Some("compact_boundary")
+    }
+}
+
+/// 未知の種別のレコード
+///
+/// sessync が認識しない `type` のレコード。内容を失わないよう、行全体を保持する。
+#[derive(Debug, Clone)]
+pub struct UnknownRecord {
+    /// `type` の値
+    pub record_type: String,
+    /// 行のトップレベルのキー全て
+    pub fields: Map<String, Value>,
+}
+
+/// トランスクリプトの1行
+///
+/// Claude Code のトランスクリプトにはメッセージ以外のレコードも含まれるため、
+/// `type` ごとに型付けして扱う。種別ごとに `SessionLog` への変換と、
+/// 重複排除に使う決定的なIDを持つ。
+#[derive(Debug, Clone)]
+pub enum TranscriptRecord {
+    /// 会話メッセージ（`user` / `assistant` など、`message` を持つもの）
+    Message(SessionLogInput),
+    /// 会話要約
+    Summary(SummaryRecord),
+    /// ファイル履歴スナップショット
+    FileHistorySnapshot(FileHistorySnapshotRecord),
+    /// システムメッセージ（コンパクション境界など）
+    System(SystemRecord),
+    /// 未知の種別
+    Unknown(UnknownRecord),
+}
+
+impl TranscriptRecord {
+    /// JSONLの1行をパースします。
+    ///
+    /// ログファイルの読み込みと、隔離された行の再試行で同じパーサーを使うための入口です。
+    /// `type` が未知でも、メッセージとして読める行は `Message`、読めない行は `Unknown` になります。
+    ///
+    /// # エラー
+    ///
+    /// 行が不正なJSON、オブジェクトでない、`type` がない、または既知の種別の
+    /// 必須フィールドが欠けている場合にserdeのエラーを返します。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::domain::entities::transcript_record::TranscriptRecord;
+    ///
+    /// let line = r#"{"type":"summary","summary":"Fix login bug","leafUuid":"u9"}"#;
+    /// let record = TranscriptRecord::from_json_line(line).unwrap();
+    /// assert_eq!(record.record_type(), "summary");
+    ///
+    /// // 同じ行からは常に同じIDが得られる
+    /// let again = TranscriptRecord::from_json_line(line).unwrap();
+    /// assert_eq!(record.identity(), again.identity());
+    ///
+    /// assert!(TranscriptRecord::from_json_line("{not json").is_err());
+    /// ```
+    pub fn from_json_line(line: &str) -> serde_json::Result<Self> {
+        let mut fields = match serde_json::from_str::<Value>(line)? {
+            Value::Object(fields) => fields,
+            other => {
+                return Err(serde::de::Error::custom(format!(
+                    "expected a JSON object, found {}",
+                    json_kind(&other)
+                )))
+            }
+        };
+
+        let record_type = match fields.get("type") {
+            Some(Value::String(t)) => t.clone(),
+            Some(_) => return Err(serde::de::Error::custom("`type` must be a string")),
+            None => return Err(serde::de::Error::missing_field("type")),
+        };
+
+        match record_type.as_str() {
+            "user" | "assistant" => {
+                serde_json::from_value(Value::Object(fields)).map(Self::Message)
+            }
+            "summary" => {
+                fields.remove("type");
+                serde_json::from_value(Value::Object(fields)).map(Self::Summary)
+            }
+            "file-history-snapshot" => {
+                fields.remove("type");
+                serde_json::from_value(Value::Object(fields)).map(Self::FileHistorySnapshot)
+            }
+            "system" => {
+                fields.remove("type");
+                serde_json::from_value(Value::Object(fields)).map(Self::System)
+            }
+            _ => match serde_json::from_value::<SessionLogInput>(Value::Object(fields.clone())) {
+                Ok(input) => Ok(Self::Message(input)),
+                Err(_) => Ok(Self::Unknown(UnknownRecord {
+                    record_type,
+                    fields,
+                })),
+            },
+        }
+    }
+
+    /// レコードの種別（BigQueryの `type` カラムに入る値）
+    pub fn record_type(&self) -> &str {
+        match self {
+            Self::Message(input) => &input.message_type,
+            Self::Summary(_) => "summary",
+            Self::FileHistorySnapshot(_) => "file-history-snapshot",
+            Self::System(_) => "system",
+            Self::Unknown(record) => &record.record_type,
+        }
+    }
+
+    /// 重複排除に使う決定的なIDを返します。
+    ///
+    /// `uuid` を持つレコードはその値を、持たないレコードは内容から導出した
+    /// UUID v5 を返します。同じ内容の行からは常に同じIDが得られます。
+    pub fn identity(&self) -> String {
+        match self {
+            Self::Message(input) => input.uuid.clone(),
+            Self::System(record) => record.uuid.clone(),
+            Self::Summary(record) => derived_identity(&[
+                "summary",
+                record.leaf_uuid.as_deref().unwrap_or_default(),
+                &record.summary,
+            ]),
+            Self::FileHistorySnapshot(record) => derived_identity(&[
+                "file-history-snapshot",
+                record.message_id.as_deref().unwrap_or_default(),
+                &record.snapshot.to_string(),
+                &record.is_snapshot_update.unwrap_or_default().to_string(),
+            ]),
+            Self::Unknown(record) => match record.fields.get("uuid") {
+                Some(Value::String(uuid)) if !uuid.is_empty() => uuid.clone(),
+                _ => derived_identity(&[
+                    &record.record_type,
+                    &Value::Object(record.fields.clone()).to_string(),
+                ]),
+            },
+        }
+    }
+
+    /// ドメインのセッションログに変換します。
+    ///
+    /// `sessionId` を持たないレコードはファイル名（Claude Code ではセッションID）を、
+    /// `timestamp` を持たないレコードはアップロード時刻を使います。
+    ///
+    /// # エラー
+    ///
+    /// IDが空の場合にエラーを返します。
+    pub fn into_session_log(self, metadata: LogMetadata) -> anyhow::Result<SessionLog> {
+        let identity = self.identity();
+        let fallback_session_id = session_id_from_source(&metadata.source_file);
+        let fallback_timestamp = metadata.uploaded_at;
+
+        match self {
+            Self::Message(input) => {
+                let extra = input.extra;
+                SessionLog::new(
+                    input.uuid,
+                    input.timestamp,
+                    input.session_id,
+                    input.agent_id,
+                    input.is_sidechain,
+                    input.parent_uuid,
+                    input.user_type,
+                    input.message_type,
+                    input.slug,
+                    input.request_id,
+                    input.cwd,
+                    input.git_branch,
+                    input.version,
+                    input.message,
+                    input.tool_use_result,
+                    metadata,
+                )
+                .map(|log| log.with_extra(extra))
+            }
+            Self::Summary(record) => {
+                let message = object([
+                    ("summary", Some(Value::String(record.summary))),
+                    ("leafUuid", record.leaf_uuid.clone().map(Value::String)),
+                ]);
+                SessionLog::new(
+                    identity,
+                    fallback_timestamp,
+                    fallback_session_id,
+                    None,
+                    None,
+                    record.leaf_uuid,
+                    None,
+                    "summary".to_string(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    message,
+                    None,
+                    metadata,
+                )
+                .map(|log| log.with_extra(record.extra))
+            }
+            Self::FileHistorySnapshot(record) => {
+                let timestamp = record
+                    .snapshot
+                    .get("timestamp")
+                    .and_then(Value::as_str)
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or(fallback_timestamp);
+                let message = object([
+                    ("messageId", record.message_id.clone().map(Value::String)),
+                    ("snapshot", Some(record.snapshot)),
+                    (
+                        "isSnapshotUpdate",
+                        record.is_snapshot_update.map(Value::Bool),
+                    ),
+                ]);
+                SessionLog::new(
+                    identity,
+                    timestamp,
+                    fallback_session_id,
+                    None,
+                    None,
+                    record.message_id,
+                    None,
+                    "file-history-snapshot".to_string(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    message,
+                    None,
+                    metadata,
+                )
+                .map(|log| log.with_extra(record.extra))
+            }
+            Self::System(record) => {
+                let message = object([
+                    ("subtype", record.subtype.map(Value::String)),
+                    ("content", record.content),
+                    ("level", record.level.map(Value::String)),
+                ]);
+                SessionLog::new(
+                    record.uuid,
+                    record.timestamp,
+                    record.session_id,
+                    record.agent_id,
+                    record.is_sidechain,
+                    record.parent_uuid,
+                    record.user_type,
+                    "system".to_string(),
+                    None,
+                    None,
+                    record.cwd,
+                    record.git_branch,
+                    record.version,
+                    message,
+                    None,
+                    metadata,
+                )
+                .map(|log| log.with_extra(record.extra))
+            }
+            Self::Unknown(record) => {
+                let fields = &record.fields;
+                let timestamp = fields
+                    .get("timestamp")
+                    .and_then(Value::as_str)
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or(fallback_timestamp);
+                let session_id = fields
+                    .get("sessionId")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or(fallback_session_id);
+                SessionLog::new(
+                    identity,
+                    timestamp,
+                    session_id,
+                    None,
+                    None,
+                    None,
+                    None,
+                    record.record_type,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Value::Object(record.fields),
+                    None,
+                    metadata,
+                )
+            }
+        }
+    }
+}
+
+/// 内容からUUID v5を導出
+fn derived_identity(parts: &[&str]) -> String {
+    Uuid::new_v5(&IDENTITY_NAMESPACE, parts.join("\u{1f}").as_bytes()).to_string()
+}
+
+/// ファイル名からセッションIDを取得（`{sessionId}.jsonl`、圧縮時は `{sessionId}.jsonl.gz` など）
+fn session_id_from_source(source_file: &str) -> String {
+    let path = Path::new(source_file);
+    let path = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gz" | "zst") => Path::new(path.file_stem().unwrap_or_default()),
+        _ => path,
+    };
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// `None` の値を除いたJSONオブジェクトを作成
+fn object<const N: usize>(entries: [(&str, Option<Value>); N]) -> Value {
+    Value::Object(
+        entries
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|v| (key.to_string(), v)))
+            .collect(),
+    )
+}
+
+/// JSON値の種類名（エラーメッセージ用）
+fn json_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn metadata() -> LogMetadata {
+        LogMetadata {
+            developer_id: "dev-001".to_string(),
+            hostname: "test-host".to_string(),
+            user_email: "test@example.com".to_string(),
+            project_name: "test-project".to_string(),
+            upload_batch_id: "batch-001".to_string(),
+            source_file: "/logs/session-abc.jsonl".to_string(),
+            uploaded_at: Utc.with_ymd_and_hms(2024, 12, 25, 12, 0, 0).unwrap(),
+        }
+    }
+
+    const MESSAGE_LINE: &str = r#"{"uuid":"u1","timestamp":"2024-01-01T00:00:00Z","sessionId":"s1","type":"user","message":{"role":"user"},"isMeta":true}"#;
+    const SUMMARY_LINE: &str = r#"{"type":"summary","summary":"Fix login bug","leafUuid":"u9"}"#;
+    const SNAPSHOT_LINE: &str = r#"{"type":"file-history-snapshot","messageId":"m1","snapshot":{"messageId":"m1","trackedFileBackups":{},"timestamp":"2024-01-02T03:04:05Z"},"isSnapshotUpdate":false}"#;
+    const COMPACT_LINE: &str = r#"{"type":"system","subtype":"compact_boundary","uuid":"sys-1","timestamp":"2024-01-01T00:00:00Z","sessionId":"s1","parentUuid":null,"logicalParentUuid":"u8","content":"Conversation compacted","level":"info","compactMetadata":{"trigger":"auto","preTokens":150000}}"#;
+    const UNKNOWN_LINE: &str =
+        r#"{"type":"queue-operation","operation":"enqueue","content":"hello"}"#;
+
+    #[test]
+    fn test_parse_message() {
+        let record = TranscriptRecord::from_json_line(MESSAGE_LINE).unwrap();
+        assert!(matches!(record, TranscriptRecord::Message(_)));
+        assert_eq!(record.identity(), "u1");
+        assert_eq!(record.record_type(), "user");
+    }
+
+    #[test]
+    fn test_parse_message_missing_fields_is_error() {
+        let line = r#"{"type":"user","sessionId":"s1"}"#;
+        assert!(TranscriptRecord::from_json_line(line).is_err());
+    }
+
+    #[test]
+    fn test_parse_summary() {
+        let record = TranscriptRecord::from_json_line(SUMMARY_LINE).unwrap();
+        match &record {
+            TranscriptRecord::Summary(summary) => {
+                assert_eq!(summary.summary, "Fix login bug");
+                assert_eq!(summary.leaf_uuid.as_deref(), Some("u9"));
+                assert!(summary.extra.is_empty());
+            }
+            other => panic!("expected summary, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_system_compact_boundary() {
+        let record = TranscriptRecord::from_json_line(COMPACT_LINE).unwrap();
+        match &record {
+            TranscriptRecord::System(system) => {
+                assert!(system.is_compact_boundary());
+                assert!(system.extra.contains_key("compactMetadata"));
+                assert!(system.extra.contains_key("logicalParentUuid"));
+            }
+            other => panic!("expected system, got {:?}", other),
+        }
+        assert_eq!(record.identity(), "sys-1");
+    }
+
+    #[test]
+    fn test_parse_unknown_type() {
+        let record = TranscriptRecord::from_json_line(UNKNOWN_LINE).unwrap();
+        assert!(matches!(record, TranscriptRecord::Unknown(_)));
+        assert_eq!(record.record_type(), "queue-operation");
+    }
+
+    #[test]
+    fn test_parse_unknown_type_with_message_fields_is_message() {
+        let line = r#"{"uuid":"u2","timestamp":"2024-01-01T00:00:00Z","sessionId":"s1","type":"text","message":{}}"#;
+        let record = TranscriptRecord::from_json_line(line).unwrap();
+        assert!(matches!(record, TranscriptRecord::Message(_)));
+    }
+
+    #[test]
+    fn test_parse_requires_object_with_type() {
+        assert!(TranscriptRecord::from_json_line("42").is_err());
+        assert!(TranscriptRecord::from_json_line(r#"{"uuid":"u1"}"#).is_err());
+        assert!(TranscriptRecord::from_json_line(r#"{"type":1}"#).is_err());
+    }
+
+    #[test]
+    fn test_derived_identity_is_deterministic_and_distinct() {
+        let summary = TranscriptRecord::from_json_line(SUMMARY_LINE).unwrap();
+        let other = TranscriptRecord::from_json_line(
+            r#"{"type":"summary","summary":"Other","leafUuid":"u9"}"#,
+        )
+        .unwrap();
+        let snapshot = TranscriptRecord::from_json_line(SNAPSHOT_LINE).unwrap();
+        let unknown = TranscriptRecord::from_json_line(UNKNOWN_LINE).unwrap();
+
+        assert_eq!(
+            summary.identity(),
+            TranscriptRecord::from_json_line(SUMMARY_LINE)
+                .unwrap()
+                .identity()
+        );
+        assert_ne!(summary.identity(), other.identity());
+        assert_eq!(
+            snapshot.identity(),
+            TranscriptRecord::from_json_line(SNAPSHOT_LINE)
+                .unwrap()
+                .identity()
+        );
+        assert_eq!(
+            unknown.identity(),
+            TranscriptRecord::from_json_line(UNKNOWN_LINE)
+                .unwrap()
+                .identity()
+        );
+        assert!(Uuid::parse_str(&summary.identity()).is_ok());
+    }
+
+    #[test]
+    fn test_message_into_session_log_keeps_extra() {
+        let log = TranscriptRecord::from_json_line(MESSAGE_LINE)
+            .unwrap()
+            .into_session_log(metadata())
+            .unwrap();
+        assert_eq!(log.uuid, "u1");
+        assert_eq!(log.extra, Some(json!({"isMeta": true})));
+    }
+
+    #[test]
+    fn test_summary_into_session_log() {
+        let record = TranscriptRecord::from_json_line(SUMMARY_LINE).unwrap();
+        let identity = record.identity();
+        let log = record.into_session_log(metadata()).unwrap();
+
+        assert_eq!(log.uuid, identity);
+        assert_eq!(log.session_id, "session-abc");
+        assert_eq!(log.message_type, "summary");
+        assert_eq!(log.parent_uuid.as_deref(), Some("u9"));
+        assert_eq!(log.timestamp, metadata().uploaded_at);
+        assert_eq!(
+            log.message,
+            json!({"summary": "Fix login bug", "leafUuid": "u9"})
+        );
+    }
+
+    #[test]
+    fn test_summary_content_hash_ignores_upload_time() {
+        let convert = |hour| {
+            let mut metadata = metadata();
+            metadata.uploaded_at = Utc.with_ymd_and_hms(2024, 12, 25, hour, 0, 0).unwrap();
+            TranscriptRecord::from_json_line(SUMMARY_LINE)
+                .unwrap()
+                .into_session_log(metadata)
+                .unwrap()
+        };
+
+        assert_eq!(convert(12).content_hash(), convert(13).content_hash());
+    }
+
+    #[test]
+    fn test_session_id_from_compressed_source() {
+        assert_eq!(
+            session_id_from_source("/p/session-abc.jsonl"),
+            "session-abc"
+        );
+        assert_eq!(
+            session_id_from_source("/p/session-abc.jsonl.gz"),
+            "session-abc"
+        );
+        assert_eq!(
+            session_id_from_source("/p/session-abc.jsonl.zst"),
+            "session-abc"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_into_session_log_uses_snapshot_timestamp() {
+        let log = TranscriptRecord::from_json_line(SNAPSHOT_LINE)
+            .unwrap()
+            .into_session_log(metadata())
+            .unwrap();
+
+        assert_eq!(log.message_type, "file-history-snapshot");
+        assert_eq!(
+            log.timestamp,
+            Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()
+        );
+        assert_eq!(log.parent_uuid.as_deref(), Some("m1"));
+        assert_eq!(log.message["isSnapshotUpdate"], json!(false));
+    }
+
+    #[test]
+    fn test_system_into_session_log() {
+        let log = TranscriptRecord::from_json_line(COMPACT_LINE)
+            .unwrap()
+            .into_session_log(metadata())
+            .unwrap();
+
+        assert_eq!(log.uuid, "sys-1");
+        assert_eq!(log.session_id, "s1");
+        assert_eq!(log.message_type, "system");
+        assert_eq!(log.message["subtype"], json!("compact_boundary"));
+        assert_eq!(
+            log.extra.unwrap()["compactMetadata"]["trigger"],
+            json!("auto")
+        );
+    }
+
+    #[test]
+    fn test_unknown_into_session_log_keeps_whole_line() {
+        let log = TranscriptRecord::from_json_line(UNKNOWN_LINE)
+            .unwrap()
+            .into_session_log(metadata())
+            .unwrap();
+
+        assert_eq!(log.message_type, "queue-operation");
+        assert_eq!(log.session_id, "session-abc");
+        assert_eq!(log.message["operation"], json!("enqueue"));
+        assert_eq!(log.message["type"], json!("queue-operation"));
+    }
+}
@@ -88,6 +88,9 @@ impl UploadBatch {
     /// #         version: None,
     /// #         message: json!({}),
     /// #         tool_use_result: None,
+    /// #         extra: None,
+    /// #         lineage: None,
+    /// #         record_version: 0,
     /// #         metadata,
     /// #     }
     /// # }
@@ -155,7 +158,7 @@ impl UploadBatch {
     /// #         user_type: None, message_type: "user".to_string(),
     /// #         slug: None, request_id: None, cwd: None,
     /// #         git_branch: None, version: None,
-    /// #         message: json!({}), tool_use_result: None, metadata,
+    /// #         message: json!({}), tool_use_result: None, extra: None, lineage: None, record_version: 0, metadata,
     /// #     }
     /// # }
     ///
@@ -198,7 +201,7 @@ impl UploadBatch {
     /// #         user_type: None, message_type: "user".to_string(),
     /// #         slug: None, request_id: None, cwd: None,
     /// #         git_branch: None, version: None,
-    /// #         message: json!({}), tool_use_result: None, metadata,
+    /// #         message: json!({}), tool_use_result: None, extra: None, lineage: None, record_version: 0, metadata,
     /// #     }
     /// # }
     ///
@@ -273,6 +276,9 @@ mod tests {
             version: None,
             message: json!({}),
             tool_use_result: None,
+            extra: None,
+            lineage: None,
+            record_version: 0,
             metadata,
         }
     }
@@ -0,0 +1,54 @@
+//! # Journal Repository Trait
+//!
+//! アップロードジャーナル（先行書き込みログ）を抽象化
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::domain::entities::journal_entry::JournalEntry;
+
+/// アップロードジャーナルのリポジトリ
+///
+/// 送信中・送信済みのバッチを記録し、強制終了後の再開に使うリポジトリ
+#[async_trait]
+pub trait JournalRepository: Send + Sync {
+    /// ジャーナルのエントリを書き込み順に全て読み込む
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - ジャーナルのパス
+    ///
+    /// # Returns
+    ///
+    /// エントリのリスト（ジャーナルが存在しない場合は空）
+    ///
+    /// # Errors
+    ///
+    /// ジャーナルの読み込みに失敗した場合にエラーを返す
+    async fn load(&self, path: &str) -> Result<Vec<JournalEntry>>;
+
+    /// エントリを追記する
+    ///
+    /// 戻った時点でエントリが永続化されている必要がある（強制終了されても失われない）。
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - ジャーナルのパス
+    /// * `entry` - 追記するエントリ
+    ///
+    /// # Errors
+    ///
+    /// ジャーナルへの書き込みに失敗した場合にエラーを返す
+    async fn append(&self, path: &str, entry: &JournalEntry) -> Result<()>;
+
+    /// 全てのエントリが状態に反映された後にジャーナルを空にする
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - ジャーナルのパス
+    ///
+    /// # Errors
+    ///
+    /// ジャーナルの削除に失敗した場合にエラーを返す
+    async fn clear(&self, path: &str) -> Result<()>;
+}
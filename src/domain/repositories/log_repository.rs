@@ -4,9 +4,82 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
 use std::path::{Path, PathBuf};
 
-use crate::domain::entities::session_log::SessionLogInput;
+use crate::domain::entities::file_checkpoint::FileCheckpoint;
+use crate::domain::entities::rejected_line::RejectedLine;
+use crate::domain::entities::transcript_record::TranscriptRecord;
+
+/// 差分パースの結果
+#[derive(Debug, Clone)]
+pub struct ParsedLogFile {
+    /// チェックポイント以降にパースされたレコード
+    pub records: Vec<TranscriptRecord>,
+    /// パースできなかった行
+    pub rejected: Vec<RejectedLine>,
+    /// 読み込み後の新しいチェックポイント（差分読み込み非対応の実装では `None`）
+    pub checkpoint: Option<FileCheckpoint>,
+}
+
+impl ParsedLogFile {
+    /// パース済みのレコードから作成する（チェックポイントなし）
+    pub fn from_records(records: Vec<TranscriptRecord>) -> Self {
+        Self {
+            records,
+            rejected: Vec::new(),
+            checkpoint: None,
+        }
+    }
+
+    /// ストリームを最後まで読み込んでまとめる
+    pub async fn collect(mut stream: LogStream<'_>) -> Result<Self> {
+        let mut parsed = Self::from_records(Vec::new());
+        while let Some(item) = stream.next().await {
+            match item? {
+                LogStreamItem::Record(record) => parsed.records.push(*record),
+                LogStreamItem::Rejected(reject) => parsed.rejected.push(*reject),
+                LogStreamItem::Checkpoint(cp) => parsed.checkpoint = Some(cp),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// レコード、パースできなかった行、チェックポイントの順に流すストリームに変換する
+    pub fn into_stream<'a>(self) -> LogStream<'a> {
+        let records = self
+            .records
+            .into_iter()
+            .map(|record| Ok(LogStreamItem::Record(Box::new(record))));
+        let rejected = self
+            .rejected
+            .into_iter()
+            .map(|reject| Ok(LogStreamItem::Rejected(Box::new(reject))));
+        let checkpoint = self.checkpoint.map(|cp| Ok(LogStreamItem::Checkpoint(cp)));
+        stream::iter(
+            records
+                .chain(rejected)
+                .chain(checkpoint)
+                .collect::<Vec<_>>(),
+        )
+        .boxed()
+    }
+}
+
+/// ログストリームの要素
+#[derive(Debug, Clone)]
+pub enum LogStreamItem {
+    /// パースされたレコード（1行分）
+    Record(Box<TranscriptRecord>),
+    /// パースできなかった行（隔離ストアに保存される）
+    Rejected(Box<RejectedLine>),
+    /// ファイルを最後まで読み終えた時点のチェックポイント（ストリームの最後に1回だけ流れる）
+    Checkpoint(FileCheckpoint),
+}
+
+/// ログファイルを1行ずつ読み込むストリーム
+pub type LogStream<'a> = BoxStream<'a, Result<LogStreamItem>>;
 
 /// ログリポジトリ
 ///
@@ -24,7 +97,76 @@ pub trait LogRepository: Send + Sync {
     /// 発見されたログファイルのパスのリスト
     async fn discover_log_files(&self, log_dir: &str) -> Result<Vec<PathBuf>>;
 
-    /// ログファイルをパースする
+    /// ログファイルの更新時刻を取得する
+    ///
+    /// デフォルト実装は更新時刻を持たない（`None`）。
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - ログファイルのパス
+    async fn modified_at(&self, _file_path: &Path) -> Result<Option<DateTime<Utc>>> {
+        Ok(None)
+    }
+
+    /// ログファイルが封印済みか削除済みかを判定する
+    ///
+    /// 封印済みとは、チェックポイントの位置まで読み込み済みで、その後変更されていないこと。
+    /// 状態の圧縮で、UUIDを忘れても再アップロードされないことの確認に使う。
+    /// デフォルト実装は判定できないため `false` を返す（圧縮の対象にしない）。
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - ログファイルのパス
+    /// * `checkpoint` - コミット済みのチェックポイント
+    async fn is_sealed(
+        &self,
+        _file_path: &Path,
+        _checkpoint: Option<&FileCheckpoint>,
+    ) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// チェックポイント以降をストリームとして読み込む
+    ///
+    /// ファイル全体をメモリに載せずに1行ずつレコードを流す。
+    /// 差分読み込みに対応する実装は、最後に新しいチェックポイントを流す。
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - ログファイルのパス
+    /// * `checkpoint` - 前回コミットされたチェックポイント
+    ///
+    /// # Returns
+    ///
+    /// レコード（パースできなかった行を含む）と、最後に新しいチェックポイント（対応している場合）を流すストリーム
+    fn stream_log_file(
+        &self,
+        file_path: PathBuf,
+        checkpoint: Option<FileCheckpoint>,
+    ) -> LogStream<'_>;
+
+    /// チェックポイント以降をまとめてパースする
+    ///
+    /// `stream_log_file` を最後まで読み込んだ結果を返す。
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - ログファイルのパス
+    /// * `checkpoint` - 前回コミットされたチェックポイント
+    ///
+    /// # Returns
+    ///
+    /// パースされたレコードと新しいチェックポイント
+    async fn parse_log_file_incremental(
+        &self,
+        file_path: &Path,
+        checkpoint: Option<&FileCheckpoint>,
+    ) -> Result<ParsedLogFile> {
+        ParsedLogFile::collect(self.stream_log_file(file_path.to_path_buf(), checkpoint.cloned()))
+            .await
+    }
+
+    /// ログファイル全体をパースする
     ///
     /// # Arguments
     ///
@@ -32,6 +174,11 @@ pub trait LogRepository: Send + Sync {
     ///
     /// # Returns
     ///
-    /// パースされたセッションログのリスト
-    async fn parse_log_file(&self, file_path: &Path) -> Result<Vec<SessionLogInput>>;
+    /// パースされたレコードのリスト
+    async fn parse_log_file(&self, file_path: &Path) -> Result<Vec<TranscriptRecord>> {
+        Ok(self
+            .parse_log_file_incremental(file_path, None)
+            .await?
+            .records)
+    }
 }
@@ -8,6 +8,9 @@
 //! - Adapter層で具体的な実装を提供
 //! - 依存性逆転の原則（DIP）を実現
 
+pub mod journal_repository;
 pub mod log_repository;
+pub mod reject_repository;
+pub mod remote_record_repository;
 pub mod state_repository;
 pub mod upload_repository;
@@ -0,0 +1,53 @@
+//! # Reject Repository Trait
+//!
+//! パースできなかったログ行の隔離ストアを抽象化
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::domain::entities::rejected_line::RejectedLine;
+
+/// 隔離ストアのリポジトリ
+///
+/// パースできなかったログ行の永続化を担当するリポジトリ
+#[async_trait]
+pub trait RejectRepository: Send + Sync {
+    /// 隔離された行を全て読み込む
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - 隔離ストアのパス
+    ///
+    /// # Returns
+    ///
+    /// 隔離された行のリスト（ストアが存在しない場合は空）
+    ///
+    /// # Errors
+    ///
+    /// ストアの読み込みに失敗した場合にエラーを返す
+    async fn load(&self, path: &str) -> Result<Vec<RejectedLine>>;
+
+    /// 隔離された行を追記する
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - 隔離ストアのパス
+    /// * `rejects` - 追記する行
+    ///
+    /// # Errors
+    ///
+    /// ストアへの書き込みに失敗した場合にエラーを返す
+    async fn append(&self, path: &str, rejects: &[RejectedLine]) -> Result<()>;
+
+    /// 隔離ストアの内容を置き換える
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - 隔離ストアのパス
+    /// * `rejects` - 新しい内容
+    ///
+    /// # Errors
+    ///
+    /// ストアへの書き込みに失敗した場合にエラーを返す
+    async fn replace(&self, path: &str, rejects: &[RejectedLine]) -> Result<()>;
+}
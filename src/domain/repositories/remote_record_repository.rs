@@ -0,0 +1,48 @@
+//! # Remote Record Repository Trait
+//!
+//! 送信先に既に存在するレコードの照会を抽象化
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// 送信先に存在するレコード
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteRecord {
+    /// レコードのID（重複排除キー）
+    pub uuid: String,
+    /// セッションID
+    pub session_id: String,
+    /// アップロード元のログファイルのパス
+    pub source_file: String,
+    /// レコードのタイムスタンプ
+    pub timestamp: DateTime<Utc>,
+    /// アップロード日時
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// 送信先のレコードを照会するリポジトリ
+///
+/// ローカルの状態が失われた場合に、送信済みのレコードから状態を再構築するために使う
+#[async_trait]
+pub trait RemoteRecordRepository: Send + Sync {
+    /// タイムスタンプが期間内のレコードを返す
+    ///
+    /// # Arguments
+    ///
+    /// * `since` - 期間の開始（この日時を含む）
+    /// * `until` - 期間の終了（この日時を含む）
+    ///
+    /// # Returns
+    ///
+    /// 送信先に存在するレコード（同じIDが複数回含まれる場合がある）
+    ///
+    /// # Errors
+    ///
+    /// 照会に失敗した場合にエラーを返す
+    async fn find_records(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<RemoteRecord>>;
+}
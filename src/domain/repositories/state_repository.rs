@@ -4,8 +4,11 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::domain::entities::file_checkpoint::FileCheckpoint;
 
 /// アップロード状態
 ///
@@ -20,6 +23,21 @@ pub struct UploadState {
     pub last_upload_batch_id: Option<String>,
     /// アップロード総数
     pub total_uploaded: u64,
+    /// ログファイルごとのチェックポイント（キーはファイルパス）
+    #[serde(default)]
+    pub file_checkpoints: HashMap<String, FileCheckpoint>,
+    /// セッションごとのアップロード状況（キーはセッションID）
+    #[serde(default)]
+    pub sessions: HashMap<String, SessionUploads>,
+    /// アップロード済みレコードの内容のハッシュ（キーはUUID）
+    #[serde(default)]
+    pub record_hashes: HashMap<String, RecordHash>,
+    /// アップロード済みレコードの記録（`uploaded_uuids`・`record_hashes`・セッションのUUID）を
+    /// 読み込んでいないか（[`StateRepository::load_for_upload`]）
+    ///
+    /// 立っている場合、照合するレコードは [`StateRepository::find_records`] で読み込む。
+    #[serde(skip)]
+    pub records_omitted: bool,
 }
 
 impl UploadState {
@@ -30,6 +48,10 @@ impl UploadState {
             uploaded_uuids: HashSet::new(),
             last_upload_batch_id: None,
             total_uploaded: 0,
+            file_checkpoints: HashMap::new(),
+            sessions: HashMap::new(),
+            record_hashes: HashMap::new(),
+            records_omitted: false,
         }
     }
 
@@ -38,6 +60,83 @@ impl UploadState {
         self.uploaded_uuids.contains(uuid)
     }
 
+    /// レコードがアップロード済みかどうかを確認します。
+    ///
+    /// UUIDに加え、圧縮済みセッションの透かし以前のタイムスタンプを持つレコードも
+    /// アップロード済みとみなします。透かしはセッションのレコードを読み込んだログファイル
+    /// （圧縮・移動後も同じファイル名のもの）のレコードにだけ適用し、絞り込みで除外していた
+    /// ファイルを後から読み込んだ場合はUUIDだけで判定します
+    /// （ファイルを記録する前のセッションは全てのファイルに適用）。
+    ///
+    /// 隔離した行の再試行（`rejects retry`）は透かしを使わず、UUIDだけで判定してください。
+    /// 隔離した行はアップロードされていなくても透かしの範囲に含まれるためです。
+    ///
+    /// # 引数
+    ///
+    /// * `uuid` - レコードのID（重複排除キー）
+    /// * `session_id` - セッションID
+    /// * `source_file` - レコードを読み込んだログファイル
+    /// * `timestamp` - レコードのタイムスタンプ
+    pub fn is_record_uploaded(
+        &self,
+        uuid: &str,
+        session_id: &str,
+        source_file: &str,
+        timestamp: DateTime<Utc>,
+    ) -> bool {
+        self.is_uploaded(uuid)
+            || self.sessions.get(session_id).is_some_and(|session| {
+                session
+                    .compacted_until
+                    .is_some_and(|watermark| timestamp <= watermark)
+                    && (session.source_files.is_empty()
+                        || session
+                            .source_files
+                            .iter()
+                            .any(|file| transcript_name(file) == transcript_name(source_file)))
+            })
+    }
+
+    /// アップロード済みのレコードの内容が変わったかどうかを確認します。
+    ///
+    /// ハッシュを記録する前にアップロードしたレコード（ハッシュが不明なもの）は
+    /// 変わっていないとみなします。
+    ///
+    /// # 引数
+    ///
+    /// * `uuid` - レコードのID（重複排除キー）
+    /// * `content_hash` - 現在の内容のハッシュ（[`SessionLog::content_hash`]）
+    ///
+    /// [`SessionLog::content_hash`]: crate::domain::entities::session_log::SessionLog::content_hash
+    pub fn is_content_changed(&self, uuid: &str, content_hash: &str) -> bool {
+        self.record_hashes
+            .get(uuid)
+            .is_some_and(|recorded| recorded.hash != content_hash)
+    }
+
+    /// アップロード済みのレコードの最新バージョンを返す（未記録の場合は0）
+    pub fn record_version(&self, uuid: &str) -> u32 {
+        self.record_hashes
+            .get(uuid)
+            .map_or(0, |recorded| recorded.version)
+    }
+
+    /// 照合用に読み込んだアップロード済みレコードに置き換えます。
+    ///
+    /// [`StateRepository::load_for_upload`] で読み込んだ状態に、
+    /// [`StateRepository::find_records`] の結果を反映するために使います。
+    /// 前に反映したレコードは取り除きます（照合の単位ごとにメモリを解放するため）。
+    pub fn replace_records(&mut self, records: HashMap<String, Option<RecordHash>>) {
+        self.uploaded_uuids.clear();
+        self.record_hashes.clear();
+        for (uuid, hash) in records {
+            if let Some(hash) = hash {
+                self.record_hashes.insert(uuid.clone(), hash);
+            }
+            self.uploaded_uuids.insert(uuid);
+        }
+    }
+
     /// アップロード済みUUIDを追加
     pub fn add_uploaded(&mut self, uuids: Vec<String>, batch_id: String, timestamp: String) {
         for uuid in uuids {
@@ -46,6 +145,189 @@ impl UploadState {
         self.last_upload_batch_id = Some(batch_id);
         self.last_upload_timestamp = Some(timestamp);
     }
+
+    /// ファイルのチェックポイントを取得
+    pub fn checkpoint(&self, source_file: &str) -> Option<&FileCheckpoint> {
+        self.file_checkpoints.get(source_file)
+    }
+
+    /// ファイルのチェックポイントを更新
+    pub fn update_checkpoints(&mut self, checkpoints: HashMap<String, FileCheckpoint>) {
+        self.file_checkpoints.extend(checkpoints);
+    }
+
+    /// 保持期間を過ぎたセッションのログファイルを返します。
+    ///
+    /// 圧縮の前に、これらのファイルが封印済みか削除済みかを確認するために使います。
+    ///
+    /// # 引数
+    ///
+    /// * `cutoff` - この日時より前に最後にアップロードされたセッションが対象
+    pub fn compaction_candidates(&self, cutoff: DateTime<Utc>) -> BTreeSet<String> {
+        self.sessions
+            .values()
+            .filter(|session| session.is_expired(cutoff))
+            .flat_map(|session| session.source_files.iter().cloned())
+            .collect()
+    }
+
+    /// 保持期間を過ぎたセッションのUUIDを忘れ、タイムスタンプの透かしに置き換えます。
+    ///
+    /// ログファイルが全て封印済み（最後まで読み込み済みで以後変更がない）か削除済みの
+    /// セッションだけが対象です。透かし以前のレコードは以後もアップロード済みとみなされるため
+    /// （[`UploadState::is_record_uploaded`]）、ファイルを読み直しても再アップロードされません。
+    ///
+    /// # 引数
+    ///
+    /// * `cutoff` - この日時より前に最後にアップロードされたセッションが対象
+    /// * `is_sealed` - ログファイルが封印済みまたは削除済みかを判定する関数
+    ///
+    /// # 戻り値
+    ///
+    /// 圧縮したセッション数と忘れたUUIDの数
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::domain::repositories::state_repository::{
+    ///     StateUpdate, UploadState, UploadedRecord,
+    /// };
+    /// use chrono::{Duration, TimeZone, Utc};
+    ///
+    /// let recorded_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    /// let mut state = UploadState::new();
+    /// StateUpdate {
+    ///     batch_id: "batch-001".to_string(),
+    ///     timestamp: recorded_at,
+    ///     uploaded: vec![UploadedRecord {
+    ///         uuid: "uuid-1".to_string(),
+    ///         session_id: "session-1".to_string(),
+    ///         source_file: "/logs/a.jsonl".to_string(),
+    ///         timestamp: recorded_at,
+    ///         content_hash: None,
+    ///         record_version: 0,
+    ///     }],
+    ///     ..Default::default()
+    /// }
+    /// .apply(&mut state);
+    ///
+    /// let summary = state.compact(recorded_at + Duration::days(30), |_| true);
+    ///
+    /// assert_eq!(summary.forgotten_uuids, 1);
+    /// assert!(!state.is_uploaded("uuid-1"));
+    /// // 透かし以前のレコードはアップロード済みのまま
+    /// assert!(state.is_record_uploaded("uuid-1", "session-1", "/logs/a.jsonl", recorded_at));
+    /// // 透かしは読み込んだことのないファイルのレコードには適用しない
+    /// assert!(!state.is_record_uploaded("uuid-2", "session-1", "/logs/b.jsonl", recorded_at));
+    /// ```
+    pub fn compact(
+        &mut self,
+        cutoff: DateTime<Utc>,
+        is_sealed: impl Fn(&str) -> bool,
+    ) -> CompactionSummary {
+        let mut summary = CompactionSummary::default();
+
+        for session in self.sessions.values_mut() {
+            if !session.is_expired(cutoff)
+                || !session.source_files.iter().all(|file| is_sealed(file))
+            {
+                continue;
+            }
+
+            for uuid in std::mem::take(&mut session.uuids) {
+                self.record_hashes.remove(&uuid);
+                if self.uploaded_uuids.remove(&uuid) {
+                    summary.forgotten_uuids += 1;
+                }
+            }
+            session.compacted_until = session.compacted_until.max(session.latest_record_at);
+            summary.sessions += 1;
+        }
+
+        summary
+    }
+
+    /// 条件に合うセッションのアップロード記録を忘れ、次回の実行で再アップロードされるようにします。
+    ///
+    /// セッションのUUID・内容のハッシュ・圧縮時の透かしに加え、ログファイルのチェックポイントも削除します
+    /// （先頭から読み直さないと、チェックポイント以前のレコードが流れないため）。
+    /// セッション情報を記録する前にアップロードしたUUIDは対象外です。
+    ///
+    /// # 引数
+    ///
+    /// * `select` - セッションIDとアップロード状況を受け取り、忘れるかどうかを返す関数
+    ///
+    /// # 戻り値
+    ///
+    /// 忘れたセッション数・UUIDの数・チェックポイントの数
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::domain::repositories::state_repository::{
+    ///     StateUpdate, UploadState, UploadedRecord,
+    /// };
+    /// use sessync::domain::entities::file_checkpoint::FileCheckpoint;
+    /// use chrono::Utc;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut state = UploadState::new();
+    /// StateUpdate {
+    ///     batch_id: "batch-001".to_string(),
+    ///     timestamp: Utc::now(),
+    ///     uploaded: vec![UploadedRecord {
+    ///         uuid: "uuid-1".to_string(),
+    ///         session_id: "session-1".to_string(),
+    ///         source_file: "/logs/a.jsonl".to_string(),
+    ///         timestamp: Utc::now(),
+    ///         content_hash: None,
+    ///         record_version: 0,
+    ///     }],
+    ///     checkpoints: HashMap::from([(
+    ///         "/logs/a.jsonl".to_string(),
+    ///         FileCheckpoint::new(None, 10, None, 10, 1),
+    ///     )]),
+    /// }
+    /// .apply(&mut state);
+    ///
+    /// let summary = state.forget_sessions(|session_id, _| session_id == "session-1");
+    ///
+    /// assert_eq!(summary.uuids, 1);
+    /// assert!(!state.is_uploaded("uuid-1"));
+    /// assert!(state.checkpoint("/logs/a.jsonl").is_none());
+    /// ```
+    pub fn forget_sessions(
+        &mut self,
+        select: impl Fn(&str, &SessionUploads) -> bool,
+    ) -> ForgetSummary {
+        let mut summary = ForgetSummary::default();
+
+        let selected: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(session_id, session)| select(session_id, session))
+            .map(|(session_id, _)| session_id.clone())
+            .collect();
+        for session_id in selected {
+            let Some(session) = self.sessions.remove(&session_id) else {
+                continue;
+            };
+            for uuid in &session.uuids {
+                self.record_hashes.remove(uuid);
+                if self.uploaded_uuids.remove(uuid) {
+                    summary.uuids += 1;
+                }
+            }
+            for source_file in &session.source_files {
+                if self.file_checkpoints.remove(source_file).is_some() {
+                    summary.checkpoints += 1;
+                }
+            }
+            summary.sessions += 1;
+        }
+
+        summary
+    }
 }
 
 impl Default for UploadState {
@@ -54,6 +336,210 @@ impl Default for UploadState {
     }
 }
 
+/// ログファイルのファイル名（圧縮形式の拡張子を除く）
+///
+/// 圧縮・移動されたログファイルを元のファイルと同じものとして扱うために使う。
+fn transcript_name(source_file: &str) -> &str {
+    let name = source_file
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(source_file);
+    name.strip_suffix(".gz")
+        .or_else(|| name.strip_suffix(".zst"))
+        .unwrap_or(name)
+}
+
+/// セッションごとのアップロード状況
+///
+/// 保持期間の判定と、UUIDを忘れた後の透かしの管理に使う。
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SessionUploads {
+    /// 最後にアップロードした日時
+    pub last_uploaded_at: DateTime<Utc>,
+    /// アップロード済みレコードのタイムスタンプの最大値（記録前のセッションでは `None`）
+    pub latest_record_at: Option<DateTime<Utc>>,
+    /// このセッションのレコードを読み込んだログファイル
+    #[serde(default)]
+    pub source_files: BTreeSet<String>,
+    /// アップロード済みのUUID（圧縮すると空になる）
+    #[serde(default)]
+    pub uuids: BTreeSet<String>,
+    /// 圧縮時の透かし（これ以前のタイムスタンプのレコードはアップロード済みとみなす）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compacted_until: Option<DateTime<Utc>>,
+}
+
+impl SessionUploads {
+    /// 保持期間を過ぎていて圧縮できるUUIDを持つかを判定
+    fn is_expired(&self, cutoff: DateTime<Utc>) -> bool {
+        !self.uuids.is_empty() && self.latest_record_at.is_some() && self.last_uploaded_at < cutoff
+    }
+
+    /// 別の記録と統合する（UUIDとファイルは和集合、日時は新しい方を残す）
+    pub fn merge(&mut self, other: &SessionUploads) {
+        self.last_uploaded_at = self.last_uploaded_at.max(other.last_uploaded_at);
+        self.latest_record_at = self.latest_record_at.max(other.latest_record_at);
+        self.source_files.extend(other.source_files.iter().cloned());
+        self.uuids.extend(other.uuids.iter().cloned());
+        self.compacted_until = self.compacted_until.max(other.compacted_until);
+    }
+}
+
+/// アップロード済みレコードの内容のハッシュ
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RecordHash {
+    /// 最後にアップロードした内容のハッシュ
+    pub hash: String,
+    /// 最後にアップロードしたバージョン（最初のアップロードは0）
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub version: u32,
+}
+
+fn is_zero(version: &u32) -> bool {
+    *version == 0
+}
+
+/// 状態の圧縮結果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactionSummary {
+    /// 圧縮したセッション数
+    pub sessions: usize,
+    /// 忘れたUUIDの数
+    pub forgotten_uuids: usize,
+}
+
+/// アップロード記録の削除結果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ForgetSummary {
+    /// 忘れたセッション数
+    pub sessions: usize,
+    /// 忘れたUUIDの数
+    pub uuids: usize,
+    /// 削除したチェックポイントの数
+    pub checkpoints: usize,
+}
+
+/// アップロード済みのレコード
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedRecord {
+    /// レコードのID（重複排除キー）
+    pub uuid: String,
+    /// セッションID
+    pub session_id: String,
+    /// 元のログファイルのパス
+    pub source_file: String,
+    /// レコードのタイムスタンプ
+    pub timestamp: DateTime<Utc>,
+    /// アップロードした内容のハッシュ（不明な場合は `None`）
+    pub content_hash: Option<String>,
+    /// アップロードしたバージョン（最初のアップロードは0）
+    pub record_version: u32,
+}
+
+/// 1回のアップロードで状態に加える変更
+#[derive(Debug, Clone, Default)]
+pub struct StateUpdate {
+    /// アップロードバッチID
+    pub batch_id: String,
+    /// アップロード日時
+    pub timestamp: DateTime<Utc>,
+    /// アップロードされたレコード
+    pub uploaded: Vec<UploadedRecord>,
+    /// コミットするチェックポイント（キーはファイルパス）
+    pub checkpoints: HashMap<String, FileCheckpoint>,
+}
+
+impl StateUpdate {
+    /// 反映する変更がないかを判定します。
+    pub fn is_empty(&self) -> bool {
+        self.uploaded.is_empty() && self.checkpoints.is_empty()
+    }
+
+    /// 変更を状態に反映します。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::domain::repositories::state_repository::{
+    ///     StateUpdate, UploadState, UploadedRecord,
+    /// };
+    /// use chrono::Utc;
+    ///
+    /// let update = StateUpdate {
+    ///     batch_id: "batch-001".to_string(),
+    ///     timestamp: Utc::now(),
+    ///     uploaded: vec![UploadedRecord {
+    ///         uuid: "uuid-1".to_string(),
+    ///         session_id: "session-1".to_string(),
+    ///         source_file: "/logs/a.jsonl".to_string(),
+    ///         timestamp: Utc::now(),
+    ///         content_hash: Some("hash-1".to_string()),
+    ///         record_version: 0,
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut state = UploadState::new();
+    /// update.apply(&mut state);
+    ///
+    /// assert!(state.is_uploaded("uuid-1"));
+    /// assert!(state.is_content_changed("uuid-1", "hash-2"));
+    /// assert_eq!(state.total_uploaded, 1);
+    /// assert_eq!(state.last_upload_batch_id.as_deref(), Some("batch-001"));
+    /// assert_eq!(state.sessions["session-1"].uuids.len(), 1);
+    /// ```
+    pub fn apply(&self, state: &mut UploadState) {
+        if !self.uploaded.is_empty() {
+            state.add_uploaded(
+                self.uploaded.iter().map(|r| r.uuid.clone()).collect(),
+                self.batch_id.clone(),
+                self.timestamp.to_rfc3339(),
+            );
+            state.total_uploaded += self.uploaded.len() as u64;
+        }
+        for record in &self.uploaded {
+            if let Some(hash) = &record.content_hash {
+                state.record_hashes.insert(
+                    record.uuid.clone(),
+                    RecordHash {
+                        hash: hash.clone(),
+                        version: record.record_version,
+                    },
+                );
+            }
+        }
+        for (session_id, session) in self.sessions() {
+            match state.sessions.get_mut(&session_id) {
+                Some(existing) => existing.merge(&session),
+                None => {
+                    state.sessions.insert(session_id, session);
+                }
+            }
+        }
+        state.update_checkpoints(self.checkpoints.clone());
+    }
+
+    /// アップロードされたレコードをセッションごとに集計します。
+    pub fn sessions(&self) -> HashMap<String, SessionUploads> {
+        let mut sessions: HashMap<String, SessionUploads> = HashMap::new();
+        for record in &self.uploaded {
+            let session = sessions
+                .entry(record.session_id.clone())
+                .or_insert_with(|| SessionUploads {
+                    last_uploaded_at: self.timestamp,
+                    latest_record_at: None,
+                    source_files: BTreeSet::new(),
+                    uuids: BTreeSet::new(),
+                    compacted_until: None,
+                });
+            session.latest_record_at = session.latest_record_at.max(Some(record.timestamp));
+            session.source_files.insert(record.source_file.clone());
+            session.uuids.insert(record.uuid.clone());
+        }
+        sessions
+    }
+}
+
 /// 状態リポジトリ
 ///
 /// アップロード状態の永続化を担当するリポジトリ
@@ -74,8 +560,28 @@ pub trait StateRepository: Send + Sync {
     /// ファイルの読み込みに失敗した場合にエラーを返す
     async fn load(&self, path: &str) -> Result<UploadState>;
 
+    /// アップロード（重複排除）用に状態を読み込む
+    ///
+    /// デフォルト実装は `load` と同じく状態全体を読み込む。アップロード済みレコードを
+    /// 索引で引ける実装は、レコードの記録を読み込まずに `records_omitted` を立て、
+    /// 照合するレコードだけを `find_records` で返す。
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - 状態ファイルのパス
+    ///
+    /// # Errors
+    ///
+    /// ファイルの読み込みに失敗した場合にエラーを返す
+    async fn load_for_upload(&self, path: &str) -> Result<UploadState> {
+        self.load(path).await
+    }
+
     /// 状態を保存する
     ///
+    /// 読み込み後に他のプロセスが保存した内容を失わないよう、保存済みの状態とマージする
+    /// （アップロード済みUUIDは和集合を取り、削除はしない）。
+    ///
     /// # Arguments
     ///
     /// * `path` - 状態ファイルのパス
@@ -85,6 +591,105 @@ pub trait StateRepository: Send + Sync {
     ///
     /// ファイルの書き込みに失敗した場合にエラーを返す
     async fn save(&self, path: &str, state: &UploadState) -> Result<()>;
+
+    /// 状態を置き換える
+    ///
+    /// `save` と違い保存済みの状態とマージしないため、UUIDやチェックポイントを取り除ける
+    /// （圧縮など）。他のプロセスの書き込みを消さないよう、実行ロックを取得した上で呼び出す。
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - 状態ファイルのパス
+    /// * `state` - 新しいアップロード状態
+    ///
+    /// # Errors
+    ///
+    /// ファイルの書き込みに失敗した場合にエラーを返す
+    async fn replace(&self, path: &str, state: &UploadState) -> Result<()>;
+
+    /// アップロード結果を状態に反映する
+    ///
+    /// デフォルト実装は状態全体を読み込み、変更を加えて保存する。
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - 状態ファイルのパス
+    /// * `update` - 反映する変更
+    ///
+    /// # Errors
+    ///
+    /// 状態の読み込みまたは保存に失敗した場合にエラーを返す
+    async fn commit(&self, path: &str, update: &StateUpdate) -> Result<()> {
+        let mut state = self.load(path).await?;
+        update.apply(&mut state);
+        self.save(path, &state).await
+    }
+
+    /// 指定したIDのうちアップロード済みのものを返す
+    ///
+    /// デフォルト実装は状態全体を読み込んで照合する。
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - 状態ファイルのパス
+    /// * `ids` - 照合するレコードのID
+    ///
+    /// # Returns
+    ///
+    /// アップロード済みのID
+    async fn find_uploaded(&self, path: &str, ids: &[String]) -> Result<HashSet<String>> {
+        let state = self.load(path).await?;
+        Ok(ids
+            .iter()
+            .filter(|id| state.is_uploaded(id))
+            .cloned()
+            .collect())
+    }
+
+    /// 指定したIDのうちアップロード済みのものを、内容のハッシュとともに返す
+    ///
+    /// `load_for_upload` で読み込んだ状態（`records_omitted`）の照合に使う。
+    /// デフォルト実装は状態全体を読み込んで照合する。
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - 状態ファイルのパス
+    /// * `ids` - 照合するレコードのID
+    ///
+    /// # Returns
+    ///
+    /// アップロード済みのIDと内容のハッシュ（ハッシュを記録する前のレコードは `None`）
+    async fn find_records(
+        &self,
+        path: &str,
+        ids: &[String],
+    ) -> Result<HashMap<String, Option<RecordHash>>> {
+        let state = self.load(path).await?;
+        Ok(ids
+            .iter()
+            .filter(|id| state.is_uploaded(id))
+            .map(|id| (id.clone(), state.record_hashes.get(id).cloned()))
+            .collect())
+    }
+
+    /// 状態の保存先のサイズを返す
+    ///
+    /// デフォルト実装はサイズを持たないものとして `None` を返す。
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - 状態ファイルのパス
+    ///
+    /// # Returns
+    ///
+    /// 保存先のバイト数（まだ作成されていない場合は `None`）
+    ///
+    /// # Errors
+    ///
+    /// サイズの取得に失敗した場合にエラーを返す
+    async fn storage_size(&self, _path: &str) -> Result<Option<u64>> {
+        Ok(None)
+    }
 }
 
 #[cfg(test)]
@@ -126,6 +731,307 @@ mod tests {
         assert_eq!(state.last_upload_timestamp, Some(timestamp));
     }
 
+    #[test]
+    fn test_update_checkpoints() {
+        let mut state = UploadState::new();
+        let checkpoint = FileCheckpoint::new(Some(1), 100, Some(10), 100, 2);
+        state.update_checkpoints(HashMap::from([(
+            "/logs/a.jsonl".to_string(),
+            checkpoint.clone(),
+        )]));
+
+        assert_eq!(state.checkpoint("/logs/a.jsonl"), Some(&checkpoint));
+        assert!(state.checkpoint("/logs/b.jsonl").is_none());
+
+        // 同じファイルは上書きされる
+        let newer = FileCheckpoint::new(Some(1), 200, Some(20), 200, 4);
+        state.update_checkpoints(HashMap::from([(
+            "/logs/a.jsonl".to_string(),
+            newer.clone(),
+        )]));
+        assert_eq!(state.checkpoint("/logs/a.jsonl"), Some(&newer));
+    }
+
+    #[test]
+    fn test_deserialize_legacy_state_without_checkpoints() {
+        let json = r#"{
+            "last_upload_timestamp": null,
+            "uploaded_uuids": ["uuid-1"],
+            "last_upload_batch_id": null,
+            "total_uploaded": 1
+        }"#;
+        let state: UploadState = serde_json::from_str(json).unwrap();
+        assert!(state.file_checkpoints.is_empty());
+        assert!(state.sessions.is_empty());
+        assert!(state.is_uploaded("uuid-1"));
+    }
+
+    fn time(hour: u32) -> DateTime<Utc> {
+        use chrono::TimeZone;
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    fn uploaded(uuid: &str, session_id: &str, source_file: &str, hour: u32) -> UploadedRecord {
+        UploadedRecord {
+            uuid: uuid.to_string(),
+            session_id: session_id.to_string(),
+            source_file: source_file.to_string(),
+            timestamp: time(hour),
+            content_hash: None,
+            record_version: 0,
+        }
+    }
+
+    #[test]
+    fn test_state_update_apply_tracks_sessions() {
+        let mut state = UploadState::new();
+        StateUpdate {
+            batch_id: "batch-001".to_string(),
+            timestamp: time(12),
+            uploaded: vec![
+                uploaded("uuid-1", "session-1", "/logs/a.jsonl", 10),
+                uploaded("uuid-2", "session-1", "/logs/a/subagents/agent-1.jsonl", 11),
+                uploaded("uuid-3", "session-2", "/logs/b.jsonl", 9),
+            ],
+            ..Default::default()
+        }
+        .apply(&mut state);
+        StateUpdate {
+            batch_id: "batch-002".to_string(),
+            timestamp: time(13),
+            uploaded: vec![uploaded("uuid-4", "session-1", "/logs/a.jsonl", 8)],
+            ..Default::default()
+        }
+        .apply(&mut state);
+
+        let session = &state.sessions["session-1"];
+        assert_eq!(session.last_uploaded_at, time(13));
+        assert_eq!(session.latest_record_at, Some(time(11)));
+        assert_eq!(session.source_files.len(), 2);
+        assert_eq!(session.uuids.len(), 3);
+        assert_eq!(state.sessions["session-2"].uuids.len(), 1);
+    }
+
+    #[test]
+    fn test_compact_only_expired_sealed_sessions() {
+        let mut state = UploadState::new();
+        StateUpdate {
+            timestamp: time(1),
+            uploaded: vec![
+                uploaded("uuid-1", "session-1", "/logs/a.jsonl", 0),
+                uploaded("uuid-2", "session-2", "/logs/b.jsonl", 0),
+            ],
+            ..Default::default()
+        }
+        .apply(&mut state);
+        StateUpdate {
+            timestamp: time(5),
+            uploaded: vec![uploaded("uuid-3", "session-3", "/logs/c.jsonl", 0)],
+            ..Default::default()
+        }
+        .apply(&mut state);
+        // 記録前のUUIDはセッションに紐付かないため対象外
+        state.uploaded_uuids.insert("uuid-legacy".to_string());
+
+        let cutoff = time(3);
+        assert_eq!(
+            state.compaction_candidates(cutoff),
+            BTreeSet::from(["/logs/a.jsonl".to_string(), "/logs/b.jsonl".to_string()])
+        );
+
+        // session-2 のファイルはまだ書き込み中
+        let summary = state.compact(cutoff, |file| file != "/logs/b.jsonl");
+
+        assert_eq!(
+            summary,
+            CompactionSummary {
+                sessions: 1,
+                forgotten_uuids: 1
+            }
+        );
+        assert!(!state.is_uploaded("uuid-1"));
+        assert!(state.is_uploaded("uuid-2"));
+        assert!(state.is_uploaded("uuid-3"));
+        assert!(state.is_uploaded("uuid-legacy"));
+        assert_eq!(state.sessions["session-1"].compacted_until, Some(time(0)));
+        assert!(state.sessions["session-1"].uuids.is_empty());
+        assert!(state
+            .compaction_candidates(cutoff)
+            .contains("/logs/b.jsonl"));
+        assert!(!state
+            .compaction_candidates(cutoff)
+            .contains("/logs/a.jsonl"));
+    }
+
+    #[test]
+    fn test_watermark_applies_only_to_files_read_before_compaction() {
+        let mut state = UploadState::new();
+        StateUpdate {
+            timestamp: time(1),
+            uploaded: vec![uploaded("uuid-1", "session-1", "/logs/a.jsonl", 5)],
+            ..Default::default()
+        }
+        .apply(&mut state);
+        state.compact(time(3), |_| true);
+
+        assert!(state.is_record_uploaded("uuid-2", "session-1", "/logs/a.jsonl", time(4)));
+        // 圧縮・移動したファイルを読み直しても再アップロードしない
+        assert!(state.is_record_uploaded("uuid-2", "session-1", "/archive/a.jsonl.gz", time(4)));
+        // 絞り込みで除外していたサブエージェントのファイルは後から取り込める
+        assert!(!state.is_record_uploaded(
+            "uuid-3",
+            "session-1",
+            "/logs/a/subagents/agent-1.jsonl",
+            time(4)
+        ));
+
+        // ファイルを記録する前に圧縮したセッションは全てのファイルに適用する
+        state
+            .sessions
+            .get_mut("session-1")
+            .unwrap()
+            .source_files
+            .clear();
+        assert!(state.is_record_uploaded(
+            "uuid-3",
+            "session-1",
+            "/logs/a/subagents/agent-1.jsonl",
+            time(4)
+        ));
+    }
+
+    #[test]
+    fn test_forget_sessions_removes_uuids_watermarks_and_checkpoints() {
+        let mut state = UploadState::new();
+        StateUpdate {
+            timestamp: time(1),
+            uploaded: vec![
+                uploaded("uuid-1", "session-1", "/logs/a.jsonl", 0),
+                uploaded("uuid-2", "session-2", "/logs/b.jsonl", 0),
+            ],
+            checkpoints: HashMap::from([
+                (
+                    "/logs/a.jsonl".to_string(),
+                    FileCheckpoint::new(None, 10, None, 10, 1),
+                ),
+                (
+                    "/logs/b.jsonl".to_string(),
+                    FileCheckpoint::new(None, 10, None, 10, 1),
+                ),
+            ]),
+            ..Default::default()
+        }
+        .apply(&mut state);
+        state.compact(time(3), |file| file == "/logs/a.jsonl");
+        assert!(state.is_record_uploaded("uuid-1", "session-1", "/logs/a.jsonl", time(0)));
+
+        let summary = state.forget_sessions(|session_id, _| session_id == "session-1");
+
+        assert_eq!(
+            summary,
+            ForgetSummary {
+                sessions: 1,
+                uuids: 0,
+                checkpoints: 1
+            }
+        );
+        // 透かしも消えるため、圧縮済みのレコードも再アップロードされる
+        assert!(!state.is_record_uploaded("uuid-1", "session-1", "/logs/a.jsonl", time(0)));
+        assert!(state.checkpoint("/logs/a.jsonl").is_none());
+        assert!(state.is_uploaded("uuid-2"));
+        assert!(state.checkpoint("/logs/b.jsonl").is_some());
+
+        let summary = state.forget_sessions(|_, _| true);
+        assert_eq!(summary.uuids, 1);
+        assert!(state.sessions.is_empty());
+    }
+
+    #[test]
+    fn test_is_record_uploaded_after_compaction() {
+        let mut state = UploadState::new();
+        StateUpdate {
+            timestamp: time(1),
+            uploaded: vec![uploaded("uuid-1", "session-1", "/logs/a.jsonl", 0)],
+            ..Default::default()
+        }
+        .apply(&mut state);
+        state.compact(time(2), |_| true);
+
+        // 透かし以前のレコードはUUIDを忘れてもアップロード済み
+        assert!(state.is_record_uploaded("uuid-1", "session-1", "/logs/a.jsonl", time(0)));
+        // 透かしより新しいレコードや別セッションは未アップロード
+        assert!(!state.is_record_uploaded("uuid-2", "session-1", "/logs/a.jsonl", time(1)));
+        assert!(!state.is_record_uploaded("uuid-1", "session-2", "/logs/a.jsonl", time(0)));
+
+        // 圧縮後に追加アップロードされても透かしは動かない
+        StateUpdate {
+            timestamp: time(3),
+            uploaded: vec![uploaded("uuid-5", "session-1", "/logs/a.jsonl", 5)],
+            ..Default::default()
+        }
+        .apply(&mut state);
+        assert_eq!(state.sessions["session-1"].compacted_until, Some(time(0)));
+        assert!(!state.is_record_uploaded("uuid-4", "session-1", "/logs/a.jsonl", time(4)));
+    }
+
+    #[test]
+    fn test_record_hashes_track_changes_and_versions() {
+        let hashed = |uuid: &str, hash: &str, version: u32| UploadedRecord {
+            content_hash: Some(hash.to_string()),
+            record_version: version,
+            ..uploaded(uuid, "session-1", "/logs/a.jsonl", 0)
+        };
+        let mut state = UploadState::new();
+        StateUpdate {
+            timestamp: time(1),
+            uploaded: vec![
+                hashed("uuid-1", "hash-a", 0),
+                uploaded("uuid-legacy", "session-1", "/logs/a.jsonl", 0),
+            ],
+            ..Default::default()
+        }
+        .apply(&mut state);
+
+        assert!(!state.is_content_changed("uuid-1", "hash-a"));
+        assert!(state.is_content_changed("uuid-1", "hash-b"));
+        // ハッシュが不明なレコードは変更なしとみなす
+        assert!(!state.is_content_changed("uuid-legacy", "hash-b"));
+        assert_eq!(state.record_version("uuid-1"), 0);
+
+        // 新しいバージョンをアップロードすると、以後はその内容と比較する
+        StateUpdate {
+            timestamp: time(2),
+            uploaded: vec![hashed("uuid-1", "hash-b", 1)],
+            ..Default::default()
+        }
+        .apply(&mut state);
+        assert!(!state.is_content_changed("uuid-1", "hash-b"));
+        assert_eq!(state.record_version("uuid-1"), 1);
+
+        // 圧縮で忘れたUUIDはハッシュも忘れる
+        state.compact(time(3), |_| true);
+        assert!(state.record_hashes.is_empty());
+    }
+
+    #[test]
+    fn test_state_update_apply_checkpoints_only() {
+        let mut state = UploadState::new();
+        let update = StateUpdate {
+            checkpoints: HashMap::from([(
+                "/logs/a.jsonl".to_string(),
+                FileCheckpoint::new(Some(1), 100, Some(10), 100, 2),
+            )]),
+            ..Default::default()
+        };
+        assert!(!update.is_empty());
+
+        update.apply(&mut state);
+
+        assert_eq!(state.total_uploaded, 0);
+        assert!(state.last_upload_batch_id.is_none());
+        assert!(state.checkpoint("/logs/a.jsonl").is_some());
+    }
+
     #[test]
     fn test_default() {
         let state = UploadState::default();
@@ -3,8 +3,25 @@
 //! 重複排除サービス
 
 use crate::domain::entities::session_log::SessionLog;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+/// アップロード済みのレコードの内容が変わっていた場合の扱い
+///
+/// Claude Code がツール結果を後から書き足した場合や、sessync 側の変換処理を変えた場合に、
+/// 同じUUIDのまま内容が変わることがある。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangePolicy {
+    /// 無視する（UUIDだけで重複排除する）
+    #[default]
+    Skip,
+    /// `record_version` を1つ増やした新しい行としてアップロードする
+    NewVersion,
+    /// アップロードせずに報告だけする
+    Report,
+}
+
 /// 重複排除サービス
 ///
 /// セッションログの重複を排除するビジネスロジック
@@ -50,7 +67,7 @@ impl DeduplicationService {
     /// #         user_type: None, message_type: "user".to_string(),
     /// #         slug: None, request_id: None, cwd: None,
     /// #         git_branch: None, version: None,
-    /// #         message: json!({}), tool_use_result: None, metadata,
+    /// #         message: json!({}), tool_use_result: None, extra: None, lineage: None, record_version: 0, metadata,
     /// #     }
     /// # }
     ///
@@ -98,7 +115,7 @@ impl DeduplicationService {
     /// #         user_type: None, message_type: "user".to_string(),
     /// #         slug: None, request_id: None, cwd: None,
     /// #         git_branch: None, version: None,
-    /// #         message: json!({}), tool_use_result: None, metadata,
+    /// #         message: json!({}), tool_use_result: None, extra: None, lineage: None, record_version: 0, metadata,
     /// #     }
     /// # }
     ///
@@ -126,10 +143,32 @@ impl DeduplicationService {
         }
 
         logs.into_iter()
-            .filter(|log| !uploaded_uuids.contains(&log.uuid))
+            .filter(|log| !Self::is_duplicate(&log.uuid, uploaded_uuids, enabled))
             .collect()
     }
 
+    /// 単一のUUIDが重複（アップロード済み）かどうかを判定します。
+    ///
+    /// ストリーム処理でログを1件ずつ判定する際に使用します。
+    ///
+    /// # 例
+    ///
+    /// ```
+    /// use sessync::domain::services::deduplication::DeduplicationService;
+    /// use std::collections::HashSet;
+    ///
+    /// let uploaded = HashSet::from(["uuid-1".to_string()]);
+    ///
+    /// assert!(DeduplicationService::is_duplicate("uuid-1", &uploaded, true));
+    /// assert!(!DeduplicationService::is_duplicate("uuid-2", &uploaded, true));
+    /// // 重複排除が無効な場合は常に false
+    /// assert!(!DeduplicationService::is_duplicate("uuid-1", &uploaded, false));
+    /// ```
+    #[inline]
+    pub fn is_duplicate(uuid: &str, uploaded_uuids: &HashSet<String>, enabled: bool) -> bool {
+        enabled && uploaded_uuids.contains(uuid)
+    }
+
     /// ログのUUIDリストを抽出します。
     ///
     /// # 引数
@@ -164,7 +203,7 @@ impl DeduplicationService {
     /// #         user_type: None, message_type: "user".to_string(),
     /// #         slug: None, request_id: None, cwd: None,
     /// #         git_branch: None, version: None,
-    /// #         message: json!({}), tool_use_result: None, metadata,
+    /// #         message: json!({}), tool_use_result: None, extra: None, lineage: None, record_version: 0, metadata,
     /// #     }
     /// # }
     ///
@@ -218,6 +257,9 @@ mod tests {
             version: None,
             message: json!({}),
             tool_use_result: None,
+            extra: None,
+            lineage: None,
+            record_version: 0,
             metadata,
         }
     }
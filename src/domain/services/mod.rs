@@ -6,6 +6,7 @@
 //!
 //! - エンティティに属さないビジネスルールをカプセル化
 //! - 純粋な関数として実装（外部依存なし）
-//! - ステートレス
+//! - ステートレス（`SessionTree` は1回の処理の間だけ軽量なインデックスを保持）
 
 pub mod deduplication;
+pub mod session_tree;
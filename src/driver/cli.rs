@@ -2,7 +2,7 @@
 //!
 //! CLIの引数解析
 
-use clap::Parser;
+use clap::{ArgGroup, Args as ClapArgs, Parser, Subcommand};
 
 /// セッションログをBigQueryにアップロードするCLI
 #[derive(Parser, Debug, Clone)]
@@ -10,7 +10,7 @@ use clap::Parser;
 #[command(about = "Upload Claude Code session logs to BigQuery", long_about = None)]
 pub struct Args {
     /// Dry run mode - don't actually upload
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub dry_run: bool,
 
     /// Automatic mode (called from session-end hook)
@@ -26,8 +26,131 @@ pub struct Args {
     pub all_projects: bool,
 
     /// Config file path
-    #[arg(short, long, default_value = "./.claude/sessync/config.json")]
+    #[arg(
+        short,
+        long,
+        global = true,
+        default_value = "./.claude/sessync/config.json"
+    )]
     pub config: String,
+
+    /// Only use the configured destinations with this id or type (e.g. `ndjson`)
+    #[arg(long, global = true, value_name = "DESTINATION")]
+    pub destination: Option<String>,
+
+    /// Narrow down which logs are discovered and uploaded
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    /// Subcommand (uploads logs when omitted)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// ログの絞り込み条件（設定ファイルの `filter` セクションより優先）
+#[derive(ClapArgs, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterArgs {
+    /// Only logs at or after this time (RFC 3339, YYYY-MM-DD, or relative like 7d / 12h)
+    #[arg(long, value_name = "TIME")]
+    pub since: Option<String>,
+
+    /// Only logs before this time (RFC 3339, YYYY-MM-DD, or relative like 7d / 12h)
+    #[arg(long, value_name = "TIME")]
+    pub until: Option<String>,
+
+    /// Apply --since/--until to record timestamps or to file modification times
+    #[arg(long, value_parser = ["record", "mtime"])]
+    pub time_basis: Option<String>,
+
+    /// Only logs from this session id (repeatable; includes its sub-agents)
+    #[arg(long = "session", value_name = "ID")]
+    pub sessions: Vec<String>,
+
+    /// Only log files whose path matches this glob (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Skip log files whose path matches this glob (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+}
+
+/// サブコマンド
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Inspect and retry transcript lines that failed to parse
+    Rejects {
+        #[command(subcommand)]
+        action: RejectsAction,
+    },
+    /// Import session logs from an exported .tar, .tar.gz or .zip bundle
+    Import {
+        /// Archive of a ~/.claude/projects directory
+        archive: String,
+    },
+    /// Maintain the local upload state
+    State {
+        #[command(subcommand)]
+        action: StateAction,
+    },
+}
+
+/// `state` サブコマンドの操作
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum StateAction {
+    /// Show totals, per-project and per-session counts, the last batch and the state size
+    Show,
+    /// Write the upload state to a portable JSON file
+    Export {
+        /// Destination file (overwritten if it exists)
+        path: String,
+    },
+    /// Merge an exported JSON state file into the upload state
+    Import {
+        /// File written by `state export`
+        path: String,
+    },
+    /// Forget uploaded records so they are uploaded again on the next run
+    #[command(group(ArgGroup::new("target").required(true).args(["session", "since"])))]
+    Forget {
+        /// Forget this session
+        #[arg(long, value_name = "ID")]
+        session: Option<String>,
+        /// Forget sessions with records at or after this time
+        /// (RFC 3339, YYYY-MM-DD, or relative like 7d / 12h)
+        #[arg(long, value_name = "TIME")]
+        since: Option<String>,
+    },
+    /// Clear the upload state so every log is uploaded again on the next run
+    Reset {
+        /// Skip the confirmation prompt
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Forget uploaded UUIDs of old sessions whose log files are sealed or deleted
+    Compact {
+        /// Keep UUIDs of sessions uploaded within this many days
+        /// (defaults to state_retention_days in the config, or 30)
+        #[arg(long, value_name = "DAYS")]
+        retention_days: Option<u32>,
+    },
+    /// Rebuild the upload state from records already present in the destination table
+    Rebuild {
+        /// Query BigQuery for the records of the local transcripts
+        #[arg(long, required = true)]
+        from_remote: bool,
+    },
+}
+
+/// `rejects` サブコマンドの操作
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectsAction {
+    /// List quarantined lines with their parse errors
+    List,
+    /// Count quarantined lines per log file
+    Count,
+    /// Re-parse quarantined lines and upload the ones that now succeed
+    Retry,
 }
 
 #[cfg(test)]
@@ -40,6 +163,21 @@ mod tests {
         assert_eq!(args.config, "./.claude/sessync/config.json");
         assert!(!args.dry_run);
         assert!(!args.all_projects);
+        assert!(args.command.is_none());
+        assert!(args.destination.is_none());
+        assert_eq!(args.filter, FilterArgs::default());
+    }
+
+    #[test]
+    fn test_args_destination() {
+        let args = Args::parse_from(["sessync", "state", "show", "--destination", "ndjson"]);
+        assert_eq!(args.destination.as_deref(), Some("ndjson"));
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Show
+            })
+        );
     }
 
     #[test]
@@ -67,4 +205,201 @@ mod tests {
         assert!(args.all_projects);
         assert!(args.auto);
     }
+
+    #[test]
+    fn test_args_import() {
+        let args = Args::parse_from(["sessync", "--since", "30d", "import", "bundle.tar.gz"]);
+        assert_eq!(
+            args.command,
+            Some(Command::Import {
+                archive: "bundle.tar.gz".to_string()
+            })
+        );
+        assert_eq!(args.filter.since.as_deref(), Some("30d"));
+    }
+
+    #[test]
+    fn test_args_state_compact() {
+        let args = Args::parse_from(["sessync", "state", "compact", "--retention-days", "7"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Compact {
+                    retention_days: Some(7)
+                }
+            })
+        );
+
+        let args = Args::parse_from(["sessync", "state", "compact"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Compact {
+                    retention_days: None
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_args_state_rebuild() {
+        let args = Args::parse_from(["sessync", "state", "rebuild", "--from-remote"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Rebuild { from_remote: true }
+            })
+        );
+
+        assert!(Args::try_parse_from(["sessync", "state", "rebuild"]).is_err());
+    }
+
+    #[test]
+    fn test_args_state_show_export_import() {
+        let args = Args::parse_from(["sessync", "state", "show"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Show
+            })
+        );
+
+        let args = Args::parse_from(["sessync", "state", "export", "state.json"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Export {
+                    path: "state.json".to_string()
+                }
+            })
+        );
+
+        let args = Args::parse_from(["sessync", "state", "import", "state.json"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Import {
+                    path: "state.json".to_string()
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_args_state_forget() {
+        let args = Args::parse_from(["sessync", "state", "forget", "--session", "s1"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Forget {
+                    session: Some("s1".to_string()),
+                    since: None
+                }
+            })
+        );
+        // 上位の絞り込み条件とは別の引数
+        assert!(args.filter.sessions.is_empty());
+
+        let args = Args::parse_from(["sessync", "state", "forget", "--since", "7d"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Forget {
+                    session: None,
+                    since: Some("7d".to_string())
+                }
+            })
+        );
+
+        assert!(Args::try_parse_from(["sessync", "state", "forget"]).is_err());
+        assert!(Args::try_parse_from([
+            "sessync",
+            "state",
+            "forget",
+            "--session",
+            "s1",
+            "--since",
+            "7d"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_args_state_reset() {
+        let args = Args::parse_from(["sessync", "state", "reset"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Reset { yes: false }
+            })
+        );
+
+        let args = Args::parse_from(["sessync", "state", "reset", "-y"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                action: StateAction::Reset { yes: true }
+            })
+        );
+    }
+
+    #[test]
+    fn test_args_filter() {
+        let args = Args::parse_from([
+            "sessync",
+            "--since",
+            "7d",
+            "--time-basis",
+            "mtime",
+            "--session",
+            "s1",
+            "--session",
+            "s2",
+            "--exclude",
+            "*scratch*",
+        ]);
+        assert_eq!(args.filter.since.as_deref(), Some("7d"));
+        assert!(args.filter.until.is_none());
+        assert_eq!(args.filter.time_basis.as_deref(), Some("mtime"));
+        assert_eq!(args.filter.sessions, vec!["s1", "s2"]);
+        assert!(args.filter.include.is_empty());
+        assert_eq!(args.filter.exclude, vec!["*scratch*"]);
+    }
+
+    #[test]
+    fn test_args_invalid_time_basis() {
+        assert!(Args::try_parse_from(["sessync", "--time-basis", "ctime"]).is_err());
+    }
+
+    #[test]
+    fn test_args_rejects_subcommands() {
+        let args = Args::parse_from(["sessync", "rejects", "list"]);
+        assert_eq!(
+            args.command,
+            Some(Command::Rejects {
+                action: RejectsAction::List
+            })
+        );
+
+        let args = Args::parse_from(["sessync", "rejects", "count"]);
+        assert_eq!(
+            args.command,
+            Some(Command::Rejects {
+                action: RejectsAction::Count
+            })
+        );
+    }
+
+    #[test]
+    fn test_args_rejects_retry_with_global_flags() {
+        let args = Args::parse_from(["sessync", "rejects", "retry", "--dry-run", "-c", "/c.json"]);
+        assert_eq!(
+            args.command,
+            Some(Command::Rejects {
+                action: RejectsAction::Retry
+            })
+        );
+        assert!(args.dry_run);
+        assert_eq!(args.config, "/c.json");
+    }
 }
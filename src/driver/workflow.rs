@@ -2,22 +2,95 @@
 //!
 //! ワークフローのオーケストレーション
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use futures::future;
+use futures::stream::BoxStream;
+use futures::StreamExt;
 use log::info;
 
+use std::fs;
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::adapter::bigquery::client::RealClientFactory;
+use crate::adapter::config::json_config::{DestinationConfig, StateBackend};
 use crate::adapter::config::Config;
+use crate::adapter::lock::RunLock;
+use crate::adapter::repositories::archive_log_repository::ArchiveLogRepository;
+use crate::adapter::repositories::bigquery_remote_record_repository::BigQueryRemoteRecordRepository;
 use crate::adapter::repositories::bigquery_upload_repository::BigQueryUploadRepository;
 use crate::adapter::repositories::file_log_repository::FileLogRepository;
 use crate::adapter::repositories::json_state_repository::JsonStateRepository;
+use crate::adapter::repositories::jsonl_journal_repository::JsonlJournalRepository;
+use crate::adapter::repositories::jsonl_reject_repository::JsonlRejectRepository;
+use crate::adapter::repositories::ndjson_upload_repository::NdjsonUploadRepository;
+use crate::adapter::repositories::parquet_upload_repository::ParquetUploadRepository;
+use crate::adapter::repositories::postgres_upload_repository::PostgresUploadRepository;
+use crate::adapter::repositories::s3_upload_repository::S3UploadRepository;
+use crate::adapter::repositories::sqlite_state_repository::SqliteStateRepository;
+use crate::adapter::repositories::sqlite_upload_repository::SqliteUploadRepository;
+use crate::adapter::repositories::webhook_upload_repository::WebhookUploadRepository;
+use crate::application::dto::log_filter::{parse_time, LogFilter};
+use crate::application::dto::parsed_logs::{ParsedLogs, ParsedRecord};
+use crate::application::dto::upload_config::UploadConfig;
+use crate::application::use_cases::compact_state::CompactStateUseCase;
 use crate::application::use_cases::discover_logs::DiscoverLogsUseCase;
-use crate::application::use_cases::parse_logs::ParseLogsUseCase;
-use crate::application::use_cases::upload_logs::UploadLogsUseCase;
-use crate::domain::repositories::state_repository::StateRepository;
+use crate::application::use_cases::manage_rejects::{
+    merge_retry_results, ManageRejectsUseCase, RetryOutcome,
+};
+use crate::application::use_cases::manage_state::{ForgetTarget, ManageStateUseCase};
+use crate::application::use_cases::parse_logs::{self, deduplicate_records, ParseLogsUseCase};
+use crate::application::use_cases::rebuild_state::RebuildStateUseCase;
+use crate::application::use_cases::upload_logs::{UploadLogsUseCase, UploadSummary};
+use crate::domain::repositories::log_repository::LogRepository;
+use crate::domain::repositories::state_repository::{
+    CompactionSummary, StateRepository, UploadState,
+};
+use crate::domain::repositories::upload_repository::UploadRepository;
 
-use super::cli::Args;
+use super::cli::{Args, Command, FilterArgs, RejectsAction, StateAction};
+
+/// Project-local sessync directory (project-local for multi-team support)
+const SESSYNC_DIR: &str = "./.claude/sessync";
+
+/// Parent of the per-destination directories holding the upload state and journal
+const DESTINATIONS_DIR: &str = "./.claude/sessync/destinations";
+
+/// Upload state file
+const STATE_FILE: &str = "upload-state.json";
+
+/// Upload state database used by the SQLite backend
+const SQLITE_STATE_FILE: &str = "upload-state.db";
+
+/// Lock file that serializes runs sharing the same upload state
+const LOCK_PATH: &str = "./.claude/sessync/sessync.lock";
+
+/// How long to wait for another run to release the lock
+const LOCK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How long a destination may stop reading records before the other destinations stop
+/// waiting for it (long enough for a batch to go through all of its retries)
+const FAN_OUT_STALL_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Quarantine store for transcript lines that failed to parse
+const REJECTS_PATH: &str = "./.claude/sessync/rejects.jsonl";
+
+/// Write-ahead journal of batches sent to the destination but not yet saved in the upload state
+const JOURNAL_FILE: &str = "upload-journal.jsonl";
+
+/// Files kept directly in the sessync directory before the state was scoped per destination
+const LEGACY_STATE_FILES: [&str; 5] = [
+    STATE_FILE,
+    SQLITE_STATE_FILE,
+    "upload-state.db-wal",
+    "upload-state.db-shm",
+    JOURNAL_FILE,
+];
+
+/// Retention used by `state compact` when neither the flag nor the config sets one
+const DEFAULT_RETENTION_DAYS: u32 = 30;
 
 /// Convert a path to a Claude project name
 /// Claude Code replaces '/' with '-' in project names (including leading '/')
@@ -36,12 +109,87 @@ pub fn get_all_projects_log_dir(home: &str) -> String {
     format!("{}/.claude/projects", home)
 }
 
+/// Directory name of a destination's upload state: its identity made file-name safe,
+/// plus a short hash so that distinct identities never share a directory
+pub fn destination_dir_name(destination_id: &str) -> String {
+    let readable: String = destination_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let hash = sha1_smol::Sha1::from(destination_id).digest().to_string();
+    format!("{}-{}", readable, &hash[..8])
+}
+
+/// Move the upload state written before it was scoped per destination into `destination_dir`,
+/// so the configured destination keeps its history and other destinations start empty
+///
+/// Only runs while no destination directory exists. Returns whether anything was moved.
+fn adopt_legacy_state(
+    sessync_dir: &Path,
+    destinations_dir: &Path,
+    destination_dir: &Path,
+) -> Result<bool> {
+    if destinations_dir.exists() {
+        return Ok(false);
+    }
+
+    let legacy: Vec<PathBuf> = LEGACY_STATE_FILES
+        .iter()
+        .map(|file| sessync_dir.join(file))
+        .filter(|path| path.exists())
+        .collect();
+    if legacy.is_empty() {
+        return Ok(false);
+    }
+
+    fs::create_dir_all(destination_dir).context("Failed to create destination state directory")?;
+    for path in legacy {
+        let target = destination_dir.join(path.file_name().unwrap_or_default());
+        match fs::rename(&path, &target) {
+            Ok(()) => {}
+            // Another run moved it first
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to move {}", path.display()));
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// What one destination did in an upload run
+enum DestinationOutcome {
+    /// Records were uploaded
+    Uploaded(UploadSummary),
+    /// Dry run: the report of what would be uploaded
+    DryRun(String),
+}
+
+/// A configured destination and where its upload state and journal live
+struct Destination {
+    config: DestinationConfig,
+    id: String,
+    dir: PathBuf,
+    state_path: String,
+    journal_path: String,
+}
+
 /// Session Upload Workflow
 pub struct SessionUploadWorkflow {
     config: Config,
     discover_use_case: Arc<DiscoverLogsUseCase<FileLogRepository>>,
-    parse_use_case: Arc<ParseLogsUseCase<FileLogRepository, JsonStateRepository>>,
-    state_repository: Arc<JsonStateRepository>,
+    parse_use_case: Arc<ParseLogsUseCase<FileLogRepository, dyn StateRepository>>,
+    reject_use_case: Arc<ManageRejectsUseCase<JsonlRejectRepository, dyn StateRepository>>,
+    compact_use_case: Arc<CompactStateUseCase<FileLogRepository, dyn StateRepository>>,
+    state_repository: Arc<dyn StateRepository>,
+    destinations: Vec<Destination>,
 }
 
 impl SessionUploadWorkflow {
@@ -49,154 +197,1067 @@ impl SessionUploadWorkflow {
     pub fn new(config: Config) -> Self {
         // Repository implementations
         let log_repo = Arc::new(FileLogRepository::new());
-        let state_repo = Arc::new(JsonStateRepository);
+        let (state_repo, state_file): (Arc<dyn StateRepository>, _) = match config.state_backend {
+            StateBackend::Json => (Arc::new(JsonStateRepository), STATE_FILE),
+            StateBackend::Sqlite => (Arc::new(SqliteStateRepository::new()), SQLITE_STATE_FILE),
+        };
+        // Each destination tracks its own uploads, so a new destination is backfilled
+        let destinations = config
+            .destinations()
+            .into_iter()
+            .map(|destination| {
+                let id = config.destination_id(&destination);
+                let dir = Path::new(DESTINATIONS_DIR).join(destination_dir_name(&id));
+                Destination {
+                    config: destination,
+                    state_path: dir.join(state_file).to_string_lossy().to_string(),
+                    journal_path: dir.join(JOURNAL_FILE).to_string_lossy().to_string(),
+                    id,
+                    dir,
+                }
+            })
+            .collect();
+        let reject_repo = Arc::new(JsonlRejectRepository::new());
 
         // Use Cases construction
         let discover_use_case = Arc::new(DiscoverLogsUseCase::new(log_repo.clone()));
-        let parse_use_case = Arc::new(ParseLogsUseCase::new(log_repo, state_repo.clone()));
+        let parse_use_case = Arc::new(ParseLogsUseCase::new(log_repo.clone(), state_repo.clone()));
+        let reject_use_case = Arc::new(ManageRejectsUseCase::new(reject_repo, state_repo.clone()));
+        let compact_use_case = Arc::new(CompactStateUseCase::new(log_repo, state_repo.clone()));
 
         Self {
             config,
             discover_use_case,
             parse_use_case,
+            reject_use_case,
+            compact_use_case,
             state_repository: state_repo,
+            destinations,
         }
     }
 
-    /// Execute the upload workflow
+    /// Execute the upload workflow (or the requested subcommand)
     pub async fn execute(&self, args: Args) -> Result<()> {
-        info!("Starting BigQuery uploader...");
+        // Serialize runs that write the upload state (e.g. a SessionEnd hook racing /save-session)
+        let _lock = if Self::writes_state(&args) {
+            let lock = Self::acquire_lock().await?;
+            self.adopt_legacy_state()?;
+            Some(lock)
+        } else {
+            None
+        };
+
+        match &args.command {
+            Some(Command::Rejects { action }) => {
+                return self.execute_rejects(*action, &args).await;
+            }
+            Some(Command::Import { archive }) => {
+                return self.execute_import(archive, &args).await;
+            }
+            Some(Command::State { action }) => {
+                return self.execute_state(action.clone(), &args).await;
+            }
+            None => {}
+        }
+
+        info!("Starting uploader...");
         info!("Dry run: {}", args.dry_run);
 
+        let filter = self.prepare(&args).await?;
+
+        let Some(log_dir) = Self::log_dir(args.all_projects) else {
+            return Ok(());
+        };
+
+        // Discover log files using Use Case
+        let log_files = self.discover_use_case.execute(&log_dir, &filter).await?;
+        println!("✓ Found {} log files in {}", log_files.len(), log_dir);
+
+        self.upload_log_files(&self.parse_use_case, &log_files, &filter, &args)
+            .await
+    }
+
+    /// Determine the log directory, or `None` if the current project has no logs
+    fn log_dir(all_projects: bool) -> Option<String> {
+        // Claude Code stores session logs in ~/.claude/projects/{project_name}/
+        // where project_name is CWD with '/' replaced by '-'
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        if all_projects {
+            // Legacy behavior: scan all projects
+            return Some(get_all_projects_log_dir(&home));
+        }
+
+        // Default: current project only
+        let cwd = std::env::current_dir()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| ".".to_string());
+        let project_dir = get_project_log_dir(&home, &cwd);
+
+        if !std::path::Path::new(&project_dir).exists() {
+            println!("⚠ No logs found for current project: {}", cwd);
+            println!("  Expected directory: {}", project_dir);
+            println!("  Use --all-projects to upload from all projects");
+            return None;
+        }
+
+        Some(project_dir)
+    }
+
+    /// Destinations the command applies to: all of them, or the ones matching `--destination`
+    /// by id or by type
+    fn select_destinations(&self, args: &Args) -> Result<Vec<&Destination>> {
+        let Some(selector) = args.destination.as_deref() else {
+            return Ok(self.destinations.iter().collect());
+        };
+
+        let selected: Vec<&Destination> = self
+            .destinations
+            .iter()
+            .filter(|destination| {
+                destination.id == selector || destination.id.split(':').next() == Some(selector)
+            })
+            .collect();
+        if selected.is_empty() {
+            let ids: Vec<&str> = self.destinations.iter().map(|d| d.id.as_str()).collect();
+            bail!(
+                "No configured destination matches {} (configured: {})",
+                selector,
+                ids.join(", ")
+            );
+        }
+        Ok(selected)
+    }
+
+    /// Move the state written before destinations were scoped to the first configured destination
+    ///
+    /// Only called while holding the run lock, so it never races an upload in progress.
+    fn adopt_legacy_state(&self) -> Result<()> {
+        let primary = &self.destinations[0];
+        if adopt_legacy_state(
+            Path::new(SESSYNC_DIR),
+            Path::new(DESTINATIONS_DIR),
+            &primary.dir,
+        )? {
+            println!(
+                "✓ Moved the existing upload state to {} ({})",
+                primary.dir.display(),
+                primary.id
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether this run may write the upload state and therefore needs the run lock
+    fn writes_state(args: &Args) -> bool {
+        !args.dry_run
+            && !matches!(
+                args.command,
+                Some(Command::Rejects {
+                    action: RejectsAction::List | RejectsAction::Count
+                }) | Some(Command::State {
+                    action: StateAction::Show | StateAction::Export { .. }
+                })
+            )
+    }
+
+    /// Acquire the run lock, waiting for another run to finish if necessary
+    async fn acquire_lock() -> Result<RunLock> {
+        if let Some(lock) = RunLock::try_acquire(LOCK_PATH)? {
+            return Ok(lock);
+        }
+
+        println!("⏳ Waiting for another sessync run to finish...");
+        tokio::task::spawn_blocking(|| RunLock::acquire(LOCK_PATH, LOCK_TIMEOUT))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn blocking task: {}", e))?
+    }
+
+    /// Execute the `import` subcommand: upload the logs inside an exported archive
+    async fn execute_import(&self, archive: &str, args: &Args) -> Result<()> {
+        info!("Importing archive: {}", archive);
+        info!("Dry run: {}", args.dry_run);
+
+        let filter = self.prepare(args).await?;
+
+        // Read entries straight from the archive; source_file becomes `archive!/inner/path`
+        let archive_repo = Arc::new(ArchiveLogRepository::new());
+        let discover_use_case = DiscoverLogsUseCase::new(archive_repo.clone());
+        let parse_use_case = ParseLogsUseCase::new(archive_repo, self.state_repository.clone());
+
+        let log_files = discover_use_case.execute(archive, &filter).await?;
+        println!("✓ Found {} log files in {}", log_files.len(), archive);
+
+        self.upload_log_files(&parse_use_case, &log_files, &filter, args)
+            .await
+    }
+
+    /// Print the configuration, build the log filter and report the upload state
+    async fn prepare(&self, args: &Args) -> Result<LogFilter> {
         // Use injected configuration
         println!("✓ Using configuration:");
-        println!("  Project: {}", self.config.project_id);
-        println!("  Dataset: {}", self.config.dataset);
-        println!("  Table: {}", self.config.table);
+        for destination in self.select_destinations(args)? {
+            println!("  Destination: {}", destination.id);
+        }
         println!(
             "  Developer: {} ({})",
             self.config.developer_id, self.config.user_email
         );
 
-        // Load upload state
-        // State file is project-local for multi-team support
-        let state_path = "./.claude/sessync/upload-state.json".to_string();
-        let state = self.state_repository.load(&state_path).await?;
-        println!(
-            "✓ Loaded upload state: {} records previously uploaded",
-            state.total_uploaded
-        );
+        let filter = self.log_filter(&args.filter)?;
+        if !filter.is_empty() {
+            println!("✓ Filtering logs");
+            if filter.is_selective() {
+                println!("  Record-level filter: checkpoints are not updated by this run");
+            }
+        }
+
+        // Load upload state; a destination whose state is unreadable fails on its own later
+        for destination in self.select_destinations(args)? {
+            match self
+                .state_repository
+                .load_for_upload(&destination.state_path)
+                .await
+            {
+                Ok(state) => println!(
+                    "✓ Loaded upload state for {}: {} records previously uploaded",
+                    destination.id, state.total_uploaded
+                ),
+                Err(e) => println!(
+                    "⚠ Failed to load upload state for {}: {:#}",
+                    destination.id, e
+                ),
+            }
+        }
+
+        Ok(filter)
+    }
+
+    /// Parse the discovered log files once and upload them to every selected destination
+    /// concurrently (or print them in dry-run mode)
+    ///
+    /// Each destination is deduplicated against its own upload state and commits its own
+    /// checkpoints and journal. A destination that fails is reported and skipped; the others
+    /// still receive their records.
+    async fn upload_log_files<L: LogRepository>(
+        &self,
+        parse_use_case: &ParseLogsUseCase<L, dyn StateRepository>,
+        log_files: &[PathBuf],
+        filter: &LogFilter,
+        args: &Args,
+    ) -> Result<()> {
+        if log_files.is_empty() {
+            println!("No log files to process. Exiting.");
+            return Ok(());
+        }
+
+        let destinations = self.select_destinations(args)?;
+        let total = destinations.len();
+        let fan_out = total > 1;
+        let upload_config = self.upload_config();
+        let batch_id = uuid::Uuid::new_v4().to_string();
+
+        // Finish batches left over by a killed run before parsing, so they are deduplicated
+        let mut results: Vec<Option<Result<DestinationOutcome>>> = Vec::new();
+        let mut ready = Vec::new();
+        for (index, destination) in destinations.iter().enumerate() {
+            match self
+                .open_destination(destination, &batch_id, args.dry_run)
+                .await
+            {
+                Ok((upload_use_case, state)) => {
+                    results.push(None);
+                    ready.push((index, *destination, upload_use_case, state));
+                }
+                Err(e) => results.push(Some(Err(e))),
+            }
+        }
+
+        if !ready.is_empty() {
+            // Stream records file by file, line by line (memory is bounded by batch size),
+            // reading each file once for all destinations
+            let states: Vec<_> = ready.iter().map(|(_, _, _, state)| state.clone()).collect();
+            let records = parse_use_case
+                .read(log_files, &upload_config, filter, &states, &batch_id)
+                .await;
 
-        // Create BigQuery client factory (skip if dry-run mode)
-        let factory = if args.dry_run {
+            // Save unparseable lines to the quarantine store before any destination commits
+            // their checkpoints
+            let records = if args.dry_run {
+                records
+            } else {
+                self.reject_use_case
+                    .quarantine(records, REJECTS_PATH)
+                    .await?
+            };
+
+            let (feed, streams) = parse_logs::fan_out(records, ready.len(), FAN_OUT_STALL_TIMEOUT);
+            let uploads = ready.into_iter().zip(streams).map(
+                |((index, destination, upload_use_case, state), records)| {
+                    let records = deduplicate_records(
+                        records,
+                        state,
+                        self.state_repository.clone(),
+                        &destination.state_path,
+                        &upload_config,
+                    );
+                    let upload_config = &upload_config;
+                    let batch_id = batch_id.as_str();
+                    async move {
+                        let outcome = match upload_use_case {
+                            Some(upload_use_case) => upload_use_case
+                                .execute_stream(
+                                    records,
+                                    upload_config,
+                                    &destination.state_path,
+                                    batch_id,
+                                )
+                                .await
+                                .map(DestinationOutcome::Uploaded),
+                            None => Self::dry_run(records).await.map(DestinationOutcome::DryRun),
+                        };
+                        (index, outcome)
+                    }
+                },
+            );
+            let (_, outcomes) = futures::join!(feed, future::join_all(uploads));
+            for (index, outcome) in outcomes {
+                results[index] = Some(outcome);
+            }
+        }
+
+        let results: Vec<(&Destination, Result<DestinationOutcome>)> = destinations
+            .into_iter()
+            .zip(results)
+            .map(|(destination, result)| {
+                (destination, result.expect("every destination has a result"))
+            })
+            .collect();
+        for (destination, result) in &results {
+            if fan_out {
+                println!("▶ Destination {}", destination.id);
+            }
+            match result {
+                Ok(outcome) => self.report(destination, outcome).await,
+                Err(e) if fan_out => println!("✗ Upload to {} failed: {:#}", destination.id, e),
+                Err(_) => {}
+            }
+        }
+
+        if fan_out {
+            println!("✓ Summary by destination:");
+            for (destination, result) in &results {
+                match result {
+                    Ok(DestinationOutcome::Uploaded(summary)) => println!(
+                        "  {} {}: {} uploaded, {} failed",
+                        if summary.failed_count > 0 {
+                            "⚠"
+                        } else {
+                            "✓"
+                        },
+                        destination.id,
+                        summary.uploaded_count,
+                        summary.failed_count
+                    ),
+                    Ok(DestinationOutcome::DryRun(_)) => {
+                        println!("  ✓ {}: dry run", destination.id)
+                    }
+                    Err(e) => println!("  ✗ {}: {:#}", destination.id, e),
+                }
+            }
+        }
+
+        let mut failures: Vec<(&str, anyhow::Error)> = results
+            .into_iter()
+            .filter_map(|(destination, result)| result.err().map(|e| (destination.id.as_str(), e)))
+            .collect();
+        match failures.len() {
+            0 => {
+                println!("✓ Upload complete!");
+                Ok(())
+            }
+            // A single destination fails with its own error
+            _ if !fan_out => Err(failures.remove(0).1),
+            count => {
+                let ids: Vec<&str> = failures.iter().map(|(id, _)| *id).collect();
+                bail!(
+                    "Upload failed for {} of {} destinations: {}",
+                    count,
+                    total,
+                    ids.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Create the upload use case of `destination` (none in dry-run mode), finish batches left
+    /// over by a killed run and load its upload state
+    async fn open_destination(
+        &self,
+        destination: &Destination,
+        batch_id: &str,
+        dry_run: bool,
+    ) -> Result<(
+        Option<UploadLogsUseCase<dyn UploadRepository, dyn StateRepository>>,
+        Arc<UploadState>,
+    )> {
+        let upload_use_case = if dry_run {
             None
         } else {
-            let f = RealClientFactory::new(self.config.service_account_key_path.clone());
-            println!("✓ Created BigQuery client factory");
-            Some(f)
+            let upload_use_case = self.upload_use_case(destination)?;
+            let recovery = upload_use_case
+                .recover(&destination.state_path, batch_id)
+                .await?;
+            if recovery.batches > 0 {
+                println!(
+                    "✓ Recovered {} records from an interrupted upload to {} ({} re-sent)",
+                    recovery.recorded_count, destination.id, recovery.resent_count
+                );
+            }
+            Some(upload_use_case)
         };
 
-        // Determine log directory
-        // Claude Code stores session logs in ~/.claude/projects/{project_name}/
-        // where project_name is CWD with '/' replaced by '-'
-        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        let log_dir = if args.all_projects {
-            // Legacy behavior: scan all projects
-            get_all_projects_log_dir(&home)
-        } else {
-            // Default: current project only
-            let cwd = std::env::current_dir()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|_| ".".to_string());
-            let project_dir = get_project_log_dir(&home, &cwd);
+        let state = self
+            .state_repository
+            .load_for_upload(&destination.state_path)
+            .await?;
+        Ok((upload_use_case, Arc::new(state)))
+    }
 
-            if !std::path::Path::new(&project_dir).exists() {
-                println!("⚠ No logs found for current project: {}", cwd);
-                println!("  Expected directory: {}", project_dir);
-                println!("  Use --all-projects to upload from all projects");
-                return Ok(());
+    /// Consume the records of one destination without uploading and describe what would be sent
+    async fn dry_run(mut records: BoxStream<'_, Result<ParsedRecord>>) -> Result<String> {
+        let mut report = String::from("✓ Dry-run mode (not actually uploading)\n");
+        let mut record_count = 0;
+        let mut changed_count = 0;
+        let mut rejected_count = 0;
+        while let Some(record) = records.next().await {
+            match record? {
+                ParsedRecord::Log(log) => {
+                    record_count += 1;
+                    report.push_str(&format!(
+                        "    - UUID: {} | Session: {} | Type: {}\n",
+                        log.uuid, log.session_id, log.message_type
+                    ));
+                }
+                ParsedRecord::Changed(log) => {
+                    changed_count += 1;
+                    report.push_str(&format!(
+                        "    - CHANGED: {} | Session: {} | Type: {}\n",
+                        log.uuid, log.session_id, log.message_type
+                    ));
+                }
+                ParsedRecord::Rejected(reject) => {
+                    rejected_count += 1;
+                    report.push_str(&format!(
+                        "    - REJECTED: {}:{} | {}\n",
+                        reject.source_file, reject.line_number, reject.error
+                    ));
+                }
+                ParsedRecord::FileCompleted { .. } => {}
             }
+        }
+        report.push_str(&format!("  Would upload {} records\n", record_count));
+        if changed_count > 0 {
+            report.push_str(&format!(
+                "  Found {} changed records (not uploaded)\n",
+                changed_count
+            ));
+        }
+        report.push_str(&format!(
+            "  Would quarantine {} unparseable lines",
+            rejected_count
+        ));
+        Ok(report)
+    }
 
-            project_dir
+    /// Print what happened for `destination` and apply its automatic retention policy
+    async fn report(&self, destination: &Destination, outcome: &DestinationOutcome) {
+        let summary = match outcome {
+            DestinationOutcome::Uploaded(summary) => summary,
+            DestinationOutcome::DryRun(report) => {
+                println!("{}", report);
+                return;
+            }
         };
 
-        // Discover log files using Use Case
-        let log_files = self.discover_use_case.execute(&log_dir).await?;
-        println!("✓ Found {} log files in {}", log_files.len(), log_dir);
+        if summary.uploaded_count == 0 && summary.failed_count == 0 {
+            println!("No new records to upload.");
+        } else {
+            println!(
+                "✓ Uploaded {} records ({} failed)",
+                summary.uploaded_count, summary.failed_count
+            );
+        }
+        println!("  Rejected lines: {}", summary.rejected_count);
+        if summary.changed_count > 0 {
+            println!(
+                "⚠ {} uploaded records have changed content (not uploaded; see `change_policy`)",
+                summary.changed_count
+            );
+        }
+        if summary.rejected_count > 0 {
+            println!(
+                "⚠ {} unparseable lines quarantined in {} (see `sessync rejects list`)",
+                summary.rejected_count, REJECTS_PATH
+            );
+        }
 
-        if log_files.is_empty() {
-            println!("No log files to process. Exiting.");
-            return Ok(());
+        // Automatic retention policy; the upload itself already succeeded
+        if let Some(days) = self.config.state_retention_days {
+            match self.compact_state(destination, days, false).await {
+                Ok(summary) if summary.sessions > 0 => println!(
+                    "✓ Compacted upload state: forgot {} UUIDs from {} sessions",
+                    summary.forgotten_uuids, summary.sessions
+                ),
+                Ok(_) => {}
+                Err(e) => println!("⚠ Failed to compact upload state: {:#}", e),
+            }
         }
+    }
 
-        // Parse logs using Use Case
-        // Create UploadConfig from Config
-        let upload_config = crate::application::dto::upload_config::UploadConfig::new(
-            self.config.project_id.clone(),
-            self.config.dataset.clone(),
-            self.config.table.clone(),
-            self.config.location.clone(),
-            self.config.upload_batch_size as usize,
-            self.config.enable_deduplication,
-            self.config.developer_id.clone(),
-            self.config.user_email.clone(),
-            self.config.project_name.clone(),
-        );
+    /// Execute a `rejects` subcommand against the quarantine store
+    async fn execute_rejects(&self, action: RejectsAction, args: &Args) -> Result<()> {
+        match action {
+            RejectsAction::List => {
+                let rejects = self.reject_use_case.list(REJECTS_PATH).await?;
+                for reject in &rejects {
+                    println!("{}:{}", reject.source_file, reject.line_number);
+                    println!("  Rejected at: {}", reject.rejected_at.to_rfc3339());
+                    println!("  Error: {}", reject.error);
+                    println!("  Raw: {}", reject.raw);
+                }
+                println!("{} quarantined lines", rejects.len());
+            }
+            RejectsAction::Count => {
+                let counts = self.reject_use_case.count_by_file(REJECTS_PATH).await?;
+                for (source_file, count) in &counts {
+                    println!("{:>8}  {}", count, source_file);
+                }
+                println!("{:>8}  total", counts.values().sum::<usize>());
+            }
+            RejectsAction::Retry => self.retry_rejects(args).await?,
+        }
 
+        Ok(())
+    }
+
+    /// Re-parse quarantined lines and upload the recovered ones to every selected destination
+    ///
+    /// A line leaves the quarantine store only once each destination has it, so a destination
+    /// that fails gets the line again on the next retry.
+    async fn retry_rejects(&self, args: &Args) -> Result<()> {
+        let upload_config = self.upload_config();
         let batch_id = uuid::Uuid::new_v4().to_string();
-        let domain_logs = self
-            .parse_use_case
-            .execute(&log_files, &upload_config, &state_path, &batch_id)
-            .await?;
+        let destinations = self.select_destinations(args)?;
+        let fan_out = destinations.len() > 1;
 
-        println!("✓ Parsed {} records total", domain_logs.len());
+        let mut results = Vec::new();
+        let mut failed = Vec::new();
+        for destination in destinations {
+            if fan_out {
+                println!("▶ Destination {}", destination.id);
+            }
+            match self
+                .retry_rejects_for(destination, &upload_config, &batch_id, args.dry_run)
+                .await
+            {
+                Ok(result) => results.push(result),
+                Err(e) if !fan_out => return Err(e),
+                Err(e) => {
+                    println!("✗ Retry for {} failed: {:#}", destination.id, e);
+                    failed.push(destination.id.as_str());
+                }
+            }
+        }
 
-        if domain_logs.is_empty() {
-            println!("No new records to upload. Exiting.");
+        if args.dry_run {
             return Ok(());
         }
+        if !failed.is_empty() {
+            bail!(
+                "Retry failed for {} (quarantined lines kept for the next retry)",
+                failed.join(", ")
+            );
+        }
 
-        // Upload to BigQuery
-        if args.dry_run {
+        let (outcome, uploaded_uuids) = merge_retry_results(results);
+        let remaining = self
+            .reject_use_case
+            .commit_retry(REJECTS_PATH, outcome, &uploaded_uuids)
+            .await?;
+        println!("  {} lines remain quarantined", remaining);
+
+        Ok(())
+    }
+
+    /// Re-parse quarantined lines against the state of `destination` and upload the recovered ones
+    ///
+    /// Returns the outcome and the UUIDs the destination accepted (none in dry-run mode).
+    async fn retry_rejects_for(
+        &self,
+        destination: &Destination,
+        upload_config: &UploadConfig,
+        batch_id: &str,
+        dry_run: bool,
+    ) -> Result<(RetryOutcome, Vec<String>)> {
+        let outcome = self
+            .reject_use_case
+            .retry(
+                REJECTS_PATH,
+                upload_config,
+                &destination.state_path,
+                batch_id,
+            )
+            .await?;
+        println!(
+            "✓ Re-parsed quarantined lines: {} recovered, {} already uploaded, {} still failing",
+            outcome.recovered.len(),
+            outcome.duplicates,
+            outcome.remaining.len()
+        );
+
+        if dry_run {
             println!("✓ Dry-run mode (not actually uploading)");
-            println!("  Would upload {} records:", domain_logs.len());
-            for log in &domain_logs {
+            for recovered in &outcome.recovered {
                 println!(
-                    "    - UUID: {} | Session: {} | Type: {}",
-                    log.uuid, log.session_id, log.message_type
+                    "    - UUID: {} | Source: {}:{}",
+                    recovered.log.uuid, recovered.line.source_file, recovered.line.line_number
                 );
             }
-        } else {
-            // Create BigQuery upload repository and use case
-            let client_factory =
-                Arc::new(factory.expect("Factory should exist in non-dry-run mode"));
-            let upload_repo = Arc::new(BigQueryUploadRepository::new(
-                client_factory,
-                self.config.clone(),
-            ));
-            let upload_use_case =
-                UploadLogsUseCase::new(upload_repo, self.state_repository.clone());
+            return Ok((outcome, Vec::new()));
+        }
 
-            // Execute upload (includes state update)
-            let summary = upload_use_case
-                .execute(domain_logs, &upload_config, &state_path, &batch_id)
-                .await?;
+        if outcome.recovered.is_empty() {
+            return Ok((outcome, Vec::new()));
+        }
+        let logs = outcome
+            .recovered
+            .iter()
+            .map(|recovered| recovered.log.clone())
+            .collect::<Vec<_>>();
+        let summary = self
+            .upload_use_case(destination)?
+            .execute(
+                ParsedLogs::from(logs),
+                upload_config,
+                &destination.state_path,
+                batch_id,
+            )
+            .await?;
+        println!(
+            "✓ Uploaded {} records ({} failed)",
+            summary.uploaded_count, summary.failed_count
+        );
+
+        Ok((outcome, summary.uploaded_uuids))
+    }
+
+    /// Execute a `state` subcommand against the upload state of each selected destination
+    async fn execute_state(&self, action: StateAction, args: &Args) -> Result<()> {
+        let dry_run = args.dry_run;
+        let destinations = self.select_destinations(args)?;
+        match action {
+            StateAction::Compact { retention_days } => {
+                let days = retention_days
+                    .or(self.config.state_retention_days)
+                    .unwrap_or(DEFAULT_RETENTION_DAYS);
+                for destination in destinations {
+                    let summary = self.compact_state(destination, days, dry_run).await?;
+
+                    if dry_run {
+                        println!("✓ Dry-run mode (upload state not modified)");
+                        println!(
+                            "  Would forget {} UUIDs from {} sessions ({})",
+                            summary.forgotten_uuids, summary.sessions, destination.id
+                        );
+                    } else {
+                        println!(
+                            "✓ Forgot {} UUIDs from {} sessions not uploaded in the last {} days ({})",
+                            summary.forgotten_uuids, summary.sessions, days, destination.id
+                        );
+                    }
+                }
+            }
+            StateAction::Rebuild { .. } => {
+                let Some(destination) = destinations
+                    .into_iter()
+                    .find(|destination| destination.config == DestinationConfig::BigQuery)
+                else {
+                    bail!(
+                        "state rebuild --from-remote is only supported for the BigQuery destination"
+                    );
+                };
+                self.rebuild_state(destination, args).await?
+            }
+            StateAction::Show => {
+                for (i, destination) in destinations.into_iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    self.show_state(destination).await?;
+                }
+            }
+            StateAction::Export { path } => {
+                let [destination] = destinations[..] else {
+                    bail!("state export writes the state of one destination; choose it with --destination");
+                };
+                let uuids = self
+                    .manage_state_use_case()
+                    .export(&destination.state_path, &path)
+                    .await?;
+                println!(
+                    "✓ Exported {} uploaded UUIDs of {} to {}",
+                    uuids, destination.id, path
+                );
+            }
+            StateAction::Import { path } => {
+                if !std::path::Path::new(&path).exists() {
+                    bail!("State file to import not found: {}", path);
+                }
+                for destination in destinations {
+                    let added = self
+                        .manage_state_use_case()
+                        .import(&destination.state_path, &path, dry_run)
+                        .await?;
+
+                    if dry_run {
+                        println!("✓ Dry-run mode (upload state not modified)");
+                        println!(
+                            "  Would add {} uploaded UUIDs from {} ({})",
+                            added, path, destination.id
+                        );
+                    } else {
+                        println!(
+                            "✓ Imported {} new uploaded UUIDs from {} ({})",
+                            added, path, destination.id
+                        );
+                    }
+                }
+            }
+            StateAction::Forget { session, since } => {
+                let target = match (session, since) {
+                    (Some(session), _) => ForgetTarget::Session(session),
+                    (None, Some(since)) => ForgetTarget::Since(parse_time(&since)?),
+                    (None, None) => bail!("state forget requires --session or --since"),
+                };
+                for destination in destinations {
+                    let summary = self
+                        .manage_state_use_case()
+                        .forget(&destination.state_path, &target, dry_run)
+                        .await?;
+
+                    if dry_run {
+                        println!("✓ Dry-run mode (upload state not modified)");
+                        println!(
+                            "  Would forget {} UUIDs and {} checkpoints from {} sessions ({})",
+                            summary.uuids, summary.checkpoints, summary.sessions, destination.id
+                        );
+                    } else {
+                        println!(
+                            "✓ Forgot {} UUIDs and {} checkpoints from {} sessions ({})",
+                            summary.uuids, summary.checkpoints, summary.sessions, destination.id
+                        );
+                        if summary.sessions > 0 {
+                            println!("  Their records will be uploaded again on the next run");
+                        }
+                    }
+                }
+            }
+            StateAction::Reset { yes } => {
+                let state_paths: Vec<&str> = destinations
+                    .iter()
+                    .map(|destination| destination.state_path.as_str())
+                    .collect();
+                if !dry_run && !yes && !Self::confirm_reset(&state_paths)? {
+                    println!("✓ Reset cancelled (upload state not modified)");
+                    return Ok(());
+                }
+                for destination in destinations {
+                    let uuids = self
+                        .manage_state_use_case()
+                        .reset(&destination.state_path, dry_run)
+                        .await?;
+
+                    if dry_run {
+                        println!("✓ Dry-run mode (upload state not modified)");
+                        println!(
+                            "  Would forget {} uploaded UUIDs ({})",
+                            uuids, destination.id
+                        );
+                    } else {
+                        println!(
+                            "✓ Reset the upload state of {} ({} uploaded UUIDs forgotten)",
+                            destination.id, uuids
+                        );
+                        println!("  Every log will be uploaded again on the next run");
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create the use case behind `state show/export/import/forget/reset`
+    fn manage_state_use_case(
+        &self,
+    ) -> ManageStateUseCase<dyn StateRepository, JsonStateRepository> {
+        ManageStateUseCase::new(
+            self.state_repository.clone(),
+            Arc::new(JsonStateRepository::new()),
+        )
+    }
+
+    /// Print a summary of the upload state of `destination`
+    async fn show_state(&self, destination: &Destination) -> Result<()> {
+        let report = self
+            .manage_state_use_case()
+            .show(&destination.state_path)
+            .await?;
+
+        println!("Upload state: {}", destination.state_path);
+        println!("  Destination:     {}", destination.id);
+        match report.storage_size {
+            Some(bytes) => println!("  Size:            {} bytes", bytes),
+            None => println!("  Size:            (not created yet)"),
+        }
+        println!("  Total uploaded:  {}", report.total_uploaded);
+        println!("  Tracked UUIDs:   {}", report.tracked_uuids);
+        if report.unattributed_uuids > 0 {
+            println!("    without session: {}", report.unattributed_uuids);
+        }
+        println!("  Sessions:        {}", report.sessions.len());
+        println!("  File checkpoints: {}", report.file_checkpoints);
+        println!(
+            "  Last batch:      {} ({})",
+            report.last_upload_batch_id.as_deref().unwrap_or("-"),
+            report.last_upload_timestamp.as_deref().unwrap_or("-")
+        );
+
+        if !report.projects.is_empty() {
+            println!();
+            println!("Projects:");
+            for (project, stats) in &report.projects {
+                println!(
+                    "  {}  {} sessions, {} UUIDs",
+                    project, stats.sessions, stats.uuids
+                );
+            }
+        }
+
+        if !report.sessions.is_empty() {
+            println!();
+            println!("Sessions (most recent first):");
+            for session in &report.sessions {
+                let uuids = if session.compacted {
+                    "compacted".to_string()
+                } else {
+                    format!("{} UUIDs", session.uuids)
+                };
+                println!(
+                    "  {}  {}  {}  last uploaded {}",
+                    session.session_id,
+                    session.project,
+                    uuids,
+                    session.last_uploaded_at.to_rfc3339()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ask the user to confirm `state reset`
+    fn confirm_reset(state_paths: &[&str]) -> Result<bool> {
+        print!(
+            "This forgets every upload recorded in {} and re-uploads all logs on the next run.\n\
+             Type 'yes' to continue: ",
+            state_paths.join(", ")
+        );
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        Ok(answer.trim() == "yes")
+    }
+
+    /// Rebuild the upload state of the BigQuery destination from the records of local
+    /// transcripts found in BigQuery
+    async fn rebuild_state(&self, destination: &Destination, args: &Args) -> Result<()> {
+        let filter = self.prepare(args).await?;
+        let Some(log_dir) = Self::log_dir(args.all_projects) else {
+            return Ok(());
+        };
+
+        let log_files = self.discover_use_case.execute(&log_dir, &filter).await?;
+        println!("✓ Found {} log files in {}", log_files.len(), log_dir);
+
+        let client_factory = Arc::new(RealClientFactory::new(
+            self.config.service_account_key_path.clone(),
+        ));
+        let remote_repo = Arc::new(BigQueryRemoteRecordRepository::new(
+            client_factory,
+            self.config.clone(),
+        ));
+        let use_case = RebuildStateUseCase::new(
+            Arc::new(FileLogRepository::new()),
+            remote_repo,
+            self.state_repository.clone(),
+        );
 
+        let summary = use_case
+            .execute(
+                &log_files,
+                &self.upload_config(),
+                &destination.state_path,
+                args.dry_run,
+            )
+            .await?;
+        println!(
+            "✓ {} of {} local records found in BigQuery",
+            summary.remote_records, summary.local_records
+        );
+
+        if args.dry_run {
+            println!("✓ Dry-run mode (upload state not modified)");
             println!(
-                "✓ Uploaded {} records ({} failed)",
-                summary.uploaded_count, summary.failed_count
+                "  Would mark {} records as uploaded and commit {} checkpoints",
+                summary.added_records, summary.checkpoints
+            );
+        } else {
+            println!(
+                "✓ Marked {} records as uploaded and committed {} checkpoints",
+                summary.added_records, summary.checkpoints
             );
         }
 
-        println!("✓ Upload complete!");
-
         Ok(())
     }
+
+    /// Compact the upload state, forgetting UUIDs of sealed sessions older than the retention
+    async fn compact_state(
+        &self,
+        destination: &Destination,
+        retention_days: u32,
+        dry_run: bool,
+    ) -> Result<CompactionSummary> {
+        self.compact_use_case
+            .execute(
+                &destination.state_path,
+                chrono::Duration::days(retention_days.into()),
+                dry_run,
+            )
+            .await
+    }
+
+    /// Build the upload settings from the loaded configuration
+    fn upload_config(&self) -> UploadConfig {
+        UploadConfig::new(
+            self.config.project_id.clone(),
+            self.config.dataset.clone(),
+            self.config.table.clone(),
+            self.config.location.clone(),
+            self.config.upload_batch_size as usize,
+            self.config.enable_deduplication,
+            self.config.developer_id.clone(),
+            self.config.user_email.clone(),
+            self.config.project_name.clone(),
+        )
+        .with_parse_workers(self.config.parse_workers)
+        .with_change_policy(self.config.change_policy)
+    }
+
+    /// Build the log filter (CLI flags take precedence over the config file)
+    fn log_filter(&self, args: &FilterArgs) -> Result<LogFilter> {
+        let config = &self.config.filter;
+        let since = args.since.as_ref().or(config.since.as_ref());
+        let until = args.until.as_ref().or(config.until.as_ref());
+        let time_basis = args.time_basis.as_ref().or(config.time_basis.as_ref());
+        let list = |cli: &Vec<String>, config: &Vec<String>| {
+            if cli.is_empty() {
+                config.clone()
+            } else {
+                cli.clone()
+            }
+        };
+
+        LogFilter::new()
+            .with_time_range(
+                since.map(|v| parse_time(v)).transpose()?,
+                until.map(|v| parse_time(v)).transpose()?,
+            )
+            .with_time_basis(
+                time_basis
+                    .map(|v| v.parse())
+                    .transpose()?
+                    .unwrap_or_default(),
+            )
+            .with_sessions(list(&args.sessions, &config.sessions))
+            .with_include(&list(&args.include, &config.include))?
+            .with_exclude(&list(&args.exclude, &config.exclude))
+    }
+
+    /// Create the upload use case for `destination`, journaled in its own state directory
+    fn upload_use_case(
+        &self,
+        destination: &Destination,
+    ) -> Result<UploadLogsUseCase<dyn UploadRepository, dyn StateRepository>> {
+        let upload_repo: Arc<dyn UploadRepository> = match &destination.config {
+            DestinationConfig::BigQuery => {
+                let client_factory = Arc::new(RealClientFactory::new(
+                    self.config.service_account_key_path.clone(),
+                ));
+                println!("✓ Created BigQuery client factory");
+                Arc::new(BigQueryUploadRepository::new(
+                    client_factory,
+                    self.config.clone(),
+                ))
+            }
+            DestinationConfig::Ndjson { path } => {
+                println!("✓ Writing NDJSON files to {}", path);
+                Arc::new(NdjsonUploadRepository::new(path))
+            }
+            DestinationConfig::Parquet {
+                path,
+                row_group_size,
+                compression,
+            } => {
+                println!("✓ Writing Parquet files to {}", path);
+                Arc::new(ParquetUploadRepository::new(
+                    path,
+                    *row_group_size,
+                    *compression,
+                ))
+            }
+            DestinationConfig::Sqlite { path } => {
+                println!("✓ Writing to SQLite database {}", path);
+                Arc::new(SqliteUploadRepository::new(path))
+            }
+            DestinationConfig::Postgres { url, table } => {
+                println!("✓ Writing to PostgreSQL table {}", table);
+                Arc::new(PostgresUploadRepository::new(url, table)?)
+            }
+            DestinationConfig::Webhook(webhook) => {
+                println!("✓ Posting batches to {}", webhook.url);
+                Arc::new(WebhookUploadRepository::new(webhook)?)
+            }
+            DestinationConfig::S3(s3) => {
+                println!("✓ Writing objects to bucket {}", s3.bucket);
+                Arc::new(S3UploadRepository::new(s3)?)
+            }
+        };
+        Ok(
+            UploadLogsUseCase::new(upload_repo, self.state_repository.clone()).with_journal(
+                Arc::new(JsonlJournalRepository::new()),
+                &destination.journal_path,
+            ),
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::Parser;
 
     #[test]
     fn test_path_to_project_name_absolute() {
@@ -228,6 +1289,150 @@ mod tests {
         assert_eq!(result, "/home/user/.claude/projects/-workspace-myproject");
     }
 
+    #[test]
+    fn test_writes_state() {
+        use clap::Parser;
+
+        let upload = Args::parse_from(["sessync"]);
+        assert!(SessionUploadWorkflow::writes_state(&upload));
+
+        let dry_run = Args::parse_from(["sessync", "--dry-run"]);
+        assert!(!SessionUploadWorkflow::writes_state(&dry_run));
+
+        let list = Args::parse_from(["sessync", "rejects", "list"]);
+        assert!(!SessionUploadWorkflow::writes_state(&list));
+
+        let retry = Args::parse_from(["sessync", "rejects", "retry"]);
+        assert!(SessionUploadWorkflow::writes_state(&retry));
+
+        let compact = Args::parse_from(["sessync", "state", "compact"]);
+        assert!(SessionUploadWorkflow::writes_state(&compact));
+
+        let rebuild = Args::parse_from(["sessync", "state", "rebuild", "--from-remote"]);
+        assert!(SessionUploadWorkflow::writes_state(&rebuild));
+
+        let show = Args::parse_from(["sessync", "state", "show"]);
+        assert!(!SessionUploadWorkflow::writes_state(&show));
+
+        let export = Args::parse_from(["sessync", "state", "export", "state.json"]);
+        assert!(!SessionUploadWorkflow::writes_state(&export));
+
+        let forget = Args::parse_from(["sessync", "state", "forget", "--session", "s1"]);
+        assert!(SessionUploadWorkflow::writes_state(&forget));
+
+        let reset = Args::parse_from(["sessync", "state", "reset", "--yes"]);
+        assert!(SessionUploadWorkflow::writes_state(&reset));
+    }
+
+    fn create_fan_out_workflow() -> SessionUploadWorkflow {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "project_id": "p",
+                "dataset": "d",
+                "table": "t",
+                "service_account_key_path": "/tmp/key.json",
+                "upload_batch_size": 100,
+                "enable_auto_upload": false,
+                "enable_deduplication": true,
+                "developer_id": "dev",
+                "user_email": "dev@example.com",
+                "project_name": "project",
+                "destinations": [
+                    {"type": "bigquery"},
+                    {"type": "ndjson", "path": "./archive"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        SessionUploadWorkflow::new(config)
+    }
+
+    #[test]
+    fn test_destinations_have_separate_state() {
+        let workflow = create_fan_out_workflow();
+        let ids: Vec<&str> = workflow
+            .destinations
+            .iter()
+            .map(|d| d.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["bigquery:p.d.t", "ndjson:./archive"]);
+        assert_ne!(
+            workflow.destinations[0].state_path,
+            workflow.destinations[1].state_path
+        );
+        assert_ne!(
+            workflow.destinations[0].journal_path,
+            workflow.destinations[1].journal_path
+        );
+    }
+
+    #[test]
+    fn test_select_destinations() {
+        let workflow = create_fan_out_workflow();
+        let select = |destination: Option<&str>| {
+            let mut args = Args::parse_from(["sessync"]);
+            args.destination = destination.map(str::to_string);
+            workflow
+                .select_destinations(&args)
+                .map(|selected| selected.iter().map(|d| d.id.clone()).collect::<Vec<_>>())
+        };
+
+        assert_eq!(select(None).unwrap().len(), 2);
+        assert_eq!(select(Some("ndjson")).unwrap(), vec!["ndjson:./archive"]);
+        assert_eq!(
+            select(Some("bigquery:p.d.t")).unwrap(),
+            vec!["bigquery:p.d.t"]
+        );
+        assert!(select(Some("sqlite")).is_err());
+    }
+
+    #[test]
+    fn test_destination_dir_name() {
+        let name = destination_dir_name("bigquery:my-project.logs.sessions");
+        assert!(name.starts_with("bigquery_my-project.logs.sessions-"));
+        assert_eq!(name.len(), "bigquery_my-project.logs.sessions-".len() + 8);
+
+        // Identities that sanitize to the same text still get different directories
+        assert_ne!(
+            destination_dir_name("ndjson:/a/b"),
+            destination_dir_name("ndjson:/a_b")
+        );
+    }
+
+    #[test]
+    fn test_adopt_legacy_state() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let sessync_dir = temp_dir.path();
+        let destinations_dir = sessync_dir.join("destinations");
+        let destination_dir = destinations_dir.join("bigquery_p.d.t-12345678");
+        fs::write(sessync_dir.join(STATE_FILE), "{}").unwrap();
+        fs::write(sessync_dir.join(JOURNAL_FILE), "").unwrap();
+        fs::write(sessync_dir.join("rejects.jsonl"), "").unwrap();
+
+        assert!(adopt_legacy_state(sessync_dir, &destinations_dir, &destination_dir).unwrap());
+        assert!(destination_dir.join(STATE_FILE).exists());
+        assert!(destination_dir.join(JOURNAL_FILE).exists());
+        assert!(!sessync_dir.join(STATE_FILE).exists());
+        // Files shared by all destinations stay where they are
+        assert!(sessync_dir.join("rejects.jsonl").exists());
+
+        // Once destinations exist, a state file in the old location is left alone
+        fs::write(sessync_dir.join(STATE_FILE), "{}").unwrap();
+        let other_dir = destinations_dir.join("bigquery_p.d.other-87654321");
+        assert!(!adopt_legacy_state(sessync_dir, &destinations_dir, &other_dir).unwrap());
+        assert!(!other_dir.exists());
+    }
+
+    #[test]
+    fn test_adopt_legacy_state_without_legacy_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let destinations_dir = temp_dir.path().join("destinations");
+        let destination_dir = destinations_dir.join("bigquery_p.d.t-12345678");
+
+        assert!(!adopt_legacy_state(temp_dir.path(), &destinations_dir, &destination_dir).unwrap());
+        assert!(!destinations_dir.exists());
+    }
+
     #[test]
     fn test_get_all_projects_log_dir() {
         let result = get_all_projects_log_dir("/home/user");
@@ -2,11 +2,15 @@
 //!
 //! SessionUploadWorkflow の統合テスト
 
-use sessync::driver::cli::Args;
+use sessync::driver::cli::{Args, Command, FilterArgs, StateAction};
 use sessync::driver::workflow::SessionUploadWorkflow;
 use std::fs;
 use std::path::Path;
 use tempfile::TempDir;
+use tokio::sync::Mutex;
+
+/// テストはカレントディレクトリと HOME を書き換えるため、1つずつ実行する
+static ENV_LOCK: Mutex<()> = Mutex::const_new(());
 
 /// テスト用のConfigファイルを作成
 fn create_test_config(dir: &Path) -> String {
@@ -43,6 +47,7 @@ fn create_test_log_dir(dir: &Path) -> String {
 
 #[tokio::test]
 async fn test_workflow_execute_dry_run_success() {
+    let _env = ENV_LOCK.lock().await;
     let temp_dir = TempDir::new().unwrap();
 
     // Create test config
@@ -69,6 +74,9 @@ async fn test_workflow_execute_dry_run_success() {
         auto: false,
         manual: false,
         all_projects: false,
+        destination: None,
+        filter: FilterArgs::default(),
+        command: None,
     };
 
     // Override HOME to use temp directory
@@ -93,6 +101,7 @@ async fn test_workflow_execute_dry_run_success() {
 
 #[tokio::test]
 async fn test_workflow_execute_empty_log_directory() {
+    let _env = ENV_LOCK.lock().await;
     let temp_dir = TempDir::new().unwrap();
 
     // Create test config
@@ -119,6 +128,9 @@ async fn test_workflow_execute_empty_log_directory() {
         auto: false,
         manual: false,
         all_projects: false,
+        destination: None,
+        filter: FilterArgs::default(),
+        command: None,
     };
 
     std::env::set_var("HOME", temp_dir.path());
@@ -137,3 +149,229 @@ async fn test_workflow_execute_empty_log_directory() {
         result
     );
 }
+
+#[tokio::test]
+async fn test_workflow_fan_out_isolates_failing_destination() {
+    let _env = ENV_LOCK.lock().await;
+    let temp_dir = TempDir::new().unwrap();
+
+    // NDJSON の書き出し先と、親がファイルのため開けない SQLite の書き出し先
+    fs::write(temp_dir.path().join("blocker"), "").unwrap();
+    let config_path = temp_dir.path().join("fan-out-config.json");
+    let config_content = r#"{
+  "upload_batch_size": 100,
+  "enable_auto_upload": false,
+  "enable_deduplication": true,
+  "developer_id": "test-dev",
+  "user_email": "test@example.com",
+  "project_name": "test-project",
+  "destinations": [
+    {"type": "ndjson", "path": "./archive"},
+    {"type": "sqlite", "path": "./blocker/sessync.db"}
+  ]
+}"#;
+    fs::write(&config_path, config_content).unwrap();
+
+    // --all-projects は ~/.claude/projects 以下を読む
+    let project_dir = temp_dir.path().join(".claude/projects/-test");
+    fs::create_dir_all(&project_dir).unwrap();
+    let log_dir = create_test_log_dir(temp_dir.path());
+    fs::copy(
+        Path::new(&log_dir).join("test-session.jsonl"),
+        project_dir.join("test-session.jsonl"),
+    )
+    .unwrap();
+
+    let original_dir = std::env::current_dir().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+    std::env::set_var("HOME", temp_dir.path());
+
+    let config = sessync::adapter::config::Config::load(config_path.to_str().unwrap()).unwrap();
+    let args = Args {
+        config: config_path.to_string_lossy().to_string(),
+        dry_run: false,
+        auto: false,
+        manual: false,
+        all_projects: true,
+        destination: None,
+        filter: FilterArgs::default(),
+        command: None,
+    };
+
+    let workflow = SessionUploadWorkflow::new(config);
+    let result = workflow.execute(args).await;
+
+    std::env::set_current_dir(original_dir).unwrap();
+    std::env::remove_var("HOME");
+
+    // SQLite の失敗は報告されるが、NDJSON には書き出される
+    let error = result.expect_err("the SQLite destination should fail");
+    assert!(
+        error.to_string().contains("1 of 2 destinations"),
+        "unexpected error: {:#}",
+        error
+    );
+    let archived: usize = fs::read_dir(temp_dir.path().join("archive"))
+        .unwrap()
+        .map(|project| fs::read_dir(project.unwrap().path()).unwrap().count())
+        .sum();
+    assert_eq!(archived, 1);
+
+    // アップロード状態は送信先ごとに分かれ、NDJSON の分だけ記録される
+    let destinations: Vec<String> =
+        fs::read_dir(temp_dir.path().join(".claude/sessync/destinations"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+    let ndjson_dir = destinations
+        .iter()
+        .find(|name| name.starts_with("ndjson_"))
+        .expect("NDJSON destination state directory");
+    let state = fs::read_to_string(
+        temp_dir
+            .path()
+            .join(".claude/sessync/destinations")
+            .join(ndjson_dir)
+            .join("upload-state.json"),
+    )
+    .unwrap();
+    assert!(state.contains("550e8400-e29b-41d4-a716-446655440000"));
+    assert!(!destinations.iter().any(|name| name.starts_with("sqlite_")
+        && temp_dir
+            .path()
+            .join(".claude/sessync/destinations")
+            .join(name)
+            .join("upload-state.json")
+            .exists()));
+}
+
+/// NDJSON の書き出し先に書かれた行数
+fn ndjson_rows(dir: &Path) -> usize {
+    fs::read_dir(dir)
+        .unwrap()
+        .flat_map(|project| fs::read_dir(project.unwrap().path()).unwrap())
+        .map(|file| {
+            fs::read_to_string(file.unwrap().path())
+                .unwrap()
+                .lines()
+                .count()
+        })
+        .sum()
+}
+
+#[tokio::test]
+async fn test_workflow_fan_out_backfills_new_destination() {
+    let _env = ENV_LOCK.lock().await;
+    let temp_dir = TempDir::new().unwrap();
+
+    let project_dir = temp_dir.path().join(".claude/projects/-test");
+    fs::create_dir_all(&project_dir).unwrap();
+    let log_dir = create_test_log_dir(temp_dir.path());
+    fs::copy(
+        Path::new(&log_dir).join("test-session.jsonl"),
+        project_dir.join("test-session.jsonl"),
+    )
+    .unwrap();
+
+    let original_dir = std::env::current_dir().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+    std::env::set_var("HOME", temp_dir.path());
+
+    let run = |destinations: &str| {
+        let config_path = temp_dir.path().join("fan-out-config.json");
+        fs::write(
+            &config_path,
+            format!(
+                r#"{{
+  "upload_batch_size": 100,
+  "enable_auto_upload": false,
+  "enable_deduplication": true,
+  "developer_id": "test-dev",
+  "user_email": "test@example.com",
+  "project_name": "test-project",
+  "destinations": {}
+}}"#,
+                destinations
+            ),
+        )
+        .unwrap();
+        let config = sessync::adapter::config::Config::load(config_path.to_str().unwrap()).unwrap();
+        let args = Args {
+            config: config_path.to_string_lossy().to_string(),
+            dry_run: false,
+            auto: false,
+            manual: false,
+            all_projects: true,
+            destination: None,
+            filter: FilterArgs::default(),
+            command: None,
+        };
+        async move { SessionUploadWorkflow::new(config).execute(args).await }
+    };
+
+    // 1回目は既存の送信先だけ、2回目は新しい送信先を加えて1回の読み込みで両方に送る
+    let first = run(r#"[{"type": "ndjson", "path": "./archive"}]"#).await;
+    let second = run(
+        r#"[{"type": "ndjson", "path": "./archive"}, {"type": "ndjson", "path": "./backfill"}]"#,
+    )
+    .await;
+
+    std::env::set_current_dir(original_dir).unwrap();
+    std::env::remove_var("HOME");
+
+    first.unwrap();
+    second.unwrap();
+    // 既存の送信先には重複して書かれず、新しい送信先には全ての行が書かれる
+    assert_eq!(ndjson_rows(&temp_dir.path().join("archive")), 2);
+    assert_eq!(ndjson_rows(&temp_dir.path().join("backfill")), 2);
+}
+
+#[tokio::test]
+async fn test_workflow_adopts_legacy_state_only_when_writing_state() {
+    let _env = ENV_LOCK.lock().await;
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = create_test_config(temp_dir.path());
+    let state_dir = temp_dir.path().join(".claude/sessync");
+    fs::create_dir_all(&state_dir).unwrap();
+    fs::write(
+        state_dir.join("upload-state.json"),
+        r#"{"last_upload_timestamp":null,"uploaded_uuids":[],"last_upload_batch_id":null,"total_uploaded":0}"#,
+    )
+    .unwrap();
+
+    let original_dir = std::env::current_dir().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+    std::env::set_var("HOME", temp_dir.path());
+
+    let config = sessync::adapter::config::Config::load(&config_path).unwrap();
+    let workflow = SessionUploadWorkflow::new(config);
+    let args = |action: StateAction| Args {
+        config: config_path.clone(),
+        dry_run: false,
+        auto: false,
+        manual: false,
+        all_projects: false,
+        destination: None,
+        filter: FilterArgs::default(),
+        command: Some(Command::State { action }),
+    };
+
+    // 読み取り専用のコマンドは状態を移動しない
+    let show = workflow.execute(args(StateAction::Show)).await;
+    let moved_by_show = state_dir.join("destinations").exists();
+    // 状態を書き込むコマンドはロックを取得した後に移動する
+    let compact = workflow
+        .execute(args(StateAction::Compact {
+            retention_days: None,
+        }))
+        .await;
+
+    std::env::set_current_dir(original_dir).unwrap();
+    std::env::remove_var("HOME");
+
+    assert!(show.is_ok(), "state show failed: {:?}", show);
+    assert!(!moved_by_show);
+    assert!(compact.is_ok(), "state compact failed: {:?}", compact);
+    assert!(!state_dir.join("upload-state.json").exists());
+    assert!(state_dir.join("destinations").exists());
+}